@@ -71,6 +71,9 @@ pub fn new_partial(config: &Configuration) -> Result<Service, ServiceError> {
 		)?;
 	let client = Arc::new(client);
 
+	// The blocks can't be pruned by age, as their timestamp isn't read.
+	sc_service::set_block_retention(config, &backend, Default::default())?;
+
 	let telemetry = telemetry.map(|(worker, telemetry)| {
 		task_manager.spawn_handle().spawn("telemetry", None, worker.run());
 		telemetry
@@ -130,6 +133,7 @@ pub fn new_full(config: Configuration, consensus: Consensus) -> Result<TaskManag
 			block_announce_validator_builder: None,
 			warp_sync_params: None,
 			block_relay: None,
+			private_transactions: None,
 		})?;
 
 	if config.offchain_worker.enabled {
@@ -9,6 +9,10 @@ use sc_service::{error::Error as ServiceError, Configuration, TaskManager, WarpS
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
+use sp_runtime::{
+	codec::{Decode, Encode},
+	traits::Block as BlockT,
+};
 use std::{sync::Arc, time::Duration};
 
 pub(crate) type FullClient = sc_service::TFullClient<
@@ -36,6 +40,22 @@ pub type Service = sc_service::PartialComponents<
 	),
 >;
 
+/// Reads the timestamp of a block from its timestamp inherent, to prune the blocks by age.
+fn block_timestamp(
+	_: &<Block as BlockT>::Header,
+	body: &[<Block as BlockT>::Extrinsic],
+) -> Option<u64> {
+	body.iter().find_map(|xt| {
+		let xt = node_template_runtime::UncheckedExtrinsic::decode(&mut &xt.encode()[..]).ok()?;
+		match xt.function {
+			node_template_runtime::RuntimeCall::Timestamp(
+				node_template_runtime::TimestampCall::set { now },
+			) => Some(now),
+			_ => None,
+		}
+	})
+}
+
 pub fn new_partial(config: &Configuration) -> Result<Service, ServiceError> {
 	let telemetry = config
 		.telemetry_endpoints
@@ -57,6 +77,12 @@ pub fn new_partial(config: &Configuration) -> Result<Service, ServiceError> {
 		)?;
 	let client = Arc::new(client);
 
+	sc_service::set_block_retention(
+		config,
+		&backend,
+		sc_service::BlockRetention { timestamp: Some(Arc::new(block_timestamp)), keep: None },
+	)?;
+
 	let telemetry = telemetry.map(|(worker, telemetry)| {
 		task_manager.spawn_handle().spawn("telemetry", None, worker.run());
 		telemetry
@@ -157,6 +183,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 			block_announce_validator_builder: None,
 			warp_sync_params: Some(WarpSyncParams::WithProvider(warp_sync)),
 			block_relay: None,
+			private_transactions: None,
 		})?;
 
 	if config.offchain_worker.enabled {
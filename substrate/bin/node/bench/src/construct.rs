@@ -31,8 +31,8 @@ use std::{borrow::Cow, collections::HashMap, pin::Pin, sync::Arc};
 use node_primitives::Block;
 use node_testing::bench::{BenchDb, BlockType, DatabaseType, KeyTypes};
 use sc_transaction_pool_api::{
-	ImportNotificationStream, PoolFuture, PoolStatus, ReadyTransactions, TransactionFor,
-	TransactionSource, TransactionStatusStreamFor, TxHash,
+	BlockHash, ImportNotificationStream, PoolFuture, PoolInspection, PoolStatus, ReadyTransactions,
+	TransactionFor, TransactionLifecycle, TransactionSource, TransactionStatusStreamFor, TxHash,
 };
 use sp_consensus::{Environment, Proposer};
 use sp_inherents::InherentDataProvider;
@@ -207,6 +207,10 @@ impl sc_transaction_pool_api::InPoolTransaction for PoolTransaction {
 	fn is_propagable(&self) -> bool {
 		unimplemented!()
 	}
+
+	fn is_private(&self) -> bool {
+		unimplemented!()
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -251,6 +255,15 @@ impl sc_transaction_pool_api::TransactionPool for Transactions {
 		unimplemented!()
 	}
 
+	fn submit_private(
+		&self,
+		_at: Self::Hash,
+		_source: TransactionSource,
+		_xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		unimplemented!()
+	}
+
 	fn submit_and_watch(
 		&self,
 		_at: Self::Hash,
@@ -260,6 +273,24 @@ impl sc_transaction_pool_api::TransactionPool for Transactions {
 		unimplemented!()
 	}
 
+	fn submit_bundle(
+		&self,
+		_at: Self::Hash,
+		_source: TransactionSource,
+		_xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		unimplemented!()
+	}
+
+	fn submit_and_watch_bundle(
+		&self,
+		_at: Self::Hash,
+		_source: TransactionSource,
+		_xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		unimplemented!()
+	}
+
 	fn ready_at(
 		&self,
 		_at: NumberFor<Self::Block>,
@@ -279,10 +310,16 @@ impl sc_transaction_pool_api::TransactionPool for Transactions {
 		unimplemented!()
 	}
 
+	fn ready_bundles(&self, _at: node_primitives::Hash) -> Vec<Vec<Arc<Self::InPoolTransaction>>> {
+		Default::default()
+	}
+
 	fn remove_invalid(&self, _hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
 		Default::default()
 	}
 
+	fn on_proposed(&self, _block: BlockHash<Self>, _hashes: &[TxHash<Self>]) {}
+
 	fn futures(&self) -> Vec<Self::InPoolTransaction> {
 		unimplemented!()
 	}
@@ -291,6 +328,17 @@ impl sc_transaction_pool_api::TransactionPool for Transactions {
 		unimplemented!()
 	}
 
+	fn inspect(&self) -> PoolInspection<TxHash<Self>> {
+		unimplemented!()
+	}
+
+	fn lifecycle(
+		&self,
+		_hash: &TxHash<Self>,
+	) -> Option<TransactionLifecycle<TxHash<Self>, BlockHash<Self>>> {
+		unimplemented!()
+	}
+
 	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
 		unimplemented!()
 	}
@@ -68,6 +68,12 @@ fn new_node(tokio_handle: Handle) -> node_cli::service::NewFullBase {
 		network: network_config,
 		keystore: KeystoreConfig::InMemory,
 		database: DatabaseSource::RocksDb { path: root.join("db"), cache_size: 128 },
+		database_compression: Default::default(),
+		cold_database: None,
+		extrinsic_index: false,
+		storage_diffs: false,
+		key_history: false,
+		state_leases: None,
 		trie_cache_maximum_size: Some(64 * 1024 * 1024),
 		state_pruning: Some(PruningMode::ArchiveAll),
 		blocks_pruning: BlocksPruning::KeepAll,
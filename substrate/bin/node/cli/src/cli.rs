@@ -92,9 +92,21 @@ pub enum Subcommand {
 	/// Export the state of a given block into a chain spec.
 	ExportState(sc_cli::ExportStateCmd),
 
+	/// Take a snapshot of the database at a finalized block.
+	ExportSnapshot(sc_cli::ExportSnapshotCmd),
+
+	/// Export a verifiable snapshot of the state of a finalized block.
+	ExportStateSnapshot(sc_cli::ExportStateSnapshotCmd),
+
 	/// Import blocks.
 	ImportBlocks(sc_cli::ImportBlocksCmd),
 
+	/// Restore the database from snapshots.
+	ImportSnapshot(sc_cli::ImportSnapshotCmd),
+
+	/// Bootstrap a new database from a state snapshot.
+	ImportStateSnapshot(sc_cli::ImportStateSnapshotCmd),
+
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
@@ -103,4 +115,7 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Check the consistency of the database, and optionally repair it.
+	CheckDb(sc_cli::CheckDbCmd),
 }
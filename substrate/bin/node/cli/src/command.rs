@@ -28,6 +28,7 @@ use kitchensink_runtime::{ExistentialDeposit, RuntimeApi};
 use node_primitives::Block;
 use sc_cli::{Result, SubstrateCli};
 use sc_service::PartialComponents;
+use sp_blockchain::HeaderBackend;
 use sp_keyring::Sr25519Keyring;
 
 use std::sync::Arc;
@@ -196,6 +197,31 @@ pub fn run() -> Result<()> {
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
+		Some(Subcommand::ExportSnapshot(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { backend, task_manager, .. } = new_partial(&config, None)?;
+				Ok((cmd.run(backend), task_manager))
+			})
+		},
+		Some(Subcommand::ExportStateSnapshot(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let PartialComponents { client, backend, task_manager, other, .. } =
+					new_partial(&config, None)?;
+				let (_, (_, grandpa_link, _), _, _, _) = other;
+				let warp_sync = grandpa::warp_proof::NetworkProvider::new(
+					backend,
+					grandpa_link.shared_authority_set().clone(),
+					Vec::default(),
+				);
+				// The snapshot is taken at the latest block proven final from genesis.
+				let (finality_proof, header) = warp_sync
+					.generate_finality_proof(client.info().genesis_hash)
+					.map_err(|e| sc_cli::Error::Application(Box::new(e)))?;
+				Ok((cmd.run(client, finality_proof, header), task_manager))
+			})
+		},
 		Some(Subcommand::ImportBlocks(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -204,6 +230,32 @@ pub fn run() -> Result<()> {
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
+		Some(Subcommand::ImportSnapshot(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				// The genesis block is part of the snapshots, so the backend is opened directly.
+				let backend = sc_service::new_db_backend::<Block>(config.db_config())?;
+				let executor = sc_service::new_wasm_executor::<service::HostFunctions>(&config);
+				cmd.run(backend, &executor)
+			})
+		},
+		Some(Subcommand::ImportStateSnapshot(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|mut config| {
+				// As with warp sync, the genesis state isn't written.
+				config.network.sync_mode = sc_service::config::SyncMode::Warp;
+				let PartialComponents {
+					client, backend, task_manager, import_queue, other, ..
+				} = new_partial(&config, None)?;
+				let (_, (_, grandpa_link, _), _, _, _) = other;
+				let warp_sync = Arc::new(grandpa::warp_proof::NetworkProvider::new(
+					backend,
+					grandpa_link.shared_authority_set().clone(),
+					Vec::default(),
+				));
+				Ok((cmd.run(client, import_queue, warp_sync), task_manager))
+			})
+		},
 		Some(Subcommand::PurgeChain(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.database))
@@ -231,5 +283,9 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
 		},
+		Some(Subcommand::CheckDb(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run::<Block>(&config))
+		},
 	}
 }
@@ -22,13 +22,13 @@
 //! Service implementation. Specialized wrapper over substrate service.
 
 use crate::Cli;
-use codec::Encode;
+use codec::{Decode, Encode};
 use frame_benchmarking_cli::SUBSTRATE_REFERENCE_HARDWARE;
 use frame_system_rpc_runtime_api::AccountNonceApi;
 use futures::prelude::*;
 use kitchensink_runtime::RuntimeApi;
 use node_primitives::Block;
-use sc_client_api::{Backend, BlockBackend};
+use sc_client_api::{Backend, BlockBackend, ExtrinsicIndexKey};
 use sc_consensus_babe::{self, SlotProportion};
 use sc_network::{event::Event, NetworkEventStream, NetworkService};
 use sc_network_sync::{strategy::warp::WarpSyncParams, SyncingService};
@@ -134,6 +134,37 @@ pub fn create_extrinsic(
 	)
 }
 
+/// Reads the timestamp of a block from its timestamp inherent, to prune the blocks by age.
+fn block_timestamp(
+	_: &<Block as BlockT>::Header,
+	body: &[<Block as BlockT>::Extrinsic],
+) -> Option<u64> {
+	body.iter().find_map(|xt| {
+		let xt = kitchensink_runtime::UncheckedExtrinsic::decode(&mut &xt.encode()[..]).ok()?;
+		match xt.function {
+			kitchensink_runtime::RuntimeCall::Timestamp(pallet_timestamp::Call::set { now }) =>
+				Some(now),
+			_ => None,
+		}
+	})
+}
+
+/// Lists the keys under which an extrinsic is indexed: its signer and its call.
+fn extrinsic_index_keys(xt: &<Block as BlockT>::Extrinsic) -> Vec<ExtrinsicIndexKey> {
+	let Ok(xt) = kitchensink_runtime::UncheckedExtrinsic::decode(&mut &xt.encode()[..]) else {
+		return Vec::new()
+	};
+	let mut keys = Vec::new();
+	if let Some((address, _, _)) = &xt.signature {
+		keys.push(ExtrinsicIndexKey::Signer(address.encode()));
+	}
+	// The call is encoded as the index of its pallet followed by its index within the pallet.
+	if let [pallet, call, ..] = xt.function.encode()[..] {
+		keys.push(ExtrinsicIndexKey::Call { pallet, call });
+	}
+	keys
+}
+
 /// Creates a new partial node.
 pub fn new_partial(
 	config: &Configuration,
@@ -183,6 +214,13 @@ pub fn new_partial(
 		)?;
 	let client = Arc::new(client);
 
+	sc_service::set_block_retention(
+		config,
+		&backend,
+		sc_service::BlockRetention { timestamp: Some(Arc::new(block_timestamp)), keep: None },
+	)?;
+	sc_service::set_extrinsic_indexer(config, &backend, Arc::new(extrinsic_index_keys));
+
 	let telemetry = telemetry.map(|(worker, telemetry)| {
 		task_manager.spawn_handle().spawn("telemetry", None, worker.run());
 		telemetry
@@ -262,6 +300,7 @@ pub fn new_partial(
 		let chain_spec = config.chain_spec.cloned_box();
 
 		let rpc_backend = backend.clone();
+		let spawn_handle = task_manager.spawn_handle();
 		let rpc_extensions_builder =
 			move |deny_unsafe, subscription_executor: node_rpc::SubscriptionTaskExecutor| {
 				let deps = node_rpc::FullDeps {
@@ -281,6 +320,10 @@ pub fn new_partial(
 						subscription_executor: subscription_executor.clone(),
 						finality_provider: finality_proof_provider.clone(),
 					},
+					dry_run: node_rpc::DryRunDeps {
+						spawn_handle: Box::new(spawn_handle.clone()),
+						slot_duration,
+					},
 					backend: rpc_backend.clone(),
 					mixnet_api: mixnet_api.as_ref().cloned(),
 				};
@@ -393,6 +436,7 @@ pub fn new_full_base(
 			block_announce_validator_builder: None,
 			warp_sync_params: Some(WarpSyncParams::WithProvider(warp_sync)),
 			block_relay: None,
+			private_transactions: None,
 		})?;
 
 	if let Some(mixnet_config) = mixnet_config {
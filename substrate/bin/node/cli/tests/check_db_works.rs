@@ -0,0 +1,43 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use assert_cmd::cargo::cargo_bin;
+use std::process::Command;
+use tempfile::tempdir;
+
+use substrate_cli_test_utils as common;
+
+#[tokio::test]
+#[cfg(unix)]
+async fn check_db_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	common::run_node_for_a_while(base_path.path(), &["--dev", "--no-hardware-benchmarks"]).await;
+
+	for args in [&["check-db", "--dev"][..], &["check-db", "--dev", "--repair"]] {
+		let output = Command::new(cargo_bin("substrate-node"))
+			.args(args)
+			.arg("-d")
+			.arg(base_path.path())
+			.output()
+			.unwrap();
+		assert!(output.status.success());
+		assert!(String::from_utf8_lossy(&output.stdout).contains("The database is consistent"));
+	}
+}
@@ -0,0 +1,78 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::{path::Path, process::Command};
+use tempfile::tempdir;
+
+use substrate_cli_test_utils as common;
+
+fn finalized_hash(base_path: &Path) -> String {
+	let output = Command::new(cargo_bin("substrate-node"))
+		.args(&["chain-info", "--dev", "-d"])
+		.arg(base_path)
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+	let info: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	info["finalized_hash"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn state_snapshot_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let snapshot = base_path.path().join("state.snapshot");
+
+	common::run_node_for_a_while(base_path.path(), &["--dev", "--no-hardware-benchmarks"]).await;
+	let hash = finalized_hash(base_path.path());
+
+	let status = Command::new(cargo_bin("substrate-node"))
+		.args(&["export-state-snapshot", "--dev", "-d"])
+		.arg(base_path.path())
+		.arg(&snapshot)
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	// The snapshot must be of the trusted block, if one is given.
+	let other_path = tempdir().expect("could not create a temp dir");
+	let status = Command::new(cargo_bin("substrate-node"))
+		.args(&["import-state-snapshot", "--dev", "-d"])
+		.arg(other_path.path())
+		.args(&["--hash", "0x0000000000000000000000000000000000000000000000000000000000000001"])
+		.arg(&snapshot)
+		.status()
+		.unwrap();
+	assert!(!status.success());
+
+	// Without a trusted block, the finality proof of the snapshot is enough.
+	let import_path = tempdir().expect("could not create a temp dir");
+	let status = Command::new(cargo_bin("substrate-node"))
+		.args(&["import-state-snapshot", "--dev", "-d"])
+		.arg(import_path.path())
+		.arg(&snapshot)
+		.status()
+		.unwrap();
+	assert!(status.success());
+	// The snapshot is of the latest block with a justification, which with a single authority is
+	// the last finalized block.
+	assert_eq!(finalized_hash(import_path.path()), hash);
+}
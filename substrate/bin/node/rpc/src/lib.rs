@@ -48,7 +48,8 @@ use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 use sp_consensus::SelectChain;
-use sp_consensus_babe::BabeApi;
+use sp_consensus_babe::{BabeApi, SlotDuration};
+use sp_core::traits::SpawnNamed;
 use sp_keystore::KeystorePtr;
 
 /// Extra dependencies for BABE.
@@ -73,6 +74,14 @@ pub struct GrandpaDeps<B> {
 	pub finality_provider: Arc<FinalityProofProvider<B, Block>>,
 }
 
+/// Extra dependencies for the dry runs of the block authorship.
+pub struct DryRunDeps {
+	/// Spawns the tasks of the proposer building the blocks.
+	pub spawn_handle: Box<dyn SpawnNamed>,
+	/// The duration of a BABE slot, to claim the slot of the blocks built.
+	pub slot_duration: SlotDuration,
+}
+
 /// Full client dependencies.
 pub struct FullDeps<C, P, SC, B> {
 	/// The client instance to use.
@@ -89,6 +98,8 @@ pub struct FullDeps<C, P, SC, B> {
 	pub babe: BabeDeps,
 	/// GRANDPA specific dependencies.
 	pub grandpa: GrandpaDeps<B>,
+	/// Block authorship dry run specific dependencies.
+	pub dry_run: DryRunDeps,
 	/// The backend used by the node.
 	pub backend: Arc<B>,
 	/// Mixnet API.
@@ -105,6 +116,7 @@ pub fn create_full<C, P, SC, B>(
 		deny_unsafe,
 		babe,
 		grandpa,
+		dry_run,
 		backend,
 		mixnet_api,
 	}: FullDeps<C, P, SC, B>,
@@ -115,6 +127,7 @@ where
 		+ HeaderBackend<Block>
 		+ AuxStore
 		+ HeaderMetadata<Block, Error = BlockChainError>
+		+ sp_api::CallApiAt<Block>
 		+ Sync
 		+ Send
 		+ 'static,
@@ -122,20 +135,29 @@ where
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BabeApi<Block>,
 	C::Api: BlockBuilder<Block>,
-	P: TransactionPool + 'static,
+	P: TransactionPool<Block = Block, Hash = Hash> + 'static,
 	SC: SelectChain<Block> + 'static,
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 	B::State: sc_client_api::backend::StateBackend<sp_runtime::traits::HashingFor<Block>>,
 {
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
+	use sc_basic_authorship::ProposerFactory;
+	use sc_basic_authorship_rpc::{BlockDryRun, DryRunApiServer};
 	use sc_consensus_babe_rpc::{Babe, BabeApiServer};
 	use sc_consensus_grandpa_rpc::{Grandpa, GrandpaApiServer};
 	use sc_rpc::{
 		dev::{Dev, DevApiServer},
 		mixnet::MixnetApiServer,
+		snapshot::{SnapshotApiServer, Snapshots},
+		state_lease::{StateLeaseApiServer, StateLeases},
 	};
 	use sc_rpc_spec_v2::chain_spec::{ChainSpec, ChainSpecApiServer};
 	use sc_sync_state_rpc::{SyncState, SyncStateApiServer};
+	use sp_consensus_babe::{
+		digests::{CompatibleDigestItem, PreDigest, SecondaryPlainPreDigest},
+		inherents::BabeInherentData,
+	};
+	use sp_runtime::{Digest, DigestItem};
 	use substrate_frame_rpc_system::{System, SystemApiServer};
 	use substrate_state_trie_migration_rpc::{StateMigration, StateMigrationApiServer};
 
@@ -149,13 +171,14 @@ where
 		subscription_executor,
 		finality_provider,
 	} = grandpa;
+	let DryRunDeps { spawn_handle, slot_duration } = dry_run;
 
 	let chain_name = chain_spec.name().to_string();
 	let genesis_hash = client.block_hash(0).ok().flatten().expect("Genesis block exists");
 	let properties = chain_spec.properties();
 	io.merge(ChainSpec::new(chain_name, genesis_hash, properties).into_rpc())?;
 
-	io.merge(System::new(client.clone(), pool, deny_unsafe).into_rpc())?;
+	io.merge(System::new(client.clone(), pool.clone(), deny_unsafe).into_rpc())?;
 	io.merge(TransactionPayment::new(client.clone()).into_rpc())?;
 	io.merge(
 		Babe::new(client.clone(), babe_worker_handle.clone(), keystore, select_chain, deny_unsafe)
@@ -177,7 +200,39 @@ where
 			.into_rpc(),
 	)?;
 
-	io.merge(StateMigration::new(client.clone(), backend, deny_unsafe).into_rpc())?;
+	let proposer_factory = ProposerFactory::new(spawn_handle, client.clone(), pool, None, None);
+	io.merge(
+		BlockDryRun::new(
+			proposer_factory,
+			client.clone(),
+			move |_, ()| async move {
+				let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+				let slot =
+					sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+						*timestamp,
+						slot_duration,
+					);
+				Ok::<_, Box<dyn std::error::Error + Send + Sync>>((slot, timestamp))
+			},
+			deny_unsafe,
+		)
+		// The blocks are neither signed nor imported, so they claim their slot as a secondary
+		// author.
+		.with_inherent_digests(|inherent_data| {
+			let mut digest = Digest::default();
+			if let Ok(Some(slot)) = inherent_data.babe_inherent_data() {
+				let pre_digest =
+					PreDigest::SecondaryPlain(SecondaryPlainPreDigest { authority_index: 0, slot });
+				digest.push(DigestItem::babe_pre_digest(pre_digest));
+			}
+			digest
+		})
+		.into_rpc(),
+	)?;
+
+	io.merge(StateMigration::new(client.clone(), backend.clone(), deny_unsafe).into_rpc())?;
+	io.merge(StateLeases::<Block, _>::new(backend.clone(), deny_unsafe).into_rpc())?;
+	io.merge(Snapshots::<Block, _>::new(backend, deny_unsafe).into_rpc())?;
 	io.merge(Dev::new(client, deny_unsafe).into_rpc())?;
 
 	if let Some(mixnet_api) = mixnet_api {
@@ -385,6 +385,8 @@ impl BenchDb {
 			state_pruning: Some(PruningMode::ArchiveAll),
 			source: database_type.into_settings(dir.into()),
 			blocks_pruning: sc_client_db::BlocksPruning::KeepAll,
+			compression: Default::default(),
+			cold_storage: None,
 		};
 		let task_executor = TaskExecutor::new();
 
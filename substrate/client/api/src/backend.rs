@@ -19,8 +19,9 @@
 
 //! Substrate Client data backend
 
-use std::collections::HashSet;
+use std::{collections::HashSet, time::Duration};
 
+use codec::{Decode, Encode};
 use parking_lot::RwLock;
 
 use sp_consensus::BlockOrigin;
@@ -487,6 +488,152 @@ pub trait StorageProvider<Block: BlockT, B: Backend<Block>> {
 		child_info: &ChildInfo,
 		key: &StorageKey,
 	) -> sp_blockchain::Result<Option<MerkleValue<Block::Hash>>>;
+
+	/// Given a key, return the changes of its value made by the canonical blocks numbered `from`
+	/// to `to` inclusive, in ascending order.
+	///
+	/// The key is looked up in the child trie of `child_info` if provided, in the main trie
+	/// otherwise. At most `limit` changes are returned, and only the changes made since the
+	/// index started, see [`Backend::key_history_start`]. Returns `None` if the backend doesn't
+	/// index the history of the keys.
+	fn storage_history(
+		&self,
+		child_info: Option<&ChildInfo>,
+		key: &StorageKey,
+		from: NumberFor<Block>,
+		to: NumberFor<Block>,
+		limit: usize,
+	) -> sp_blockchain::Result<Option<Vec<KeyChange<Block>>>>;
+}
+
+/// A change of the value of a storage key, found through the key history index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyChange<Block: BlockT> {
+	/// The hash of the block changing the value.
+	pub hash: Block::Hash,
+	/// The number of the block changing the value.
+	pub number: NumberFor<Block>,
+	/// The value set by the block, or `None` if the key was removed.
+	pub value: Option<StorageData>,
+}
+
+/// A key of the secondary extrinsic indexes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ExtrinsicIndexKey {
+	/// Extrinsics signed by the account with the given SCALE-encoded address.
+	Signer(Vec<u8>),
+	/// Extrinsics dispatching the call with the given pallet and call index.
+	Call {
+		/// The index of the pallet.
+		pallet: u8,
+		/// The index of the call within the pallet.
+		call: u8,
+	},
+}
+
+/// An extrinsic found through the secondary extrinsic indexes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedExtrinsic<Block: BlockT> {
+	/// The hash of the block including the extrinsic.
+	pub hash: Block::Hash,
+	/// The number of the block including the extrinsic.
+	pub number: NumberFor<Block>,
+	/// The position of the extrinsic within the block body.
+	pub index: u32,
+}
+
+/// The changes a block made to the storage, as recorded when it was imported.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode)]
+pub struct StorageDiff {
+	/// The changes to the main trie. A value of `None` is a removal.
+	pub main: StorageCollection,
+	/// The changes to the child tries, by child storage key.
+	pub children: ChildStorageCollection,
+}
+
+impl StorageDiff {
+	/// Keep only the changes to the keys starting with `prefix`, in the main trie and in the
+	/// child tries. The child tries left without changes are dropped.
+	pub fn retain_prefix(&mut self, prefix: &[u8]) {
+		self.main.retain(|(key, _)| key.starts_with(prefix));
+		for (_, changes) in &mut self.children {
+			changes.retain(|(key, _)| key.starts_with(prefix));
+		}
+		self.children.retain(|(_, changes)| !changes.is_empty());
+	}
+
+	/// Whether the diff has no change.
+	pub fn is_empty(&self) -> bool {
+		self.main.is_empty() && self.children.is_empty()
+	}
+}
+
+/// The storage changes of a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockStorageDiff<Block: BlockT> {
+	/// The hash of the block.
+	pub hash: Block::Hash,
+	/// The number of the block.
+	pub number: NumberFor<Block>,
+	/// The changes made by the block.
+	pub diff: StorageDiff,
+}
+
+/// A lease pinning the state of a block until it expires or is revoked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateLease<Hash> {
+	/// The identifier of the lease.
+	pub id: u64,
+	/// The owner the lease is accounted to.
+	pub owner: String,
+	/// The hash of the block whose state is pinned.
+	pub hash: Hash,
+	/// When the lease expires, in seconds since the UNIX epoch.
+	pub expires_at: u64,
+}
+
+/// Persisted pins of the state of blocks, with an expiration.
+///
+/// Unlike [`Backend::pin_block`], the leases survive restarts, and are released when they
+/// expire, so a consumer failing to release its lease doesn't hold the pruning back forever. The
+/// backend may limit the number of leases of an owner, and revoke the oldest leases when the
+/// state they retain exceeds a budget.
+pub trait StateLeases<Block: BlockT>: Send + Sync {
+	/// Pin the state of the block `hash` for `duration`, on behalf of `owner`.
+	fn acquire_lease(
+		&self,
+		owner: &str,
+		hash: Block::Hash,
+		duration: Duration,
+	) -> sp_blockchain::Result<StateLease<Block::Hash>>;
+
+	/// Extend the lease `id` to expire `duration` from now.
+	fn renew_lease(
+		&self,
+		id: u64,
+		duration: Duration,
+	) -> sp_blockchain::Result<StateLease<Block::Hash>>;
+
+	/// Release the lease `id`. Returns `false` if there was no such lease.
+	fn revoke_lease(&self, id: u64) -> sp_blockchain::Result<bool>;
+
+	/// The leases which haven't expired nor been revoked.
+	fn leases(&self) -> sp_blockchain::Result<Vec<StateLease<Block::Hash>>>;
+}
+
+/// Point-in-time snapshots of the database, taken while it's in use.
+pub trait Snapshots<Block: BlockT>: Send + Sync {
+	/// Write a snapshot of the database at the last finalized block to `output`, returning the
+	/// hash and number of the block.
+	///
+	/// If `base` isn't empty, it's the chain of snapshots, a full snapshot followed by the
+	/// incremental ones, the snapshot is based on. Only the changes since the last of them are
+	/// written.
+	fn export_snapshot(
+		&self,
+		base: Vec<Box<dyn std::io::Read + Send>>,
+		output: &mut dyn std::io::Write,
+	) -> sp_blockchain::Result<(Block::Hash, NumberFor<Block>)>;
 }
 
 /// Client backend.
@@ -579,6 +726,16 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 	/// Unpin the block to allow pruning.
 	fn unpin_block(&self, hash: Block::Hash);
 
+	/// The persisted state leases of the backend, if it supports them and they're enabled.
+	fn state_leases(&self) -> Option<&dyn StateLeases<Block>> {
+		None
+	}
+
+	/// The snapshots of the backend, if it supports them.
+	fn snapshots(&self) -> Option<&dyn Snapshots<Block>> {
+		None
+	}
+
 	/// Returns true if state for given block is available.
 	fn have_state_at(&self, hash: Block::Hash, _number: NumberFor<Block>) -> bool {
 		self.state_at(hash).is_ok()
@@ -622,6 +779,82 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 		AuxStore::get_aux(self, key)
 	}
 
+	/// Query the secondary extrinsic indexes.
+	///
+	/// Returns the extrinsics matching `key` in canonical blocks numbered `from` or higher, in
+	/// ascending order. At least `limit` extrinsics are returned if available, yet all the matching
+	/// extrinsics of the last returned block are included. Returns `None` if the backend doesn't
+	/// index extrinsics.
+	fn indexed_extrinsics(
+		&self,
+		_key: &ExtrinsicIndexKey,
+		_from: NumberFor<Block>,
+		_limit: usize,
+	) -> sp_blockchain::Result<Option<Vec<IndexedExtrinsic<Block>>>> {
+		Ok(None)
+	}
+
+	/// The number of the first block covered by the secondary extrinsic indexes.
+	///
+	/// The extrinsics of the canonical blocks from this block on are all indexed. Returns `None`
+	/// if the backend doesn't index extrinsics, or didn't index any block yet.
+	fn extrinsic_index_start(&self) -> Option<NumberFor<Block>> {
+		None
+	}
+
+	/// Query the storage changes recorded for the block `hash`, restricted to the keys starting
+	/// with `prefix`.
+	///
+	/// Returns `None` if the changes of the block weren't recorded, such as when the backend
+	/// doesn't record storage changes, the block was imported without its state, or before the
+	/// record last started.
+	fn storage_diff(
+		&self,
+		_hash: Block::Hash,
+		_prefix: &[u8],
+	) -> sp_blockchain::Result<Option<StorageDiff>> {
+		Ok(None)
+	}
+
+	/// Query the storage changes recorded for the canonical blocks numbered `from` to `to`
+	/// inclusive, restricted to the keys starting with `prefix`.
+	///
+	/// The blocks are returned in ascending order. The blocks without recorded changes, or
+	/// without a change under `prefix`, are skipped, as are the blocks imported before the record
+	/// last started. Returns `None` if the backend doesn't record storage changes.
+	fn storage_diffs(
+		&self,
+		_from: NumberFor<Block>,
+		_to: NumberFor<Block>,
+		_prefix: &[u8],
+	) -> sp_blockchain::Result<Option<Vec<BlockStorageDiff<Block>>>> {
+		Ok(None)
+	}
+
+	/// Query the key history index.
+	///
+	/// See [`StorageProvider::storage_history`]. Returns `None` if the backend doesn't index the
+	/// history of the keys.
+	fn storage_history(
+		&self,
+		_child_info: Option<&ChildInfo>,
+		_key: &StorageKey,
+		_from: NumberFor<Block>,
+		_to: NumberFor<Block>,
+		_limit: usize,
+	) -> sp_blockchain::Result<Option<Vec<KeyChange<Block>>>> {
+		Ok(None)
+	}
+
+	/// The number of the first block covered by the key history index.
+	///
+	/// The changes made by the canonical blocks from this block on are all indexed, and only those
+	/// are returned by [`Self::storage_history`]. Returns `None` if the backend doesn't index the
+	/// history of the keys.
+	fn key_history_start(&self) -> Option<NumberFor<Block>> {
+		None
+	}
+
 	/// Gain access to the import lock around this backend.
 	///
 	/// _Note_ Backend isn't expected to acquire the lock by itself ever. Rather
@@ -0,0 +1,399 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC api for dry runs of the block authorship.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::async_trait,
+	proc_macros::rpc,
+	types::{ErrorObject, ErrorObjectOwned},
+};
+use serde::{Deserialize, Serialize};
+
+use sc_basic_authorship::{DryRun, ProposerFactory, SkipReason as ProposerSkipReason};
+use sc_block_builder::BlockBuilderApi;
+use sc_proposer_metrics::EndProposingReason;
+use sc_rpc_api::{DenyUnsafe, UnsafeRpcError};
+use sc_transaction_pool_api::TransactionPool;
+use sp_api::{ApiExt, CallApiAt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_consensus::ProofRecording;
+use sp_core::hashing::twox_128;
+use sp_inherents::{CreateInherentDataProviders, InherentData, InherentDataProvider};
+use sp_runtime::{traits::Block as BlockT, Digest};
+
+const DRY_RUN_ERROR: i32 = 10000;
+
+/// The duration given to a dry run, of which a third is left for the block's finalization.
+const DRY_RUN_DURATION: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Provides rpc methods for dry runs of the block authorship.
+#[rpc(client, server)]
+pub trait DryRunApi<Hash> {
+	/// Build a block on top of `parent`, or of the best block if none is given, with the
+	/// transactions of the pool, and report which ones were included or skipped and why.
+	///
+	/// The block is neither imported nor announced.
+	#[method(name = "author_dryRunBlock")]
+	async fn dry_run_block(&self, parent: Option<Hash>) -> Result<DryRunResult<Hash>, Error>;
+}
+
+/// Creates the inherent digests of a block built by a dry run from its inherent data.
+pub type InherentDigestsFn = Box<dyn Fn(&InherentData) -> Digest + Send + Sync>;
+
+/// Provides RPC methods for dry runs of the block authorship.
+pub struct BlockDryRun<A: TransactionPool, C, PR, CIDP> {
+	/// The proposer factory building the blocks.
+	proposer_factory: ProposerFactory<A, C, PR>,
+	/// shared reference to the client.
+	client: Arc<C>,
+	/// Creates the inherent data providers of the blocks.
+	create_inherent_data_providers: CIDP,
+	/// Creates the inherent digests of the blocks, if any.
+	create_inherent_digests: Option<InherentDigestsFn>,
+	/// Whether to deny unsafe calls
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<A: TransactionPool, C, PR, CIDP> BlockDryRun<A, C, PR, CIDP> {
+	/// Creates a new instance of the dry run Rpc handler.
+	///
+	/// The blocks are built without inherent digests, unless set by
+	/// [`Self::with_inherent_digests`].
+	pub fn new(
+		proposer_factory: ProposerFactory<A, C, PR>,
+		client: Arc<C>,
+		create_inherent_data_providers: CIDP,
+		deny_unsafe: DenyUnsafe,
+	) -> Self {
+		Self {
+			proposer_factory,
+			client,
+			create_inherent_data_providers,
+			create_inherent_digests: None,
+			deny_unsafe,
+		}
+	}
+
+	/// Creates the inherent digests of the blocks from their inherent data, such as the
+	/// pre-runtime digest of the consensus engine.
+	pub fn with_inherent_digests(
+		mut self,
+		create_inherent_digests: impl Fn(&InherentData) -> Digest + Send + Sync + 'static,
+	) -> Self {
+		self.create_inherent_digests = Some(Box::new(create_inherent_digests));
+		self
+	}
+}
+
+#[async_trait]
+impl<Block, A, C, PR, CIDP> DryRunApiServer<Block::Hash> for BlockDryRun<A, C, PR, CIDP>
+where
+	Block: BlockT,
+	A: TransactionPool<Block = Block, Hash = Block::Hash> + 'static,
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block> + CallApiAt<Block> + Send + Sync + 'static,
+	C::Api: ApiExt<Block> + BlockBuilderApi<Block>,
+	PR: ProofRecording,
+	CIDP: CreateInherentDataProviders<Block, ()> + 'static,
+{
+	async fn dry_run_block(
+		&self,
+		parent: Option<Block::Hash>,
+	) -> Result<DryRunResult<Block::Hash>, Error> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let parent = parent.unwrap_or_else(|| self.client.info().best_hash);
+		let parent_header = self
+			.client
+			.header(parent)?
+			.ok_or_else(|| Error::UnknownBlock(format!("{:?}", parent)))?;
+
+		let inherent_data = self
+			.create_inherent_data_providers
+			.create_inherent_data_providers(parent, ())
+			.await
+			.map_err(|e| Error::InherentData(e.to_string()))?
+			.create_inherent_data()
+			.await
+			.map_err(|e| Error::InherentData(e.to_string()))?;
+
+		let inherent_digests = self
+			.create_inherent_digests
+			.as_ref()
+			.map(|create_inherent_digests| create_inherent_digests(&inherent_data))
+			.unwrap_or_default();
+
+		let dry_run = self
+			.proposer_factory
+			.dry_run(&parent_header, inherent_data, inherent_digests, DRY_RUN_DURATION, None)
+			.await?;
+
+		Ok(dry_run.into())
+	}
+}
+
+/// A block built by a dry run.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResult<Hash> {
+	/// The hashes of the transactions of the pool included into the block, in order.
+	pub included: Vec<Hash>,
+	/// The transactions of the pool tried but not included into the block, in order.
+	pub skipped: Vec<SkippedTransaction<Hash>>,
+	/// Why building the block ended.
+	pub end_reason: EndReason,
+	/// The weight consumed by the block per dispatch class, if the runtime uses FRAME.
+	pub weight: Option<BlockWeight>,
+	/// The size of the storage proof of the block, in bytes.
+	pub pov_size: u64,
+	/// The size of the encoded block, in bytes.
+	pub block_size: u64,
+}
+
+impl<Block: BlockT> From<DryRun<Block, Block::Hash>> for DryRunResult<Block::Hash> {
+	fn from(dry_run: DryRun<Block, Block::Hash>) -> Self {
+		let block_weight_key = [twox_128(b"System"), twox_128(b"BlockWeight")].concat();
+		let weight = dry_run
+			.storage_changes
+			.main_storage_changes
+			.iter()
+			.find(|(key, _)| *key == block_weight_key)
+			.and_then(|(_, value)| value.as_ref())
+			.and_then(|value| BlockWeight::decode(&mut &value[..]).ok());
+
+		DryRunResult {
+			included: dry_run.included,
+			skipped: dry_run
+				.skipped
+				.into_iter()
+				.map(|(hash, reason)| SkippedTransaction { hash, reason: reason.into() })
+				.collect(),
+			end_reason: dry_run.end_reason.into(),
+			weight,
+			pov_size: dry_run.proof.encoded_size() as u64,
+			block_size: dry_run.block.encoded_size() as u64,
+		}
+	}
+}
+
+/// A transaction of the pool which wasn't included into the block.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedTransaction<Hash> {
+	/// The hash of the transaction.
+	pub hash: Hash,
+	/// Why the transaction wasn't included.
+	pub reason: SkipReason,
+}
+
+/// Why a transaction of the pool wasn't included into the block.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+	/// It would have overflowed the block size limit.
+	ExhaustsSize,
+	/// It would have exhausted the resources of the block, e.g. its weight.
+	ExhaustsResources,
+	/// It failed to apply, with the given error.
+	Invalid(String),
+	/// The deadline was reached before it was applied.
+	Deadline,
+}
+
+impl From<ProposerSkipReason> for SkipReason {
+	fn from(reason: ProposerSkipReason) -> Self {
+		match reason {
+			ProposerSkipReason::ExhaustsSize => SkipReason::ExhaustsSize,
+			ProposerSkipReason::ExhaustsResources => SkipReason::ExhaustsResources,
+			ProposerSkipReason::Invalid(error) => SkipReason::Invalid(error),
+			ProposerSkipReason::Deadline => SkipReason::Deadline,
+		}
+	}
+}
+
+/// Why building the block ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EndReason {
+	/// All the transactions of the pool were tried.
+	NoMoreTransactions,
+	/// The deadline was reached.
+	HitDeadline,
+	/// The block size limit was reached.
+	HitBlockSizeLimit,
+	/// The resources of the block were exhausted.
+	HitBlockWeightLimit,
+}
+
+impl From<EndProposingReason> for EndReason {
+	fn from(reason: EndProposingReason) -> Self {
+		match reason {
+			EndProposingReason::NoMoreTransactions => EndReason::NoMoreTransactions,
+			EndProposingReason::HitDeadline => EndReason::HitDeadline,
+			EndProposingReason::HitBlockSizeLimit => EndReason::HitBlockSizeLimit,
+			EndProposingReason::HitBlockWeightLimit => EndReason::HitBlockWeightLimit,
+		}
+	}
+}
+
+/// The weight consumed by a block per dispatch class, as stored by `frame_system`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Decode, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockWeight {
+	/// The weight of the normal dispatches.
+	pub normal: Weight,
+	/// The weight of the operational dispatches.
+	pub operational: Weight,
+	/// The weight of the mandatory dispatches, e.g. the inherents.
+	pub mandatory: Weight,
+}
+
+/// A weight.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Weight {
+	/// The computational time used, in picoseconds.
+	pub ref_time: u64,
+	/// The size of the proof of validity used, in bytes.
+	pub proof_size: u64,
+}
+
+impl Decode for Weight {
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		let weight = sp_weights::Weight::decode(input)?;
+		Ok(Weight { ref_time: weight.ref_time(), proof_size: weight.proof_size() })
+	}
+}
+
+/// Top-level error type for the RPC handler.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// The parent block is unknown.
+	#[error("Unknown block: {0}")]
+	UnknownBlock(String),
+	/// Failed to create the inherent data.
+	#[error("Failed to create the inherent data: {0}")]
+	InherentData(String),
+	/// Failed to build the block.
+	#[error(transparent)]
+	Blockchain(#[from] sp_blockchain::Error),
+	/// Call to an unsafe RPC was denied.
+	#[error(transparent)]
+	UnsafeRpcCalled(#[from] UnsafeRpcError),
+}
+
+impl From<Error> for ErrorObjectOwned {
+	fn from(error: Error) -> Self {
+		match error {
+			Error::UnknownBlock(_) =>
+				ErrorObject::owned(DRY_RUN_ERROR + 1, error.to_string(), None::<()>),
+			Error::InherentData(_) =>
+				ErrorObject::owned(DRY_RUN_ERROR + 2, error.to_string(), None::<()>),
+			Error::Blockchain(e) =>
+				ErrorObject::owned(DRY_RUN_ERROR + 3, e.to_string(), None::<()>),
+			Error::UnsafeRpcCalled(e) => e.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpsee::{core::EmptyServerParams as EmptyParams, RpcModule};
+	use sc_transaction_pool::BasicPool;
+	use sc_transaction_pool_api::{ChainEvent, MaintainedTransactionPool, TransactionSource};
+	use sp_core::{testing::TaskExecutor, H256};
+	use substrate_test_runtime_client::{
+		runtime::{Block, ExtrinsicBuilder, Transfer},
+		AccountKeyring,
+	};
+
+	async fn test_dry_run_module(deny_unsafe: DenyUnsafe) -> (RpcModule<()>, H256) {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = TaskExecutor::new();
+		let pool = BasicPool::new_full(
+			Default::default(),
+			true.into(),
+			None,
+			spawner.clone(),
+			client.clone(),
+		);
+		let genesis_hash = client.info().genesis_hash;
+
+		let transfer = Transfer {
+			amount: Default::default(),
+			nonce: 0,
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+		};
+		let hash = pool
+			.submit_one(
+				genesis_hash,
+				TransactionSource::External,
+				ExtrinsicBuilder::new_transfer(transfer).build(),
+			)
+			.await
+			.unwrap();
+		pool.maintain(ChainEvent::NewBestBlock { hash: genesis_hash, tree_route: None })
+			.await;
+
+		let proposer_factory = ProposerFactory::new(spawner, client.clone(), pool, None, None);
+		let dry_run = BlockDryRun::new(
+			proposer_factory,
+			client,
+			|_: <Block as BlockT>::Hash, _: ()| async {
+				Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+			},
+			deny_unsafe,
+		);
+
+		let mut module = RpcModule::new(());
+		module.merge(dry_run.into_rpc()).unwrap();
+		(module, hash)
+	}
+
+	#[tokio::test]
+	async fn dry_run_block_works() {
+		let (api, hash) = test_dry_run_module(DenyUnsafe::No).await;
+
+		let result: DryRunResult<H256> =
+			api.call("author_dryRunBlock", EmptyParams::new()).await.unwrap();
+
+		assert_eq!(result.included, vec![hash]);
+		assert!(result.skipped.is_empty());
+		assert_eq!(result.end_reason, EndReason::NoMoreTransactions);
+		assert!(result.weight.unwrap().normal.ref_time > 0);
+		assert!(result.pov_size > 0);
+		assert!(result.block_size > 0);
+	}
+
+	#[tokio::test]
+	async fn dry_run_block_is_unsafe() {
+		let (api, _) = test_dry_run_module(DenyUnsafe::Yes).await;
+
+		let request = r#"{"jsonrpc":"2.0","method":"author_dryRunBlock","params":[],"id":1}"#;
+		let (response, _) = api.raw_json_request(request, 1).await.unwrap();
+		let expected = r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"RPC call is unsafe to be called externally"},"id":1}"#;
+
+		assert_eq!(&response.result, expected);
+	}
+}
@@ -29,10 +29,10 @@ use futures::{
 	select,
 };
 use log::{debug, error, info, trace, warn};
-use sc_block_builder::{BlockBuilderApi, BlockBuilderBuilder};
+use sc_block_builder::{BlockBuilderApi, BlockBuilderBuilder, BuiltBlock};
 use sc_telemetry::{telemetry, TelemetryHandle, CONSENSUS_INFO};
-use sc_transaction_pool_api::{InPoolTransaction, TransactionPool};
-use sp_api::{ApiExt, CallApiAt, ProvideRuntimeApi};
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool, TxHash};
+use sp_api::{ApiExt, CallApiAt, ProvideRuntimeApi, StorageChanges, StorageProof};
 use sp_blockchain::{ApplyExtrinsicFailed::Validity, Error::ApplyExtrinsicFailed, HeaderBackend};
 use sp_consensus::{DisableProofRecording, EnableProofRecording, ProofRecording, Proposal};
 use sp_core::traits::SpawnNamed;
@@ -43,6 +43,8 @@ use sp_runtime::{
 };
 use std::{marker::PhantomData, pin::Pin, sync::Arc, time};
 
+use crate::transaction_selector::{PriorityOrder, SelectionOutcome, TransactionSelector};
+
 use prometheus_endpoint::Registry as PrometheusRegistry;
 use sc_proposer_metrics::{EndProposingReason, MetricsLink as PrometheusMetrics};
 
@@ -60,7 +62,7 @@ const DEFAULT_SOFT_DEADLINE_PERCENT: Percent = Percent::from_percent(50);
 const LOG_TARGET: &'static str = "basic-authorship";
 
 /// [`Proposer`] factory.
-pub struct ProposerFactory<A, C, PR> {
+pub struct ProposerFactory<A: TransactionPool, C, PR> {
 	spawn_handle: Box<dyn SpawnNamed>,
 	/// The client instance.
 	client: Arc<C>,
@@ -84,11 +86,13 @@ pub struct ProposerFactory<A, C, PR> {
 	telemetry: Option<TelemetryHandle>,
 	/// When estimating the block size, should the proof be included?
 	include_proof_in_block_size_estimation: bool,
+	/// The policy selecting the transactions to include into a block.
+	transaction_selector: Arc<dyn TransactionSelector<A>>,
 	/// phantom member to pin the `ProofRecording` type.
 	_phantom: PhantomData<PR>,
 }
 
-impl<A, C> ProposerFactory<A, C, DisableProofRecording> {
+impl<A: TransactionPool + 'static, C> ProposerFactory<A, C, DisableProofRecording> {
 	/// Create a new proposer factory.
 	///
 	/// Proof recording will be disabled when using proposers built by this instance to build
@@ -109,12 +113,13 @@ impl<A, C> ProposerFactory<A, C, DisableProofRecording> {
 			telemetry,
 			client,
 			include_proof_in_block_size_estimation: false,
+			transaction_selector: Arc::new(PriorityOrder),
 			_phantom: PhantomData,
 		}
 	}
 }
 
-impl<A, C> ProposerFactory<A, C, EnableProofRecording> {
+impl<A: TransactionPool + 'static, C> ProposerFactory<A, C, EnableProofRecording> {
 	/// Create a new proposer factory with proof recording enabled.
 	///
 	/// Each proposer created by this instance will record a proof while building a block.
@@ -137,6 +142,7 @@ impl<A, C> ProposerFactory<A, C, EnableProofRecording> {
 			soft_deadline_percent: DEFAULT_SOFT_DEADLINE_PERCENT,
 			telemetry,
 			include_proof_in_block_size_estimation: true,
+			transaction_selector: Arc::new(PriorityOrder),
 			_phantom: PhantomData,
 		}
 	}
@@ -147,7 +153,7 @@ impl<A, C> ProposerFactory<A, C, EnableProofRecording> {
 	}
 }
 
-impl<A, C, PR> ProposerFactory<A, C, PR> {
+impl<A: TransactionPool, C, PR> ProposerFactory<A, C, PR> {
 	/// Set the default block size limit in bytes.
 	///
 	/// The default value for the block size limit is:
@@ -174,6 +180,14 @@ impl<A, C, PR> ProposerFactory<A, C, PR> {
 	pub fn set_soft_deadline(&mut self, percent: Percent) {
 		self.soft_deadline_percent = percent;
 	}
+
+	/// Set the policy selecting the transactions to include into a block.
+	///
+	/// The default policy is [`PriorityOrder`], trying the transactions one by one, in the order of
+	/// the pool.
+	pub fn set_transaction_selector(&mut self, selector: impl TransactionSelector<A> + 'static) {
+		self.transaction_selector = Arc::new(selector);
+	}
 }
 
 impl<Block, C, A, PR> ProposerFactory<A, C, PR>
@@ -188,26 +202,86 @@ where
 		parent_header: &<Block as BlockT>::Header,
 		now: Box<dyn Fn() -> time::Instant + Send + Sync>,
 	) -> Proposer<Block, C, A, PR> {
-		let parent_hash = parent_header.hash();
+		info!("Starting consensus session on top of parent {:?}", parent_header.hash());
 
-		info!("Starting consensus session on top of parent {:?}", parent_hash);
+		self.proposer(parent_header, now, self.metrics.clone())
+	}
 
-		let proposer = Proposer::<_, _, _, PR> {
+	fn proposer(
+		&self,
+		parent_header: &<Block as BlockT>::Header,
+		now: Box<dyn Fn() -> time::Instant + Send + Sync>,
+		metrics: PrometheusMetrics,
+	) -> Proposer<Block, C, A, PR> {
+		Proposer::<_, _, _, PR> {
 			spawn_handle: self.spawn_handle.clone(),
 			client: self.client.clone(),
-			parent_hash,
+			parent_hash: parent_header.hash(),
 			parent_number: *parent_header.number(),
 			transaction_pool: self.transaction_pool.clone(),
 			now,
-			metrics: self.metrics.clone(),
+			metrics,
 			default_block_size_limit: self.default_block_size_limit,
 			soft_deadline_percent: self.soft_deadline_percent,
 			telemetry: self.telemetry.clone(),
 			_phantom: PhantomData,
 			include_proof_in_block_size_estimation: self.include_proof_in_block_size_estimation,
-		};
+			transaction_selector: self.transaction_selector.clone(),
+		}
+	}
+}
 
-		proposer
+impl<A, Block, C, PR> ProposerFactory<A, C, PR>
+where
+	A: TransactionPool<Block = Block> + 'static,
+	Block: BlockT,
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block> + CallApiAt<Block> + Send + Sync + 'static,
+	C::Api: ApiExt<Block> + BlockBuilderApi<Block>,
+	PR: ProofRecording,
+{
+	/// Build a block on top of `parent_header` with the transactions of the pool, as
+	/// [`sp_consensus::Proposer::propose`] would, without proposing it.
+	///
+	/// The block is neither imported nor announced, the transactions found invalid are kept in the
+	/// pool and no metrics are reported. The storage proof is recorded regardless of `PR`.
+	pub fn dry_run(
+		&self,
+		parent_header: &<Block as BlockT>::Header,
+		inherent_data: InherentData,
+		inherent_digests: Digest,
+		max_duration: time::Duration,
+		block_size_limit: Option<usize>,
+	) -> impl Future<Output = Result<DryRun<Block, TxHash<A>>, sp_blockchain::Error>> {
+		let proposer = self.proposer(
+			parent_header,
+			Box::new(time::Instant::now),
+			PrometheusMetrics::new(None),
+		);
+		let (tx, rx) = oneshot::channel();
+
+		self.spawn_handle.spawn_blocking(
+			"basic-authorship-dry-run",
+			None,
+			Box::pin(async move {
+				let deadline = (proposer.now)() + max_duration - max_duration / 3;
+				let res = proposer
+					.build_block(inherent_data, inherent_digests, deadline, block_size_limit, true)
+					.await
+					.map(|(built, outcome)| DryRun {
+						block: built.block,
+						storage_changes: built.storage_changes,
+						proof: built.proof.expect("proof recording is enabled for dry runs; qed"),
+						included: outcome.included,
+						skipped: outcome.skipped,
+						end_reason: outcome.end_reason,
+					});
+				if tx.send(res).is_err() {
+					trace!(target: LOG_TARGET, "Could not send dry run result!");
+				}
+			}),
+		);
+
+		async move { rx.await? }
 	}
 }
 
@@ -241,6 +315,7 @@ pub struct Proposer<Block: BlockT, C, A: TransactionPool, PR> {
 	include_proof_in_block_size_estimation: bool,
 	soft_deadline_percent: Percent,
 	telemetry: Option<TelemetryHandle>,
+	transaction_selector: Arc<dyn TransactionSelector<A>>,
 	_phantom: PhantomData<PR>,
 }
 
@@ -295,6 +370,44 @@ where
 /// It allows us to increase block utilization.
 const MAX_SKIPPED_TRANSACTIONS: usize = 8;
 
+/// Why a transaction of the pool wasn't included into a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+	/// It would have overflowed the block size limit.
+	ExhaustsSize,
+	/// It would have exhausted the resources of the block, e.g. its weight.
+	ExhaustsResources,
+	/// It failed to apply, with the given error.
+	Invalid(String),
+	/// The deadline was reached before it was applied.
+	Deadline,
+}
+
+/// A block built by [`ProposerFactory::dry_run`].
+pub struct DryRun<Block: BlockT, Hash> {
+	/// The block built.
+	pub block: Block,
+	/// The storage changes of the block.
+	pub storage_changes: StorageChanges<Block>,
+	/// The proof of the storage accessed building the block.
+	pub proof: StorageProof,
+	/// The transactions of the pool included into the block, in order.
+	pub included: Vec<Hash>,
+	/// The transactions of the pool tried but not included into the block, in order.
+	pub skipped: Vec<(Hash, SkipReason)>,
+	/// Why building the block ended.
+	pub end_reason: EndProposingReason,
+}
+
+/// The transactions tried by [`Proposer::apply_extrinsics`].
+struct ExtrinsicsOutcome<Hash> {
+	end_reason: EndProposingReason,
+	included: Vec<Hash>,
+	skipped: Vec<(Hash, SkipReason)>,
+	/// The transactions to remove from the pool.
+	invalid: Vec<Hash>,
+}
+
 impl<A, Block, C, PR> Proposer<Block, C, A, PR>
 where
 	A: TransactionPool<Block = Block>,
@@ -311,10 +424,34 @@ where
 		block_size_limit: Option<usize>,
 	) -> Result<Proposal<Block, PR::Proof>, sp_blockchain::Error> {
 		let block_timer = time::Instant::now();
+		let (built, outcome) = self
+			.build_block(inherent_data, inherent_digests, deadline, block_size_limit, PR::ENABLED)
+			.await?;
+		self.transaction_pool.remove_invalid(&outcome.invalid);
+		let (block, storage_changes, proof) = built.into_inner();
+		self.transaction_pool.on_proposed(block.header().hash(), &outcome.included);
+		let block_took = block_timer.elapsed();
+
+		let proof =
+			PR::into_proof(proof).map_err(|e| sp_blockchain::Error::Application(Box::new(e)))?;
+
+		self.print_summary(&block, outcome.end_reason, block_took, block_timer.elapsed());
+		Ok(Proposal { block, proof, storage_changes })
+	}
+
+	/// Build a block, recording the storage proof if `record_proof` is set.
+	async fn build_block(
+		&self,
+		inherent_data: InherentData,
+		inherent_digests: Digest,
+		deadline: time::Instant,
+		block_size_limit: Option<usize>,
+		record_proof: bool,
+	) -> Result<(BuiltBlock<Block>, ExtrinsicsOutcome<TxHash<A>>), sp_blockchain::Error> {
 		let mut block_builder = BlockBuilderBuilder::new(&*self.client)
 			.on_parent_block(self.parent_hash)
 			.with_parent_block_number(self.parent_number)
-			.with_proof_recording(PR::ENABLED)
+			.with_proof_recording(record_proof)
 			.with_inherent_digests(inherent_digests)
 			.build()?;
 
@@ -323,16 +460,8 @@ where
 		// TODO call `after_inherents` and check if we should apply extrinsincs here
 		// <https://github.com/paritytech/substrate/pull/14275/>
 
-		let end_reason =
-			self.apply_extrinsics(&mut block_builder, deadline, block_size_limit).await?;
-		let (block, storage_changes, proof) = block_builder.build()?.into_inner();
-		let block_took = block_timer.elapsed();
-
-		let proof =
-			PR::into_proof(proof).map_err(|e| sp_blockchain::Error::Application(Box::new(e)))?;
-
-		self.print_summary(&block, end_reason, block_took, block_timer.elapsed());
-		Ok(Proposal { block, proof, storage_changes })
+		let outcome = self.apply_extrinsics(&mut block_builder, deadline, block_size_limit).await?;
+		Ok((block_builder.build()?, outcome))
 	}
 
 	/// Apply all inherents to the block.
@@ -380,12 +509,14 @@ where
 	}
 
 	/// Apply as many extrinsics as possible to the block.
+	///
+	/// The transactions found invalid are returned rather than removed from the pool.
 	async fn apply_extrinsics(
 		&self,
 		block_builder: &mut sc_block_builder::BlockBuilder<'_, Block, C>,
 		deadline: time::Instant,
 		block_size_limit: Option<usize>,
-	) -> Result<EndProposingReason, sp_blockchain::Error> {
+	) -> Result<ExtrinsicsOutcome<TxHash<A>>, sp_blockchain::Error> {
 		// proceed with transactions
 		// We calculate soft deadline used only in case we start skipping transactions.
 		let now = (self.now)();
@@ -394,13 +525,18 @@ where
 		let soft_deadline =
 			now + time::Duration::from_micros(self.soft_deadline_percent.mul_floor(left_micros));
 		let mut skipped = 0;
+		let mut included_txs = Vec::new();
+		let mut skipped_txs = Vec::new();
 		let mut unqueue_invalid = Vec::new();
 
-		let mut t1 = self.transaction_pool.ready_at(self.parent_number).fuse();
+		let mut t1 = self
+			.transaction_pool
+			.ready_at_block(self.parent_hash, self.parent_number)
+			.fuse();
 		let mut t2 =
 			futures_timer::Delay::new(deadline.saturating_duration_since((self.now)()) / 8).fuse();
 
-		let mut pending_iterator = select! {
+		let pending_iterator = select! {
 			res = t1 => res,
 			_ = t2 => {
 				warn!(target: LOG_TARGET,
@@ -412,6 +548,10 @@ where
 			},
 		};
 
+		let bundles = self.transaction_pool.ready_bundles(self.parent_hash);
+		let mut selection =
+			self.transaction_selector.select(self.parent_hash, pending_iterator, bundles);
+
 		let block_size_limit = block_size_limit.unwrap_or(self.default_block_size_limit);
 
 		debug!(target: LOG_TARGET, "Attempting to push transactions from the pool.");
@@ -419,8 +559,8 @@ where
 		let mut transaction_pushed = false;
 
 		let end_reason = loop {
-			let pending_tx = if let Some(pending_tx) = pending_iterator.next() {
-				pending_tx
+			let pending_txs = if let Some(pending_txs) = selection.next() {
+				pending_txs
 			} else {
 				debug!(
 					target: LOG_TARGET,
@@ -437,16 +577,28 @@ where
 					"Consensus deadline reached when pushing block transactions, \
 				proceeding with proposing."
 				);
+				skipped_txs
+					.extend(pending_txs.iter().map(|tx| (tx.hash().clone(), SkipReason::Deadline)));
 				break EndProposingReason::HitDeadline
 			}
 
-			let pending_tx_data = pending_tx.data().clone();
-			let pending_tx_hash = pending_tx.hash().clone();
+			if pending_txs.is_empty() {
+				continue
+			}
+
+			let pending_tx_data =
+				pending_txs.iter().map(|tx| tx.data().clone()).collect::<Vec<_>>();
+			let pending_tx_hashes =
+				pending_txs.iter().map(|tx| tx.hash().clone()).collect::<Vec<_>>();
 
 			let block_size =
 				block_builder.estimate_block_size(self.include_proof_in_block_size_estimation);
-			if block_size + pending_tx_data.encoded_size() > block_size_limit {
-				pending_iterator.report_invalid(&pending_tx);
+			let pending_tx_size = pending_tx_data.iter().map(Encode::encoded_size).sum::<usize>();
+			if block_size + pending_tx_size > block_size_limit {
+				selection.report(&pending_txs, SelectionOutcome::ExhaustsSize);
+				skipped_txs.extend(
+					pending_tx_hashes.iter().map(|hash| (hash.clone(), SkipReason::ExhaustsSize)),
+				);
 				if skipped < MAX_SKIPPED_TRANSACTIONS {
 					skipped += 1;
 					debug!(
@@ -473,14 +625,21 @@ where
 				}
 			}
 
-			trace!(target: LOG_TARGET, "[{:?}] Pushing to the block.", pending_tx_hash);
-			match sc_block_builder::BlockBuilder::push(block_builder, pending_tx_data) {
+			trace!(target: LOG_TARGET, "{:?} Pushing to the block.", pending_tx_hashes);
+			match sc_block_builder::BlockBuilder::push_all(block_builder, pending_tx_data) {
 				Ok(()) => {
+					selection.report(&pending_txs, SelectionOutcome::Included);
 					transaction_pushed = true;
-					debug!(target: LOG_TARGET, "[{:?}] Pushed to the block.", pending_tx_hash);
+					included_txs.extend(pending_tx_hashes.iter().cloned());
+					debug!(target: LOG_TARGET, "{:?} Pushed to the block.", pending_tx_hashes);
 				},
-				Err(ApplyExtrinsicFailed(Validity(e))) if e.exhausted_resources() => {
-					pending_iterator.report_invalid(&pending_tx);
+				Err((_, ApplyExtrinsicFailed(Validity(e)))) if e.exhausted_resources() => {
+					selection.report(&pending_txs, SelectionOutcome::ExhaustsResources);
+					skipped_txs.extend(
+						pending_tx_hashes
+							.iter()
+							.map(|hash| (hash.clone(), SkipReason::ExhaustsResources)),
+					);
 					if skipped < MAX_SKIPPED_TRANSACTIONS {
 						skipped += 1;
 						debug!(target: LOG_TARGET,
@@ -500,13 +659,22 @@ where
 						break EndProposingReason::HitBlockWeightLimit
 					}
 				},
-				Err(e) => {
-					pending_iterator.report_invalid(&pending_tx);
+				Err((index, e)) => {
+					selection.report(&pending_txs, SelectionOutcome::Invalid(index));
 					debug!(
 						target: LOG_TARGET,
-						"[{:?}] Invalid transaction: {}", pending_tx_hash, e
+						"[{:?}] Invalid transaction: {}", pending_tx_hashes[index], e
 					);
-					unqueue_invalid.push(pending_tx_hash);
+					let invalid = pending_tx_hashes[index].clone();
+					skipped_txs.extend(pending_tx_hashes.iter().map(|hash| {
+						let reason = if *hash == invalid {
+							e.to_string()
+						} else {
+							format!("Bundled with the invalid transaction {:?}", invalid)
+						};
+						(hash.clone(), SkipReason::Invalid(reason))
+					}));
+					unqueue_invalid.push(invalid);
 				},
 			}
 		};
@@ -518,8 +686,12 @@ where
 			);
 		}
 
-		self.transaction_pool.remove_invalid(&unqueue_invalid);
-		Ok(end_reason)
+		Ok(ExtrinsicsOutcome {
+			end_reason,
+			included: included_txs,
+			skipped: skipped_txs,
+			invalid: unqueue_invalid,
+		})
 	}
 
 	/// Prints a summary and does telemetry + metrics.
@@ -1134,4 +1306,199 @@ mod tests {
 			"Not enough calls to current time, which indicates the test might have ended because of deadline, not soft deadline"
 		);
 	}
+
+	#[test]
+	fn should_include_transactions_chosen_by_the_transaction_selector() {
+		// A selector trying all the ready transactions at once.
+		struct AllOrNothing;
+
+		struct AllOrNothingSelection<A: TransactionPool>(Option<Vec<Arc<A::InPoolTransaction>>>);
+
+		impl<A: TransactionPool + 'static> TransactionSelector<A> for AllOrNothing {
+			fn select(
+				&self,
+				_parent_hash: sc_transaction_pool_api::BlockHash<A>,
+				ready: crate::ReadyTransactionsFor<A>,
+				_bundles: crate::BundlesFor<A>,
+			) -> Box<dyn crate::Selection<A>> {
+				Box::new(AllOrNothingSelection::<A>(Some(ready.collect())))
+			}
+		}
+
+		impl<A: TransactionPool> crate::Selection<A> for AllOrNothingSelection<A> {
+			fn next(&mut self) -> Option<Vec<Arc<A::InPoolTransaction>>> {
+				self.0.take()
+			}
+
+			fn report(&mut self, _group: &[Arc<A::InPoolTransaction>], _: SelectionOutcome) {}
+		}
+
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let txpool = BasicPool::new_full(
+			Default::default(),
+			true.into(),
+			None,
+			spawner.clone(),
+			client.clone(),
+		);
+		let genesis_hash = client.info().genesis_hash;
+
+		let medium = |who| {
+			ExtrinsicBuilder::new_fill_block(Perbill::from_parts(MEDIUM))
+				.signer(AccountKeyring::numeric(who))
+				.build()
+		};
+
+		// only two of these fit in a block.
+		block_on(txpool.submit_at(genesis_hash, SOURCE, (0..3).map(medium).collect())).unwrap();
+
+		block_on(txpool.maintain(chain_event(
+			client.expect_header(genesis_hash).expect("there should be header"),
+		)));
+		assert_eq!(txpool.ready().count(), 3);
+
+		let mut proposer_factory =
+			ProposerFactory::new(spawner.clone(), client.clone(), txpool.clone(), None, None);
+		let propose = |proposer_factory: &mut ProposerFactory<_, _, _>| {
+			let proposer = proposer_factory.init_with_now(
+				&client.expect_header(genesis_hash).unwrap(),
+				Box::new(time::Instant::now),
+			);
+			let deadline = time::Duration::from_secs(900);
+			block_on(proposer.propose(Default::default(), Default::default(), deadline, None))
+				.map(|r| r.block)
+				.unwrap()
+		};
+
+		// when the transactions are tried one by one
+		let block = propose(&mut proposer_factory);
+
+		// then the block should have the ones which fit.
+		assert_eq!(block.extrinsics().len(), 2);
+
+		// when the transactions are tried all at once
+		proposer_factory.set_transaction_selector(AllOrNothing);
+		let block = propose(&mut proposer_factory);
+
+		// then the block should have none of them.
+		assert_eq!(block.extrinsics().len(), 0);
+		assert_eq!(txpool.ready().count(), 3);
+	}
+
+	#[test]
+	fn should_include_bundles_entirely_or_not_at_all() {
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let txpool = BasicPool::new_full(
+			Default::default(),
+			true.into(),
+			None,
+			spawner.clone(),
+			client.clone(),
+		);
+		let genesis_hash = client.info().genesis_hash;
+
+		let medium = |who, nonce| {
+			ExtrinsicBuilder::new_fill_block(Perbill::from_parts(MEDIUM))
+				.signer(AccountKeyring::numeric(who))
+				.nonce(nonce)
+				.build()
+		};
+
+		block_on(txpool.submit_at(genesis_hash, SOURCE, vec![medium(1, 0)])).unwrap();
+		// doesn't fit in a block along with the transaction above.
+		block_on(txpool.submit_bundle(genesis_hash, SOURCE, vec![medium(2, 0), medium(2, 1)]))
+			.unwrap();
+		block_on(txpool.submit_bundle(genesis_hash, SOURCE, vec![extrinsic(0), extrinsic(1)]))
+			.unwrap();
+
+		block_on(txpool.maintain(chain_event(
+			client.expect_header(genesis_hash).expect("there should be header"),
+		)));
+		assert_eq!(txpool.ready().count(), 1);
+		assert_eq!(txpool.ready_bundles(genesis_hash).len(), 2);
+
+		let mut proposer_factory =
+			ProposerFactory::new(spawner.clone(), client.clone(), txpool.clone(), None, None);
+		let proposer = proposer_factory.init_with_now(
+			&client.expect_header(genesis_hash).unwrap(),
+			Box::new(time::Instant::now),
+		);
+
+		// when
+		let deadline = time::Duration::from_secs(900);
+		let block =
+			block_on(proposer.propose(Default::default(), Default::default(), deadline, None))
+				.map(|r| r.block)
+				.unwrap();
+
+		// then
+		assert_eq!(block.extrinsics(), &[medium(1, 0), extrinsic(0), extrinsic(1)]);
+	}
+
+	#[test]
+	fn should_report_the_transactions_tried_by_a_dry_run() {
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let txpool = BasicPool::new_full(
+			Default::default(),
+			true.into(),
+			None,
+			spawner.clone(),
+			client.clone(),
+		);
+		let genesis_hash = client.info().genesis_hash;
+
+		let medium = |who| {
+			ExtrinsicBuilder::new_fill_block(Perbill::from_parts(MEDIUM))
+				.signer(AccountKeyring::numeric(who))
+				.build()
+		};
+
+		// only two of these fit in a block.
+		let mut hashes =
+			block_on(txpool.submit_at(genesis_hash, SOURCE, (0..3).map(medium).collect()))
+				.unwrap()
+				.into_iter()
+				.collect::<Result<Vec<_>, _>>()
+				.unwrap();
+
+		block_on(txpool.maintain(chain_event(
+			client.expect_header(genesis_hash).expect("there should be header"),
+		)));
+
+		let proposer_factory =
+			ProposerFactory::new(spawner.clone(), client.clone(), txpool.clone(), None, None);
+
+		// when
+		let dry_run = block_on(proposer_factory.dry_run(
+			&client.expect_header(genesis_hash).unwrap(),
+			Default::default(),
+			Default::default(),
+			time::Duration::from_secs(900),
+			None,
+		))
+		.unwrap();
+
+		// then
+		assert_eq!(dry_run.block.extrinsics().len(), 2);
+		assert_eq!(dry_run.included.len(), 2);
+		assert_eq!(dry_run.skipped.len(), 1);
+		assert_eq!(dry_run.skipped[0].1, SkipReason::ExhaustsResources);
+		let mut tried = dry_run.included.clone();
+		tried.push(dry_run.skipped[0].0);
+		tried.sort();
+		hashes.sort();
+		assert_eq!(tried, hashes);
+		assert_eq!(dry_run.end_reason, EndProposingReason::NoMoreTransactions);
+		assert!(!dry_run.proof.is_empty());
+
+		// and the block was neither imported nor were the transactions removed from the pool.
+		assert_eq!(client.info().best_number, 0);
+		assert_eq!(txpool.ready().count(), 3);
+	}
 }
@@ -72,5 +72,12 @@
 //! ```
 
 mod basic_authorship;
+mod transaction_selector;
 
-pub use crate::basic_authorship::{Proposer, ProposerFactory, DEFAULT_BLOCK_SIZE_LIMIT};
+pub use crate::{
+	basic_authorship::{DryRun, Proposer, ProposerFactory, SkipReason, DEFAULT_BLOCK_SIZE_LIMIT},
+	transaction_selector::{
+		BundlesFor, PriorityOrder, ReadyTransactionsFor, Selection, SelectionOutcome,
+		TransactionSelector,
+	},
+};
@@ -0,0 +1,138 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Selection of the transactions to include into a block.
+
+use sc_transaction_pool_api::{BlockHash, InPoolTransaction, ReadyTransactions, TransactionPool};
+use std::{collections::VecDeque, sync::Arc};
+
+/// The transactions ready in the pool `A`, in the order it yields them.
+pub type ReadyTransactionsFor<A> =
+	Box<dyn ReadyTransactions<Item = Arc<<A as TransactionPool>::InPoolTransaction>> + Send>;
+
+/// The bundles of the pool `A`, ordered by the priority of their first transaction.
+pub type BundlesFor<A> = Vec<Vec<Arc<<A as TransactionPool>::InPoolTransaction>>>;
+
+/// The outcome of trying to include a group of transactions into the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionOutcome {
+	/// All the transactions of the group were included.
+	Included,
+	/// The group would have overflowed the block size limit.
+	ExhaustsSize,
+	/// The group exhausted the resources of the block.
+	ExhaustsResources,
+	/// The transaction at the given index of the group was invalid.
+	Invalid(usize),
+}
+
+/// A policy selecting the transactions to include into a block, and their order.
+///
+/// Can be set with
+/// [`ProposerFactory::set_transaction_selector`](crate::ProposerFactory::set_transaction_selector).
+/// Defaults to [`PriorityOrder`].
+pub trait TransactionSelector<A: TransactionPool>: Send + Sync {
+	/// Start selecting the transactions of a block built on top of `parent_hash`, out of the
+	/// transactions `ready` at it and the `bundles` of the pool.
+	///
+	/// A bundle is to be returned as a single group, or not at all.
+	fn select(
+		&self,
+		parent_hash: BlockHash<A>,
+		ready: ReadyTransactionsFor<A>,
+		bundles: BundlesFor<A>,
+	) -> Box<dyn Selection<A>>;
+}
+
+/// The selection of the transactions of a single block.
+///
+/// The proposer keeps on asking for groups of transactions until there are none left, or the
+/// block is full or its deadline is reached.
+pub trait Selection<A: TransactionPool> {
+	/// Returns the next group of transactions to try.
+	///
+	/// The transactions of a group are included contiguously, in order, and either all of them or
+	/// none. Returns `None` once there are no more transactions to try.
+	fn next(&mut self) -> Option<Vec<Arc<A::InPoolTransaction>>>;
+
+	/// Report the outcome of trying to include `group`, the group last returned by
+	/// [`Selection::next`].
+	fn report(&mut self, group: &[Arc<A::InPoolTransaction>], outcome: SelectionOutcome);
+}
+
+/// The default [`TransactionSelector`].
+///
+/// Tries the transactions one by one, in the order of the pool, which is by priority. A
+/// transaction which isn't included is reported invalid to the pool's iterator, which then skips
+/// the transactions depending on it. A bundle is tried before the next transaction if its first
+/// transaction has a higher priority.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriorityOrder;
+
+impl<A: TransactionPool + 'static> TransactionSelector<A> for PriorityOrder {
+	fn select(
+		&self,
+		_parent_hash: BlockHash<A>,
+		ready: ReadyTransactionsFor<A>,
+		bundles: BundlesFor<A>,
+	) -> Box<dyn Selection<A>> {
+		Box::new(PrioritySelection::<A> {
+			ready,
+			next_ready: None,
+			bundles: bundles.into_iter().filter(|bundle| !bundle.is_empty()).collect(),
+			bundle: false,
+		})
+	}
+}
+
+struct PrioritySelection<A: TransactionPool> {
+	ready: ReadyTransactionsFor<A>,
+	/// The next ready transaction, taken from `ready` to be compared with the next bundle.
+	next_ready: Option<Arc<A::InPoolTransaction>>,
+	bundles: VecDeque<Vec<Arc<A::InPoolTransaction>>>,
+	/// Whether the group last returned is a bundle.
+	bundle: bool,
+}
+
+impl<A: TransactionPool> Selection<A> for PrioritySelection<A> {
+	fn next(&mut self) -> Option<Vec<Arc<A::InPoolTransaction>>> {
+		if self.next_ready.is_none() {
+			self.next_ready = self.ready.next();
+		}
+
+		self.bundle = match (&self.next_ready, self.bundles.front()) {
+			(Some(tx), Some(bundle)) => bundle[0].priority() > tx.priority(),
+			(None, Some(_)) => true,
+			(_, None) => false,
+		};
+		if self.bundle {
+			self.bundles.pop_front()
+		} else {
+			self.next_ready.take().map(|tx| vec![tx])
+		}
+	}
+
+	fn report(&mut self, group: &[Arc<A::InPoolTransaction>], outcome: SelectionOutcome) {
+		if outcome != SelectionOutcome::Included && !self.bundle {
+			for tx in group {
+				self.ready.report_invalid(tx);
+			}
+		}
+	}
+}
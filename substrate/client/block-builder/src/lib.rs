@@ -270,27 +270,62 @@ where
 		let version = self.version;
 
 		self.api.execute_in_transaction(|api| {
-			let res = if version < 6 {
-				#[allow(deprecated)]
-				api.apply_extrinsic_before_version_6(parent_hash, xt.clone())
-					.map(legacy::byte_sized_error::convert_to_latest)
-			} else {
-				api.apply_extrinsic(parent_hash, xt.clone())
-			};
-
-			match res {
-				Ok(Ok(_)) => {
+			match Self::apply_extrinsic(api, version, parent_hash, xt.clone()) {
+				Ok(()) => {
 					extrinsics.push(xt);
 					TransactionOutcome::Commit(Ok(()))
 				},
-				Ok(Err(tx_validity)) => TransactionOutcome::Rollback(Err(
-					ApplyExtrinsicFailed::Validity(tx_validity).into(),
-				)),
-				Err(e) => TransactionOutcome::Rollback(Err(Error::from(e))),
+				Err(e) => TransactionOutcome::Rollback(Err(e)),
 			}
 		})
 	}
 
+	/// Push a group of extrinsics onto the block's list of extrinsics, either all of them or none.
+	///
+	/// The extrinsics are executed in order. If one of them fails, the changes of the ones before
+	/// it are reverted, and its index within the group is returned along with the error.
+	pub fn push_all(
+		&mut self,
+		xts: Vec<<Block as BlockT>::Extrinsic>,
+	) -> Result<(), (usize, Error)> {
+		let parent_hash = self.parent_hash;
+		let extrinsics = &mut self.extrinsics;
+		let version = self.version;
+
+		self.api.execute_in_transaction(|api| {
+			for (index, xt) in xts.iter().enumerate() {
+				if let Err(e) = Self::apply_extrinsic(api, version, parent_hash, xt.clone()) {
+					return TransactionOutcome::Rollback(Err((index, e)))
+				}
+			}
+
+			extrinsics.extend(xts);
+			TransactionOutcome::Commit(Ok(()))
+		})
+	}
+
+	/// Apply an extrinsic on top of the current state of `api`.
+	fn apply_extrinsic(
+		api: &C::Api,
+		version: u32,
+		parent_hash: Block::Hash,
+		xt: <Block as BlockT>::Extrinsic,
+	) -> Result<(), Error> {
+		let res = if version < 6 {
+			#[allow(deprecated)]
+			api.apply_extrinsic_before_version_6(parent_hash, xt)
+				.map(legacy::byte_sized_error::convert_to_latest)
+		} else {
+			api.apply_extrinsic(parent_hash, xt)
+		};
+
+		match res {
+			Ok(Ok(_)) => Ok(()),
+			Ok(Err(tx_validity)) => Err(ApplyExtrinsicFailed::Validity(tx_validity).into()),
+			Err(e) => Err(Error::from(e)),
+		}
+	}
+
 	/// Consume the builder to build a valid `Block` containing all pushed extrinsics.
 	///
 	/// Returns the build `Block`, the changes to the storage and an optional `StorageProof`
@@ -394,6 +429,40 @@ mod tests {
 			.contains("Database missing expected key"),);
 	}
 
+	#[test]
+	fn failing_extrinsic_rolls_back_whole_group() {
+		let builder = substrate_test_runtime_client::TestClientBuilder::new();
+		let client = builder.build();
+		let genesis_hash = client.info().best_hash;
+
+		let mut block_builder = BlockBuilderBuilder::new(&client)
+			.on_parent_block(genesis_hash)
+			.with_parent_block_number(0)
+			.build()
+			.unwrap();
+
+		let (index, _) = block_builder
+			.push_all(vec![
+				ExtrinsicBuilder::new_read(8).build(),
+				ExtrinsicBuilder::new_read_and_panic(8).build(),
+			])
+			.unwrap_err();
+		assert_eq!(index, 1);
+
+		block_builder
+			.push_all(vec![
+				ExtrinsicBuilder::new_read(4).build(),
+				ExtrinsicBuilder::new_read(8).build(),
+			])
+			.unwrap();
+
+		let block = block_builder.build().unwrap().block;
+		assert_eq!(
+			block.extrinsics,
+			vec![ExtrinsicBuilder::new_read(4).build(), ExtrinsicBuilder::new_read(8).build()],
+		);
+	}
+
 	#[test]
 	fn failing_extrinsic_rolls_back_changes_in_storage_proof() {
 		let builder = substrate_test_runtime_client::TestClientBuilder::new();
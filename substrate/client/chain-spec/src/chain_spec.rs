@@ -23,7 +23,7 @@ use crate::{
 	extension::GetExtension, genesis_config_builder::HostFunctions, ChainType,
 	GenesisConfigBuilderRuntimeCaller as RuntimeCaller, Properties, RuntimeGenesis,
 };
-use sc_network::config::MultiaddrWithPeerId;
+use sc_network::config::{MultiaddrWithPeerId, WarpSyncCheckpoint};
 use sc_telemetry::TelemetryEndpoints;
 use serde::{Deserialize, Serialize};
 use serde_json as json;
@@ -294,6 +294,9 @@ struct ClientSpec<E> {
 	/// chains have the same genesis hash.
 	#[serde(default = "Default::default", skip_serializing_if = "Option::is_none")]
 	fork_id: Option<String>,
+	/// Trusted block warp sync can target without downloading warp proofs.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	warp_sync_checkpoint: Option<WarpSyncCheckpoint>,
 	properties: Option<Properties>,
 	#[serde(flatten)]
 	extensions: E,
@@ -329,6 +332,7 @@ pub struct ChainSpecBuilder<G, E = NoExtension, EHF = ()> {
 	telemetry_endpoints: Option<TelemetryEndpoints>,
 	protocol_id: Option<String>,
 	fork_id: Option<String>,
+	warp_sync_checkpoint: Option<WarpSyncCheckpoint>,
 	properties: Option<Properties>,
 	_genesis: PhantomData<(G, EHF)>,
 }
@@ -347,6 +351,7 @@ impl<G, E, EHF> ChainSpecBuilder<G, E, EHF> {
 			telemetry_endpoints: None,
 			protocol_id: None,
 			fork_id: None,
+			warp_sync_checkpoint: None,
 			properties: None,
 			_genesis: Default::default(),
 		}
@@ -394,6 +399,12 @@ impl<G, E, EHF> ChainSpecBuilder<G, E, EHF> {
 		self
 	}
 
+	/// Sets a trusted block warp sync can target without downloading warp proofs.
+	pub fn with_warp_sync_checkpoint(mut self, checkpoint: WarpSyncCheckpoint) -> Self {
+		self.warp_sync_checkpoint = Some(checkpoint);
+		self
+	}
+
 	/// Sets additional loosely-typed properties of the chain.
 	pub fn with_properties(mut self, properties: Properties) -> Self {
 		self.properties = Some(properties);
@@ -434,6 +445,7 @@ impl<G, E, EHF> ChainSpecBuilder<G, E, EHF> {
 			telemetry_endpoints: self.telemetry_endpoints,
 			protocol_id: self.protocol_id,
 			fork_id: self.fork_id,
+			warp_sync_checkpoint: self.warp_sync_checkpoint,
 			properties: self.properties,
 			extensions: self.extensions,
 			consensus_engine: (),
@@ -501,6 +513,11 @@ impl<G, E, EHF> ChainSpec<G, E, EHF> {
 		self.client_spec.fork_id.as_deref()
 	}
 
+	/// Trusted block warp sync can target without downloading warp proofs, if any.
+	pub fn warp_sync_checkpoint(&self) -> Option<&WarpSyncCheckpoint> {
+		self.client_spec.warp_sync_checkpoint.as_ref()
+	}
+
 	/// Additional loosly-typed properties of the chain.
 	///
 	/// Returns an empty JSON object if 'properties' not defined in config
@@ -549,6 +566,7 @@ impl<G, E, EHF> ChainSpec<G, E, EHF> {
 			telemetry_endpoints,
 			protocol_id: protocol_id.map(str::to_owned),
 			fork_id: fork_id.map(str::to_owned),
+			warp_sync_checkpoint: None,
 			properties,
 			extensions,
 			consensus_engine: (),
@@ -710,6 +728,10 @@ where
 		ChainSpec::fork_id(self)
 	}
 
+	fn warp_sync_checkpoint(&self) -> Option<&WarpSyncCheckpoint> {
+		ChainSpec::warp_sync_checkpoint(self)
+	}
+
 	fn properties(&self) -> Properties {
 		ChainSpec::properties(self)
 	}
@@ -857,6 +879,22 @@ mod tests {
 		assert_eq!(spec2.chain_type(), ChainType::Live)
 	}
 
+	#[test]
+	fn should_deserialize_chain_spec_with_warp_sync_checkpoint() {
+		let mut json: Value = json::from_slice(include_bytes!("../res/chain_spec.json")).unwrap();
+		json["warpSyncCheckpoint"] = json!({ "hash": "0x0102", "header": "0x0304" });
+		let spec = TestSpec::from_json_bytes(json::to_vec(&json).unwrap()).unwrap();
+
+		let checkpoint = WarpSyncCheckpoint {
+			hash: vec![1, 2].into(),
+			header: vec![3, 4].into(),
+			justifications: None,
+		};
+		assert_eq!(spec.warp_sync_checkpoint(), Some(&checkpoint));
+		let json: Value = from_str(&spec.as_json(false).unwrap()).unwrap();
+		assert_eq!(json["warpSyncCheckpoint"], json!({ "hash": "0x0102", "header": "0x0304" }));
+	}
+
 	#[derive(Debug, Serialize, Deserialize, Clone)]
 	#[serde(rename_all = "camelCase")]
 	struct Extension1 {
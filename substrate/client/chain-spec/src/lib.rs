@@ -343,7 +343,7 @@ pub use self::{
 };
 pub use sc_chain_spec_derive::{ChainSpecExtension, ChainSpecGroup};
 
-use sc_network::config::MultiaddrWithPeerId;
+use sc_network::config::{MultiaddrWithPeerId, WarpSyncCheckpoint};
 use sc_telemetry::TelemetryEndpoints;
 use serde::{de::DeserializeOwned, Serialize};
 use sp_core::storage::Storage;
@@ -394,6 +394,11 @@ pub trait ChainSpec: BuildStorage + Send + Sync {
 	fn protocol_id(&self) -> Option<&str>;
 	/// Optional network fork identifier. `None` by default.
 	fn fork_id(&self) -> Option<&str>;
+	/// Trusted block warp sync can target without downloading warp proofs, if the chain has no warp
+	/// sync provider. `None` by default.
+	fn warp_sync_checkpoint(&self) -> Option<&WarpSyncCheckpoint> {
+		None
+	}
 	/// Additional loosly-typed properties of the chain.
 	///
 	/// Returns an empty JSON object if 'properties' not defined in config
@@ -237,6 +237,25 @@ pub enum OffchainWorkerEnabled {
 	WhenAuthority,
 }
 
+/// Which transactions the transaction pool keeps in its journal.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+#[value(rename_all = "kebab-case")]
+pub enum PoolJournalScope {
+	/// Only the transactions submitted to this node.
+	Local,
+	/// All the transactions of the pool.
+	All,
+}
+
+impl Into<sc_service::config::TransactionPoolJournalScope> for PoolJournalScope {
+	fn into(self) -> sc_service::config::TransactionPoolJournalScope {
+		match self {
+			PoolJournalScope::Local => sc_service::config::TransactionPoolJournalScope::Local,
+			PoolJournalScope::All => sc_service::config::TransactionPoolJournalScope::All,
+		}
+	}
+}
+
 /// Syncing mode.
 #[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
 #[value(rename_all = "kebab-case")]
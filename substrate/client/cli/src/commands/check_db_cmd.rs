@@ -0,0 +1,94 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{CliConfiguration, DatabaseParams, PruningParams, Result as CliResult, SharedParams};
+use sp_runtime::traits::Block as BlockT;
+
+/// The `check-db` command used to check the consistency of the database.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct CheckDbCmd {
+	/// Repair the recoverable inconsistencies.
+	///
+	/// The leaves are rebuilt from the headers, and an invalid chain is truncated to its last
+	/// consistent finalized block.
+	#[arg(long)]
+	pub repair: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl CheckDbCmd {
+	/// Run the `check-db` command
+	pub fn run<B>(&self, config: &sc_service::Configuration) -> CliResult<()>
+	where
+		B: BlockT,
+	{
+		let db_config = sc_client_db::DatabaseSettings {
+			trie_cache_maximum_size: config.trie_cache_maximum_size,
+			state_pruning: config.state_pruning.clone(),
+			source: config.database.clone(),
+			blocks_pruning: config.blocks_pruning,
+			compression: config.database_compression.clone(),
+			cold_storage: config.cold_database.clone(),
+		};
+		let backend = sc_service::new_db_backend::<B>(db_config)?;
+		let report = backend.check_consistency(self.repair)?;
+		for inconsistency in &report.inconsistencies {
+			println!("{}", inconsistency);
+		}
+		for repair in &report.repairs {
+			println!("Repair: {}", repair);
+		}
+
+		let remaining = if report.repairs.is_empty() {
+			report.inconsistencies.len()
+		} else {
+			backend.check_consistency(false)?.inconsistencies.len()
+		};
+		if remaining != 0 {
+			return Err(format!("The database has {} inconsistencies", remaining).into())
+		}
+		println!("The database is consistent");
+		Ok(())
+	}
+}
+
+impl CliConfiguration for CheckDbCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
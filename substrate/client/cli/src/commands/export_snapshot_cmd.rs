@@ -0,0 +1,83 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{DatabaseParams, PruningParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use log::info;
+use sc_service::{chain_ops::export_snapshot, TFullBackend};
+use sp_runtime::traits::Block as BlockT;
+use std::{fs, io, path::PathBuf, sync::Arc};
+
+/// The `export-snapshot` command used to take a snapshot of the database at the last finalized
+/// block.
+///
+/// The command opens the database, so the node must be stopped. The snapshots of a running node
+/// are taken with the `snapshot_export` RPC instead.
+#[derive(Debug, Clone, Parser)]
+pub struct ExportSnapshotCmd {
+	/// Output file name.
+	#[arg()]
+	pub output: PathBuf,
+
+	/// Previous snapshots to take an incremental snapshot against, starting with the full
+	/// snapshot followed by the incremental snapshots in order.
+	#[arg(long, value_name = "FILE", num_args = 1..)]
+	pub base: Vec<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ExportSnapshotCmd {
+	/// Run the export-snapshot command
+	pub async fn run<B: BlockT>(&self, backend: Arc<TFullBackend<B>>) -> error::Result<()> {
+		let bases = self.base.iter().map(fs::File::open).collect::<io::Result<Vec<_>>>()?;
+		info!("Exporting snapshot to {}", self.output.display());
+		let output = io::BufWriter::new(fs::File::create(&self.output)?);
+
+		export_snapshot(backend, output, bases).map_err(Into::into)
+	}
+}
+
+impl CliConfiguration for ExportSnapshotCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -0,0 +1,88 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{DatabaseParams, PruningParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use sc_client_api::{BlockBackend, HeaderBackend, ProofProvider};
+use sc_service::{chain_ops::export_state_snapshot, EncodedProof};
+use sp_runtime::traits::Block as BlockT;
+use std::{fs, io, path::PathBuf, sync::Arc};
+
+/// The `export-state-snapshot` command used to export a verifiable snapshot of the state of the
+/// latest block with a finality proof.
+#[derive(Debug, Clone, Parser)]
+pub struct ExportStateSnapshotCmd {
+	/// Output file name or stdout if unspecified.
+	#[arg()]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ExportStateSnapshotCmd {
+	/// Run the export-state-snapshot command
+	///
+	/// The snapshot is of the block `header`, whose finality is proven by the warp sync proofs
+	/// `finality_proof` starting at genesis, such as the latest block with a justification.
+	pub async fn run<B, C>(
+		&self,
+		client: Arc<C>,
+		finality_proof: Vec<EncodedProof>,
+		header: B::Header,
+	) -> error::Result<()>
+	where
+		B: BlockT,
+		C: HeaderBackend<B> + BlockBackend<B> + ProofProvider<B>,
+	{
+		let file: Box<dyn io::Write> = match &self.output {
+			Some(filename) => Box::new(io::BufWriter::new(fs::File::create(filename)?)),
+			None => Box::new(io::stdout()),
+		};
+
+		export_state_snapshot(client, finality_proof, header, file).map_err(Into::into)
+	}
+}
+
+impl CliConfiguration for ExportStateSnapshotCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
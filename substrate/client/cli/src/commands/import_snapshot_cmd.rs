@@ -0,0 +1,79 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{DatabaseParams, PruningParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use sc_executor::RuntimeVersionOf;
+use sc_service::{chain_ops::import_snapshot, TFullBackend};
+use sp_runtime::traits::Block as BlockT;
+use std::{fs, io, path::PathBuf, sync::Arc};
+
+/// The `import-snapshot` command used to restore the database from snapshots.
+#[derive(Debug, Clone, Parser)]
+pub struct ImportSnapshotCmd {
+	/// Snapshots to restore, starting with a full snapshot followed by the incremental snapshots
+	/// based on it in order.
+	#[arg(required = true)]
+	pub input: Vec<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl ImportSnapshotCmd {
+	/// Run the import-snapshot command
+	///
+	/// The backend must be opened on an empty database, without its genesis block written.
+	pub fn run<B, E>(&self, backend: Arc<TFullBackend<B>>, executor: &E) -> error::Result<()>
+	where
+		B: BlockT,
+		E: RuntimeVersionOf,
+	{
+		let inputs = self.input.iter().map(fs::File::open).collect::<io::Result<Vec<_>>>()?;
+
+		import_snapshot(backend, executor, inputs).map_err(Into::into)
+	}
+}
+
+impl CliConfiguration for ImportSnapshotCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -0,0 +1,107 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{ImportParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use sc_client_api::{HeaderBackend, ProofProvider};
+use sc_service::{chain_ops::import_state_snapshot, WarpSyncProvider};
+use sp_runtime::traits::Block as BlockT;
+use std::{
+	fmt::Debug,
+	fs,
+	io::{self, Read},
+	path::PathBuf,
+	str::FromStr,
+	sync::Arc,
+};
+
+/// The `import-state-snapshot` command used to bootstrap a new database from a state snapshot.
+#[derive(Debug, Parser)]
+pub struct ImportStateSnapshotCmd {
+	/// Input file or stdin if unspecified.
+	#[arg()]
+	pub input: Option<PathBuf>,
+
+	/// Hash of the block the snapshot must be of.
+	///
+	/// The finality of the block is proven by the snapshot regardless.
+	#[arg(long, value_name = "HASH")]
+	pub hash: Option<String>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl ImportStateSnapshotCmd {
+	/// Run the import-state-snapshot command
+	///
+	/// The client must be opened on a new database without the genesis state written, as done for
+	/// warp sync. The finality of the block of the snapshot is verified by `warp_sync_provider`,
+	/// starting from the genesis.
+	pub async fn run<B, C, IQ>(
+		&self,
+		client: Arc<C>,
+		import_queue: IQ,
+		warp_sync_provider: Arc<dyn WarpSyncProvider<B>>,
+	) -> error::Result<()>
+	where
+		B: BlockT,
+		C: HeaderBackend<B> + ProofProvider<B> + Send + Sync + 'static,
+		IQ: sc_service::ImportQueue<B> + 'static,
+		<B::Hash as FromStr>::Err: Debug,
+	{
+		let hash = self
+			.hash
+			.as_ref()
+			.map(|hash| {
+				B::Hash::from_str(hash.strip_prefix("0x").unwrap_or(hash)).map_err(|e| {
+					error::Error::Input(format!("Failed to parse block hash: {:?}", e))
+				})
+			})
+			.transpose()?;
+
+		let file: Box<dyn Read + Send> = match &self.input {
+			Some(filename) => Box::new(fs::File::open(filename)?),
+			None => Box::new(io::stdin()),
+		};
+
+		import_state_snapshot(client, import_queue, warp_sync_provider, file, hash)
+			.await
+			.map_err(Into::into)
+	}
+}
+
+impl CliConfiguration for ImportStateSnapshotCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
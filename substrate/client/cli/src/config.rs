@@ -27,9 +27,10 @@ use log::warn;
 use names::{Generator, Name};
 use sc_service::{
 	config::{
-		BasePath, Configuration, DatabaseSource, KeystoreConfig, NetworkConfiguration,
-		NodeKeyConfig, OffchainWorkerConfig, OutputFormat, PrometheusConfig, PruningMode, Role,
-		RpcMethods, TelemetryEndpoints, TransactionPoolOptions, WasmExecutionMethod,
+		BasePath, ColdStorageSettings, CompressionSettings, Configuration, DatabaseSource,
+		KeystoreConfig, NetworkConfiguration, NodeKeyConfig, OffchainWorkerConfig, OutputFormat,
+		PrometheusConfig, PruningMode, Role, RpcMethods, StateLeaseSettings, TelemetryEndpoints,
+		TransactionPoolOptions, WasmExecutionMethod,
 	},
 	BlocksPruning, ChainSpec, TracingReceiver,
 };
@@ -230,6 +231,61 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		})
 	}
 
+	/// Get the compression of the block bodies and justifications.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise the blocks
+	/// aren't compressed.
+	fn database_compression(&self) -> Result<CompressionSettings> {
+		Ok(self.database_params().map(|x| x.compression()).unwrap_or_default())
+	}
+
+	/// Get the cold database configuration object for the parameters provided, if any.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its `None`.
+	fn cold_database(
+		&self,
+		cache_size: usize,
+		database: Database,
+	) -> Result<Option<ColdStorageSettings>> {
+		let Some((path, after)) = self.database_params().and_then(|x| x.cold_database()) else {
+			return Ok(None)
+		};
+		let source = self.database_config(&path, cache_size, database)?;
+		Ok(Some(ColdStorageSettings { source, after }))
+	}
+
+	/// Whether to index the extrinsics of the imported blocks by signer and by call.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its
+	/// `false`.
+	fn extrinsic_index(&self) -> Result<bool> {
+		Ok(self.database_params().map(|x| x.extrinsic_index()).unwrap_or_default())
+	}
+
+	/// Whether to record the storage changes of the imported blocks.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its
+	/// `false`.
+	fn storage_diffs(&self) -> Result<bool> {
+		Ok(self.database_params().map(|x| x.storage_diffs()).unwrap_or_default())
+	}
+
+	/// Whether to index the history of the storage keys changed by the imported blocks.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its
+	/// `false`.
+	fn key_history(&self) -> Result<bool> {
+		Ok(self.database_params().map(|x| x.key_history()).unwrap_or_default())
+	}
+
+	/// Get the settings of the state leases, if enabled.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise the state
+	/// leases are disabled.
+	fn state_leases(&self) -> Result<Option<StateLeaseSettings>> {
+		Ok(self.database_params().and_then(|x| x.state_leases()))
+	}
+
 	/// Get the trie cache maximum size.
 	///
 	/// By default this is retrieved from `ImportParams` if it is available. Otherwise its `0`.
@@ -470,12 +526,17 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		let keystore = self.keystore_config(&config_dir)?;
 		let telemetry_endpoints = self.telemetry_endpoints(&chain_spec)?;
 		let runtime_cache_size = self.runtime_cache_size()?;
+		let mut transaction_pool = self.transaction_pool(is_dev)?;
+		if let Some(journal) = &mut transaction_pool.journal {
+			// A relative journal path is relative to the chain's configuration directory.
+			journal.path = config_dir.join(&journal.path);
+		}
 
 		Ok(Configuration {
 			impl_name: C::impl_name(),
 			impl_version: C::impl_version(),
 			tokio_handle,
-			transaction_pool: self.transaction_pool(is_dev)?,
+			transaction_pool,
 			network: self.network_config(
 				&chain_spec,
 				is_dev,
@@ -488,6 +549,12 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			)?,
 			keystore,
 			database: self.database_config(&config_dir, database_cache_size, database)?,
+			database_compression: self.database_compression()?,
+			cold_database: self.cold_database(database_cache_size, database)?,
+			extrinsic_index: self.extrinsic_index()?,
+			storage_diffs: self.storage_diffs()?,
+			key_history: self.key_history()?,
+			state_leases: self.state_leases()?,
 			data_path: config_dir,
 			trie_cache_maximum_size: self.trie_cache_maximum_size()?,
 			state_pruning: self.state_pruning()?,
@@ -19,6 +19,8 @@
 
 use crate::arg_enums::Database;
 use clap::Args;
+use sc_service::config::{CompressionSettings, StateLeaseSettings};
+use std::{path::PathBuf, time::Duration};
 
 /// Parameters for database
 #[derive(Debug, Clone, PartialEq, Args)]
@@ -30,6 +32,66 @@ pub struct DatabaseParams {
 	/// Limit the memory the database cache can use.
 	#[arg(long = "db-cache", value_name = "MiB")]
 	pub database_cache_size: Option<usize>,
+
+	/// Compress the block bodies written to the database with zstd, at the given level.
+	#[arg(long, value_name = "LEVEL")]
+	pub db_body_compression: Option<i32>,
+
+	/// Compress the justifications written to the database with zstd, at the given level.
+	#[arg(long, value_name = "LEVEL")]
+	pub db_justifications_compression: Option<i32>,
+
+	/// Compress the block bodies with a dictionary of this size, trained on the extrinsics of
+	/// the blocks imported next.
+	#[arg(long, value_name = "BYTES", requires = "db_body_compression")]
+	pub db_compression_dictionary: Option<usize>,
+
+	/// Move the bodies and justifications of the old finalized blocks to a database at this
+	/// path.
+	#[arg(long, value_name = "PATH")]
+	pub db_cold_path: Option<PathBuf>,
+
+	/// Move the blocks to the cold database once finalized for this many blocks.
+	#[arg(long, value_name = "COUNT", default_value_t = 100_000, requires = "db_cold_path")]
+	pub db_cold_after: u32,
+
+	/// Index the extrinsics of the blocks imported from now on by signer and by call, for the
+	/// `archive_unstable_extrinsicsBySigner` and `archive_unstable_extrinsicsByCall` RPCs.
+	///
+	/// The indexes start over whenever blocks are imported without this flag.
+	#[arg(long)]
+	pub db_extrinsic_index: bool,
+
+	/// Record the storage changes of the blocks imported from now on, for the
+	/// `archive_unstable_storageDiff` and `archive_unstable_storageDiffs` RPCs.
+	///
+	/// The record starts over whenever blocks are imported without this flag.
+	#[arg(long)]
+	pub db_storage_diffs: bool,
+
+	/// Index the history of the storage keys changed by the blocks imported from now on, for the
+	/// `archive_unstable_storageHistory` RPC.
+	///
+	/// The index starts over whenever blocks are imported without this flag.
+	#[arg(long)]
+	pub db_key_history: bool,
+
+	/// Allow the RPC clients to lease the state of blocks, which keeps it from being pruned.
+	#[arg(long)]
+	pub db_state_leases: bool,
+
+	/// The longest duration a state lease can be acquired or renewed for, in seconds.
+	#[arg(long, value_name = "SECONDS", default_value_t = 86_400, requires = "db_state_leases")]
+	pub db_state_lease_max_duration: u64,
+
+	/// The maximum number of state leases of a client.
+	#[arg(long, value_name = "COUNT", default_value_t = 16, requires = "db_state_leases")]
+	pub db_state_lease_max_per_owner: usize,
+
+	/// Revoke the oldest state leases once the state they retain is estimated to exceed this
+	/// size.
+	#[arg(long, value_name = "MiB", requires = "db_state_leases")]
+	pub db_state_lease_disk_budget: Option<u64>,
 }
 
 impl DatabaseParams {
@@ -42,4 +104,42 @@ impl DatabaseParams {
 	pub fn database_cache_size(&self) -> Option<usize> {
 		self.database_cache_size
 	}
+
+	/// Compression of the block bodies and justifications.
+	pub fn compression(&self) -> CompressionSettings {
+		CompressionSettings {
+			body: self.db_body_compression,
+			justifications: self.db_justifications_compression,
+			dictionary_size: self.db_compression_dictionary,
+		}
+	}
+
+	/// Whether to index the extrinsics of the imported blocks.
+	pub fn extrinsic_index(&self) -> bool {
+		self.db_extrinsic_index
+	}
+
+	/// Whether to record the storage changes of the imported blocks.
+	pub fn storage_diffs(&self) -> bool {
+		self.db_storage_diffs
+	}
+
+	/// Whether to index the history of the storage keys.
+	pub fn key_history(&self) -> bool {
+		self.db_key_history
+	}
+
+	/// The settings of the state leases, if enabled.
+	pub fn state_leases(&self) -> Option<StateLeaseSettings> {
+		self.db_state_leases.then(|| StateLeaseSettings {
+			max_duration: Duration::from_secs(self.db_state_lease_max_duration),
+			max_leases_per_owner: self.db_state_lease_max_per_owner,
+			disk_budget: self.db_state_lease_disk_budget.map(|budget| budget * 1024 * 1024),
+		})
+	}
+
+	/// Path of the cold database, and after how many blocks the finalized blocks are moved there.
+	pub fn cold_database(&self) -> Option<(PathBuf, u32)> {
+		self.db_cold_path.clone().map(|path| (path, self.db_cold_after))
+	}
 }
@@ -21,7 +21,8 @@ use crate::{arg_enums::SyncMode, params::node_key_params::NodeKeyParams};
 use clap::Args;
 use sc_network::{
 	config::{
-		NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SetConfig, TransportConfig,
+		GapSyncConfig, NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SetConfig,
+		TransportConfig, WarpSyncCheckpoint,
 	},
 	multiaddr::Protocol,
 };
@@ -29,7 +30,12 @@ use sc_service::{
 	config::{Multiaddr, MultiaddrWithPeerId},
 	ChainSpec, ChainType,
 };
-use std::{borrow::Cow, num::NonZeroUsize, path::PathBuf};
+use std::{borrow::Cow, fs::File, num::NonZeroUsize, path::PathBuf};
+
+fn parse_warp_sync_checkpoint(path: &str) -> Result<WarpSyncCheckpoint, String> {
+	let file = File::open(path).map_err(|e| format!("Error opening warp sync checkpoint: {e}"))?;
+	serde_json::from_reader(file).map_err(|e| format!("Error parsing warp sync checkpoint: {e}"))
+}
 
 /// Parameters used to create the network configuration.
 #[derive(Debug, Clone, Args)]
@@ -163,6 +169,39 @@ pub struct NetworkParams {
 	/// and observe block requests timing out.
 	#[arg(long, value_name = "COUNT", default_value_t = 64)]
 	pub max_blocks_per_request: u32,
+
+	/// Only download the block history missing after warp or state sync once requested through
+	/// the `system_startGapSync` RPC.
+	#[arg(long)]
+	pub gap_sync_on_demand: bool,
+
+	/// Only download the blocks of the block history missing after warp or state sync within the
+	/// given number of blocks of the best block.
+	///
+	/// The older blocks are never downloaded.
+	#[arg(long, value_name = "COUNT")]
+	pub gap_sync_depth: Option<u32>,
+
+	/// Maximum bandwidth used to download the block history missing after warp or state sync, in
+	/// KiB per second.
+	#[arg(long, value_name = "KIB_PER_SECOND")]
+	pub gap_sync_max_bandwidth: Option<u64>,
+
+	/// Warp sync to the trusted block of the given checkpoint file, instead of the block proved
+	/// by finality proofs downloaded from peers.
+	///
+	/// The file is a JSON object with the hex-encoded `hash`, SCALE-encoded `header` and
+	/// optional SCALE-encoded `justifications` of the block. It overrides the warp sync provider
+	/// of the chain, as well as the checkpoint of the chain spec, if any.
+	#[arg(long, value_name = "PATH", value_parser = parse_warp_sync_checkpoint)]
+	pub warp_sync_checkpoint: Option<WarpSyncCheckpoint>,
+
+	/// Send the transactions of the pool to the given trusted block authoring peers only,
+	/// instead of gossiping them.
+	///
+	/// Enables the private transactions protocol. May be passed multiple times.
+	#[arg(long, value_name = "ADDR")]
+	pub private_transactions_peer: Vec<MultiaddrWithPeerId>,
 }
 
 impl NetworkParams {
@@ -257,6 +296,15 @@ impl NetworkParams {
 			kademlia_replication_factor: self.kademlia_replication_factor,
 			yamux_window_size: None,
 			sync_mode: self.sync.into(),
+			gap_sync: GapSyncConfig {
+				on_demand: self.gap_sync_on_demand,
+				depth: self.gap_sync_depth,
+				max_bytes_per_second: self
+					.gap_sync_max_bandwidth
+					.map(|kib| kib.saturating_mul(1024)),
+			},
+			warp_sync_checkpoint: self.warp_sync_checkpoint.clone(),
+			private_transactions_peers: self.private_transactions_peer.clone(),
 		}
 	}
 }
@@ -55,6 +55,19 @@ pub struct PruningParams {
 		default_value = "archive-canonical"
 	)]
 	pub blocks_pruning: DatabasePruningMode,
+
+	/// Keep the finalized blocks which are at most this many seconds old.
+	///
+	/// Overrides `--blocks-pruning`. The age of a block is derived from its timestamp, and a node
+	/// which can't read the timestamp of its blocks refuses to start with this option.
+	#[arg(long, value_name = "SECONDS", conflicts_with = "blocks_pruning_max_size")]
+	pub blocks_pruning_max_age: Option<u64>,
+
+	/// Keep the most recent finalized blocks whose bodies take at most this many MiB.
+	///
+	/// Overrides `--blocks-pruning`.
+	#[arg(long, value_name = "MIB")]
+	pub blocks_pruning_max_size: Option<u64>,
 }
 
 impl PruningParams {
@@ -65,6 +78,12 @@ impl PruningParams {
 
 	/// Get the block pruning value from the parameters
 	pub fn blocks_pruning(&self) -> error::Result<BlocksPruning> {
+		if let Some(max_age) = self.blocks_pruning_max_age {
+			return Ok(BlocksPruning::MaxAge(std::time::Duration::from_secs(max_age)))
+		}
+		if let Some(max_size) = self.blocks_pruning_max_size {
+			return Ok(BlocksPruning::MaxBodySize(max_size.saturating_mul(1024 * 1024)))
+		}
 		Ok(self.blocks_pruning.into())
 	}
 }
@@ -17,8 +17,12 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::arg_enums::PoolJournalScope;
 use clap::Args;
-use sc_service::config::TransactionPoolOptions;
+use sc_service::config::{TransactionPoolJournalOptions, TransactionPoolOptions};
+
+/// Path of the transaction pool journal, relative to the chain's configuration directory.
+pub const DEFAULT_POOL_JOURNAL_PATH: &str = "txpool/journal";
 
 /// Parameters used to create the pool configuration.
 #[derive(Debug, Clone, Args)]
@@ -36,6 +40,14 @@ pub struct TransactionPoolParams {
 	/// If it is considered invalid. Defaults to 1800s.
 	#[arg(long, value_name = "SECONDS")]
 	pub tx_ban_seconds: Option<u64>,
+
+	/// Keep the transactions of the pool in a journal on disk, to resubmit them after a restart.
+	#[arg(long, value_name = "SCOPE", value_enum)]
+	pub pool_journal: Option<PoolJournalScope>,
+
+	/// Maximum number of kilobytes of the transactions kept in the journal.
+	#[arg(long, value_name = "COUNT", default_value_t = 20480, requires = "pool_journal")]
+	pub pool_journal_kbytes: usize,
 }
 
 impl TransactionPoolParams {
@@ -60,6 +72,14 @@ impl TransactionPoolParams {
 			std::time::Duration::from_secs(30 * 60)
 		};
 
+		opts.journal = self.pool_journal.map(|scope| {
+			let mut journal =
+				TransactionPoolJournalOptions::new(DEFAULT_POOL_JOURNAL_PATH.into(), scope.into());
+			journal.limit.count = self.pool_limit;
+			journal.limit.total_bytes = self.pool_journal_kbytes * 1024;
+			journal
+		});
+
 		opts
 	}
 }
@@ -249,6 +249,12 @@ mod tests {
 				network: NetworkConfiguration::new_memory(),
 				keystore: sc_service::config::KeystoreConfig::InMemory,
 				database: sc_client_db::DatabaseSource::ParityDb { path: root.clone() },
+				database_compression: Default::default(),
+				cold_database: None,
+				extrinsic_index: false,
+				storage_diffs: false,
+				key_history: false,
+				state_leases: None,
 				trie_cache_maximum_size: None,
 				state_pruning: None,
 				blocks_pruning: sc_client_db::BlocksPruning::KeepAll,
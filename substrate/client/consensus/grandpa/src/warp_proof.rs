@@ -24,7 +24,9 @@ use crate::{
 	BlockNumberOps, GrandpaJustification, SharedAuthoritySet,
 };
 use sc_client_api::Backend as ClientBackend;
-use sc_network_sync::strategy::warp::{EncodedProof, VerificationResult, WarpSyncProvider};
+use sc_network_sync::strategy::warp::{
+	EncodedProof, EncodedVerifierState, VerificationResult, WarpSyncProvider,
+};
 use sp_blockchain::{Backend as BlockchainBackend, HeaderBackend};
 use sp_consensus_grandpa::{AuthorityList, SetId, GRANDPA_ENGINE_ID};
 use sp_runtime::{
@@ -267,6 +269,39 @@ where
 	}
 }
 
+impl<Block: BlockT, Backend: ClientBackend<Block>> NetworkProvider<Block, Backend>
+where
+	NumberFor<Block>: BlockNumberOps,
+{
+	/// Generate the warp sync proofs starting at `begin`, up to the latest block with a
+	/// justification, returning the header of that block.
+	///
+	/// Unlike [`WarpSyncProvider::generate`], the proofs are generated until they're finished, as
+	/// many proofs as a warp sync would request.
+	pub fn generate_finality_proof(
+		&self,
+		begin: Block::Hash,
+	) -> Result<(Vec<EncodedProof>, Block::Header), Error> {
+		let set_changes = self.authority_set.authority_set_changes();
+		let mut begin = begin;
+		let mut proofs = Vec::new();
+		loop {
+			let proof = WarpSyncProof::<Block>::generate(&*self.backend, begin, &set_changes)?;
+			let last_header = proof
+				.proofs
+				.last()
+				.map(|fragment| fragment.header.clone())
+				.ok_or(Error::MissingData)?;
+			let is_finished = proof.is_finished;
+			proofs.push(EncodedProof(proof.encode()));
+			if is_finished {
+				return Ok((proofs, last_header))
+			}
+			begin = last_header.hash();
+		}
+	}
+}
+
 impl<Block: BlockT, Backend: ClientBackend<Block>> WarpSyncProvider<Block>
 	for NetworkProvider<Block, Backend>
 where
@@ -288,9 +323,12 @@ where
 	fn verify(
 		&self,
 		proof: &EncodedProof,
-		set_id: SetId,
-		authorities: AuthorityList,
+		verifier_state: &EncodedVerifierState,
 	) -> Result<VerificationResult<Block>, Box<dyn std::error::Error + Send + Sync>> {
+		let EncodedVerifierState(verifier_state) = verifier_state;
+		let (set_id, authorities) =
+			<(SetId, AuthorityList)>::decode_all(&mut verifier_state.as_slice())
+				.map_err(|e| format!("Verifier state decoding error: {:?}", e))?;
 		let EncodedProof(proof) = proof;
 		let proof = WarpSyncProof::<Block>::decode_all(&mut proof.as_slice())
 			.map_err(|e| format!("Proof decoding error: {:?}", e))?;
@@ -302,18 +340,19 @@ where
 		let (next_set_id, next_authorities) =
 			proof.verify(set_id, authorities, &self.hard_forks).map_err(Box::new)?;
 		if proof.is_finished {
-			Ok(VerificationResult::<Block>::Complete(next_set_id, next_authorities, last_header))
+			Ok(VerificationResult::<Block>::Complete(last_header))
 		} else {
 			Ok(VerificationResult::<Block>::Partial(
-				next_set_id,
-				next_authorities,
+				EncodedVerifierState((next_set_id, next_authorities).encode()),
 				last_header.hash(),
 			))
 		}
 	}
 
-	fn current_authorities(&self) -> AuthorityList {
-		self.authority_set.inner().current_authorities.clone()
+	fn initial_verifier_state(&self) -> EncodedVerifierState {
+		// Proofs are verified from the genesis, whose authority set has id 0.
+		let set_id: SetId = 0;
+		EncodedVerifierState((set_id, &self.authority_set.inner().current_authorities).encode())
 	}
 }
 
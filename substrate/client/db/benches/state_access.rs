@@ -123,6 +123,8 @@ fn create_backend(config: BenchmarkConfig, temp_dir: &TempDir) -> Backend<Block>
 		state_pruning: Some(PruningMode::ArchiveAll),
 		source: DatabaseSource::ParityDb { path },
 		blocks_pruning: BlocksPruning::KeepAll,
+		compression: Default::default(),
+		cold_storage: None,
 	};
 
 	Backend::new(settings, 100).expect("Creates backend")
@@ -0,0 +1,825 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Consistency checks of the database, and the repair of the inconsistencies left behind by a
+//! crash during a commit.
+//!
+//! The checks cover the blocks referenced by the `meta` column, the canonical chain, the leaves,
+//! the state-db journals and the state retained for the blocks of the pruning window, the
+//! non-canonical blocks and the leaves. The state of the finalized block and of the leaves is
+//! iterated entirely, for the other blocks only the presence of the state root is checked.
+//!
+//! The database is read directly, so the backend must not be used concurrently.
+
+use std::{
+	collections::{HashMap, HashSet, VecDeque},
+	fmt,
+};
+
+use codec::Decode;
+use log::{info, warn};
+use sc_client_api::leaves::LeafSet;
+use sc_state_db::IsPruned;
+use sp_blockchain::{Error as ClientError, HeaderMetadata, Result as ClientResult};
+use sp_core::storage::{well_known_keys, ChildInfo};
+use sp_database::Transaction;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, One, SaturatedConversion, Zero},
+};
+use sp_state_machine::{Backend as StateBackend, IterArgs};
+
+use crate::{
+	apply_state_commit, children, columns,
+	utils::{self, meta_keys},
+	Backend, DbHash, DbStateBuilder, PruningMode,
+};
+
+/// How often the progress of the canonical chain check is logged.
+const PROGRESS_INTERVAL: u64 = 100_000;
+
+/// An inconsistency found in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency<Block: BlockT> {
+	/// A block referenced by the database has no header.
+	UnknownBlock {
+		/// What references the block.
+		reference: &'static str,
+		/// The hash of the block.
+		hash: Block::Hash,
+	},
+	/// A block referenced by the `meta` column isn't the canonical block at its number.
+	NonCanonicalMetaBlock {
+		/// What references the block.
+		reference: &'static str,
+		/// The hash of the block.
+		hash: Block::Hash,
+		/// The number of the block.
+		number: NumberFor<Block>,
+	},
+	/// The best block is behind the finalized block.
+	BestBelowFinalized {
+		/// The number of the best block.
+		best: NumberFor<Block>,
+		/// The number of the finalized block.
+		finalized: NumberFor<Block>,
+	},
+	/// No canonical block is recorded at a number outside of the block gap.
+	MissingCanonicalBlock {
+		/// The number of the block.
+		number: NumberFor<Block>,
+	},
+	/// The canonical block recorded at a number has no matching header.
+	InvalidCanonicalBlock {
+		/// The number of the block.
+		number: NumberFor<Block>,
+		/// The hash of the block.
+		hash: Block::Hash,
+	},
+	/// The canonical block at a number isn't a child of the canonical block before it.
+	BrokenCanonicalChain {
+		/// The number of the block.
+		number: NumberFor<Block>,
+		/// The hash of the block.
+		hash: Block::Hash,
+		/// The parent hash of the block.
+		parent_hash: Block::Hash,
+	},
+	/// A canonical block is recorded above the best and the finalized block.
+	CanonicalAboveBest {
+		/// The number of the block.
+		number: NumberFor<Block>,
+		/// The hash of the block.
+		hash: Block::Hash,
+	},
+	/// A leaf has children, or is below the finalized block.
+	InvalidLeaf {
+		/// The hash of the leaf.
+		hash: Block::Hash,
+		/// The number of the leaf.
+		number: NumberFor<Block>,
+	},
+	/// A descendant of the finalized block without children isn't a leaf.
+	MissingLeaf {
+		/// The hash of the block.
+		hash: Block::Hash,
+		/// The number of the block.
+		number: NumberFor<Block>,
+	},
+	/// The last block canonicalized by the state-db isn't the canonical block at its number.
+	InvalidLastCanonicalized {
+		/// The hash of the block.
+		hash: Block::Hash,
+		/// The number of the block.
+		number: NumberFor<Block>,
+	},
+	/// A block in the journal of the state-db doesn't match its header.
+	InvalidJournalBlock {
+		/// The hash of the block.
+		hash: Block::Hash,
+		/// The number of the block in the journal.
+		number: NumberFor<Block>,
+	},
+	/// A canonical block in the pruning window is missing from the pruning journal.
+	MissingPruningJournal {
+		/// The hash of the block.
+		hash: Block::Hash,
+		/// The number of the block.
+		number: NumberFor<Block>,
+	},
+	/// The state root of a block with a retained state is missing.
+	MissingStateRoot {
+		/// The hash of the block.
+		hash: Block::Hash,
+		/// The number of the block.
+		number: NumberFor<Block>,
+	},
+	/// The state of a block couldn't be iterated, usually because a trie node is missing.
+	UnreachableState {
+		/// The hash of the block.
+		hash: Block::Hash,
+		/// The number of the block.
+		number: NumberFor<Block>,
+		/// The error the iteration failed with.
+		error: String,
+	},
+}
+
+impl<Block: BlockT> fmt::Display for Inconsistency<Block> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::UnknownBlock { reference, hash } => write!(f, "Unknown {} {:?}", reference, hash),
+			Self::NonCanonicalMetaBlock { reference, hash, number } =>
+				write!(f, "The {} #{} ({:?}) isn't canonical", reference, number, hash),
+			Self::BestBelowFinalized { best, finalized } =>
+				write!(f, "The best block #{} is below the finalized block #{}", best, finalized),
+			Self::MissingCanonicalBlock { number } =>
+				write!(f, "No canonical block is recorded at #{}", number),
+			Self::InvalidCanonicalBlock { number, hash } =>
+				write!(f, "The canonical block #{} ({:?}) has no matching header", number, hash),
+			Self::BrokenCanonicalChain { number, hash, parent_hash } => write!(
+				f,
+				"The canonical block #{} ({:?}) has the non-canonical parent {:?}",
+				number, hash, parent_hash,
+			),
+			Self::CanonicalAboveBest { number, hash } => write!(
+				f,
+				"A canonical block #{} ({:?}) is recorded above the best block",
+				number, hash
+			),
+			Self::InvalidLeaf { hash, number } =>
+				write!(f, "The block #{} ({:?}) isn't a leaf", number, hash),
+			Self::MissingLeaf { hash, number } =>
+				write!(f, "The leaf #{} ({:?}) is missing from the leaves", number, hash),
+			Self::InvalidLastCanonicalized { hash, number } => write!(
+				f,
+				"The block #{} ({:?}) last canonicalized by the state-db isn't canonical",
+				number, hash,
+			),
+			Self::InvalidJournalBlock { hash, number } => write!(
+				f,
+				"The block #{} ({:?}) in the state-db journal doesn't match its header",
+				number, hash,
+			),
+			Self::MissingPruningJournal { hash, number } =>
+				write!(f, "The block #{} ({:?}) is missing from the pruning journal", number, hash),
+			Self::MissingStateRoot { hash, number } =>
+				write!(f, "The state root of the block #{} ({:?}) is missing", number, hash),
+			Self::UnreachableState { hash, number, error } => write!(
+				f,
+				"The state of the block #{} ({:?}) is unreachable: {}",
+				number, hash, error
+			),
+		}
+	}
+}
+
+/// A repair of the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair<Block: BlockT> {
+	/// The leaves were rebuilt from the headers.
+	RebuildLeaves {
+		/// The number of leaves.
+		leaves: usize,
+	},
+	/// The chain was truncated to a finalized block.
+	Truncate {
+		/// The hash of the block the chain was truncated to.
+		hash: Block::Hash,
+		/// The number of the block the chain was truncated to.
+		number: NumberFor<Block>,
+	},
+}
+
+impl<Block: BlockT> fmt::Display for Repair<Block> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::RebuildLeaves { leaves } => write!(f, "Rebuilt the leaves ({} leaves)", leaves),
+			Self::Truncate { hash, number } =>
+				write!(f, "Truncated the chain to the finalized block #{} ({:?})", number, hash),
+		}
+	}
+}
+
+/// The result of a consistency check of the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyReport<Block: BlockT> {
+	/// The inconsistencies found.
+	pub inconsistencies: Vec<Inconsistency<Block>>,
+	/// The repairs made.
+	pub repairs: Vec<Repair<Block>>,
+}
+
+struct Checker<Block: BlockT> {
+	inconsistencies: Vec<Inconsistency<Block>>,
+	/// The lowest block the chain has to be truncated below.
+	first_invalid: Option<NumberFor<Block>>,
+	/// Whether the leaves have to be rebuilt.
+	invalid_leaves: bool,
+}
+
+impl<Block: BlockT> Checker<Block> {
+	fn report(&mut self, inconsistency: Inconsistency<Block>) {
+		warn!(target: "db", "{}", inconsistency);
+		self.inconsistencies.push(inconsistency);
+	}
+
+	fn invalid(&mut self, inconsistency: Inconsistency<Block>, number: NumberFor<Block>) {
+		self.report(inconsistency);
+		self.first_invalid = Some(self.first_invalid.map_or(number, |first| first.min(number)));
+	}
+
+	fn invalid_leaves(&mut self, inconsistency: Inconsistency<Block>) {
+		self.report(inconsistency);
+		self.invalid_leaves = true;
+	}
+}
+
+/// A block which is part of the leaves once repaired.
+struct Leaf<Block: BlockT> {
+	hash: Block::Hash,
+	number: NumberFor<Block>,
+	parent_hash: Block::Hash,
+}
+
+impl<Block: BlockT> Backend<Block> {
+	/// Check the consistency of the database, and repair the recoverable inconsistencies if
+	/// `repair` is set.
+	///
+	/// Invalid leaves are repaired by rebuilding the leaves from the headers. An invalid chain is
+	/// repaired by truncating it to the last consistent finalized block, as long as the state of
+	/// that block is retained and wasn't canonicalized past by the state-db.
+	pub fn check_consistency(&self, repair: bool) -> ClientResult<ConsistencyReport<Block>> {
+		let mut checker =
+			Checker { inconsistencies: Vec::new(), first_invalid: None, invalid_leaves: false };
+		let db = &*self.storage.db;
+		let Some(genesis_hash) = utils::read_genesis_hash::<Block::Hash>(db)? else {
+			return Ok(ConsistencyReport { inconsistencies: Vec::new(), repairs: Vec::new() })
+		};
+		let block_gap = utils::read_meta::<Block>(db, columns::HEADER)?.block_gap;
+		let in_gap = |number: NumberFor<Block>| {
+			block_gap.map_or(false, |(start, end)| number >= start && number <= end)
+		};
+
+		// The blocks referenced by the `meta` column.
+		if self.read_header(genesis_hash)?.is_none() {
+			checker.invalid(
+				Inconsistency::UnknownBlock { reference: "genesis block", hash: genesis_hash },
+				Zero::zero(),
+			);
+		}
+		let best = self.check_meta_block(&mut checker, "best block", meta_keys::BEST_BLOCK)?;
+		let finalized =
+			self.check_meta_block(&mut checker, "finalized block", meta_keys::FINALIZED_BLOCK)?;
+		let finalized_state =
+			self.check_meta_block(&mut checker, "finalized state", meta_keys::FINALIZED_STATE)?;
+		let best_number = best.map_or(Zero::zero(), |(_, number)| number);
+		let (finalized_hash, finalized_number) = finalized.unwrap_or((genesis_hash, Zero::zero()));
+		if best_number < finalized_number {
+			checker.invalid(
+				Inconsistency::BestBelowFinalized {
+					best: best_number,
+					finalized: finalized_number,
+				},
+				finalized_number + One::one(),
+			);
+		}
+
+		// The canonical chain.
+		let top = best_number.max(finalized_number).saturated_into::<u64>();
+		let mut parent_hash = None;
+		for number in 0.. {
+			let canonical = self.read_canonical(number)?;
+			if number > top && canonical.is_none() {
+				break
+			}
+			if number % PROGRESS_INTERVAL == 0 && number != 0 {
+				info!(target: "db", "Checked the canonical chain up to #{}", number);
+			}
+			let block_number = number.saturated_into::<NumberFor<Block>>();
+			parent_hash = match canonical {
+				_ if in_gap(block_number) => None,
+				None => {
+					checker.invalid(
+						Inconsistency::MissingCanonicalBlock { number: block_number },
+						block_number,
+					);
+					None
+				},
+				Some(hash) if number > top => {
+					checker.invalid(
+						Inconsistency::CanonicalAboveBest { number: block_number, hash },
+						block_number,
+					);
+					None
+				},
+				Some(hash) => match self.read_header(hash)? {
+					Some(header) if *header.number() == block_number => {
+						if parent_hash
+							.map_or(false, |parent_hash| parent_hash != *header.parent_hash())
+						{
+							checker.invalid(
+								Inconsistency::BrokenCanonicalChain {
+									number: block_number,
+									hash,
+									parent_hash: *header.parent_hash(),
+								},
+								block_number,
+							);
+						}
+						Some(hash)
+					},
+					_ => {
+						checker.invalid(
+							Inconsistency::InvalidCanonicalBlock { number: block_number, hash },
+							block_number,
+						);
+						None
+					},
+				},
+			};
+		}
+
+		// The leaves, which are the blocks without children at or above the finalized block.
+		let leaves = LeafSet::<Block::Hash, NumberFor<Block>>::read_from_db(
+			db,
+			columns::META,
+			meta_keys::LEAF_PREFIX,
+		)?;
+		let mut valid_leaves = Vec::new();
+		for hash in leaves.hashes() {
+			let Some(header) = self.read_header(hash)? else {
+				checker.invalid_leaves(Inconsistency::UnknownBlock { reference: "leaf", hash });
+				continue
+			};
+			let number = *header.number();
+			if number < finalized_number || !self.known_children(hash)?.0.is_empty() {
+				checker.invalid_leaves(Inconsistency::InvalidLeaf { hash, number });
+				continue
+			}
+			valid_leaves.push(Leaf { hash, number, parent_hash: *header.parent_hash() });
+		}
+		let mut dangling_children = HashMap::new();
+		let mut queue = VecDeque::from([finalized_hash]);
+		while let Some(hash) = queue.pop_front() {
+			let (children, dangling) = self.known_children(hash)?;
+			if !dangling.is_empty() {
+				for child in dangling {
+					checker.invalid_leaves(Inconsistency::UnknownBlock {
+						reference: "child",
+						hash: child,
+					});
+				}
+				dangling_children.insert(hash, children.clone());
+			}
+			if children.is_empty() {
+				let Some(header) = self.read_header(hash)? else { continue };
+				let number = *header.number();
+				if !leaves.contains(number, hash) {
+					checker.invalid_leaves(Inconsistency::MissingLeaf { hash, number });
+					valid_leaves.push(Leaf { hash, number, parent_hash: *header.parent_hash() });
+				}
+			}
+			queue.extend(children);
+		}
+
+		// The journals of the state-db.
+		let state_db = &self.storage.state_db;
+		let last_canonicalized = state_db.last_canonicalized_block();
+		if let Some((hash, number)) = last_canonicalized {
+			let block_number = number.saturated_into::<NumberFor<Block>>();
+			if self.read_canonical(number)? != Some(hash) {
+				checker.invalid(
+					Inconsistency::InvalidLastCanonicalized { hash, number: block_number },
+					block_number,
+				);
+			}
+		}
+		let non_canonical = state_db.non_canonical_blocks();
+		for (hash, number, parent_hash) in &non_canonical {
+			let number = (*number).saturated_into::<NumberFor<Block>>();
+			match self.read_header(*hash)? {
+				Some(header)
+					if *header.number() == number && header.parent_hash() == parent_hash =>
+					self.check_state_root(&mut checker, &header, false),
+				Some(_) => checker.invalid(
+					Inconsistency::InvalidJournalBlock { hash: *hash, number },
+					number.max(finalized_number + One::one()),
+				),
+				None => checker.invalid(
+					Inconsistency::UnknownBlock {
+						reference: "state-db journal block",
+						hash: *hash,
+					},
+					number.max(finalized_number + One::one()),
+				),
+			}
+		}
+		if let Some((base, size)) = state_db.pruning_window() {
+			for number in base..base + size {
+				let Some(hash) = self.read_canonical(number)? else { continue };
+				let Some(header) = self.read_header(hash)? else { continue };
+				if state_db.is_pruned(&hash, number) == IsPruned::Pruned {
+					let number = *header.number();
+					checker.invalid(Inconsistency::MissingPruningJournal { hash, number }, number);
+				} else {
+					self.check_state_root(&mut checker, &header, false);
+				}
+			}
+		}
+
+		// The state of the finalized block and of the leaves.
+		let mut walked = HashSet::new();
+		if let Some((hash, _)) = finalized_state {
+			if let Some(header) = self.read_header(hash)? {
+				if walked.insert(*header.state_root()) {
+					self.check_state_root(&mut checker, &header, true);
+				}
+			}
+		}
+		for leaf in &valid_leaves {
+			let Some(header) = self.read_header(leaf.hash)? else { continue };
+			// Leaves may be imported without their state, so only the state of the leaves in the
+			// state-db journal, or found in an archive, is iterated.
+			let retained = non_canonical.iter().any(|(hash, _, _)| *hash == leaf.hash) ||
+				(state_db.pruning_mode() == PruningMode::ArchiveAll &&
+					self.has_node(header.state_root()));
+			if retained && walked.insert(*header.state_root()) {
+				self.check_state_root(&mut checker, &header, true);
+			}
+		}
+
+		info!(
+			target: "db",
+			"Found {} inconsistencies in the database",
+			checker.inconsistencies.len(),
+		);
+		let mut repairs = Vec::new();
+		if !repair {
+			return Ok(ConsistencyReport { inconsistencies: checker.inconsistencies, repairs })
+		}
+
+		if let Some(first_invalid) = checker.first_invalid {
+			let target = (!first_invalid.is_zero())
+				.then(|| finalized_number.min(first_invalid - One::one()))
+				.filter(|target| {
+					!in_gap(*target) &&
+						last_canonicalized
+							.map_or(true, |(_, number)| target.saturated_into::<u64>() >= number)
+				});
+			let target = match target {
+				Some(target) =>
+					self.read_canonical(target.saturated_into::<u64>())?.map(|hash| (hash, target)),
+				None => None,
+			};
+			let target = match target {
+				Some((hash, number)) => match self.read_header(hash)? {
+					Some(header)
+						if state_db.pruning_mode() == PruningMode::ArchiveAll ||
+							self.has_node(header.state_root()) =>
+						Some((hash, number)),
+					_ => None,
+				},
+				None => None,
+			};
+			match target {
+				Some((hash, number)) => {
+					let leaves = self.truncate(
+						hash,
+						number,
+						finalized_state.map_or(false, |(_, state_number)| state_number > number),
+						valid_leaves,
+						&non_canonical,
+					)?;
+					repairs.push(Repair::Truncate { hash, number });
+					repairs.push(Repair::RebuildLeaves { leaves });
+				},
+				None => warn!(
+					target: "db",
+					"The chain can't be truncated to a consistent finalized block below #{}",
+					first_invalid,
+				),
+			}
+		} else if checker.invalid_leaves {
+			let mut transaction = Transaction::new();
+			for (parent_hash, children) in dangling_children {
+				children::write_children(
+					&mut transaction,
+					columns::META,
+					meta_keys::CHILDREN_PREFIX,
+					parent_hash,
+					children,
+				);
+			}
+			let leaves = self.write_leaves(&mut transaction, valid_leaves);
+			self.storage.db.commit(transaction)?;
+			repairs.push(Repair::RebuildLeaves { leaves });
+		}
+		for repair in &repairs {
+			info!(target: "db", "{}", repair);
+		}
+		Ok(ConsistencyReport { inconsistencies: checker.inconsistencies, repairs })
+	}
+
+	fn read_header(&self, hash: Block::Hash) -> ClientResult<Option<Block::Header>> {
+		let header = utils::read_header::<Block>(
+			&*self.storage.db,
+			columns::KEY_LOOKUP,
+			columns::HEADER,
+			BlockId::Hash(hash),
+		)?;
+		Ok(header.filter(|header| header.hash() == hash))
+	}
+
+	/// Read the canonical hash at a number, as recorded in the lookup column.
+	fn read_canonical(&self, number: u64) -> ClientResult<Option<Block::Hash>> {
+		match self.storage.db.get(columns::KEY_LOOKUP, &utils::number_index_key(number)?) {
+			Some(key) => decode_lookup_key::<Block>(&key).map(|(_, hash)| Some(hash)),
+			None => Ok(None),
+		}
+	}
+
+	/// Check a block referenced by the `meta` column, returning its hash and number.
+	fn check_meta_block(
+		&self,
+		checker: &mut Checker<Block>,
+		reference: &'static str,
+		key: &[u8],
+	) -> ClientResult<Option<(Block::Hash, NumberFor<Block>)>> {
+		let Some(key) = self.storage.db.get(columns::META, key) else { return Ok(None) };
+		let (number, hash) = decode_lookup_key::<Block>(&key)?;
+		if self.read_header(hash)?.map_or(true, |header| *header.number() != number) {
+			checker.invalid(Inconsistency::UnknownBlock { reference, hash }, number);
+		} else if self.read_canonical(number.saturated_into::<u64>())? != Some(hash) {
+			checker
+				.invalid(Inconsistency::NonCanonicalMetaBlock { reference, hash, number }, number);
+		}
+		Ok(Some((hash, number)))
+	}
+
+	/// Returns the children of a block which have a header, and those which don't.
+	fn known_children(
+		&self,
+		hash: Block::Hash,
+	) -> ClientResult<(Vec<Block::Hash>, Vec<Block::Hash>)> {
+		let children: Vec<Block::Hash> = children::read_children(
+			&*self.storage.db,
+			columns::META,
+			meta_keys::CHILDREN_PREFIX,
+			hash,
+		)?;
+		let mut known = Vec::new();
+		let mut dangling = Vec::new();
+		for child in children {
+			match self.read_header(child)? {
+				Some(header) if *header.parent_hash() == hash => known.push(child),
+				_ => dangling.push(child),
+			}
+		}
+		Ok((known, dangling))
+	}
+
+	fn has_node(&self, root: &Block::Hash) -> bool {
+		sp_state_machine::Storage::get(self.storage.as_ref(), root, (&[], None))
+			.unwrap_or(None)
+			.is_some()
+	}
+
+	/// Check that the state of a block is retained, iterating all of it if `walk` is set.
+	fn check_state_root(&self, checker: &mut Checker<Block>, header: &Block::Header, walk: bool) {
+		let (hash, number) = (header.hash(), *header.number());
+		if !self.has_node(header.state_root()) {
+			checker.invalid(Inconsistency::MissingStateRoot { hash, number }, number);
+			return
+		}
+		if walk {
+			if let Err(error) = self.walk_state(*header.state_root()) {
+				checker.invalid(Inconsistency::UnreachableState { hash, number, error }, number);
+			}
+		}
+	}
+
+	fn walk_state(&self, root: Block::Hash) -> Result<(), String> {
+		// The trie cache is not used, as every node has to be read from the database.
+		let state = DbStateBuilder::<Block>::new(self.storage.clone(), root).build();
+		let mut child_tries = Vec::new();
+		for pair in state.pairs(IterArgs::default())? {
+			let (key, _) = pair?;
+			if let Some(storage_key) =
+				key.strip_prefix(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX)
+			{
+				child_tries.push(ChildInfo::new_default(storage_key));
+			}
+		}
+		for child_info in child_tries {
+			let args = IterArgs { child_info: Some(child_info), ..Default::default() };
+			for pair in state.pairs(args)? {
+				pair?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Write the given leaves to the database and to the in-memory leaves, returning their count.
+	fn write_leaves(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		leaves: Vec<Leaf<Block>>,
+	) -> usize {
+		let mut leaf_set = LeafSet::new();
+		for leaf in leaves {
+			leaf_set.import(leaf.hash, leaf.number, leaf.parent_hash);
+		}
+		leaf_set.prepare_transaction(transaction, columns::META, meta_keys::LEAF_PREFIX);
+		let count = leaf_set.count();
+		*self.blockchain.leaves.write() = leaf_set;
+		count
+	}
+
+	/// Truncate the chain to a finalized block, discarding its descendants along with the blocks
+	/// above it on the canonical chain and in the state-db journal.
+	fn truncate(
+		&self,
+		hash: Block::Hash,
+		number: NumberFor<Block>,
+		reset_finalized_state: bool,
+		leaves: Vec<Leaf<Block>>,
+		non_canonical: &[(Block::Hash, u64, Block::Hash)],
+	) -> ClientResult<usize> {
+		let db = &*self.storage.db;
+		let target = number.saturated_into::<u64>();
+		let mut transaction = Transaction::new();
+
+		let mut queue: VecDeque<_> = children::read_children::<_, Block::Hash>(
+			db,
+			columns::META,
+			meta_keys::CHILDREN_PREFIX,
+			hash,
+		)?
+		.into();
+		queue.extend(
+			non_canonical
+				.iter()
+				.filter(|(_, number, _)| *number > target)
+				.map(|(hash, _, _)| *hash),
+		);
+		let mut number_above = target + 1;
+		while let Some(hash) = self.read_canonical(number_above)? {
+			utils::remove_number_to_key_mapping(
+				&mut transaction,
+				columns::KEY_LOOKUP,
+				number_above,
+			)?;
+			queue.push_back(hash);
+			number_above += 1;
+		}
+		let mut discarded = HashSet::new();
+		while let Some(hash) = queue.pop_front() {
+			if discarded.insert(hash) {
+				queue.extend(children::read_children::<_, Block::Hash>(
+					db,
+					columns::META,
+					meta_keys::CHILDREN_PREFIX,
+					hash,
+				)?);
+			}
+		}
+
+		// Revert the state of the discarded blocks.
+		while non_canonical_above(&self.storage.state_db.non_canonical_blocks(), target) {
+			match self.storage.state_db.revert_one() {
+				Some(commit) => apply_state_commit(&mut transaction, commit),
+				None => break,
+			}
+		}
+
+		// Forget the discarded blocks, keeping their data unreferenced.
+		let mut parents = HashMap::new();
+		for discarded_hash in &discarded {
+			if let Some(header) = self.read_header(*discarded_hash)? {
+				if !discarded.contains(header.parent_hash()) {
+					parents.insert(*header.parent_hash(), *header.number() - One::one());
+				}
+			}
+			transaction.remove(columns::KEY_LOOKUP, discarded_hash.as_ref());
+			children::remove_children(
+				&mut transaction,
+				columns::META,
+				meta_keys::CHILDREN_PREFIX,
+				*discarded_hash,
+			);
+		}
+		let mut leaves: Vec<_> = leaves
+			.into_iter()
+			.filter(|leaf| leaf.number >= number && !discarded.contains(&leaf.hash))
+			.collect();
+		for (parent_hash, parent_number) in parents {
+			let children: Vec<Block::Hash> = children::read_children(
+				db,
+				columns::META,
+				meta_keys::CHILDREN_PREFIX,
+				parent_hash,
+			)?
+			.into_iter()
+			.filter(|child| !discarded.contains(child))
+			.collect();
+			if !children.is_empty() {
+				children::write_children(
+					&mut transaction,
+					columns::META,
+					meta_keys::CHILDREN_PREFIX,
+					parent_hash,
+					children,
+				);
+				continue
+			}
+			children::remove_children(
+				&mut transaction,
+				columns::META,
+				meta_keys::CHILDREN_PREFIX,
+				parent_hash,
+			);
+			if parent_number >= number {
+				if let Some(header) = self.read_header(parent_hash)? {
+					leaves.push(Leaf {
+						hash: parent_hash,
+						number: parent_number,
+						parent_hash: *header.parent_hash(),
+					});
+				}
+			}
+		}
+		if !leaves.iter().any(|leaf| leaf.hash == hash) {
+			let header = self.read_header(hash)?.ok_or_else(|| {
+				ClientError::UnknownBlock(format!("Header of the truncation target {}", hash))
+			})?;
+			leaves.push(Leaf { hash, number, parent_hash: *header.parent_hash() });
+		}
+
+		let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+		transaction.set_from_vec(columns::META, meta_keys::BEST_BLOCK, lookup_key.clone());
+		transaction.set_from_vec(columns::META, meta_keys::FINALIZED_BLOCK, lookup_key.clone());
+		if reset_finalized_state {
+			transaction.set_from_vec(columns::META, meta_keys::FINALIZED_STATE, lookup_key);
+		}
+		let count = self.write_leaves(&mut transaction, leaves);
+		db.commit(transaction)?;
+
+		*self.blockchain.meta.write() = utils::read_meta::<Block>(db, columns::HEADER)?;
+		for discarded_hash in discarded {
+			self.blockchain.remove_header_metadata(discarded_hash);
+		}
+		Ok(count)
+	}
+}
+
+fn non_canonical_above<H>(non_canonical: &[(H, u64, H)], number: u64) -> bool {
+	non_canonical.iter().any(|(_, block_number, _)| *block_number > number)
+}
+
+/// Decode a lookup key, made of the big endian block number followed by the block hash.
+fn decode_lookup_key<Block: BlockT>(key: &[u8]) -> ClientResult<(NumberFor<Block>, Block::Hash)> {
+	let invalid = || ClientError::Backend("Invalid lookup key".into());
+	if key.len() < 4 {
+		return Err(invalid())
+	}
+	let number = u32::from_be_bytes([key[0], key[1], key[2], key[3]]);
+	let hash = Block::Hash::decode(&mut &key[4..]).map_err(|_| invalid())?;
+	Ok((number.into(), hash))
+}
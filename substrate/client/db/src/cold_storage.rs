@@ -0,0 +1,219 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Tiered storage of the block bodies and justifications.
+//!
+//! The bodies and justifications of the finalized blocks old enough are moved from the main
+//! database to a cold database, possibly on cheaper storage. Reads fall back to the cold
+//! database, and removals of the values no longer in the main database apply to the cold one, so
+//! the move is transparent to the rest of the backend.
+
+use std::{collections::HashSet, sync::Arc};
+
+use codec::{Decode, Encode};
+use log::debug;
+use sp_blockchain::{Error as ClientError, HeaderBackend, Result as ClientResult};
+use sp_database::{error::DatabaseError, Change, ColumnEntry, ColumnId, Database, Transaction};
+use sp_runtime::traits::{Block as BlockT, NumberFor, One, Saturating};
+
+use crate::{
+	columns,
+	utils::{self, meta_keys, DatabaseType},
+	Backend, ColdStorageSettings, DbHash,
+};
+
+/// The columns moved to the cold database.
+const COLD_COLUMNS: [ColumnId; 3] = [columns::BODY, columns::BODY_INDEX, columns::JUSTIFICATIONS];
+
+/// Maximum number of blocks moved to the cold database by a finalization. The remaining ones
+/// are moved by the next finalizations.
+const MAX_COLD_MOVES: u32 = 1024;
+
+fn is_cold_column(col: ColumnId) -> bool {
+	COLD_COLUMNS.contains(&col)
+}
+
+/// A `Database` adapter reading the block columns from the cold database when the main database
+/// doesn't have the value.
+///
+/// The removals of values of the block columns which aren't in the main database are applied to
+/// the cold database. The removals of values still in the main database only apply to it, as
+/// they're either not moved yet, or being moved.
+struct TieredDb {
+	hot: Arc<dyn Database<DbHash>>,
+	cold: Arc<dyn Database<DbHash>>,
+}
+
+impl Database<DbHash> for TieredDb {
+	fn commit(&self, transaction: Transaction<DbHash>) -> Result<(), DatabaseError> {
+		let mut cold_transaction = Transaction::new();
+		for change in &transaction.0 {
+			if let Change::Remove(col, key) = change {
+				if is_cold_column(*col) && !self.hot.contains(*col, key) {
+					cold_transaction.remove(*col, key);
+				}
+			}
+		}
+		self.hot.commit(transaction)?;
+		if !cold_transaction.0.is_empty() {
+			self.cold.commit(cold_transaction)?;
+		}
+		Ok(())
+	}
+
+	fn get(&self, col: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
+		self.hot
+			.get(col, key)
+			.or_else(|| is_cold_column(col).then(|| self.cold.get(col, key)).flatten())
+	}
+
+	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
+		self.hot.contains(col, key) || (is_cold_column(col) && self.cold.contains(col, key))
+	}
+
+	fn value_size(&self, col: ColumnId, key: &[u8]) -> Option<usize> {
+		self.hot
+			.value_size(col, key)
+			.or_else(|| is_cold_column(col).then(|| self.cold.value_size(col, key)).flatten())
+	}
+
+	fn supports_ref_counting(&self) -> bool {
+		self.hot.supports_ref_counting()
+	}
+
+	/// The entries of the block columns in the main database are visited before those in the cold
+	/// database.
+	fn iter_column_while(&self, col: ColumnId, f: &mut dyn FnMut(ColumnEntry) -> bool) -> bool {
+		let mut stopped = false;
+		let supported = self.hot.iter_column_while(col, &mut |entry| {
+			stopped = !f(entry);
+			!stopped
+		});
+		if supported && !stopped && is_cold_column(col) {
+			return self.cold.iter_column_while(col, f)
+		}
+		supported
+	}
+
+	fn iter_prefix_while(
+		&self,
+		col: ColumnId,
+		prefix: &[u8],
+		f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> bool {
+		// The entries of the two databases can't be merged in key order.
+		!is_cold_column(col) && self.hot.iter_prefix_while(col, prefix, f)
+	}
+
+	fn sanitize_key(&self, key: &mut Vec<u8>) {
+		self.hot.sanitize_key(key)
+	}
+}
+
+/// The cold database of a [`Backend`].
+pub(crate) struct ColdStorage {
+	/// The main database, without the compression adapter.
+	hot: Arc<dyn Database<DbHash>>,
+	cold: Arc<dyn Database<DbHash>>,
+	after: u32,
+}
+
+impl ColdStorage {
+	/// Open the cold database, and wrap the main database into a [`TieredDb`] reading from it.
+	pub(crate) fn open<Block: BlockT>(
+		hot: Arc<dyn Database<DbHash>>,
+		settings: &ColdStorageSettings,
+	) -> ClientResult<(Self, Arc<dyn Database<DbHash>>)> {
+		let cold = utils::open_database::<Block>(&settings.source, DatabaseType::Full, true)?;
+		let tiered = Arc::new(TieredDb { hot: hot.clone(), cold: cold.clone() });
+		Ok((Self { hot, cold, after: settings.after }, tiered))
+	}
+}
+
+impl<Block: BlockT> Backend<Block> {
+	/// Move the bodies and justifications of the blocks finalized at least
+	/// [`crate::ColdStorageSettings::after`] blocks before `finalized` to the cold database.
+	///
+	/// The values are written to the cold database right away, and removed from the main one by
+	/// `transaction`, so they're only removed once durably moved, along with the finalization.
+	/// If `transaction` isn't committed, they're left in both and moved again later.
+	pub(crate) fn move_blocks_to_cold(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		finalized: NumberFor<Block>,
+	) -> ClientResult<()> {
+		let Some(cold) = &self.cold_storage else { return Ok(()) };
+		if finalized < cold.after.into() {
+			return Ok(())
+		}
+		let until = finalized.saturating_sub(cold.after.into());
+
+		// The cursor may have been advanced by a previous finalization of `transaction`.
+		let pending = transaction.0.iter().rev().find_map(|change| match change {
+			Change::Set(columns::META, key, cursor) if key == meta_keys::COLD_BLOCKS =>
+				Some(cursor.clone()),
+			_ => None,
+		});
+		let cursor = pending.or_else(|| cold.hot.get(columns::META, meta_keys::COLD_BLOCKS));
+		let mut cursor: NumberFor<Block> = match cursor {
+			Some(cursor) => Decode::decode(&mut &cursor[..]).map_err(|err| {
+				ClientError::Backend(format!("Error decoding cold storage cursor: {}", err))
+			})?,
+			None => Default::default(),
+		};
+		if cursor > until {
+			return Ok(())
+		}
+
+		// The values pruned by `transaction` aren't moved, and any copy left in the cold database
+		// by an interrupted move is removed.
+		let pruned = transaction
+			.0
+			.iter()
+			.filter_map(|change| match change {
+				Change::Remove(col, key) if is_cold_column(*col) => Some((*col, key.clone())),
+				_ => None,
+			})
+			.collect::<HashSet<_>>();
+
+		let mut cold_transaction = Transaction::new();
+		let mut steps = 0;
+		while cursor <= until && steps < MAX_COLD_MOVES {
+			if let Some(hash) = self.blockchain.hash(cursor)? {
+				let lookup_key = utils::number_and_hash_to_lookup_key(cursor, hash)?;
+				for col in COLD_COLUMNS {
+					if pruned.contains(&(col, lookup_key.clone())) {
+						cold_transaction.remove(col, &lookup_key);
+					} else if let Some(value) = cold.hot.get(col, &lookup_key) {
+						// The value is moved as stored, compressed or not.
+						cold_transaction.set_from_vec(col, &lookup_key, value);
+						transaction.remove(col, &lookup_key);
+					}
+				}
+			}
+			cursor = cursor.saturating_add(One::one());
+			steps += 1;
+		}
+		debug!(target: "db", "Moving {} blocks before #{} to the cold database", steps, cursor);
+
+		cold.cold.commit(cold_transaction)?;
+		transaction.set_from_vec(columns::META, meta_keys::COLD_BLOCKS, cursor.encode());
+		Ok(())
+	}
+}
@@ -0,0 +1,289 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Compression of the block bodies and justifications.
+//!
+//! The compressed values are prefixed with a tag which can't start a SCALE-encoded vector, so
+//! the values stored before the compression was enabled remain readable, as do the compressed
+//! values once it's disabled. The columns for which the compression was ever enabled are
+//! recorded in the `meta` column, and only their values are decompressed. The bodies may be
+//! compressed with a dictionary trained on the extrinsics of the first blocks imported, which is
+//! stored in the `meta` column.
+
+use std::{io::Read, sync::Arc};
+
+use codec::{Decode, Encode};
+use log::{error, info, warn};
+use parking_lot::{Mutex, RwLock};
+use sp_blockchain::{Error as ClientError, Result as ClientResult};
+use sp_database::{error::DatabaseError, Change, ColumnEntry, ColumnId, Database, Transaction};
+
+use crate::{columns, utils::meta_keys, CompressionSettings, DbHash};
+
+/// Tag of the compressed values.
+///
+/// As the first byte of a compact integer, it would announce a length encoded in 67 bytes,
+/// which no vector can have.
+const ZSTD_TAG: u8 = 0xff;
+/// Follows [`ZSTD_TAG`] in the values compressed with the trained dictionary.
+///
+/// The values compressed without dictionary are followed by the zstd frame instead, which
+/// starts with the first byte of its magic number, `0x28`.
+const DICTIONARY_TAG: u8 = 0x00;
+
+/// The samples used to train the dictionary total this many times its size.
+const DICTIONARY_SAMPLES_RATIO: usize = 100;
+
+/// A `Database` adapter compressing the values written to the block columns, and decompressing
+/// the values read from them.
+pub(crate) struct CompressedDb {
+	inner: Arc<dyn Database<DbHash>>,
+	/// The columns for which the compression was ever enabled.
+	compressed_columns: Vec<ColumnId>,
+	body_level: Option<i32>,
+	justifications_level: Option<i32>,
+	dictionary: RwLock<Option<Vec<u8>>>,
+	/// Extrinsics sampled to train the dictionary, until it's trained.
+	samples: Mutex<Option<Samples>>,
+}
+
+struct Samples {
+	dictionary_size: usize,
+	samples: Vec<Vec<u8>>,
+	size: usize,
+}
+
+impl CompressedDb {
+	/// Wrap `inner`, recording the columns `settings` compresses.
+	pub(crate) fn new(
+		inner: Arc<dyn Database<DbHash>>,
+		settings: &CompressionSettings,
+	) -> ClientResult<Self> {
+		let mut compressed_columns = match inner.get(columns::META, meta_keys::COMPRESSED_COLUMNS) {
+			Some(compressed_columns) => Vec::<ColumnId>::decode(&mut &compressed_columns[..])
+				.map_err(|err| {
+					ClientError::Backend(format!("Error decoding the compressed columns: {}", err))
+				})?,
+			None => Vec::new(),
+		};
+		let enabled = settings
+			.body
+			.map(|_| [columns::BODY, columns::BODY_INDEX])
+			.into_iter()
+			.flatten()
+			.chain(settings.justifications.map(|_| columns::JUSTIFICATIONS));
+		let recorded = compressed_columns.len();
+		for col in enabled {
+			if !compressed_columns.contains(&col) {
+				compressed_columns.push(col);
+			}
+		}
+		if compressed_columns.len() != recorded {
+			let mut transaction = Transaction::new();
+			transaction.set_from_vec(
+				columns::META,
+				meta_keys::COMPRESSED_COLUMNS,
+				compressed_columns.encode(),
+			);
+			inner.commit(transaction).map_err(|err| {
+				ClientError::Backend(format!("Error recording the compressed columns: {}", err))
+			})?;
+		}
+
+		let dictionary = inner.get(columns::META, meta_keys::COMPRESSION_DICTIONARY);
+		let samples = match (settings.body, settings.dictionary_size, &dictionary) {
+			(Some(_), Some(dictionary_size), None) if dictionary_size > 0 =>
+				Some(Samples { dictionary_size, samples: Vec::new(), size: 0 }),
+			_ => None,
+		};
+		Ok(Self {
+			inner,
+			compressed_columns,
+			body_level: settings.body,
+			justifications_level: settings.justifications,
+			dictionary: RwLock::new(dictionary),
+			samples: Mutex::new(samples),
+		})
+	}
+
+	fn is_compressed_column(&self, col: ColumnId) -> bool {
+		self.compressed_columns.contains(&col)
+	}
+
+	/// Sample the extrinsics of a body written to `transaction`, to train the dictionary.
+	///
+	/// Once enough extrinsics are sampled, the dictionary is trained and written to
+	/// `transaction`, and compresses the bodies from then on.
+	pub(crate) fn sample_extrinsics(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		extrinsics: impl Iterator<Item = Vec<u8>>,
+	) {
+		let mut samples = self.samples.lock();
+		let Some(sampling) = samples.as_mut() else { return };
+
+		for extrinsic in extrinsics {
+			sampling.size += extrinsic.len();
+			sampling.samples.push(extrinsic);
+		}
+		if sampling.size < sampling.dictionary_size.saturating_mul(DICTIONARY_SAMPLES_RATIO) {
+			return
+		}
+
+		match zstd::dict::from_samples(&sampling.samples, sampling.dictionary_size) {
+			Ok(dictionary) => {
+				info!(
+					target: "db",
+					"Trained a compression dictionary of {} bytes on {} extrinsics",
+					dictionary.len(),
+					sampling.samples.len(),
+				);
+				transaction.set(columns::META, meta_keys::COMPRESSION_DICTIONARY, &dictionary);
+				*self.dictionary.write() = Some(dictionary);
+			},
+			Err(err) => {
+				warn!(target: "db", "Failed to train a compression dictionary: {}", err);
+			},
+		}
+		*samples = None;
+	}
+
+	fn level(&self, col: ColumnId) -> Option<i32> {
+		match col {
+			columns::BODY | columns::BODY_INDEX => self.body_level,
+			columns::JUSTIFICATIONS => self.justifications_level,
+			_ => None,
+		}
+	}
+
+	fn compress(&self, level: i32, col: ColumnId, value: Vec<u8>) -> Vec<u8> {
+		let dictionary = self.dictionary.read();
+		// The dictionary is trained on extrinsics, which justifications aren't made of.
+		let dictionary = dictionary.as_ref().filter(|_| col != columns::JUSTIFICATIONS);
+		let (tag, compressed): (&[u8], _) = match dictionary {
+			Some(dictionary) => (
+				&[ZSTD_TAG, DICTIONARY_TAG],
+				zstd::bulk::Compressor::with_dictionary(level, dictionary)
+					.and_then(|mut compressor| compressor.compress(&value)),
+			),
+			None => (&[ZSTD_TAG], zstd::bulk::compress(&value, level)),
+		};
+		match compressed {
+			Ok(compressed) if compressed.len() + tag.len() < value.len() => {
+				let mut tagged = Vec::with_capacity(compressed.len() + tag.len());
+				tagged.extend_from_slice(tag);
+				tagged.extend_from_slice(&compressed);
+				tagged
+			},
+			Ok(_) => value,
+			Err(err) => {
+				warn!(target: "db", "Failed to compress a value of column {}: {}", col, err);
+				value
+			},
+		}
+	}
+
+	/// Decompress a value of `col`.
+	///
+	/// A value which fails to decompress is logged and returned as stored. Starting with
+	/// [`ZSTD_TAG`], it then fails to decode, so reading it fails with an error.
+	fn decompress(&self, col: ColumnId, value: Vec<u8>) -> Vec<u8> {
+		if !self.is_compressed_column(col) {
+			return value
+		}
+		let decompressed = match &value[..] {
+			[ZSTD_TAG, DICTIONARY_TAG, compressed @ ..] => match self.dictionary.read().as_ref() {
+				Some(dictionary) =>
+					zstd::stream::read::Decoder::with_dictionary(compressed, dictionary)
+						.and_then(|mut decoder| {
+							let mut decompressed = Vec::new();
+							decoder.read_to_end(&mut decompressed).map(|_| decompressed)
+						})
+						.map_err(|err| err.to_string()),
+				None => Err("missing compression dictionary".into()),
+			},
+			[ZSTD_TAG, compressed @ ..] =>
+				zstd::stream::decode_all(compressed).map_err(|err| err.to_string()),
+			_ => return value,
+		};
+		decompressed.unwrap_or_else(|err| {
+			error!(target: "db", "Failed to decompress a value of column {}: {}", col, err);
+			value
+		})
+	}
+}
+
+impl Database<DbHash> for CompressedDb {
+	fn commit(&self, mut transaction: Transaction<DbHash>) -> Result<(), DatabaseError> {
+		for change in transaction.0.iter_mut() {
+			if let Change::Set(col, _, value) = change {
+				if let Some(level) = self.level(*col) {
+					*value = self.compress(level, *col, std::mem::take(value));
+				}
+			}
+		}
+		self.inner.commit(transaction)
+	}
+
+	fn get(&self, col: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
+		self.inner.get(col, key).map(|value| self.decompress(col, value))
+	}
+
+	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
+		self.inner.contains(col, key)
+	}
+
+	/// The size of the value as stored, which is the compressed size for a compressed value.
+	fn value_size(&self, col: ColumnId, key: &[u8]) -> Option<usize> {
+		self.inner.value_size(col, key)
+	}
+
+	fn supports_ref_counting(&self) -> bool {
+		self.inner.supports_ref_counting()
+	}
+
+	fn iter_column_while(&self, col: ColumnId, f: &mut dyn FnMut(ColumnEntry) -> bool) -> bool {
+		if !self.is_compressed_column(col) {
+			return self.inner.iter_column_while(col, f)
+		}
+		self.inner.iter_column_while(col, &mut |entry| {
+			let value = self.decompress(col, entry.value.to_vec());
+			f(ColumnEntry { key: entry.key, value: &value, ref_count: entry.ref_count })
+		})
+	}
+
+	fn iter_prefix_while(
+		&self,
+		col: ColumnId,
+		prefix: &[u8],
+		f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> bool {
+		if !self.is_compressed_column(col) {
+			return self.inner.iter_prefix_while(col, prefix, f)
+		}
+		self.inner.iter_prefix_while(col, prefix, &mut |entry| {
+			let value = self.decompress(col, entry.value.to_vec());
+			f(ColumnEntry { key: entry.key, value: &value, ref_count: entry.ref_count })
+		})
+	}
+
+	fn sanitize_key(&self, key: &mut Vec<u8>) {
+		self.inner.sanitize_key(key)
+	}
+}
@@ -0,0 +1,238 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Opt-in secondary indexes of the extrinsics by signer and by call.
+//!
+//! The extrinsics of the imported blocks are passed to the [`ExtrinsicIndexFn`] installed with
+//! [`Backend::set_extrinsic_indexer`], and an entry is written for every key it returns. The
+//! entries of a block are removed along with its body when it's pruned.
+//!
+//! The first block the indexes cover is persisted when the indexing starts, and forgotten as soon
+//! as a block is imported without being indexed, such as when the node is restarted without the
+//! indexer. The indexing then starts over from the next block.
+//!
+//! An entry is keyed by `tag ++ key ++ number ++ hash ++ extrinsic index`, with the block number
+//! and the extrinsic index big-endian, so the entries of a key are ordered by block number. The
+//! list of the entries of a block is stored under `BLOCK_TAG ++ lookup key`.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use sc_client_api::backend::{ExtrinsicIndexKey, IndexedExtrinsic};
+use sp_blockchain::{Error as ClientError, HeaderBackend, Result as ClientResult};
+use sp_database::Transaction;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+
+use crate::{columns, utils::meta_keys, Backend, DbHash};
+
+/// Returns the index keys of an extrinsic, such as its signer and its call.
+pub type ExtrinsicIndexFn<Block> =
+	Arc<dyn Fn(&<Block as BlockT>::Extrinsic) -> Vec<ExtrinsicIndexKey> + Send + Sync>;
+
+/// Tag of the entries of [`ExtrinsicIndexKey::Signer`].
+const SIGNER_TAG: u8 = 0;
+/// Tag of the entries of [`ExtrinsicIndexKey::Call`].
+const CALL_TAG: u8 = 1;
+/// Tag of the lists of the entries written for a block.
+const BLOCK_TAG: u8 = 2;
+
+/// The entries are iterated by ranges of `2^BUCKET_BITS` blocks, which allows to start iterating
+/// at a given block number without a seek.
+const BUCKET_BITS: u32 = 16;
+
+fn key_prefix(key: &ExtrinsicIndexKey) -> Vec<u8> {
+	match key {
+		ExtrinsicIndexKey::Signer(signer) => {
+			let mut prefix = vec![SIGNER_TAG];
+			signer.encode_to(&mut prefix);
+			prefix
+		},
+		ExtrinsicIndexKey::Call { pallet, call } => vec![CALL_TAG, *pallet, *call],
+	}
+}
+
+fn block_key(lookup_key: &[u8]) -> Vec<u8> {
+	let mut key = vec![BLOCK_TAG];
+	key.extend_from_slice(lookup_key);
+	key
+}
+
+// Decodes the `number ++ hash ++ extrinsic index` suffix of an entry.
+fn decode_entry<Block: BlockT>(suffix: &[u8]) -> Option<(u64, Block::Hash, u32)> {
+	if suffix.len() < 8 {
+		return None
+	}
+	let (lookup_key, index) = suffix.split_at(suffix.len() - 4);
+	let number = u32::from_be_bytes(lookup_key[..4].try_into().ok()?);
+	let hash = Block::Hash::decode(&mut &lookup_key[4..]).ok()?;
+	Some((number.into(), hash, u32::from_be_bytes(index.try_into().ok()?)))
+}
+
+impl<Block: BlockT> Backend<Block> {
+	/// Index the extrinsics of the blocks imported from now on with `indexer`, enabling
+	/// [`sc_client_api::Backend::indexed_extrinsics`].
+	///
+	/// Blocks imported before the indexer was installed aren't indexed, see
+	/// [`sc_client_api::Backend::extrinsic_index_start`].
+	pub fn set_extrinsic_indexer(&self, indexer: ExtrinsicIndexFn<Block>) {
+		*self.extrinsic_indexer.write() = Some(indexer);
+	}
+
+	pub(crate) fn index_extrinsics(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		number: NumberFor<Block>,
+		lookup_key: &[u8],
+		body: &[Block::Extrinsic],
+	) {
+		let indexer = self.extrinsic_indexer.read().clone();
+		self.update_index_start(
+			transaction,
+			&self.extrinsic_index_start,
+			meta_keys::EXTRINSIC_INDEX_START,
+			indexer.is_some(),
+			number,
+		);
+		let Some(indexer) = indexer else { return };
+
+		let mut entries = Vec::new();
+		for (index, extrinsic) in body.iter().enumerate() {
+			let first = entries.len();
+			for key in indexer(extrinsic) {
+				let mut entry = key_prefix(&key);
+				entry.extend_from_slice(lookup_key);
+				entry.extend_from_slice(&(index as u32).to_be_bytes());
+				if !entries[first..].contains(&entry) {
+					transaction.set(columns::EXTRINSIC_INDEX, &entry, &[]);
+					entries.push(entry);
+				}
+			}
+		}
+		if !entries.is_empty() {
+			transaction.set_from_vec(
+				columns::EXTRINSIC_INDEX,
+				&block_key(lookup_key),
+				entries.encode(),
+			);
+		}
+	}
+
+	pub(crate) fn remove_indexed_extrinsics(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		lookup_key: &[u8],
+	) -> ClientResult<()> {
+		let block_key = block_key(lookup_key);
+		let Some(entries) = self.storage.db.get(columns::EXTRINSIC_INDEX, &block_key) else {
+			return Ok(())
+		};
+		let entries = Vec::<Vec<u8>>::decode(&mut &entries[..]).map_err(|err| {
+			ClientError::Backend(format!("Error decoding extrinsic index entries: {}", err))
+		})?;
+		for entry in entries {
+			transaction.remove(columns::EXTRINSIC_INDEX, &entry);
+		}
+		transaction.remove(columns::EXTRINSIC_INDEX, &block_key);
+		Ok(())
+	}
+
+	pub(crate) fn extrinsic_index_coverage(&self) -> Option<NumberFor<Block>> {
+		self.extrinsic_indexer.read().as_ref().and(*self.extrinsic_index_start.read())
+	}
+
+	pub(crate) fn query_indexed_extrinsics(
+		&self,
+		key: &ExtrinsicIndexKey,
+		from: NumberFor<Block>,
+		limit: usize,
+	) -> ClientResult<Option<Vec<IndexedExtrinsic<Block>>>> {
+		if self.extrinsic_indexer.read().is_none() {
+			return Ok(None)
+		}
+
+		let prefix = key_prefix(key);
+		let best: u64 = crate::utils::number_index_key(self.blockchain.info().best_number)
+			.map(u32::from_be_bytes)?
+			.into();
+		let mut cursor: u64 = match crate::utils::number_index_key(from) {
+			Ok(from) => u32::from_be_bytes(from).into(),
+			// Beyond any block which can be stored.
+			Err(_) => return Ok(Some(Vec::new())),
+		};
+
+		let mut found = Vec::new();
+		let mut canonical = None;
+		while found.len() < limit && cursor <= best {
+			let bucket = cursor >> BUCKET_BITS;
+			let mut bucket_prefix = prefix.clone();
+			bucket_prefix.extend_from_slice(&(bucket as u16).to_be_bytes());
+
+			// Collect the entries of whole blocks, then keep those of the canonical blocks. The
+			// database isn't read while iterating.
+			let wanted = limit - found.len();
+			let mut entries: Vec<(u64, Block::Hash, u32)> = Vec::new();
+			let mut exhausted = true;
+			let supported = self.storage.db.iter_prefix_while(
+				columns::EXTRINSIC_INDEX,
+				&bucket_prefix,
+				&mut |entry| {
+					let Some(entry) =
+						entry.key.and_then(|key| decode_entry::<Block>(&key[prefix.len()..]))
+					else {
+						return true
+					};
+					if entry.0 < cursor {
+						return true
+					}
+					if entries.len() >= wanted && entries.last().map_or(false, |l| l.0 != entry.0) {
+						exhausted = false;
+						return false
+					}
+					entries.push(entry);
+					true
+				},
+			);
+			if !supported {
+				return Err(ClientError::Backend(
+					"The database doesn't support iterating over the extrinsic indexes".into(),
+				))
+			}
+
+			cursor = match entries.last() {
+				Some(last) if !exhausted => last.0 + 1,
+				_ => (bucket + 1) << BUCKET_BITS,
+			};
+			for (number, hash, index) in entries {
+				let number: NumberFor<Block> = (number as u32).into();
+				let canonical_hash = match canonical {
+					Some((n, canonical_hash)) if n == number => canonical_hash,
+					_ => {
+						let canonical_hash = self.blockchain.hash(number)?;
+						canonical = Some((number, canonical_hash));
+						canonical_hash
+					},
+				};
+				if canonical_hash == Some(hash) {
+					found.push(IndexedExtrinsic { hash, number, index });
+				}
+			}
+		}
+		Ok(Some(found))
+	}
+}
@@ -0,0 +1,249 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Opt-in index of the history of the storage keys.
+//!
+//! Once enabled with [`Backend::set_key_history`], an entry is written for every main or child
+//! trie key changed by a block imported with its state, holding the new value of the key. The
+//! entries of a block are removed along with its body when it's pruned.
+//!
+//! The first block from which the changes of all the canonical blocks are indexed is kept in the
+//! `meta` column. Only the changes made from it on are returned, and the index starts over
+//! whenever a block is imported while it's disabled, or without its state.
+//!
+//! An entry is keyed by `tag ++ (child storage key) ++ key ++ number ++ hash`, with the storage
+//! keys SCALE-encoded and the block number big-endian, so the entries of a key are ordered by
+//! block number. The list of the entries of a block is stored under `BLOCK_TAG ++ lookup key`.
+
+use std::sync::atomic::Ordering;
+
+use codec::{Decode, Encode};
+use sc_client_api::backend::KeyChange;
+use sp_blockchain::{Error as ClientError, HeaderBackend, Result as ClientResult};
+use sp_core::storage::{ChildInfo, StorageData, StorageKey};
+use sp_database::Transaction;
+use sp_runtime::traits::{Block as BlockT, NumberFor, One, Saturating};
+use sp_state_machine::{ChildStorageCollection, StorageCollection};
+
+use crate::{
+	columns,
+	utils::{self, meta_keys},
+	Backend, DbHash,
+};
+
+/// Tag of the entries of the main trie keys.
+const MAIN_TAG: u8 = 0;
+/// Tag of the entries of the child trie keys.
+const CHILD_TAG: u8 = 1;
+/// Tag of the lists of the entries written for a block.
+const BLOCK_TAG: u8 = 2;
+
+/// The entries are iterated by ranges of `2^BUCKET_BITS` blocks, which allows to start iterating
+/// at a given block number without a seek.
+const BUCKET_BITS: u32 = 16;
+
+fn key_prefix(child_storage_key: Option<&[u8]>, key: &[u8]) -> Vec<u8> {
+	let mut prefix = match child_storage_key {
+		Some(child_storage_key) => {
+			let mut prefix = vec![CHILD_TAG];
+			child_storage_key.encode_to(&mut prefix);
+			prefix
+		},
+		None => vec![MAIN_TAG],
+	};
+	key.encode_to(&mut prefix);
+	prefix
+}
+
+fn block_key(lookup_key: &[u8]) -> Vec<u8> {
+	let mut key = vec![BLOCK_TAG];
+	key.extend_from_slice(lookup_key);
+	key
+}
+
+// Decodes the `number ++ hash` suffix of an entry.
+fn decode_entry<Block: BlockT>(suffix: &[u8]) -> Option<(u64, Block::Hash)> {
+	if suffix.len() < 4 {
+		return None
+	}
+	let number = u32::from_be_bytes(suffix[..4].try_into().ok()?);
+	let hash = Block::Hash::decode(&mut &suffix[4..]).ok()?;
+	Some((number.into(), hash))
+}
+
+impl<Block: BlockT> Backend<Block> {
+	/// Index the history of the storage keys changed by the blocks imported from now on,
+	/// enabling [`sc_client_api::Backend::storage_history`].
+	///
+	/// The changes made by blocks imported before the index was enabled, or while it was
+	/// disabled, aren't indexed.
+	pub fn set_key_history(&self, enabled: bool) {
+		self.key_history.store(enabled, Ordering::Relaxed);
+	}
+
+	/// Index the storage changes of the block `number`, if it's imported with its state.
+	pub(crate) fn index_key_history(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		number: NumberFor<Block>,
+		lookup_key: &[u8],
+		changes: Option<(&StorageCollection, &ChildStorageCollection)>,
+	) {
+		let enabled = self.key_history.load(Ordering::Relaxed);
+		self.update_index_start(
+			transaction,
+			&self.key_history_start,
+			meta_keys::KEY_HISTORY_START,
+			enabled && changes.is_some(),
+			number,
+		);
+		let (true, Some((main, children))) = (enabled, changes) else { return };
+
+		let changes =
+			main.iter()
+				.map(|(key, value)| (None, key, value))
+				.chain(children.iter().flat_map(|(child_storage_key, changes)| {
+					changes
+						.iter()
+						.map(move |(key, value)| (Some(&child_storage_key[..]), key, value))
+				}));
+		let mut entries = Vec::new();
+		for (child_storage_key, key, value) in changes {
+			let mut entry = key_prefix(child_storage_key, key);
+			entry.extend_from_slice(lookup_key);
+			transaction.set_from_vec(columns::KEY_HISTORY, &entry, value.encode());
+			entries.push(entry);
+		}
+		if !entries.is_empty() {
+			transaction.set_from_vec(
+				columns::KEY_HISTORY,
+				&block_key(lookup_key),
+				entries.encode(),
+			);
+		}
+	}
+
+	/// The first block from which the changes of all the canonical blocks are indexed, if the
+	/// index is enabled. Until a block is indexed, that's the block following the best block.
+	pub(crate) fn key_history_coverage(&self) -> Option<NumberFor<Block>> {
+		if !self.key_history.load(Ordering::Relaxed) {
+			return None
+		}
+		let start = *self.key_history_start.read();
+		Some(start.unwrap_or_else(|| self.blockchain.info().best_number.saturating_add(One::one())))
+	}
+
+	pub(crate) fn remove_key_history(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		lookup_key: &[u8],
+	) -> ClientResult<()> {
+		let block_key = block_key(lookup_key);
+		let Some(entries) = self.storage.db.get(columns::KEY_HISTORY, &block_key) else {
+			return Ok(())
+		};
+		let entries = Vec::<Vec<u8>>::decode(&mut &entries[..]).map_err(|err| {
+			ClientError::Backend(format!("Error decoding key history entries: {}", err))
+		})?;
+		for entry in entries {
+			transaction.remove(columns::KEY_HISTORY, &entry);
+		}
+		transaction.remove(columns::KEY_HISTORY, &block_key);
+		Ok(())
+	}
+
+	pub(crate) fn query_key_history(
+		&self,
+		child_info: Option<&ChildInfo>,
+		key: &StorageKey,
+		from: NumberFor<Block>,
+		to: NumberFor<Block>,
+		limit: usize,
+	) -> ClientResult<Option<Vec<KeyChange<Block>>>> {
+		let Some(start) = self.key_history_coverage() else { return Ok(None) };
+
+		let prefix = key_prefix(child_info.map(|child_info| child_info.storage_key()), &key.0);
+		let best = self.blockchain.info().best_number.min(to);
+		let best: u64 = utils::number_index_key(best).map(u32::from_be_bytes)?.into();
+		// Only the changes made since the index started are returned.
+		let mut cursor: u64 = match utils::number_index_key(from.max(start)) {
+			Ok(from) => u32::from_be_bytes(from).into(),
+			// Beyond any block which can be stored.
+			Err(_) => return Ok(Some(Vec::new())),
+		};
+
+		let mut found = Vec::new();
+		while found.len() < limit && cursor <= best {
+			let bucket = cursor >> BUCKET_BITS;
+			let mut bucket_prefix = prefix.clone();
+			bucket_prefix.extend_from_slice(&(bucket as u16).to_be_bytes());
+
+			// Collect the entries, then keep those of the canonical blocks. The database isn't
+			// read while iterating.
+			let wanted = limit - found.len();
+			let mut entries: Vec<(u64, Block::Hash, Vec<u8>)> = Vec::new();
+			let mut exhausted = true;
+			let supported = self.storage.db.iter_prefix_while(
+				columns::KEY_HISTORY,
+				&bucket_prefix,
+				&mut |entry| {
+					let Some((number, hash)) =
+						entry.key.and_then(|key| decode_entry::<Block>(&key[prefix.len()..]))
+					else {
+						return true
+					};
+					if number < cursor {
+						return true
+					}
+					if number > best {
+						return false
+					}
+					// The entries of the forks of a block are kept together.
+					if entries.len() >= wanted && entries.last().map_or(false, |l| l.0 != number) {
+						exhausted = false;
+						return false
+					}
+					entries.push((number, hash, entry.value.to_vec()));
+					true
+				},
+			);
+			if !supported {
+				return Err(ClientError::Backend(
+					"The database doesn't support iterating over the key history".into(),
+				))
+			}
+
+			cursor = match entries.last() {
+				Some(last) if !exhausted => last.0 + 1,
+				_ => (bucket + 1) << BUCKET_BITS,
+			};
+			for (number, hash, value) in entries {
+				let number: NumberFor<Block> = (number as u32).into();
+				if self.blockchain.hash(number)? != Some(hash) {
+					continue
+				}
+				let value = Option::<Vec<u8>>::decode(&mut &value[..]).map_err(|err| {
+					ClientError::Backend(format!("Error decoding key history entry: {}", err))
+				})?;
+				found.push(KeyChange { hash, number, value: value.map(StorageData) });
+			}
+		}
+		Ok(Some(found))
+	}
+}
@@ -33,11 +33,20 @@ pub mod offchain;
 
 pub mod bench;
 
+mod check;
 mod children;
+mod cold_storage;
+mod compression;
+mod extrinsic_index;
+mod key_history;
 mod parity_db;
 mod pinned_blocks_cache;
+mod pruning_migration;
 mod record_stats_state;
+mod snapshot;
+mod state_leases;
 mod stats;
+mod storage_diff;
 #[cfg(any(feature = "rocksdb", test))]
 mod upgrade;
 mod utils;
@@ -49,10 +58,12 @@ use std::{
 	collections::{HashMap, HashSet},
 	io,
 	path::{Path, PathBuf},
-	sync::Arc,
+	sync::{atomic::AtomicBool, Arc},
 };
 
 use crate::{
+	cold_storage::ColdStorage,
+	compression::CompressedDb,
 	pinned_blocks_cache::PinnedBlocksCache,
 	record_stats_state::RecordStatsState,
 	stats::StateUsageStats,
@@ -60,8 +71,13 @@ use crate::{
 };
 use codec::{Decode, Encode};
 use hash_db::Prefix;
+use prometheus_endpoint::Registry;
+use pruning_migration::StatePruningMigration;
 use sc_client_api::{
-	backend::NewBlockState,
+	backend::{
+		BlockStorageDiff, ExtrinsicIndexKey, IndexedExtrinsic, KeyChange, NewBlockState, Snapshots,
+		StateLeases, StorageDiff,
+	},
 	leaves::{FinalizationOutcome, LeafSet},
 	utils::is_descendent_of,
 	IoInfo, MemoryInfo, MemorySize, UsageInfo,
@@ -98,6 +114,11 @@ pub use sc_state_db::PruningMode;
 pub use sp_database::Database;
 
 pub use bench::BenchmarkingState;
+pub use check::{ConsistencyReport, Inconsistency, Repair};
+pub use extrinsic_index::ExtrinsicIndexFn;
+pub use pruning_migration::{StatePruningMigrationPhase, StatePruningMigrationProgress};
+pub use snapshot::{SnapshotChain, SnapshotHeader, SNAPSHOT_MAGIC, SNAPSHOT_VERSION};
+pub use state_leases::StateLeaseSettings;
 
 const CACHE_HEADERS: usize = 8;
 
@@ -308,6 +329,41 @@ pub struct DatabaseSettings {
 	///
 	/// NOTE: only finalized blocks are subject for removal!
 	pub blocks_pruning: BlocksPruning,
+	/// Compression of the block bodies and justifications.
+	pub compression: CompressionSettings,
+	/// Where to move the bodies and justifications of the old finalized blocks, if anywhere.
+	pub cold_storage: Option<ColdStorageSettings>,
+}
+
+/// Compression of the block bodies and justifications, with zstd.
+///
+/// Only the values written while the compression is enabled are compressed. The values remain
+/// readable whatever the settings. As ParityDB compresses these columns already, this mostly
+/// benefits RocksDB, or ParityDB when a dictionary is used.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompressionSettings {
+	/// Compression level of the block bodies, `None` to store them uncompressed.
+	pub body: Option<i32>,
+	/// Compression level of the justifications, `None` to store them uncompressed.
+	pub justifications: Option<i32>,
+	/// Size of the dictionary used to compress the block bodies, if any.
+	///
+	/// The dictionary is trained once, on the extrinsics of the blocks imported after the
+	/// compression of the bodies is enabled. It's then kept in the database.
+	pub dictionary_size: Option<usize>,
+}
+
+/// Settings of the cold database, where the bodies and justifications of the old finalized
+/// blocks are moved.
+///
+/// The moved blocks are still read through the backend. The cold database must stay available
+/// as long as the main database is used.
+#[derive(Debug, Clone)]
+pub struct ColdStorageSettings {
+	/// Where to find the cold database.
+	pub source: DatabaseSource,
+	/// Blocks are moved once finalized for this many blocks.
+	pub after: u32,
 }
 
 /// Block pruning settings.
@@ -319,6 +375,13 @@ pub enum BlocksPruning {
 	KeepFinalized,
 	/// Keep N recent finalized blocks.
 	Some(u32),
+	/// Keep the finalized blocks which are at most this old, relative to the last finalized block.
+	///
+	/// The age of a block is derived from [`BlockRetention::timestamp`], without which no block is
+	/// pruned. Blocks without a timestamp, such as the genesis block, are kept.
+	MaxAge(std::time::Duration),
+	/// Keep the most recent finalized blocks whose stored bodies take at most this many bytes.
+	MaxBodySize(u64),
 }
 
 impl BlocksPruning {
@@ -326,11 +389,70 @@ impl BlocksPruning {
 	pub fn is_archive(&self) -> bool {
 		match *self {
 			BlocksPruning::KeepAll | BlocksPruning::KeepFinalized => true,
-			BlocksPruning::Some(_) => false,
+			BlocksPruning::Some(_) | BlocksPruning::MaxAge(_) | BlocksPruning::MaxBodySize(_) =>
+				false,
 		}
 	}
 }
 
+/// Returns the timestamp of a block in milliseconds, usually read from its timestamp inherent.
+pub type BlockTimestampFn<Block> = Arc<
+	dyn Fn(&<Block as BlockT>::Header, &[<Block as BlockT>::Extrinsic]) -> Option<u64>
+		+ Send
+		+ Sync,
+>;
+
+/// Returns `true` if the body and justifications of a block have to be kept.
+pub type KeepBlockFn<Block> =
+	Arc<dyn Fn(&<Block as BlockT>::Header, &[<Block as BlockT>::Extrinsic]) -> bool + Send + Sync>;
+
+/// Chain specific hooks deciding which finalized blocks are pruned, see
+/// [`Backend::set_block_retention`].
+pub struct BlockRetention<Block: BlockT> {
+	/// Timestamp of a block, required by [`BlocksPruning::MaxAge`].
+	pub timestamp: Option<BlockTimestampFn<Block>>,
+	/// Blocks matching this predicate are never pruned, whatever the [`BlocksPruning`]. Their
+	/// bodies don't count towards [`BlocksPruning::MaxBodySize`].
+	pub keep: Option<KeepBlockFn<Block>>,
+}
+
+impl<Block: BlockT> Default for BlockRetention<Block> {
+	fn default() -> Self {
+		Self { timestamp: None, keep: None }
+	}
+}
+
+impl<Block: BlockT> Clone for BlockRetention<Block> {
+	fn clone(&self) -> Self {
+		Self { timestamp: self.timestamp.clone(), keep: self.keep.clone() }
+	}
+}
+
+/// Progress of [`BlocksPruning::MaxAge`] and [`BlocksPruning::MaxBodySize`], stored under
+/// `meta_keys::BLOCKS_RETENTION`.
+#[derive(Debug, Clone, Default, Encode, Decode)]
+struct RetentionCursor<N> {
+	/// The oldest finalized block which wasn't pruned yet.
+	oldest: N,
+	/// The blocks before this one are accounted for in `body_size`.
+	accounted_until: N,
+	/// Size of the bodies from `oldest` to `accounted_until`, excluding the kept blocks.
+	body_size: u64,
+}
+
+/// A finalized block considered for pruning.
+struct RetainedBlock<Hash> {
+	hash: Hash,
+	/// Size of the stored body.
+	size: u64,
+	keep: bool,
+	timestamp: Option<u64>,
+}
+
+/// Maximum number of blocks visited by a finalization with [`BlocksPruning::MaxAge`] or
+/// [`BlocksPruning::MaxBodySize`]. The remaining ones are visited by the next finalizations.
+const MAX_RETENTION_STEPS: u32 = 1024;
+
 /// Where to find the database..
 #[derive(Debug, Clone)]
 pub enum DatabaseSource {
@@ -434,6 +556,12 @@ pub(crate) mod columns {
 	/// Transactions
 	pub const TRANSACTION: u32 = 11;
 	pub const BODY_INDEX: u32 = 12;
+	/// Opt-in secondary indexes of extrinsics by signer and by call.
+	pub const EXTRINSIC_INDEX: u32 = 13;
+	/// Opt-in record of the storage changes of the blocks.
+	pub const STORAGE_DIFF: u32 = 14;
+	/// Opt-in index of the history of the storage keys.
+	pub const KEY_HISTORY: u32 = 15;
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -852,6 +980,8 @@ pub struct BlockImportOperation<Block: BlockT> {
 	finalized_blocks: Vec<(Block::Hash, Option<Justification>)>,
 	set_head: Option<Block::Hash>,
 	commit_state: bool,
+	/// Whether the storage changes of the block were provided with `update_storage`.
+	storage_updated: bool,
 	index_ops: Vec<IndexOperation>,
 }
 
@@ -976,6 +1106,7 @@ impl<Block: BlockT> sc_client_api::backend::BlockImportOperation<Block>
 	) -> ClientResult<()> {
 		self.storage_updates = update;
 		self.child_storage_updates = child_update;
+		self.storage_updated = true;
 		Ok(())
 	}
 
@@ -1122,12 +1253,23 @@ pub struct Backend<Block: BlockT> {
 	blockchain: BlockchainDb<Block>,
 	canonicalization_delay: u64,
 	import_lock: Arc<RwLock<()>>,
-	is_archive: bool,
 	blocks_pruning: BlocksPruning,
 	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo)>,
 	state_usage: Arc<StateUsageStats>,
 	genesis_state: RwLock<Option<Arc<DbGenesisStorage<Block>>>>,
 	shared_trie_cache: Option<sp_trie::cache::SharedTrieCache<HashingFor<Block>>>,
+	state_pruning_migration: Mutex<Option<StatePruningMigration>>,
+	block_retention: RwLock<BlockRetention<Block>>,
+	retention_cursor: Mutex<Option<RetentionCursor<NumberFor<Block>>>>,
+	extrinsic_indexer: RwLock<Option<ExtrinsicIndexFn<Block>>>,
+	extrinsic_index_start: RwLock<Option<NumberFor<Block>>>,
+	storage_diffs: AtomicBool,
+	storage_diffs_start: RwLock<Option<NumberFor<Block>>>,
+	key_history: AtomicBool,
+	key_history_start: RwLock<Option<NumberFor<Block>>>,
+	state_leases: Mutex<Option<state_leases::Leases<Block::Hash>>>,
+	compression: Arc<CompressedDb>,
+	cold_storage: Option<ColdStorage>,
 }
 
 impl<Block: BlockT> Backend<Block> {
@@ -1176,7 +1318,9 @@ impl<Block: BlockT> Backend<Block> {
 		let db = sp_database::as_database(db);
 		let state_pruning = match blocks_pruning {
 			BlocksPruning::KeepAll => PruningMode::ArchiveAll,
-			BlocksPruning::KeepFinalized => PruningMode::ArchiveCanonical,
+			BlocksPruning::KeepFinalized |
+			BlocksPruning::MaxAge(_) |
+			BlocksPruning::MaxBodySize(_) => PruningMode::ArchiveCanonical,
 			BlocksPruning::Some(n) => PruningMode::blocks_pruning(n),
 		};
 		let db_setting = DatabaseSettings {
@@ -1184,6 +1328,8 @@ impl<Block: BlockT> Backend<Block> {
 			state_pruning: Some(state_pruning),
 			source: DatabaseSource::Custom { db, require_create_flag: true },
 			blocks_pruning,
+			compression: Default::default(),
+			cold_storage: None,
 		};
 
 		Self::new(db_setting, canonicalization_delay).expect("failed to create test-db")
@@ -1212,6 +1358,16 @@ impl<Block: BlockT> Backend<Block> {
 		config: &DatabaseSettings,
 		should_init: bool,
 	) -> ClientResult<Self> {
+		let (cold_storage, db) = match &config.cold_storage {
+			Some(settings) => {
+				let (cold_storage, db) = ColdStorage::open::<Block>(db, settings)?;
+				(Some(cold_storage), db)
+			},
+			None => (None, db),
+		};
+		let compression = Arc::new(CompressedDb::new(db, &config.compression)?);
+		let db: Arc<dyn Database<DbHash>> = compression.clone();
+
 		let mut db_init_transaction = Transaction::new();
 
 		let requested_state_pruning = config.state_pruning.clone();
@@ -1228,8 +1384,6 @@ impl<Block: BlockT> Backend<Block> {
 
 		apply_state_commit(&mut db_init_transaction, state_db_init_commit_set);
 
-		let state_pruning_used = state_db.pruning_mode();
-		let is_archive_pruning = state_pruning_used.is_archive();
 		let blockchain = BlockchainDb::new(db.clone())?;
 
 		let storage_db =
@@ -1243,7 +1397,6 @@ impl<Block: BlockT> Backend<Block> {
 			blockchain,
 			canonicalization_delay,
 			import_lock: Default::default(),
-			is_archive: is_archive_pruning,
 			io_stats: FrozenForDuration::new(std::time::Duration::from_secs(1)),
 			state_usage: Arc::new(StateUsageStats::new()),
 			blocks_pruning: config.blocks_pruning,
@@ -1251,6 +1404,27 @@ impl<Block: BlockT> Backend<Block> {
 			shared_trie_cache: config.trie_cache_maximum_size.map(|maximum_size| {
 				SharedTrieCache::new(sp_trie::cache::CacheSize::new(maximum_size))
 			}),
+			state_pruning_migration: Mutex::new(None),
+			block_retention: Default::default(),
+			retention_cursor: Mutex::new(None),
+			extrinsic_indexer: RwLock::new(None),
+			extrinsic_index_start: RwLock::new(utils::read_index_start(
+				&*db,
+				meta_keys::EXTRINSIC_INDEX_START,
+			)?),
+			storage_diffs: AtomicBool::new(false),
+			storage_diffs_start: RwLock::new(utils::read_index_start(
+				&*db,
+				meta_keys::STORAGE_DIFFS_START,
+			)?),
+			key_history: AtomicBool::new(false),
+			key_history_start: RwLock::new(utils::read_index_start(
+				&*db,
+				meta_keys::KEY_HISTORY_START,
+			)?),
+			state_leases: Mutex::new(None),
+			compression,
+			cold_storage,
 		};
 
 		// Older DB versions have no last state key. Check if the state is available and set it.
@@ -1273,9 +1447,105 @@ impl<Block: BlockT> Backend<Block> {
 
 		db.commit(db_init_transaction)?;
 
+		// Resume removing the history of a database switched to a constrained pruning mode.
+		backend.start_state_pruning_migration(None)?;
+
 		Ok(backend)
 	}
 
+	/// Switch the state pruning mode of the database while it's open.
+	///
+	/// A constrained pruning window may be resized, and a database created with
+	/// `PruningMode::ArchiveCanonical` may be switched to a constrained mode. The state of the
+	/// blocks before the last canonicalized one is then removed in the background, see
+	/// [`Self::state_pruning_migration_progress`]. The removal is resumed if the database is
+	/// reopened before it's done. The new mode has to be used as `DatabaseSettings::state_pruning`
+	/// from then on, as the stored mode is checked when the database is opened.
+	pub fn migrate_state_pruning(
+		&self,
+		mode: PruningMode,
+		prometheus_registry: Option<&Registry>,
+	) -> ClientResult<()> {
+		let _lock = self.import_lock.write();
+		let state_meta_db = StateMetaDb(self.storage.db.clone());
+		let commit = self
+			.storage
+			.state_db
+			.set_pruning_mode(mode, state_meta_db.clone())
+			.map_err(sp_blockchain::Error::from_state_db)?;
+		let mut transaction = Transaction::new();
+		apply_state_commit(&mut transaction, commit);
+		if let Err(e) = self.storage.db.commit(transaction) {
+			self.storage
+				.state_db
+				.reset(state_meta_db)
+				.map_err(sp_blockchain::Error::from_state_db)?;
+			return Err(e.into())
+		}
+		self.start_state_pruning_migration(prometheus_registry)
+	}
+
+	/// Set the hooks deciding which finalized blocks are pruned.
+	///
+	/// They are required by [`BlocksPruning::MaxAge`], and allow to keep blocks of interest with
+	/// any pruning mode which prunes finalized blocks.
+	pub fn set_block_retention(&self, retention: BlockRetention<Block>) {
+		*self.block_retention.write() = retention;
+	}
+
+	/// Track the first block of an opt-in index from which all the canonical blocks are covered,
+	/// when importing the block `number` while the index is `enabled` or not.
+	///
+	/// The start is set, and persisted under `key`, when the index gets enabled. It's cleared when
+	/// a block following it is imported while the index is disabled, as the index then no longer
+	/// covers all the blocks since.
+	fn update_index_start(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		start: &RwLock<Option<NumberFor<Block>>>,
+		key: &[u8],
+		enabled: bool,
+		number: NumberFor<Block>,
+	) {
+		let mut start = start.write();
+		if !enabled {
+			if start.map_or(false, |start| number >= start) {
+				*start = None;
+				transaction.remove(columns::META, key);
+			}
+		} else if start.is_none() {
+			// The blocks following the best block are all indexed, not the forks of older blocks.
+			let info = self.blockchain.info();
+			let first = if info.best_hash == Default::default() {
+				number
+			} else {
+				number.max(info.best_number + One::one())
+			};
+			*start = Some(first);
+			transaction.set_from_vec(columns::META, key, first.encode());
+		}
+	}
+
+	/// Progress of the removal of the historical state started by
+	/// [`Self::migrate_state_pruning`], if any was started since the database was opened.
+	pub fn state_pruning_migration_progress(&self) -> Option<StatePruningMigrationProgress> {
+		let migration = self.state_pruning_migration.lock();
+		migration.as_ref().map(StatePruningMigration::progress)
+	}
+
+	fn start_state_pruning_migration(
+		&self,
+		prometheus_registry: Option<&Registry>,
+	) -> ClientResult<()> {
+		if let Some((hash, _)) = self.storage.state_db.pruning_migration() {
+			let root = self.blockchain.header_metadata(hash)?.state_root;
+			debug!(target: "db", "Removing the state before {:?}", hash);
+			*self.state_pruning_migration.lock() =
+				Some(StatePruningMigration::start(self.storage.clone(), root, prometheus_registry));
+		}
+		Ok(())
+	}
+
 	/// Handle setting head within a transaction. `route_to` should be the last
 	/// block that existed in the database. `best_to` should be the best block
 	/// to be set.
@@ -1479,6 +1749,7 @@ impl<Block: BlockT> Backend<Block> {
 			last_finalized_num = *block_header.number();
 		}
 
+		let mut state_written = 0;
 		let imported = if let Some(pending_block) = operation.pending_block {
 			let hash = pending_block.header.hash();
 
@@ -1504,6 +1775,9 @@ impl<Block: BlockT> Backend<Block> {
 
 			transaction.set_from_vec(columns::HEADER, &lookup_key, pending_block.header.encode());
 			if let Some(body) = pending_block.body {
+				self.index_extrinsics(&mut transaction, number, &lookup_key, &body);
+				self.compression
+					.sample_extrinsics(&mut transaction, body.iter().map(Encode::encode));
 				// If we have any index operations we save block in the new format with indexed
 				// extrinsic headers Otherwise we save the body as a single blob.
 				if operation.index_ops.is_empty() {
@@ -1524,6 +1798,22 @@ impl<Block: BlockT> Backend<Block> {
 					justifications.encode(),
 				);
 			}
+			self.record_storage_diff(
+				&mut transaction,
+				number,
+				&lookup_key,
+				operation
+					.storage_updated
+					.then(|| (&operation.storage_updates, &operation.child_storage_updates)),
+			);
+			self.index_key_history(
+				&mut transaction,
+				number,
+				&lookup_key,
+				operation
+					.storage_updated
+					.then(|| (&operation.storage_updates, &operation.child_storage_updates)),
+			);
 
 			if number.is_zero() {
 				transaction.set(columns::META, meta_keys::GENESIS_HASH, hash.as_ref());
@@ -1574,6 +1864,7 @@ impl<Block: BlockT> Backend<Block> {
 					}
 				}
 				self.state_usage.tally_writes_nodes(ops, bytes);
+				state_written = bytes;
 				self.state_usage.tally_removed_nodes(removal, bytes_removal);
 
 				let mut ops: u64 = 0;
@@ -1688,9 +1979,15 @@ impl<Block: BlockT> Backend<Block> {
 					);
 				}
 
-				if let Some((mut start, end)) = block_gap {
-					if number == start {
-						start += One::one();
+				if let Some((mut start, mut end)) = block_gap {
+					// Gap sync imports the blocks downwards from the end of the gap, so that the
+					// history it doesn't download remains recorded as a gap.
+					if number == start || number == end {
+						if number == end {
+							end -= One::one();
+						} else {
+							start += One::one();
+						}
 						utils::insert_number_to_key_mapping(
 							&mut transaction,
 							columns::KEY_LOOKUP,
@@ -1757,6 +2054,8 @@ impl<Block: BlockT> Backend<Block> {
 			}
 		}
 
+		let state_leases = self.update_state_leases(&mut transaction, state_written);
+
 		self.storage.db.commit(transaction)?;
 
 		// Apply all in-memory state changes.
@@ -1773,6 +2072,9 @@ impl<Block: BlockT> Backend<Block> {
 			self.blockchain.update_meta(m);
 		}
 		self.blockchain.update_block_gap(block_gap);
+		if let Some(state_leases) = state_leases {
+			state_leases.committed();
+		}
 
 		Ok(())
 	}
@@ -1819,6 +2121,7 @@ impl<Block: BlockT> Backend<Block> {
 			&new_displaced,
 			current_transaction_justifications,
 		)?;
+		self.move_blocks_to_cold(transaction, f_num)?;
 
 		Ok(())
 	}
@@ -1838,26 +2141,34 @@ impl<Block: BlockT> Backend<Block> {
 				let keep = std::cmp::max(blocks_pruning, 1);
 				if finalized_number >= keep.into() {
 					let number = finalized_number.saturating_sub(keep.into());
-
-					// Before we prune a block, check if it is pinned
-					if let Some(hash) = self.blockchain.hash(number)? {
-						self.blockchain.insert_persisted_body_if_pinned(hash)?;
-
-						// If the block was finalized in this transaction, it will not be in the db
-						// yet.
-						if let Some(justification) =
-							current_transaction_justifications.remove(&hash)
-						{
-							self.blockchain.insert_justifications_if_pinned(hash, justification);
-						} else {
-							self.blockchain.insert_persisted_justifications_if_pinned(hash)?;
-						}
-					};
-
-					self.prune_block(transaction, BlockId::<Block>::number(number))?;
+					let retention = self.block_retention.read().clone();
+					match self.blockchain.hash(number)? {
+						Some(hash) => {
+							let kept = self
+								.retained_block(number, &retention, false)?
+								.map_or(false, |block| block.keep);
+							if !kept {
+								self.prune_finalized_block(
+									transaction,
+									hash,
+									number,
+									current_transaction_justifications,
+								)?;
+							}
+						},
+						None => self.prune_block(transaction, BlockId::<Block>::number(number))?,
+					}
 				}
 				self.prune_displaced_branches(transaction, finalized_hash, displaced)?;
 			},
+			BlocksPruning::MaxAge(_) | BlocksPruning::MaxBodySize(_) => {
+				self.prune_blocks_by_retention(
+					transaction,
+					finalized_number,
+					current_transaction_justifications,
+				)?;
+				self.prune_displaced_branches(transaction, finalized_hash, displaced)?;
+			},
 			BlocksPruning::KeepFinalized => {
 				self.prune_displaced_branches(transaction, finalized_hash, displaced)?;
 			},
@@ -1865,6 +2176,145 @@ impl<Block: BlockT> Backend<Block> {
 		Ok(())
 	}
 
+	fn prune_finalized_block(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		hash: Block::Hash,
+		number: NumberFor<Block>,
+		current_transaction_justifications: &mut HashMap<Block::Hash, Justification>,
+	) -> ClientResult<()> {
+		// Before we prune a block, check if it is pinned
+		self.blockchain.insert_persisted_body_if_pinned(hash)?;
+
+		// If the block was finalized in this transaction, it will not be in the db yet.
+		if let Some(justification) = current_transaction_justifications.remove(&hash) {
+			self.blockchain.insert_justifications_if_pinned(hash, justification);
+		} else {
+			self.blockchain.insert_persisted_justifications_if_pinned(hash)?;
+		}
+
+		self.prune_block(transaction, BlockId::<Block>::number(number))
+	}
+
+	// Prunes the finalized blocks which exceed `BlocksPruning::MaxAge` or
+	// `BlocksPruning::MaxBodySize`, from the oldest one.
+	fn prune_blocks_by_retention(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		finalized_number: NumberFor<Block>,
+		current_transaction_justifications: &mut HashMap<Block::Hash, Justification>,
+	) -> ClientResult<()> {
+		let retention = self.block_retention.read().clone();
+		let with_timestamp = match self.blocks_pruning {
+			BlocksPruning::MaxAge(_) if retention.timestamp.is_none() => return Ok(()),
+			BlocksPruning::MaxAge(_) => true,
+			_ => false,
+		};
+
+		let mut cached_cursor = self.retention_cursor.lock();
+		let mut cursor = match cached_cursor.take() {
+			Some(cursor) => cursor,
+			None => match self.storage.db.get(columns::META, meta_keys::BLOCKS_RETENTION) {
+				Some(cursor) => RetentionCursor::decode(&mut &cursor[..]).map_err(|err| {
+					sp_blockchain::Error::Backend(format!(
+						"Error decoding retention cursor: {}",
+						err
+					))
+				})?,
+				None => Default::default(),
+			},
+		};
+
+		// The last finalized block may not be in the database yet, and is never pruned.
+		let mut steps = 0;
+		while cursor.accounted_until < finalized_number && steps < MAX_RETENTION_STEPS {
+			let number = cursor.accounted_until;
+			if let Some(block) = self.retained_block(number, &retention, false)? {
+				if !block.keep {
+					cursor.body_size += block.size;
+				}
+			}
+			cursor.accounted_until = number + One::one();
+			steps += 1;
+		}
+		let fully_accounted = cursor.accounted_until == finalized_number;
+
+		let now = if with_timestamp {
+			match self.retained_block(finalized_number, &retention, true)? {
+				Some(RetainedBlock { timestamp: Some(timestamp), .. }) => Some(timestamp),
+				_ => self
+					.retained_block(finalized_number.saturating_sub(One::one()), &retention, true)?
+					.and_then(|block| block.timestamp),
+			}
+		} else {
+			None
+		};
+
+		while cursor.oldest < cursor.accounted_until && steps < MAX_RETENTION_STEPS {
+			let number = cursor.oldest;
+			steps += 1;
+			if let Some(block) = self.retained_block(number, &retention, with_timestamp)? {
+				let expired = match self.blocks_pruning {
+					BlocksPruning::MaxAge(max_age) => match (block.timestamp, now) {
+						(Some(timestamp), Some(now)) =>
+							now.saturating_sub(timestamp) > max_age.as_millis() as u64,
+						_ => false,
+					},
+					BlocksPruning::MaxBodySize(max_size) =>
+						fully_accounted && cursor.body_size > max_size,
+					_ => false,
+				};
+				// Blocks without a timestamp are kept, like the blocks matching the predicate.
+				let kept = block.keep || (with_timestamp && block.timestamp.is_none());
+				if !kept {
+					if !expired {
+						break
+					}
+					cursor.body_size = cursor.body_size.saturating_sub(block.size);
+					self.prune_finalized_block(
+						transaction,
+						block.hash,
+						number,
+						current_transaction_justifications,
+					)?;
+				}
+			}
+			cursor.oldest = number + One::one();
+		}
+
+		transaction.set_from_vec(columns::META, meta_keys::BLOCKS_RETENTION, cursor.encode());
+		*cached_cursor = Some(cursor);
+		Ok(())
+	}
+
+	// Returns the finalized block `number` if its body is stored.
+	fn retained_block(
+		&self,
+		number: NumberFor<Block>,
+		retention: &BlockRetention<Block>,
+		with_timestamp: bool,
+	) -> ClientResult<Option<RetainedBlock<Block::Hash>>> {
+		let Some(hash) = self.blockchain.hash(number)? else { return Ok(None) };
+		let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+		let size = [columns::BODY, columns::BODY_INDEX]
+			.into_iter()
+			.filter_map(|column| self.storage.db.value_size(column, &lookup_key))
+			.fold(None, |total: Option<u64>, size| Some(total.unwrap_or(0) + size as u64));
+		let Some(size) = size else { return Ok(None) };
+
+		let mut block = RetainedBlock { hash, size, keep: false, timestamp: None };
+		let timestamp = retention.timestamp.as_ref().filter(|_| with_timestamp);
+		if retention.keep.is_some() || timestamp.is_some() {
+			if let (Some(header), Some(body)) =
+				(self.blockchain.header(hash)?, self.blockchain.body(hash)?)
+			{
+				block.keep = retention.keep.as_ref().map_or(false, |keep| keep(&header, &body));
+				block.timestamp = timestamp.and_then(|timestamp| timestamp(&header, &body));
+			}
+		}
+		Ok(Some(block))
+	}
+
 	fn prune_displaced_branches(
 		&self,
 		transaction: &mut Transaction<DbHash>,
@@ -1894,6 +2344,13 @@ impl<Block: BlockT> Backend<Block> {
 		id: BlockId<Block>,
 	) -> ClientResult<()> {
 		debug!(target: "db", "Removing block #{}", id);
+		if let Some(lookup_key) =
+			utils::block_id_to_lookup_key(&*self.storage.db, columns::KEY_LOOKUP, id)?
+		{
+			self.remove_indexed_extrinsics(transaction, &lookup_key)?;
+			self.remove_storage_diff(transaction, &lookup_key);
+			self.remove_key_history(transaction, &lookup_key)?;
+		}
 		utils::remove_from_db(
 			transaction,
 			&*self.storage.db,
@@ -2078,6 +2535,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			finalized_blocks: Vec::new(),
 			set_head: None,
 			commit_state: false,
+			storage_updated: false,
 			index_ops: Default::default(),
 		})
 	}
@@ -2102,6 +2560,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		self.state_usage.merge_sm(usage);
 
 		if let Err(e) = self.try_commit_operation(operation) {
+			*self.retention_cursor.lock() = None;
 			let state_meta_db = StateMetaDb(self.storage.db.clone());
 			self.storage
 				.state_db
@@ -2133,7 +2592,10 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			&mut current_transaction_justifications,
 		)?;
 
-		self.storage.db.commit(transaction)?;
+		if let Err(e) = self.storage.db.commit(transaction) {
+			*self.retention_cursor.lock() = None;
+			return Err(e.into())
+		}
 		self.blockchain.update_meta(m);
 		Ok(())
 	}
@@ -2469,7 +2931,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 	}
 
 	fn have_state_at(&self, hash: Block::Hash, number: NumberFor<Block>) -> bool {
-		if self.is_archive {
+		if self.storage.state_db.pruning_mode().is_archive() {
 			match self.blockchain.header_metadata(hash) {
 				Ok(header) => sp_state_machine::Storage::get(
 					self.storage.as_ref(),
@@ -2498,6 +2960,47 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		}
 	}
 
+	fn indexed_extrinsics(
+		&self,
+		key: &ExtrinsicIndexKey,
+		from: NumberFor<Block>,
+		limit: usize,
+	) -> ClientResult<Option<Vec<IndexedExtrinsic<Block>>>> {
+		self.query_indexed_extrinsics(key, from, limit)
+	}
+
+	fn extrinsic_index_start(&self) -> Option<NumberFor<Block>> {
+		self.extrinsic_index_coverage()
+	}
+
+	fn storage_diff(&self, hash: Block::Hash, prefix: &[u8]) -> ClientResult<Option<StorageDiff>> {
+		self.query_storage_diff(hash, prefix)
+	}
+
+	fn storage_diffs(
+		&self,
+		from: NumberFor<Block>,
+		to: NumberFor<Block>,
+		prefix: &[u8],
+	) -> ClientResult<Option<Vec<BlockStorageDiff<Block>>>> {
+		self.query_storage_diffs(from, to, prefix)
+	}
+
+	fn storage_history(
+		&self,
+		child_info: Option<&ChildInfo>,
+		key: &sp_core::storage::StorageKey,
+		from: NumberFor<Block>,
+		to: NumberFor<Block>,
+		limit: usize,
+	) -> ClientResult<Option<Vec<KeyChange<Block>>>> {
+		self.query_key_history(child_info, key, from, to, limit)
+	}
+
+	fn key_history_start(&self) -> Option<NumberFor<Block>> {
+		self.key_history_coverage()
+	}
+
 	fn get_import_lock(&self) -> &RwLock<()> {
 		&self.import_lock
 	}
@@ -2551,6 +3054,18 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			self.blockchain.unpin(hash);
 		}
 	}
+
+	fn snapshots(&self) -> Option<&dyn Snapshots<Block>> {
+		Some(self)
+	}
+
+	fn state_leases(&self) -> Option<&dyn StateLeases<Block>> {
+		if self.state_leases.lock().is_some() {
+			Some(self)
+		} else {
+			None
+		}
+	}
 }
 
 impl<Block: BlockT> sc_client_api::backend::LocalBackend<Block> for Backend<Block> {}
@@ -2626,6 +3141,34 @@ pub(crate) mod tests {
 		Ok(header.hash())
 	}
 
+	/// Insert a block with the given storage changes. The forks get a different extrinsics root.
+	pub fn insert_block_with_storage_changes(
+		backend: &Backend<Block>,
+		number: u64,
+		parent_hash: H256,
+		changes: &StorageDiff,
+		state: NewBlockState,
+	) -> H256 {
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, parent_hash).unwrap();
+		let (root, overlay) = op.old_state.storage_root(
+			vec![(parent_hash.as_ref(), Some(parent_hash.as_ref()))].into_iter(),
+			StateVersion::V1,
+		);
+		op.update_db_storage(overlay).unwrap();
+		let header = Header {
+			number,
+			parent_hash,
+			state_root: root.into(),
+			digest: Default::default(),
+			extrinsics_root: H256::from_low_u64_be(u64::from(state == NewBlockState::Normal)),
+		};
+		op.update_storage(changes.main.clone(), changes.children.clone()).unwrap();
+		op.set_block_data(header.clone(), Some(vec![]), None, None, state).unwrap();
+		backend.commit_operation(op).unwrap();
+		header.hash()
+	}
+
 	pub fn insert_header_no_head(
 		backend: &Backend<Block>,
 		number: u64,
@@ -2702,6 +3245,8 @@ pub(crate) mod tests {
 				state_pruning: Some(PruningMode::blocks_pruning(1)),
 				source: DatabaseSource::Custom { db: backing, require_create_flag: false },
 				blocks_pruning: BlocksPruning::KeepFinalized,
+				compression: Default::default(),
+				cold_storage: None,
 			},
 			0,
 		)
@@ -3392,6 +3937,69 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn prune_blocks_by_retention() {
+		let body_size = vec![ExtrinsicWrapper::from(0u64)].encode().len() as u64;
+		let pruning_modes = [
+			BlocksPruning::MaxBodySize(2 * body_size),
+			BlocksPruning::MaxAge(std::time::Duration::from_secs(2)),
+		];
+
+		for pruning_mode in pruning_modes {
+			let backend = Backend::<Block>::new_test_with_tx_storage(pruning_mode, 0);
+			backend.set_block_retention(BlockRetention {
+				timestamp: Some(Arc::new(|header: &Header, _: &[ExtrinsicWrapper<u64>]| {
+					(header.number > 0).then(|| header.number * 1000)
+				})),
+				keep: Some(Arc::new(move |header: &Header, _: &[ExtrinsicWrapper<u64>]| {
+					matches!(pruning_mode, BlocksPruning::MaxBodySize(_)) && header.number == 1
+				})),
+			});
+
+			let mut blocks = Vec::new();
+			let mut prev_hash = Default::default();
+			for i in 0..6 {
+				let hash = insert_block(
+					&backend,
+					i,
+					prev_hash,
+					None,
+					Default::default(),
+					vec![i.into()],
+					None,
+				)
+				.unwrap();
+				blocks.push(hash);
+				prev_hash = hash;
+			}
+
+			{
+				let mut op = backend.begin_operation().unwrap();
+				backend.begin_state_operation(&mut op, blocks[5]).unwrap();
+				for i in 1..6 {
+					op.mark_finalized(blocks[i], None).unwrap();
+				}
+				backend.commit_operation(op).unwrap();
+			}
+
+			let bc = backend.blockchain();
+			if matches!(pruning_mode, BlocksPruning::MaxBodySize(_)) {
+				// The kept block doesn't count towards the size.
+				assert_eq!(None, bc.body(blocks[0]).unwrap());
+				assert_eq!(Some(vec![1.into()]), bc.body(blocks[1]).unwrap());
+				assert_eq!(None, bc.body(blocks[2]).unwrap());
+			} else {
+				// The genesis block has no timestamp.
+				assert_eq!(Some(vec![0.into()]), bc.body(blocks[0]).unwrap());
+				assert_eq!(None, bc.body(blocks[1]).unwrap());
+				assert_eq!(None, bc.body(blocks[2]).unwrap());
+			}
+			for i in 3..6 {
+				assert_eq!(Some(vec![(i as u64).into()]), bc.body(blocks[i]).unwrap());
+			}
+		}
+	}
+
 	#[test]
 	fn prune_blocks_on_finalize() {
 		let pruning_modes =
@@ -3521,6 +4129,480 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn compresses_and_moves_blocks_to_cold_storage() {
+		let hot = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let cold = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let backend = Backend::<Block>::new(
+			DatabaseSettings {
+				trie_cache_maximum_size: None,
+				state_pruning: Some(PruningMode::ArchiveCanonical),
+				source: DatabaseSource::Custom { db: hot.clone(), require_create_flag: true },
+				blocks_pruning: BlocksPruning::KeepFinalized,
+				compression: CompressionSettings {
+					body: Some(3),
+					justifications: None,
+					dictionary_size: None,
+				},
+				cold_storage: Some(ColdStorageSettings {
+					source: DatabaseSource::Custom { db: cold.clone(), require_create_flag: false },
+					after: 2,
+				}),
+			},
+			0,
+		)
+		.unwrap();
+
+		let body = |i: u64| vec![ExtrinsicWrapper::from(i); 100];
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		for i in 0..6 {
+			let hash =
+				insert_block(&backend, i, prev_hash, None, Default::default(), body(i), None)
+					.unwrap();
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, blocks[5]).unwrap();
+		for i in 1..6 {
+			op.mark_finalized(blocks[i], None).unwrap();
+		}
+		backend.commit_operation(op).unwrap();
+
+		for (i, hash) in blocks.iter().enumerate() {
+			let lookup_key = utils::number_and_hash_to_lookup_key(i as u64, hash).unwrap();
+			// The blocks finalized for at least 2 blocks were moved, as compressed.
+			let (moved, kept) = if i <= 3 { (&cold, &hot) } else { (&hot, &cold) };
+			let stored = moved.get(columns::BODY, &lookup_key).unwrap();
+			assert_eq!(stored[0], 0xff);
+			assert!(stored.len() < body(i as u64).encode().len());
+			assert_eq!(kept.get(columns::BODY, &lookup_key), None);
+
+			assert_eq!(backend.blockchain().body(*hash).unwrap(), Some(body(i as u64)));
+		}
+	}
+
+	#[test]
+	fn reads_uncompressed_values_starting_like_a_compact_length() {
+		let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		let open = |compression| {
+			Backend::<Block>::new(
+				DatabaseSettings {
+					trie_cache_maximum_size: None,
+					state_pruning: Some(PruningMode::ArchiveAll),
+					source: DatabaseSource::Custom { db: db.clone(), require_create_flag: false },
+					blocks_pruning: BlocksPruning::KeepAll,
+					compression,
+					cold_storage: None,
+				},
+				0,
+			)
+			.unwrap()
+		};
+
+		// The length of this body is encoded as `[0xfe, 0x00, 0x01, 0x00]`, and its random
+		// extrinsics can't be compressed, so it's stored as is.
+		let body = (0..0x403f).map(|_| rand::random::<u64>().into()).collect::<Vec<_>>();
+		let backend = open(CompressionSettings::default());
+		let hash = insert_block(
+			&backend,
+			0,
+			Default::default(),
+			None,
+			Default::default(),
+			body.clone(),
+			None,
+		)
+		.unwrap();
+		assert_eq!(body.encode()[..4], [0xfe, 0x00, 0x01, 0x00]);
+		drop(backend);
+
+		let backend = open(CompressionSettings {
+			body: Some(3),
+			justifications: Some(3),
+			dictionary_size: None,
+		});
+		assert_eq!(backend.blockchain().body(hash).unwrap(), Some(body));
+	}
+
+	#[test]
+	fn indexes_extrinsics() {
+		let backend = Backend::<Block>::new_test_with_tx_storage(BlocksPruning::Some(2), 0);
+		let all = ExtrinsicIndexKey::Call { pallet: 1, call: 2 };
+		let signer = |i: u64| ExtrinsicIndexKey::Signer(ExtrinsicWrapper::from(i).encode());
+		assert_eq!(backend.indexed_extrinsics(&all, 0, 10).unwrap(), None);
+
+		backend.set_extrinsic_indexer(Arc::new(|extrinsic: &ExtrinsicWrapper<u64>| {
+			vec![
+				ExtrinsicIndexKey::Call { pallet: 1, call: 2 },
+				ExtrinsicIndexKey::Signer(extrinsic.encode()),
+			]
+		}));
+
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		for i in 0..5 {
+			let body = vec![i.into(), 100.into()];
+			let hash =
+				insert_block(&backend, i, prev_hash, None, Default::default(), body, None).unwrap();
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+		insert_block(&backend, 2, blocks[1], None, H256::random(), vec![2.into()], None).unwrap();
+
+		let found = |key: &ExtrinsicIndexKey, from: u64, limit: usize| {
+			backend
+				.indexed_extrinsics(key, from, limit)
+				.unwrap()
+				.unwrap()
+				.into_iter()
+				.map(|e| (e.hash, e.number, e.index))
+				.collect::<Vec<_>>()
+		};
+
+		// The last block isn't split, and the non-canonical blocks are ignored.
+		assert_eq!(
+			found(&all, 0, 3),
+			vec![(blocks[0], 0, 0), (blocks[0], 0, 1), (blocks[1], 1, 0), (blocks[1], 1, 1)],
+		);
+		assert_eq!(found(&all, 3, 1), vec![(blocks[3], 3, 0), (blocks[3], 3, 1)]);
+		assert_eq!(found(&signer(2), 0, 10), vec![(blocks[2], 2, 0)]);
+		assert_eq!(found(&signer(100), 4, 10), vec![(blocks[4], 4, 1)]);
+		assert!(found(&ExtrinsicIndexKey::Call { pallet: 1, call: 3 }, 0, 10).is_empty());
+
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, blocks[4]).unwrap();
+		for i in 1..5 {
+			op.mark_finalized(blocks[i], None).unwrap();
+		}
+		backend.commit_operation(op).unwrap();
+
+		// The entries of the pruned blocks are removed.
+		assert_eq!(
+			found(&all, 0, 10),
+			vec![(blocks[3], 3, 0), (blocks[3], 3, 1), (blocks[4], 4, 0), (blocks[4], 4, 1)],
+		);
+		assert!(found(&signer(2), 0, 10).is_empty());
+		let mut entries = 0;
+		backend.storage.db.iter_column_while(columns::EXTRINSIC_INDEX, &mut |_| {
+			entries += 1;
+			true
+		});
+		// Two calls, two signers and the list of the entries, for each of the two blocks left.
+		assert_eq!(entries, 10);
+		assert_eq!(backend.extrinsic_index_start(), Some(0));
+
+		// A block imported without the indexer breaks the coverage, which starts over.
+		let indexer = backend.extrinsic_indexer.write().take();
+		let hash =
+			insert_block(&backend, 5, blocks[4], None, Default::default(), vec![], None).unwrap();
+		assert_eq!(backend.extrinsic_index_start(), None);
+		*backend.extrinsic_indexer.write() = indexer;
+		assert_eq!(backend.extrinsic_index_start(), None);
+		insert_block(&backend, 6, hash, None, Default::default(), vec![6.into()], None).unwrap();
+		assert_eq!(backend.extrinsic_index_start(), Some(6));
+		assert_eq!(
+			utils::read_index_start::<u64>(&*backend.storage.db, meta_keys::EXTRINSIC_INDEX_START)
+				.unwrap(),
+			Some(6),
+		);
+	}
+
+	#[test]
+	fn records_storage_diffs() {
+		let backend = Backend::<Block>::new_test_with_tx_storage(BlocksPruning::Some(2), 0);
+		let genesis =
+			insert_block(&backend, 0, Default::default(), None, Default::default(), vec![], None)
+				.unwrap();
+		assert_eq!(backend.storage_diffs(0, 10, &[]).unwrap(), None);
+
+		backend.set_storage_diffs(true);
+
+		let diff = |i: u8| StorageDiff {
+			main: vec![(vec![1, i], Some(vec![i])), (vec![2], None)],
+			children: vec![(b"child".to_vec(), vec![(vec![1, 0], Some(vec![i]))])],
+		};
+
+		let insert = |number: u64, parent_hash: H256, diff: &StorageDiff, state: NewBlockState| {
+			insert_block_with_storage_changes(&backend, number, parent_hash, diff, state)
+		};
+		let mut blocks = vec![genesis];
+		for i in 1..4 {
+			blocks.push(insert(i, blocks[i as usize - 1], &diff(i as u8), NewBlockState::Best));
+		}
+		insert(2, blocks[1], &diff(9), NewBlockState::Normal);
+
+		assert_eq!(backend.storage_diff(genesis, &[]).unwrap(), None);
+		assert_eq!(backend.storage_diff(blocks[1], &[]).unwrap(), Some(diff(1)));
+		assert_eq!(
+			backend.storage_diff(blocks[2], &[1]).unwrap(),
+			Some(StorageDiff {
+				main: vec![(vec![1, 2], Some(vec![2]))],
+				children: vec![(b"child".to_vec(), vec![(vec![1, 0], Some(vec![2]))])],
+			}),
+		);
+		assert_eq!(
+			backend.storage_diff(blocks[2], &[2]).unwrap(),
+			Some(StorageDiff { main: vec![(vec![2], None)], children: vec![] }),
+		);
+
+		// The non-canonical blocks are ignored, as are the blocks without a matching change.
+		let found = |from: u64, to: u64, prefix: &[u8]| {
+			backend
+				.storage_diffs(from, to, prefix)
+				.unwrap()
+				.unwrap()
+				.into_iter()
+				.map(|diff| (diff.hash, diff.number))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(found(0, 10, &[]), vec![(blocks[1], 1), (blocks[2], 2), (blocks[3], 3)]);
+		assert_eq!(found(2, 2, &[1, 2]), vec![(blocks[2], 2)]);
+		assert!(found(0, 10, &[1, 9]).is_empty());
+		assert!(found(0, 10, &[3]).is_empty());
+
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, blocks[3]).unwrap();
+		for hash in &blocks[1..] {
+			op.mark_finalized(*hash, None).unwrap();
+		}
+		backend.commit_operation(op).unwrap();
+
+		// The diffs of the pruned blocks are removed.
+		assert_eq!(backend.storage_diff(blocks[1], &[]).unwrap(), None);
+		assert_eq!(found(0, 10, &[]), vec![(blocks[2], 2), (blocks[3], 3)]);
+		let mut entries = 0;
+		backend.storage.db.iter_column_while(columns::STORAGE_DIFF, &mut |_| {
+			entries += 1;
+			true
+		});
+		assert_eq!(entries, 2);
+
+		// A block imported without recording its changes restarts the record after it.
+		backend.set_storage_diffs(false);
+		blocks.push(insert(4, blocks[3], &diff(4), NewBlockState::Best));
+		assert_eq!(backend.storage_diffs(0, 10, &[]).unwrap(), None);
+		assert_eq!(backend.storage_diff(blocks[3], &[]).unwrap(), None);
+		backend.set_storage_diffs(true);
+		blocks.push(insert(5, blocks[4], &diff(5), NewBlockState::Best));
+		assert_eq!(backend.storage_diff(blocks[3], &[]).unwrap(), None);
+		assert_eq!(found(0, 10, &[]), vec![(blocks[5], 5)]);
+	}
+
+	#[test]
+	fn indexes_key_history() {
+		let backend = Backend::<Block>::new_test_with_tx_storage(BlocksPruning::Some(2), 0);
+		let genesis =
+			insert_block(&backend, 0, Default::default(), None, Default::default(), vec![], None)
+				.unwrap();
+		let key = sp_core::storage::StorageKey(vec![1]);
+		assert_eq!(backend.storage_history(None, &key, 0, 10, 10).unwrap(), None);
+
+		backend.set_key_history(true);
+
+		// Blocks 1 to 3 set the key, in the main trie and in a child trie for block 3, and block 4
+		// removes it.
+		let changes = |i: u8| StorageDiff {
+			main: vec![(vec![1], (i < 4).then(|| vec![i])), (vec![1, 0], Some(vec![i]))],
+			children: if i == 3 {
+				vec![(b"child".to_vec(), vec![(vec![1], Some(vec![i]))])]
+			} else {
+				vec![]
+			},
+		};
+		let mut blocks = vec![genesis];
+		for i in 1..5 {
+			blocks.push(insert_block_with_storage_changes(
+				&backend,
+				i,
+				blocks[i as usize - 1],
+				&changes(i as u8),
+				NewBlockState::Best,
+			));
+		}
+		insert_block_with_storage_changes(
+			&backend,
+			2,
+			blocks[1],
+			&changes(9),
+			NewBlockState::Normal,
+		);
+
+		let history = |child_info: Option<&ChildInfo>, from: u64, to: u64, limit: usize| {
+			backend
+				.storage_history(child_info, &key, from, to, limit)
+				.unwrap()
+				.unwrap()
+				.into_iter()
+				.map(|change| (change.hash, change.number, change.value.map(|value| value.0)))
+				.collect::<Vec<_>>()
+		};
+
+		// The non-canonical blocks are ignored.
+		assert_eq!(
+			history(None, 0, 10, 10),
+			vec![
+				(blocks[1], 1, Some(vec![1])),
+				(blocks[2], 2, Some(vec![2])),
+				(blocks[3], 3, Some(vec![3])),
+				(blocks[4], 4, None),
+			],
+		);
+		assert_eq!(history(None, 2, 10, 1), vec![(blocks[2], 2, Some(vec![2]))]);
+		assert_eq!(
+			history(None, 0, 2, 10),
+			vec![(blocks[1], 1, Some(vec![1])), (blocks[2], 2, Some(vec![2]))],
+		);
+		let child_info = ChildInfo::new_default(b"child");
+		assert_eq!(history(Some(&child_info), 0, 10, 10), vec![(blocks[3], 3, Some(vec![3]))]);
+
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, blocks[4]).unwrap();
+		for hash in &blocks[1..] {
+			op.mark_finalized(*hash, None).unwrap();
+		}
+		backend.commit_operation(op).unwrap();
+
+		// The entries of the pruned blocks are removed.
+		assert_eq!(
+			history(None, 0, 10, 10),
+			vec![(blocks[3], 3, Some(vec![3])), (blocks[4], 4, None)]
+		);
+		let mut entries = 0;
+		backend.storage.db.iter_column_while(columns::KEY_HISTORY, &mut |_| {
+			entries += 1;
+			true
+		});
+		// Two keys and the list of the entries for each of the two blocks left, and the child key.
+		assert_eq!(entries, 7);
+		assert_eq!(backend.key_history_start(), Some(1));
+
+		// A block imported without indexing its changes restarts the index after it.
+		backend.set_key_history(false);
+		blocks.push(insert_block_with_storage_changes(
+			&backend,
+			5,
+			blocks[4],
+			&changes(5),
+			NewBlockState::Best,
+		));
+		assert_eq!(backend.key_history_start(), None);
+		backend.set_key_history(true);
+		assert_eq!(backend.key_history_start(), Some(6));
+		blocks.push(insert_block_with_storage_changes(
+			&backend,
+			6,
+			blocks[5],
+			&changes(6),
+			NewBlockState::Best,
+		));
+		assert_eq!(history(None, 0, 10, 10), vec![(blocks[6], 6, None)]);
+	}
+
+	#[test]
+	fn persists_state_leases() {
+		let backend = Backend::<Block>::new_test(1, 0);
+		let genesis =
+			insert_block(&backend, 0, Default::default(), None, Default::default(), vec![], None)
+				.unwrap();
+		assert!(backend.state_leases().is_none());
+
+		let settings = StateLeaseSettings {
+			max_duration: std::time::Duration::from_secs(3600),
+			max_leases_per_owner: 1,
+			disk_budget: None,
+		};
+		backend.enable_state_leases(settings.clone(), None).unwrap();
+		let leases = backend.state_leases().unwrap();
+
+		let lease = leases.acquire_lease("a", genesis, std::time::Duration::from_secs(60)).unwrap();
+		assert_eq!((lease.id, lease.owner.as_str(), lease.hash), (0, "a", genesis));
+		// The owner already holds as many leases as allowed.
+		assert!(leases.acquire_lease("a", genesis, std::time::Duration::from_secs(60)).is_err());
+		// The lease would last too long.
+		assert!(leases
+			.acquire_lease("b", genesis, std::time::Duration::from_secs(7200))
+			.is_err());
+		// The lease expires right away.
+		leases.acquire_lease("b", genesis, std::time::Duration::ZERO).unwrap();
+		assert_eq!(leases.leases().unwrap(), vec![lease.clone()]);
+
+		let mut blocks = vec![genesis];
+		for i in 1..4 {
+			blocks.push(insert_block_with_storage_changes(
+				&backend,
+				i,
+				blocks[i as usize - 1],
+				&Default::default(),
+				NewBlockState::Best,
+			));
+		}
+		assert!(backend.have_state_at(genesis, 0));
+
+		// The lease pins the state again once the database is reopened.
+		let backend = Backend::<Block>::new(
+			DatabaseSettings {
+				trie_cache_maximum_size: Some(16 * 1024 * 1024),
+				state_pruning: Some(PruningMode::blocks_pruning(1)),
+				source: DatabaseSource::Custom {
+					db: backend.storage.db.clone(),
+					require_create_flag: false,
+				},
+				blocks_pruning: BlocksPruning::Some(1),
+				compression: Default::default(),
+				cold_storage: None,
+			},
+			0,
+		)
+		.unwrap();
+		backend.enable_state_leases(settings, None).unwrap();
+		let leases = backend.state_leases().unwrap();
+		assert_eq!(leases.leases().unwrap(), vec![lease.clone()]);
+
+		blocks.push(insert_block_with_storage_changes(
+			&backend,
+			4,
+			blocks[3],
+			&Default::default(),
+			NewBlockState::Best,
+		));
+		assert!(backend.have_state_at(genesis, 0));
+
+		// Once revoked, the state is pruned along with the next block.
+		assert!(leases.revoke_lease(lease.id).unwrap());
+		assert!(!leases.revoke_lease(lease.id).unwrap());
+		blocks.push(insert_block_with_storage_changes(
+			&backend,
+			5,
+			blocks[4],
+			&Default::default(),
+			NewBlockState::Best,
+		));
+		assert!(!backend.have_state_at(genesis, 0));
+
+		// The lease is revoked once the state written after it exceeds the disk budget.
+		backend
+			.enable_state_leases(
+				StateLeaseSettings { disk_budget: Some(0), ..Default::default() },
+				None,
+			)
+			.unwrap();
+		let leases = backend.state_leases().unwrap();
+		leases
+			.acquire_lease("a", blocks[5], std::time::Duration::from_secs(60))
+			.unwrap();
+		insert_block_with_storage_changes(
+			&backend,
+			6,
+			blocks[5],
+			&Default::default(),
+			NewBlockState::Best,
+		);
+		assert!(leases.leases().unwrap().is_empty());
+	}
+
 	#[test]
 	fn prune_blocks_on_finalize_and_reorg() {
 		//	0 - 1b
@@ -4433,4 +5515,211 @@ pub(crate) mod tests {
 		backend.unpin_block(fork_hash_3);
 		assert!(bc.body(fork_hash_3).unwrap().is_none());
 	}
+
+	#[test]
+	fn migrate_archive_state_to_constrained_pruning() {
+		let backend = Backend::<Block>::new_test_with_tx_storage(BlocksPruning::KeepFinalized, 0);
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		for i in 0..5 {
+			let hash = insert_header(&backend, i, prev_hash, None, Default::default());
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+		let old_root = backend.blockchain.header_metadata(blocks[1]).unwrap().state_root;
+		let old_root_key = sp_trie::prefixed_key::<BlakeTwo256>(&old_root, EMPTY_PREFIX);
+		assert!(backend.storage.db.get(columns::STATE, &old_root_key).is_some());
+		assert!(backend.have_state_at(blocks[1], 1));
+
+		backend.migrate_state_pruning(PruningMode::blocks_pruning(1), None).unwrap();
+		assert!(!backend.have_state_at(blocks[1], 1));
+		assert!(backend.state_at(blocks[1]).is_err());
+
+		let start = std::time::Instant::now();
+		let progress = loop {
+			let progress = backend.state_pruning_migration_progress().unwrap();
+			if progress.phase == StatePruningMigrationPhase::Done {
+				break progress
+			}
+			assert!(start.elapsed() < std::time::Duration::from_secs(10));
+			std::thread::sleep(std::time::Duration::from_millis(10));
+		};
+		assert!(progress.removed_nodes > 0);
+		assert_eq!(backend.storage.state_db.pruning_migration(), None);
+		assert!(backend.storage.db.get(columns::STATE, &old_root_key).is_none());
+
+		let state = backend.state_at(blocks[4]).unwrap();
+		assert_eq!(state.storage(blocks[3].as_ref()).unwrap(), Some(blocks[3].as_ref().to_vec()));
+		drop(state);
+
+		// The window is pruned again once the migration is done.
+		assert!(backend.have_state_at(blocks[3], 3));
+		insert_header(&backend, 5, blocks[4], None, Default::default());
+		assert!(!backend.have_state_at(blocks[3], 3));
+	}
+
+	#[test]
+	fn snapshot_export_and_restore() {
+		use sc_client_api::backend::AuxStore;
+		use sp_core::storage::StorageChild;
+		use std::{collections::BTreeMap, io::Cursor};
+
+		let child_info = ChildInfo::new_default(b"child");
+		let state = |number: u64| {
+			let mut top = BTreeMap::new();
+			top.insert(b"const".to_vec(), vec![7; 64]);
+			top.insert(b"number".to_vec(), number.encode());
+			if number < 2 {
+				top.insert(b"early".to_vec(), vec![1]);
+			}
+			let mut data = BTreeMap::new();
+			data.insert(b"number".to_vec(), number.encode());
+			let mut children_default = HashMap::new();
+			children_default.insert(
+				child_info.storage_key().to_vec(),
+				StorageChild { data, child_info: child_info.clone() },
+			);
+			Storage { top, children_default }
+		};
+		let contents = |storage: Storage| {
+			let children = storage.children_default.into_iter().map(|(k, c)| (k, c.data));
+			(storage.top, children.collect::<BTreeMap<_, _>>())
+		};
+
+		let backend = Backend::<Block>::new_test(1000, 0);
+		let mut hashes = Vec::new();
+		let import = |hashes: &mut Vec<_>, number: u64| {
+			let mut op = backend.begin_operation().unwrap();
+			let state_root = op.reset_storage(state(number), StateVersion::V1).unwrap();
+			let header = Header {
+				number,
+				parent_hash: hashes.last().cloned().unwrap_or_default(),
+				state_root,
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			hashes.push(header.hash());
+			op.set_block_data(header, Some(vec![number.into()]), None, None, NewBlockState::Final)
+				.unwrap();
+			backend.commit_operation(op).unwrap();
+		};
+		let read_state = |inputs: &[&[u8]]| {
+			SnapshotChain::<Block, _>::open(inputs.iter().copied(), |_, _, _| Ok(()))
+				.and_then(|chain| chain.read_state())
+		};
+
+		import(&mut hashes, 0);
+		import(&mut hashes, 1);
+		AuxStore::insert_aux(
+			&backend,
+			&[(&b"aux"[..], &b"old"[..]), (&b"gone"[..], &b"1"[..])],
+			&[],
+		)
+		.unwrap();
+
+		let mut full = Vec::new();
+		let no_base: Option<SnapshotChain<Block, &[u8]>> = None;
+		assert_eq!(backend.export_snapshot(no_base, &mut full).unwrap().number, 1);
+		let (storage, aux) = read_state(&[&full[..]]).unwrap();
+		assert_eq!(contents(storage), contents(state(1)));
+		assert!(aux.contains(&(b"gone".to_vec(), b"1".to_vec())));
+
+		import(&mut hashes, 2);
+		import(&mut hashes, 3);
+		AuxStore::insert_aux(&backend, &[(&b"aux"[..], &b"value"[..])], &[&b"gone"[..]]).unwrap();
+
+		let mut incremental = Vec::new();
+		let base = SnapshotChain::<Block, _>::open([&full[..]], |_, _, _| Ok(())).unwrap();
+		let header = backend.export_snapshot(Some(base), &mut incremental).unwrap();
+		assert_eq!((header.number, header.base), (3, Some((hashes[1], 1))));
+		let (storage, aux) = read_state(&[&full[..], &incremental[..]]).unwrap();
+		assert_eq!(contents(storage), contents(state(3)));
+		assert!(aux.contains(&(b"aux".to_vec(), b"value".to_vec())));
+		assert!(aux.iter().all(|(key, _)| key != b"gone"));
+
+		// Snapshots must be complete and in order.
+		assert!(read_state(&[&incremental[..]]).is_err());
+		assert!(read_state(&[&full[..], &incremental[..incremental.len() - 1]]).is_err());
+
+		let restored = Backend::<Block>::new_test(1000, 0);
+		let mut inputs = [Cursor::new(full), Cursor::new(incremental)];
+		assert!(matches!(
+			restored.import_snapshot(&mut inputs, |_| Ok(StateVersion::V0)),
+			Err(sp_blockchain::Error::InvalidStateRoot)
+		));
+		assert_eq!(restored.blockchain().info().genesis_hash, Default::default());
+
+		for input in inputs.iter_mut() {
+			input.set_position(0);
+		}
+		assert_eq!(
+			restored.import_snapshot(&mut inputs, |_| Ok(StateVersion::V1)).unwrap(),
+			header
+		);
+		let info = restored.blockchain().info();
+		assert_eq!(info.genesis_hash, hashes[0]);
+		assert_eq!((info.finalized_number, info.finalized_hash), (3, hashes[3]));
+		assert_eq!(restored.blockchain().body(hashes[2]).unwrap(), Some(vec![2.into()]));
+		let state = restored.state_at(hashes[3]).unwrap();
+		assert_eq!(state.storage(b"number").unwrap(), Some(3u64.encode()));
+		assert_eq!(state.storage(b"early").unwrap(), None);
+		assert_eq!(state.child_storage(&child_info, b"number").unwrap(), Some(3u64.encode()));
+		assert_eq!(AuxStore::get_aux(&restored, b"aux").unwrap(), Some(b"value".to_vec()));
+	}
+
+	#[test]
+	fn check_consistency_rebuilds_leaves_and_truncates() {
+		let backend = Backend::<Block>::new_test(10, 10);
+		let mut hashes =
+			vec![insert_header(&backend, 0, Default::default(), None, Default::default())];
+		for number in 1..5 {
+			let parent_hash = hashes[number as usize - 1];
+			hashes.push(insert_header(&backend, number, parent_hash, None, Default::default()));
+		}
+		backend.finalize_block(hashes[2], None).unwrap();
+		assert_eq!(backend.check_consistency(false).unwrap().inconsistencies, Vec::new());
+
+		// Lose the leaves.
+		let mut transaction = Transaction::new();
+		let leaves = Vec::<(u64, Vec<H256>)>::new();
+		transaction.set_from_vec(columns::META, meta_keys::LEAF_PREFIX, leaves.encode());
+		backend.storage.db.commit(transaction).unwrap();
+		let report = backend.check_consistency(true).unwrap();
+		assert_eq!(
+			report.inconsistencies,
+			vec![Inconsistency::MissingLeaf { hash: hashes[4], number: 4 }],
+		);
+		assert_eq!(report.repairs, vec![Repair::RebuildLeaves { leaves: 1 }]);
+		assert_eq!(backend.check_consistency(false).unwrap().inconsistencies, Vec::new());
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![hashes[4]]);
+
+		// Lose the header of the best block.
+		let mut transaction = Transaction::new();
+		let key = utils::number_and_hash_to_lookup_key(4u64, hashes[4]).unwrap();
+		transaction.remove(columns::HEADER, &key);
+		backend.storage.db.commit(transaction).unwrap();
+		let report = backend.check_consistency(true).unwrap();
+		assert!(report
+			.inconsistencies
+			.contains(&Inconsistency::UnknownBlock { reference: "best block", hash: hashes[4] }));
+		assert!(report
+			.inconsistencies
+			.contains(&Inconsistency::InvalidCanonicalBlock { number: 4, hash: hashes[4] }));
+		assert_eq!(
+			report.repairs,
+			vec![
+				Repair::Truncate { hash: hashes[2], number: 2 },
+				Repair::RebuildLeaves { leaves: 1 }
+			],
+		);
+		assert_eq!(backend.check_consistency(false).unwrap().inconsistencies, Vec::new());
+		assert_eq!(backend.blockchain().info().best_hash, hashes[2]);
+		assert_eq!(backend.blockchain().leaves().unwrap(), vec![hashes[2]]);
+		assert_eq!(backend.blockchain().header(hashes[3]).unwrap(), None);
+
+		// The truncated blocks can be imported again.
+		assert_eq!(insert_header(&backend, 3, hashes[2], None, Default::default()), hashes[3]);
+		assert_eq!(backend.blockchain().info().best_hash, hashes[3]);
+		assert_eq!(backend.check_consistency(false).unwrap().inconsistencies, Vec::new());
+	}
 }
@@ -21,7 +21,7 @@ use crate::{
 	utils::{DatabaseType, NUM_COLUMNS},
 };
 /// A `Database` adapter for parity-db.
-use sp_database::{error::DatabaseError, Change, ColumnId, Database, Transaction};
+use sp_database::{error::DatabaseError, Change, ColumnEntry, ColumnId, Database, Transaction};
 
 struct DbAdapter(parity_db::Db);
 
@@ -52,6 +52,7 @@ pub fn open<H: Clone + AsRef<[u8]>>(
 				columns::BODY_INDEX,
 				columns::TRANSACTION,
 				columns::JUSTIFICATIONS,
+				columns::STORAGE_DIFF,
 			];
 
 			for i in compressed {
@@ -68,6 +69,12 @@ pub fn open<H: Clone + AsRef<[u8]>>(
 			tx_col.ref_counted = true;
 			tx_col.preimage = true;
 			tx_col.uniform = true;
+
+			let index_col = &mut config.columns[columns::EXTRINSIC_INDEX as usize];
+			index_col.btree_index = true;
+
+			let history_col = &mut config.columns[columns::KEY_HISTORY as usize];
+			history_col.btree_index = true;
 		},
 	}
 
@@ -91,6 +98,10 @@ fn ref_counted_column(col: u32) -> bool {
 	col == columns::TRANSACTION || col == columns::STATE
 }
 
+fn btree_indexed_column(col: u32) -> bool {
+	col == columns::EXTRINSIC_INDEX || col == columns::KEY_HISTORY
+}
+
 impl<H: Clone + AsRef<[u8]>> Database<H> for DbAdapter {
 	fn commit(&self, transaction: Transaction<H>) -> Result<(), DatabaseError> {
 		let mut not_ref_counted_column = Vec::new();
@@ -153,6 +164,40 @@ impl<H: Clone + AsRef<[u8]>> Database<H> for DbAdapter {
 		handle_err(self.0.get_size(col as u8, key)).map(|s| s as usize)
 	}
 
+	fn iter_column_while(&self, col: ColumnId, f: &mut dyn FnMut(ColumnEntry) -> bool) -> bool {
+		// Only the hash of the key is stored, which isn't the key itself.
+		let ref_counted = ref_counted_column(col);
+		handle_err(self.0.iter_column_while(col as u8, |state| {
+			f(ColumnEntry {
+				key: None,
+				value: &state.value,
+				ref_count: ref_counted.then_some(state.rc),
+			})
+		}));
+		true
+	}
+
+	fn iter_prefix_while(
+		&self,
+		col: ColumnId,
+		prefix: &[u8],
+		f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> bool {
+		if !btree_indexed_column(col) {
+			return false
+		}
+		let mut iter = handle_err(self.0.iter(col as u8));
+		handle_err(iter.seek(prefix));
+		while let Some((key, value)) = handle_err(iter.next()) {
+			if !key.starts_with(prefix) ||
+				!f(ColumnEntry { key: Some(&key), value: &value, ref_count: None })
+			{
+				break
+			}
+		}
+		true
+	}
+
 	fn supports_ref_counting(&self) -> bool {
 		true
 	}
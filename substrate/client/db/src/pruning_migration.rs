@@ -0,0 +1,524 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Removal of the historical state of a database switched from `PruningMode::ArchiveCanonical`
+//! to a constrained pruning mode.
+//!
+//! The pruning window of a switched database starts at the last canonicalized block, so the
+//! states of the blocks before it aren't referenced by any journal. They are removed in the
+//! background by marking every node of the state the window starts at, and then sweeping the
+//! `STATE` column for nodes which aren't marked. Nodes touched by the blocks canonicalized in the
+//! meantime are left to the pruning window.
+//!
+//! To bound the memory used, the nodes are split in partitions by their hash, marked and swept one
+//! partition at a time. A partition is halved whenever its marked nodes don't fit in memory, the
+//! other half being marked and swept later by walking the state again.
+//!
+//! With ParityDB, the nodes of the marked state which were also inserted by earlier blocks keep the
+//! references of those blocks. They are never removed once pruned by the window.
+
+use std::{
+	collections::HashSet,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use hash_db::{Hasher, Prefix};
+use log::{debug, error, info};
+use parking_lot::Mutex;
+use prometheus_endpoint::{register, Gauge, PrometheusError, Registry, U64};
+use sp_core::storage::{well_known_keys, ChildInfo};
+use sp_database::{ColumnEntry, Transaction};
+use sp_runtime::traits::{Block as BlockT, HashingFor};
+use sp_state_machine::{Backend as StateBackend, DBValue, IterArgs};
+use sp_trie::prefixed_key;
+
+use crate::{apply_state_commit, columns, DbStateBuilder, StorageDb};
+
+const LOG_TARGET: &str = "db::pruning-migration";
+
+/// How often to check whether the states pinned before the migration are released.
+const PIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of nodes collected for removal by a single sweep over the column.
+const MAX_SWEEP_CANDIDATES: usize = 1 << 20;
+
+/// Number of nodes removed per database transaction.
+const REMOVAL_BATCH: usize = 16 * 1024;
+
+/// Maximum number of marked nodes kept in memory, above which the partition being marked is
+/// halved.
+#[cfg(not(test))]
+const MAX_MARKED_NODES: usize = 1 << 22;
+#[cfg(test)]
+const MAX_MARKED_NODES: usize = 4;
+
+/// Nodes whose database key, read as a number, is `residue` modulo `modulus`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Partition {
+	residue: u64,
+	modulus: u64,
+}
+
+impl Partition {
+	/// Partition of all the nodes.
+	const ALL: Self = Self { residue: 0, modulus: 1 };
+
+	fn contains(&self, key: &[u8]) -> bool {
+		// The keys end with the hash of the node, so their last bytes are uniformly distributed.
+		let tail = &key[key.len().saturating_sub(8)..];
+		let mut value = [0; 8];
+		value[8 - tail.len()..].copy_from_slice(tail);
+		u64::from_be_bytes(value) % self.modulus == self.residue
+	}
+
+	/// Splits the partition in two halves, the first being a subset of the nodes of `self` which
+	/// are in it.
+	fn split(self) -> (Self, Self) {
+		let modulus = self.modulus * 2;
+		(
+			Self { residue: self.residue, modulus },
+			Self { residue: self.residue + self.modulus, modulus },
+		)
+	}
+}
+
+/// Phase of a state pruning migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatePruningMigrationPhase {
+	/// Waiting for the states pinned before the migration started to be released.
+	WaitingForPins,
+	/// Marking the nodes of the state the pruning window starts at.
+	Marking,
+	/// Removing the nodes which weren't marked.
+	Sweeping,
+	/// The historical state was removed.
+	Done,
+	/// The migration failed, and is retried when the database is opened again.
+	Failed,
+}
+
+/// Progress of a state pruning migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatePruningMigrationProgress {
+	/// Current phase.
+	pub phase: StatePruningMigrationPhase,
+	/// Number of nodes of the state the pruning window starts at.
+	pub marked_nodes: u64,
+	/// Number of database entries visited while sweeping.
+	pub scanned_nodes: u64,
+	/// Number of historical nodes removed.
+	pub removed_nodes: u64,
+	/// Size of the historical nodes removed.
+	pub removed_bytes: u64,
+}
+
+impl Default for StatePruningMigrationProgress {
+	fn default() -> Self {
+		Self {
+			phase: StatePruningMigrationPhase::WaitingForPins,
+			marked_nodes: 0,
+			scanned_nodes: 0,
+			removed_nodes: 0,
+			removed_bytes: 0,
+		}
+	}
+}
+
+/// State pruning migration Prometheus metrics.
+struct Metrics {
+	marked_nodes: Gauge<U64>,
+	scanned_nodes: Gauge<U64>,
+	removed_nodes: Gauge<U64>,
+	removed_bytes: Gauge<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			marked_nodes: register(
+				Gauge::new(
+					"substrate_state_pruning_migration_marked_nodes",
+					"Number of nodes of the state kept by the state pruning migration",
+				)?,
+				registry,
+			)?,
+			scanned_nodes: register(
+				Gauge::new(
+					"substrate_state_pruning_migration_scanned_nodes",
+					"Number of database entries visited by the state pruning migration",
+				)?,
+				registry,
+			)?,
+			removed_nodes: register(
+				Gauge::new(
+					"substrate_state_pruning_migration_removed_nodes",
+					"Number of historical nodes removed by the state pruning migration",
+				)?,
+				registry,
+			)?,
+			removed_bytes: register(
+				Gauge::new(
+					"substrate_state_pruning_migration_removed_bytes",
+					"Size of the historical nodes removed by the state pruning migration",
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	fn report(&self, progress: &StatePruningMigrationProgress) {
+		self.marked_nodes.set(progress.marked_nodes);
+		self.scanned_nodes.set(progress.scanned_nodes);
+		self.removed_nodes.set(progress.removed_nodes);
+		self.removed_bytes.set(progress.removed_bytes);
+	}
+}
+
+/// Handle to a state pruning migration running in the background. The migration is stopped when
+/// the handle is dropped.
+pub(crate) struct StatePruningMigration {
+	progress: Arc<Mutex<StatePruningMigrationProgress>>,
+	stop: Arc<AtomicBool>,
+}
+
+impl StatePruningMigration {
+	/// Start removing the states before the block with the state `root`, the block the pruning
+	/// window of `storage` starts at.
+	pub(crate) fn start<Block: BlockT>(
+		storage: Arc<StorageDb<Block>>,
+		root: Block::Hash,
+		registry: Option<&Registry>,
+	) -> Self {
+		let progress = Arc::new(Mutex::new(StatePruningMigrationProgress::default()));
+		let stop = Arc::new(AtomicBool::new(false));
+		let metrics = registry.and_then(|registry| {
+			Metrics::register(registry)
+				.map_err(|err| {
+					log::warn!("Failed to register prometheus metrics: {}", err);
+				})
+				.ok()
+		});
+
+		let worker =
+			Worker { storage, root, progress: progress.clone(), metrics, stop: stop.clone() };
+		if let Err(e) = std::thread::Builder::new()
+			.name("state-pruning-migration".into())
+			.spawn(move || worker.run())
+		{
+			error!(target: LOG_TARGET, "Failed to spawn the state pruning migration: {}", e);
+			progress.lock().phase = StatePruningMigrationPhase::Failed;
+		}
+
+		Self { progress, stop }
+	}
+
+	/// Current progress of the migration.
+	pub(crate) fn progress(&self) -> StatePruningMigrationProgress {
+		self.progress.lock().clone()
+	}
+}
+
+impl Drop for StatePruningMigration {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::Relaxed);
+	}
+}
+
+/// Storage recording the database key of every node read which is in the partition marked.
+struct MarkingStorage<Block: BlockT> {
+	storage: Arc<StorageDb<Block>>,
+	partition: Mutex<Partition>,
+	marked: Mutex<HashSet<Vec<u8>>>,
+}
+
+impl<Block: BlockT> MarkingStorage<Block> {
+	/// Halves the partition marked while its marked nodes don't fit in memory, pushing the other
+	/// halves to `pending`.
+	fn shrink(&self, pending: &mut Vec<Partition>) {
+		let mut partition = self.partition.lock();
+		let mut marked = self.marked.lock();
+		while marked.len() > MAX_MARKED_NODES {
+			let (kept, other) = partition.split();
+			*partition = kept;
+			pending.push(other);
+			marked.retain(|key| kept.contains(key));
+		}
+	}
+}
+
+impl<Block: BlockT> sp_state_machine::Storage<HashingFor<Block>> for MarkingStorage<Block> {
+	fn get(&self, key: &Block::Hash, prefix: Prefix) -> Result<Option<DBValue>, String> {
+		let value = sp_state_machine::Storage::get(self.storage.as_ref(), key, prefix)?;
+		if value.is_some() {
+			let db_key = if self.storage.prefix_keys {
+				prefixed_key::<HashingFor<Block>>(key, prefix)
+			} else {
+				key.as_ref().to_vec()
+			};
+			if self.partition.lock().contains(&db_key) {
+				self.marked.lock().insert(db_key);
+			}
+		}
+		Ok(value)
+	}
+}
+
+struct Worker<Block: BlockT> {
+	storage: Arc<StorageDb<Block>>,
+	root: Block::Hash,
+	progress: Arc<Mutex<StatePruningMigrationProgress>>,
+	metrics: Option<Metrics>,
+	stop: Arc<AtomicBool>,
+}
+
+impl<Block: BlockT> Worker<Block> {
+	fn run(self) {
+		match self.migrate() {
+			Ok(true) => {
+				let progress =
+					self.update(|progress| progress.phase = StatePruningMigrationPhase::Done);
+				info!(
+					target: LOG_TARGET,
+					"State pruning migration done, removed {} historical nodes ({} bytes)",
+					progress.removed_nodes,
+					progress.removed_bytes,
+				);
+			},
+			Ok(false) => debug!(target: LOG_TARGET, "State pruning migration stopped"),
+			Err(e) => {
+				self.update(|progress| progress.phase = StatePruningMigrationPhase::Failed);
+				error!(target: LOG_TARGET, "State pruning migration failed: {}", e);
+			},
+		}
+	}
+
+	fn stopped(&self) -> bool {
+		self.stop.load(Ordering::Relaxed)
+	}
+
+	fn update(
+		&self,
+		f: impl FnOnce(&mut StatePruningMigrationProgress),
+	) -> StatePruningMigrationProgress {
+		let mut progress = self.progress.lock();
+		f(&mut progress);
+		if let Some(metrics) = &self.metrics {
+			metrics.report(&progress);
+		}
+		progress.clone()
+	}
+
+	/// Returns `false` if the migration was stopped before it completed.
+	fn migrate(&self) -> Result<bool, String> {
+		while self.storage.state_db.has_pre_migration_pins() {
+			if self.stopped() {
+				return Ok(false)
+			}
+			std::thread::sleep(PIN_POLL_INTERVAL);
+		}
+
+		let mut pending = vec![Partition::ALL];
+		let mut marked_nodes = 0;
+		while let Some(partition) = pending.pop() {
+			self.update(|progress| progress.phase = StatePruningMigrationPhase::Marking);
+			let (partition, mut marked) = match self.mark(partition, &mut pending, marked_nodes)? {
+				Some(marked) => marked,
+				None => return Ok(false),
+			};
+			marked_nodes += marked.len() as u64;
+
+			self.update(|progress| progress.phase = StatePruningMigrationPhase::Sweeping);
+			loop {
+				let (candidates, complete) = self.scan(partition, &marked)?;
+				if self.stopped() {
+					return Ok(false)
+				}
+				for skipped in self.remove(candidates)? {
+					marked.insert(skipped);
+				}
+				if complete {
+					break
+				}
+			}
+		}
+
+		self.storage
+			.state_db
+			.finish_pruning_migration(|commit| {
+				let mut transaction = Transaction::new();
+				apply_state_commit(&mut transaction, commit);
+				self.storage.db.commit(transaction)
+			})
+			.map_err(|e| format!("Database backend error: {:?}", e))?;
+		Ok(true)
+	}
+
+	/// Returns the database keys of the nodes of the state at `self.root` which are in
+	/// `partition`, or `None` if stopped.
+	///
+	/// The partition is halved as needed for the keys to fit in memory, in which case the halves
+	/// left to mark are pushed to `pending`. The partition actually marked is returned along with
+	/// the keys.
+	fn mark(
+		&self,
+		partition: Partition,
+		pending: &mut Vec<Partition>,
+		previously_marked: u64,
+	) -> Result<Option<(Partition, HashSet<Vec<u8>>)>, String> {
+		let marking = Arc::new(MarkingStorage {
+			storage: self.storage.clone(),
+			partition: Mutex::new(partition),
+			marked: Mutex::new(HashSet::new()),
+		});
+		let report = |marking: &MarkingStorage<Block>| {
+			let marked_nodes = previously_marked + marking.marked.lock().len() as u64;
+			self.update(|progress| progress.marked_nodes = marked_nodes);
+		};
+		{
+			// The trie cache is not used, as every node has to be read from the database.
+			let state = DbStateBuilder::<Block>::new(marking.clone(), self.root).build();
+			let mut child_tries = Vec::new();
+			for (i, pair) in state.pairs(IterArgs::default())?.enumerate() {
+				let (key, _) = pair?;
+				if let Some(storage_key) =
+					key.strip_prefix(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX)
+				{
+					child_tries.push(ChildInfo::new_default(storage_key));
+				}
+				if i % 4096 == 0 {
+					if self.stopped() {
+						return Ok(None)
+					}
+					marking.shrink(pending);
+					report(&marking);
+				}
+			}
+			for child_info in child_tries {
+				let mut args = IterArgs::default();
+				args.child_info = Some(child_info);
+				for (i, pair) in state.pairs(args)?.enumerate() {
+					pair?;
+					if i % 4096 == 0 {
+						marking.shrink(pending);
+					}
+				}
+				if self.stopped() {
+					return Ok(None)
+				}
+			}
+		}
+
+		marking.shrink(pending);
+		report(&marking);
+		let partition = *marking.partition.lock();
+		let marked = std::mem::take(&mut *marking.marked.lock());
+		Ok(Some((partition, marked)))
+	}
+
+	/// Collects the nodes of `partition` which aren't marked, once per reference, along with their
+	/// size. Returns `false` along with them if the sweep didn't visit the whole column.
+	fn scan(
+		&self,
+		partition: Partition,
+		marked: &HashSet<Vec<u8>>,
+	) -> Result<(Vec<(Vec<u8>, usize)>, bool), String> {
+		let mut candidates = Vec::new();
+		let mut scanned = 0u64;
+		let mut complete = true;
+		// Nothing may be committed while iterating.
+		let supported = self.storage.db.iter_column_while(columns::STATE, &mut |entry| {
+			let ColumnEntry { key, value, ref_count } = entry;
+			let key = match key {
+				Some(key) => key.to_vec(),
+				None => <HashingFor<Block> as Hasher>::hash(value).as_ref().to_vec(),
+			};
+			if partition.contains(&key) && !marked.contains(&key) {
+				for _ in 0..ref_count.unwrap_or(1) {
+					candidates.push((key.clone(), value.len()));
+				}
+			}
+			scanned += 1;
+			if candidates.len() >= MAX_SWEEP_CANDIDATES || self.stopped() {
+				complete = false;
+				return false
+			}
+			true
+		});
+		if !supported {
+			return Err("The database doesn't support iterating over the state".into())
+		}
+		self.update(|progress| progress.scanned_nodes += scanned);
+		Ok((candidates, complete))
+	}
+
+	/// Removes the `candidates`, returning the keys skipped as they were touched by a block
+	/// canonicalized since the start of the migration.
+	fn remove(&self, candidates: Vec<(Vec<u8>, usize)>) -> Result<HashSet<Vec<u8>>, String> {
+		let mut skipped = HashSet::new();
+		for batch in candidates.chunks(REMOVAL_BATCH) {
+			let removals = batch.iter().map(|(key, _)| key.clone()).collect();
+			let batch_skipped: HashSet<_> = self
+				.storage
+				.state_db
+				.commit_migration_removals(removals, |commit| {
+					let mut transaction = Transaction::new();
+					apply_state_commit(&mut transaction, commit);
+					self.storage.db.commit(transaction)
+				})
+				.map_err(|e| format!("Database backend error: {:?}", e))?
+				.into_iter()
+				.collect();
+
+			let (removed_nodes, removed_bytes) = batch
+				.iter()
+				.filter(|(key, _)| !batch_skipped.contains(key))
+				.fold((0u64, 0u64), |(nodes, bytes), (_, size)| (nodes + 1, bytes + *size as u64));
+			self.update(|progress| {
+				progress.removed_nodes += removed_nodes;
+				progress.removed_bytes += removed_bytes;
+			});
+			skipped.extend(batch_skipped);
+		}
+		Ok(skipped)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Partition;
+
+	#[test]
+	fn split_partitions_cover_their_parent() {
+		let keys = (0u16..1024).map(|i| i.to_be_bytes().to_vec()).collect::<Vec<_>>();
+		let (first, second) = Partition::ALL.split();
+		let (third, fourth) = second.split();
+		for key in &keys {
+			let in_halves = [first, third, fourth].iter().filter(|p| p.contains(key)).count();
+			assert_eq!(in_halves, 1);
+			assert_eq!(third.contains(key) || fourth.contains(key), second.contains(key));
+		}
+		assert!(keys.iter().filter(|key| first.contains(key)).count() == 512);
+		// Short keys belong to a partition too.
+		assert!(first.contains(&[]));
+	}
+}
@@ -0,0 +1,619 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Point-in-time snapshots of the database at a finalized block.
+//!
+//! A snapshot is a SCALE encoded stream of [`SNAPSHOT_MAGIC`], a [`SnapshotHeader`] and records
+//! terminated by an end record: the blocks in order, then the state and auxiliary entries in
+//! ascending order of their key. A full snapshot carries every block from genesis up to the
+//! snapshot block, the whole state at it and the auxiliary data. An incremental snapshot is based
+//! on a previous snapshot, and only carries the blocks imported since and the entries which
+//! changed.
+//!
+//! Snapshots are taken at the last finalized block while the node keeps importing blocks. The
+//! auxiliary data is read under the import lock along with the last finalized block, so it matches
+//! the snapshot block, and the state of the block is pinned while it's written. As the entries are
+//! ordered, the state is streamed out of the trie and compared to the base snapshots as they're
+//! read, without loading either in memory.
+
+use std::{
+	collections::BTreeMap,
+	io::{BufReader, Read, Seek, SeekFrom, Write},
+};
+
+use codec::{Decode, Encode, IoReader};
+use log::{info, warn};
+use sc_client_api::backend::{Backend as _, BlockImportOperation as _, NewBlockState, Snapshots};
+use sp_blockchain::{Backend as _, Error as ClientError, HeaderBackend, Result as ClientResult};
+use sp_core::storage::{well_known_keys, ChildInfo, Storage, StorageChild};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, One, SaturatedConversion, Zero},
+	Justifications, StateVersion,
+};
+use sp_state_machine::{backend::Backend as StateBackend, IterArgs};
+
+use crate::{columns, Backend};
+
+/// The magic bytes every snapshot starts with.
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"substsnp";
+
+/// The version of the snapshot format.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// The header of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SnapshotHeader<Block: BlockT> {
+	/// The version of the snapshot format.
+	pub version: u32,
+	/// The hash of the genesis block of the chain.
+	pub genesis_hash: Block::Hash,
+	/// The hash of the block the snapshot was taken at.
+	pub hash: Block::Hash,
+	/// The number of the block the snapshot was taken at.
+	pub number: NumberFor<Block>,
+	/// The state root of the block the snapshot was taken at.
+	pub state_root: Block::Hash,
+	/// The block of the snapshot an incremental snapshot is based on.
+	pub base: Option<(Block::Hash, NumberFor<Block>)>,
+}
+
+/// A record of a snapshot.
+#[derive(Encode, Decode)]
+enum SnapshotRecord<Block: BlockT> {
+	/// A block, following its parent.
+	Block {
+		header: Block::Header,
+		body: Option<Vec<Block::Extrinsic>>,
+		justifications: Option<Justifications>,
+	},
+	/// A state entry. `None` removes the entry of the base snapshot.
+	Storage { child: Option<Vec<u8>>, key: Vec<u8>, value: Option<Vec<u8>> },
+	/// An auxiliary entry. `None` removes the entry of the base snapshot.
+	Aux { key: Vec<u8>, value: Option<Vec<u8>> },
+	/// The end of the snapshot, with the number of records written.
+	End { blocks: u64, entries: u64 },
+}
+
+/// The key of a state or auxiliary entry.
+///
+/// Entries are written in the order of their key: the entries of the top trie, then the ones of
+/// the child tries and finally the auxiliary entries.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum EntryKey {
+	Storage(Option<Vec<u8>>, Vec<u8>),
+	Aux(Vec<u8>),
+}
+
+impl EntryKey {
+	fn into_record<Block: BlockT>(self, value: Option<Vec<u8>>) -> SnapshotRecord<Block> {
+		match self {
+			EntryKey::Storage(child, key) => SnapshotRecord::Storage { child, key, value },
+			EntryKey::Aux(key) => SnapshotRecord::Aux { key, value },
+		}
+	}
+}
+
+fn io_err(e: std::io::Error) -> ClientError {
+	ClientError::Backend(format!("Snapshot I/O error: {}", e))
+}
+
+fn invalid(msg: &str) -> ClientError {
+	ClientError::Backend(format!("Invalid snapshot: {}", msg))
+}
+
+fn state_err(e: impl sp_state_machine::Error) -> ClientError {
+	ClientError::from_state(Box::new(e))
+}
+
+/// Reads the records of a single snapshot.
+struct SnapshotReader<Block: BlockT, R> {
+	input: IoReader<BufReader<R>>,
+	header: SnapshotHeader<Block>,
+	/// The next entry, once the blocks are read. `None` once the end record is read.
+	entry: Option<(EntryKey, Option<Vec<u8>>)>,
+	blocks: u64,
+	entries: u64,
+}
+
+impl<Block: BlockT, R: Read> SnapshotReader<Block, R> {
+	fn open(input: R) -> ClientResult<Self> {
+		let mut input = IoReader(BufReader::new(input));
+		let mut magic = [0; 8];
+		input.0.read_exact(&mut magic).map_err(io_err)?;
+		if magic != SNAPSHOT_MAGIC {
+			return Err(invalid("unknown format"))
+		}
+		let header = SnapshotHeader::<Block>::decode(&mut input)
+			.map_err(|e| invalid(&format!("header: {}", e)))?;
+		if header.version != SNAPSHOT_VERSION {
+			return Err(invalid(&format!("unsupported version {}", header.version)))
+		}
+		Ok(Self { input, header, entry: None, blocks: 0, entries: 0 })
+	}
+
+	fn read(&mut self) -> ClientResult<SnapshotRecord<Block>> {
+		SnapshotRecord::<Block>::decode(&mut self.input)
+			.map_err(|e| invalid(&format!("record: {}", e)))
+	}
+
+	/// Reads the next block, or the first entry once all the blocks are read.
+	fn next_block(
+		&mut self,
+	) -> ClientResult<Option<(Block::Header, Option<Vec<Block::Extrinsic>>, Option<Justifications>)>>
+	{
+		match self.read()? {
+			SnapshotRecord::Block { header, body, justifications } => {
+				self.blocks += 1;
+				Ok(Some((header, body, justifications)))
+			},
+			record => {
+				self.set_entry(record, None)?;
+				Ok(None)
+			},
+		}
+	}
+
+	/// Reads the entry following the current one.
+	fn advance(&mut self) -> ClientResult<()> {
+		let previous = self.entry.take().map(|(key, _)| key);
+		let record = self.read()?;
+		self.set_entry(record, previous)
+	}
+
+	fn set_entry(
+		&mut self,
+		record: SnapshotRecord<Block>,
+		previous: Option<EntryKey>,
+	) -> ClientResult<()> {
+		let (key, value) = match record {
+			SnapshotRecord::Block { .. } => return Err(invalid("block after the entries")),
+			SnapshotRecord::Storage { child, key, value } => (EntryKey::Storage(child, key), value),
+			SnapshotRecord::Aux { key, value } => (EntryKey::Aux(key), value),
+			SnapshotRecord::End { blocks, entries } => {
+				if (self.blocks, self.entries) != (blocks, entries) {
+					return Err(invalid("record count mismatch"))
+				}
+				return Ok(())
+			},
+		};
+		if previous.map_or(false, |previous| previous >= key) {
+			return Err(invalid("entries out of order"))
+		}
+		self.entries += 1;
+		self.entry = Some((key, value));
+		Ok(())
+	}
+}
+
+/// A chain of snapshots, a full snapshot followed by the incremental snapshots based on each
+/// other, whose entries are read as a stream.
+pub struct SnapshotChain<Block: BlockT, R> {
+	readers: Vec<SnapshotReader<Block, R>>,
+}
+
+impl<Block: BlockT, R: Read> SnapshotChain<Block, R> {
+	/// Opens a chain of snapshots, calling `on_block` for every block in order.
+	///
+	/// The blocks of all the snapshots are read and checked to follow each other up to the block
+	/// of the last snapshot.
+	pub fn open(
+		inputs: impl IntoIterator<Item = R>,
+		mut on_block: impl FnMut(
+			Block::Header,
+			Option<Vec<Block::Extrinsic>>,
+			Option<Justifications>,
+		) -> ClientResult<()>,
+	) -> ClientResult<Self> {
+		let mut readers: Vec<SnapshotReader<Block, R>> = Vec::new();
+		for input in inputs {
+			let mut reader = SnapshotReader::<Block, R>::open(input)?;
+			let header = reader.header.clone();
+
+			let mut last = match (readers.last().map(|previous| &previous.header), header.base) {
+				(None, None) => None,
+				(Some(previous), Some(base)) => {
+					if previous.genesis_hash != header.genesis_hash ||
+						(previous.hash, previous.number) != base
+					{
+						return Err(invalid("not based on the previous snapshot"))
+					}
+					Some(base)
+				},
+				(None, Some(_)) =>
+					return Err(invalid("the first snapshot must be a full snapshot")),
+				(Some(_), None) =>
+					return Err(invalid("a full snapshot can't follow another snapshot")),
+			};
+
+			let mut last_state_root = None;
+			while let Some((block, body, justifications)) = reader.next_block()? {
+				let hash = block.hash();
+				let number = *block.number();
+				let linked = match last {
+					Some((parent_hash, parent_number)) =>
+						*block.parent_hash() == parent_hash && number == parent_number + One::one(),
+					None => number.is_zero() && hash == header.genesis_hash,
+				};
+				if !linked {
+					return Err(invalid(&format!("block #{} doesn't follow its parent", number)))
+				}
+				last = Some((hash, number));
+				last_state_root = Some(*block.state_root());
+				on_block(block, body, justifications)?;
+			}
+			if last != Some((header.hash, header.number)) ||
+				last_state_root != Some(header.state_root)
+			{
+				return Err(invalid("the last block isn't the snapshot block"))
+			}
+			readers.push(reader);
+		}
+
+		if readers.is_empty() {
+			return Err(invalid("no snapshot given"))
+		}
+		Ok(Self { readers })
+	}
+
+	/// The header of the last snapshot of the chain.
+	pub fn header(&self) -> &SnapshotHeader<Block> {
+		&self.readers.last().expect("a chain has at least one snapshot; qed").header
+	}
+
+	/// Reads the next entry of the chain, the entries of a snapshot overriding the ones of the
+	/// snapshots it's based on.
+	fn next_entry(&mut self) -> ClientResult<Option<(EntryKey, Vec<u8>)>> {
+		loop {
+			let Some(key) = self
+				.readers
+				.iter()
+				.filter_map(|reader| reader.entry.as_ref())
+				.map(|(key, _)| key)
+				.min()
+				.cloned()
+			else {
+				return Ok(None)
+			};
+			let mut value = None;
+			for reader in &mut self.readers {
+				if reader.entry.as_ref().map_or(false, |(next, _)| *next == key) {
+					value = reader.entry.as_mut().and_then(|(_, value)| value.take());
+					reader.advance()?;
+				}
+			}
+			if let Some(value) = value {
+				return Ok(Some((key, value)))
+			}
+		}
+	}
+
+	/// Reads the state and the auxiliary data of the chain in memory, to restore them.
+	pub fn read_state(mut self) -> ClientResult<(Storage, Vec<(Vec<u8>, Vec<u8>)>)> {
+		let mut storage = Storage::default();
+		let mut aux = Vec::new();
+		while let Some((key, value)) = self.next_entry()? {
+			match key {
+				EntryKey::Storage(None, key) => {
+					storage.top.insert(key, value);
+				},
+				EntryKey::Storage(Some(child), key) => {
+					storage
+						.children_default
+						.entry(child.clone())
+						.or_insert_with(|| StorageChild {
+							data: Default::default(),
+							child_info: ChildInfo::new_default(&child),
+						})
+						.data
+						.insert(key, value);
+				},
+				EntryKey::Aux(key) => aux.push((key, value)),
+			}
+		}
+		Ok((storage, aux))
+	}
+}
+
+/// Writes the records of a snapshot, only writing the entries which differ from its base.
+struct SnapshotWriter<'a, Block: BlockT, R> {
+	output: &'a mut dyn Write,
+	base: Option<SnapshotChain<Block, R>>,
+	/// The next entry of the base not compared yet.
+	base_next: Option<(EntryKey, Vec<u8>)>,
+	blocks: u64,
+	entries: u64,
+}
+
+impl<'a, Block: BlockT, R: Read> SnapshotWriter<'a, Block, R> {
+	fn new(
+		output: &'a mut dyn Write,
+		mut base: Option<SnapshotChain<Block, R>>,
+	) -> ClientResult<Self> {
+		let base_next = match &mut base {
+			Some(base) => base.next_entry()?,
+			None => None,
+		};
+		Ok(Self { output, base, base_next, blocks: 0, entries: 0 })
+	}
+
+	fn write(&mut self, record: SnapshotRecord<Block>) -> ClientResult<()> {
+		match record {
+			SnapshotRecord::Block { .. } => self.blocks += 1,
+			SnapshotRecord::Storage { .. } | SnapshotRecord::Aux { .. } => self.entries += 1,
+			SnapshotRecord::End { .. } => {},
+		}
+		self.output.write_all(&record.encode()).map_err(io_err)
+	}
+
+	fn next_base(&mut self) -> ClientResult<()> {
+		self.base_next = match &mut self.base {
+			Some(base) => base.next_entry()?,
+			None => None,
+		};
+		Ok(())
+	}
+
+	/// Removes the entries of the base up to `key`, excluded, which `keep` rejects.
+	fn remove_base_until(
+		&mut self,
+		key: Option<&EntryKey>,
+		keep: impl Fn(&EntryKey) -> bool,
+	) -> ClientResult<()> {
+		while let Some((base_key, _)) = &self.base_next {
+			if key.map_or(false, |key| base_key >= key) {
+				break
+			}
+			let (base_key, _) = self.base_next.take().expect("matched above; qed");
+			if !keep(&base_key) {
+				self.write(base_key.into_record(None))?;
+			}
+			self.next_base()?;
+		}
+		Ok(())
+	}
+
+	/// Writes an entry, which must follow the entries written before it, unless it's unchanged
+	/// since the base.
+	fn write_entry(&mut self, key: EntryKey, value: Vec<u8>) -> ClientResult<()> {
+		self.remove_base_until(Some(&key), |_| false)?;
+		if self.base_next.as_ref().map_or(false, |(base_key, _)| *base_key == key) {
+			let (_, base_value) = self.base_next.take().expect("matched above; qed");
+			self.next_base()?;
+			if base_value == value {
+				return Ok(())
+			}
+		}
+		self.write(key.into_record(Some(value)))
+	}
+
+	/// Removes the remaining entries of the base, keeping its auxiliary entries unless `aux` is
+	/// set, and ends the snapshot.
+	fn finish(mut self, aux: bool) -> ClientResult<()> {
+		self.remove_base_until(None, |key| !aux && matches!(key, EntryKey::Aux(_)))?;
+		let (blocks, entries) = (self.blocks, self.entries);
+		self.write(SnapshotRecord::End { blocks, entries })?;
+		self.output.flush().map_err(io_err)
+	}
+}
+
+impl<Block: BlockT> Snapshots<Block> for Backend<Block> {
+	fn export_snapshot(
+		&self,
+		base: Vec<Box<dyn Read + Send>>,
+		output: &mut dyn Write,
+	) -> ClientResult<(Block::Hash, NumberFor<Block>)> {
+		let base = if base.is_empty() {
+			None
+		} else {
+			Some(SnapshotChain::<Block, _>::open(base, |_, _, _| Ok(()))?)
+		};
+		let header = Backend::export_snapshot(self, base, output)?;
+		Ok((header.hash, header.number))
+	}
+}
+
+impl<Block: BlockT> Backend<Block> {
+	/// Read the auxiliary data, or `None` if the database can't enumerate it.
+	fn snapshot_aux(&self) -> Option<BTreeMap<Vec<u8>, Vec<u8>>> {
+		let mut aux = BTreeMap::new();
+		let mut keyed = true;
+		let supported = self.storage.db.iter_column_while(columns::AUX, &mut |entry| {
+			match entry.key {
+				Some(key) => {
+					aux.insert(key.to_vec(), entry.value.to_vec());
+				},
+				None => keyed = false,
+			}
+			keyed
+		});
+		(supported && keyed).then_some(aux)
+	}
+
+	/// Write a snapshot of the database at the last finalized block to `output`.
+	///
+	/// If `base` is given, an incremental snapshot holding the changes since the last snapshot of
+	/// the chain is written. The snapshot may be taken while blocks are imported: the last
+	/// finalized block and the auxiliary data are read at once under the import lock.
+	pub fn export_snapshot<R: Read>(
+		&self,
+		base: Option<SnapshotChain<Block, R>>,
+		output: &mut dyn Write,
+	) -> ClientResult<SnapshotHeader<Block>> {
+		let (info, aux, state) = {
+			let _lock = self.import_lock.read();
+			let info = self.blockchain.info();
+			let aux = self.snapshot_aux();
+			// Keeps the state pinned until the snapshot is written.
+			let state = self.state_at(info.finalized_hash)?;
+			(info, aux, state)
+		};
+		let (hash, number) = (info.finalized_hash, info.finalized_number);
+		let header = self.blockchain.expect_header(hash)?;
+		if matches!(info.block_gap, Some((start, _)) if start <= number) {
+			return Err(ClientError::Backend(format!(
+				"Can't take a snapshot at #{}, blocks before it are missing",
+				number
+			)))
+		}
+
+		let base_header = match &base {
+			Some(base) => {
+				let base = base.header();
+				if base.genesis_hash != info.genesis_hash ||
+					base.number >= number ||
+					self.blockchain.hash(base.number)? != Some(base.hash)
+				{
+					return Err(ClientError::Backend(format!(
+						"The base snapshot at #{} ({}) isn't an ancestor of #{}",
+						base.number, base.hash, number
+					)))
+				}
+				Some((base.hash, base.number))
+			},
+			None => None,
+		};
+
+		let snapshot = SnapshotHeader::<Block> {
+			version: SNAPSHOT_VERSION,
+			genesis_hash: info.genesis_hash,
+			hash,
+			number,
+			state_root: *header.state_root(),
+			base: base_header,
+		};
+		info!(
+			target: "db",
+			"Writing {} snapshot at #{} ({})",
+			if base_header.is_some() { "incremental" } else { "full" },
+			number,
+			hash,
+		);
+		output.write_all(&SNAPSHOT_MAGIC).map_err(io_err)?;
+		output.write_all(&snapshot.encode()).map_err(io_err)?;
+		let mut writer = SnapshotWriter::new(output, base)?;
+
+		let from = base_header.map_or(Zero::zero(), |(_, n)| n + One::one());
+		for n in from.saturated_into::<u64>()..=number.saturated_into::<u64>() {
+			let block_hash = self
+				.blockchain
+				.hash(n.saturated_into())?
+				.ok_or_else(|| ClientError::UnknownBlock(format!("#{}", n)))?;
+			writer.write(SnapshotRecord::Block {
+				header: self.blockchain.expect_header(block_hash)?,
+				body: self.blockchain.body(block_hash)?,
+				justifications: self.blockchain.justifications(block_hash)?,
+			})?;
+		}
+
+		let mut children = Vec::new();
+		for pair in state.pairs(IterArgs::default()).map_err(state_err)? {
+			let (key, value) = pair.map_err(state_err)?;
+			match key.strip_prefix(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+				Some(child) => children.push(child.to_vec()),
+				None => writer.write_entry(EntryKey::Storage(None, key), value)?,
+			}
+		}
+		for child in children {
+			let args =
+				IterArgs { child_info: Some(ChildInfo::new_default(&child)), ..Default::default() };
+			for pair in state.pairs(args).map_err(state_err)? {
+				let (key, value) = pair.map_err(state_err)?;
+				writer.write_entry(EntryKey::Storage(Some(child.clone()), key), value)?;
+			}
+		}
+		drop(state);
+
+		match aux {
+			Some(aux) => {
+				for (key, value) in aux {
+					writer.write_entry(EntryKey::Aux(key), value)?;
+				}
+				writer.finish(true)?;
+			},
+			None => {
+				warn!(
+					target: "db",
+					"The auxiliary data of this database can't be enumerated, and is not part of the snapshot",
+				);
+				writer.finish(false)?;
+			},
+		}
+
+		Ok(snapshot)
+	}
+
+	/// Restore a chain of snapshots, a full snapshot followed by the incremental snapshots based
+	/// on each other, into this database, which must be empty.
+	///
+	/// The state root of the restored state is checked against the header of the snapshot block
+	/// before anything is written. `state_version` resolves the state version of the restored
+	/// state.
+	pub fn import_snapshot<R: Read + Seek>(
+		&self,
+		inputs: &mut [R],
+		state_version: impl FnOnce(&Storage) -> ClientResult<StateVersion>,
+	) -> ClientResult<SnapshotHeader<Block>> {
+		if self.blockchain.info().genesis_hash != Default::default() {
+			return Err(ClientError::Backend(
+				"Snapshots can only be restored into an empty database".into(),
+			))
+		}
+
+		let mut last = None;
+		let chain =
+			SnapshotChain::<Block, _>::open(inputs.iter_mut(), |header, body, justifications| {
+				last = Some((header, body, justifications));
+				Ok(())
+			})?;
+		let snapshot = chain.header().clone();
+		let (storage, aux) = chain.read_state()?;
+		let (header, body, justifications) = last.ok_or_else(|| invalid("no blocks"))?;
+
+		let mut op = self.begin_operation()?;
+		let state_version = state_version(&storage)?;
+		let root = op.reset_storage(storage, state_version)?;
+		if root != snapshot.state_root {
+			return Err(ClientError::InvalidStateRoot)
+		}
+		info!(
+			target: "db",
+			"Verified the state root of the snapshot at #{} ({}), restoring blocks",
+			snapshot.number,
+			snapshot.hash,
+		);
+
+		for input in inputs.iter_mut() {
+			input.seek(SeekFrom::Start(0)).map_err(io_err)?;
+		}
+		SnapshotChain::<Block, _>::open(inputs.iter_mut(), |header, body, justifications| {
+			if header.hash() == snapshot.hash {
+				return Ok(())
+			}
+			let mut op = self.begin_operation()?;
+			op.set_block_data(header, body, None, justifications, NewBlockState::Final)?;
+			self.commit_operation(op)
+		})?;
+
+		op.set_block_data(header, body, None, justifications, NewBlockState::Final)?;
+		op.insert_aux(aux.into_iter().map(|(key, value)| (key, Some(value))))?;
+		self.commit_operation(op)?;
+
+		Ok(snapshot)
+	}
+}
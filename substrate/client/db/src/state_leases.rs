@@ -0,0 +1,402 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Persisted, lease-based pins of the state of blocks.
+//!
+//! A lease pins its block like [`sc_client_api::Backend::pin_block`] until it expires or is
+//! revoked. The leases are stored in the `meta` column, and pinned again when they're enabled
+//! after the database is opened.
+//!
+//! As a pinned block holds back the pruning of the blocks after it, the state retained by the
+//! leases is estimated as the size of the state nodes written since the oldest lease was
+//! acquired. The oldest leases are revoked when this estimate exceeds the disk budget.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use codec::{Decode, Encode};
+use log::{debug, warn};
+use parking_lot::MutexGuard;
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
+use sc_client_api::backend::{Backend as _, StateLease, StateLeases};
+use sp_blockchain::{Error as ClientError, Result as ClientResult};
+use sp_database::Transaction;
+use sp_runtime::traits::Block as BlockT;
+
+use crate::{columns, utils::meta_keys, Backend, DbHash};
+
+/// Settings of the state leases of a [`Backend`].
+#[derive(Debug, Clone)]
+pub struct StateLeaseSettings {
+	/// The longest duration a lease can be acquired or renewed for.
+	pub max_duration: Duration,
+	/// The maximum number of leases of an owner.
+	pub max_leases_per_owner: usize,
+	/// The estimated size of the state the leases may retain, in bytes.
+	pub disk_budget: Option<u64>,
+}
+
+impl Default for StateLeaseSettings {
+	fn default() -> Self {
+		Self {
+			max_duration: Duration::from_secs(24 * 60 * 60),
+			max_leases_per_owner: 16,
+			disk_budget: None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+struct LeaseRecord<Hash> {
+	id: u64,
+	owner: String,
+	hash: Hash,
+	expires_at: u64,
+	/// The value of [`LeaseRecords::written`] when the lease was acquired.
+	written_at: u64,
+}
+
+impl<Hash: Clone> LeaseRecord<Hash> {
+	fn lease(&self) -> StateLease<Hash> {
+		StateLease {
+			id: self.id,
+			owner: self.owner.clone(),
+			hash: self.hash.clone(),
+			expires_at: self.expires_at,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+struct LeaseRecords<Hash> {
+	next_id: u64,
+	/// Total size of the state nodes written while there were leases.
+	written: u64,
+	leases: Vec<LeaseRecord<Hash>>,
+}
+
+impl<Hash> Default for LeaseRecords<Hash> {
+	fn default() -> Self {
+		Self { next_id: 0, written: 0, leases: Vec::new() }
+	}
+}
+
+impl<Hash> LeaseRecords<Hash> {
+	/// The estimated size of the state retained by the leases.
+	fn retained(&self) -> u64 {
+		self.leases
+			.iter()
+			.map(|lease| self.written.saturating_sub(lease.written_at))
+			.max()
+			.unwrap_or(0)
+	}
+}
+
+/// The enabled state leases of a [`Backend`].
+pub(crate) struct Leases<Hash> {
+	settings: StateLeaseSettings,
+	records: LeaseRecords<Hash>,
+	metrics: Option<Metrics>,
+}
+
+/// State leases Prometheus metrics.
+struct Metrics {
+	leases: Gauge<U64>,
+	retained_bytes: Gauge<U64>,
+	expired: Counter<U64>,
+	evicted: Counter<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			leases: register(
+				Gauge::new("substrate_state_leases", "Number of state leases")?,
+				registry,
+			)?,
+			retained_bytes: register(
+				Gauge::new(
+					"substrate_state_leases_retained_bytes",
+					"Estimated size of the state retained by the state leases",
+				)?,
+				registry,
+			)?,
+			expired: register(
+				Counter::new("substrate_state_leases_expired", "Number of expired state leases")?,
+				registry,
+			)?,
+			evicted: register(
+				Counter::new(
+					"substrate_state_leases_evicted",
+					"Number of state leases revoked to respect the disk budget",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs())
+}
+
+impl<Hash: Encode> Leases<Hash> {
+	fn report(&self) {
+		if let Some(metrics) = &self.metrics {
+			metrics.leases.set(self.records.leases.len() as u64);
+			metrics.retained_bytes.set(self.records.retained());
+		}
+	}
+
+	/// Remove the expired leases, and the oldest leases while the disk budget is exceeded.
+	fn expire(&mut self) -> Vec<LeaseRecord<Hash>> {
+		let now = now();
+		let (expired, mut leases): (Vec<_>, Vec<_>) = std::mem::take(&mut self.records.leases)
+			.into_iter()
+			.partition(|l| l.expires_at <= now);
+
+		let mut evicted = Vec::new();
+		if let Some(budget) = self.settings.disk_budget {
+			leases.sort_by_key(|lease| lease.written_at);
+			let written = self.records.written;
+			while leases.first().map_or(false, |l| written.saturating_sub(l.written_at) > budget) {
+				evicted.push(leases.remove(0));
+			}
+			leases.sort_by_key(|lease| lease.id);
+		}
+		self.records.leases = leases;
+
+		if let Some(metrics) = &self.metrics {
+			metrics.expired.inc_by(expired.len() as u64);
+			metrics.evicted.inc_by(evicted.len() as u64);
+		}
+		for lease in &evicted {
+			warn!(
+				target: "db",
+				"Revoked the state lease {} of {} to respect the disk budget",
+				lease.id,
+				lease.owner,
+			);
+		}
+		expired.into_iter().chain(evicted).collect()
+	}
+}
+
+impl<Block: BlockT> Backend<Block> {
+	/// Enable the persisted state leases, see [`sc_client_api::backend::StateLeases`].
+	///
+	/// The leases stored by a previous run pin their blocks again, unless they expired meanwhile.
+	/// This should be called before blocks are imported, so the pruning doesn't discard the state
+	/// of these blocks first.
+	pub fn enable_state_leases(
+		&self,
+		settings: StateLeaseSettings,
+		prometheus_registry: Option<&Registry>,
+	) -> ClientResult<()> {
+		let mut leases = self.state_leases.lock();
+		if let Some(leases) = leases.as_mut() {
+			leases.settings = settings;
+			return Ok(())
+		}
+
+		let records = match self.storage.db.get(columns::META, meta_keys::STATE_LEASES) {
+			Some(records) => LeaseRecords::decode(&mut &records[..]).map_err(|err| {
+				ClientError::Backend(format!("Error decoding state leases: {}", err))
+			})?,
+			None => LeaseRecords::default(),
+		};
+		let metrics = prometheus_registry.and_then(|registry| {
+			Metrics::register(registry)
+				.map_err(|err| {
+					warn!("Failed to register prometheus metrics: {}", err);
+				})
+				.ok()
+		});
+		let mut enabled = Leases { settings, records, metrics };
+
+		enabled.expire();
+		enabled.records.leases.retain(|lease| match self.pin_block(lease.hash) {
+			Ok(()) => true,
+			Err(err) => {
+				warn!(target: "db", "Dropped the state lease {}: {}", lease.id, err);
+				false
+			},
+		});
+		self.write_state_leases(&enabled.records)?;
+		enabled.report();
+		*leases = Some(enabled);
+		Ok(())
+	}
+
+	/// Account the state nodes written by an imported block, and remove the leases which expired
+	/// or exceed the disk budget, writing the leases to the import `transaction`.
+	///
+	/// The leases are locked until the returned update is either applied with
+	/// [`StateLeasesUpdate::committed`] once `transaction` is committed, or dropped to discard it.
+	pub(crate) fn update_state_leases(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		written: u64,
+	) -> Option<StateLeasesUpdate<'_, Block>> {
+		let mut leases = self.state_leases.lock();
+		let enabled = leases.as_mut()?;
+		if enabled.records.leases.is_empty() {
+			return None
+		}
+
+		let previous = enabled.records.clone();
+		enabled.records.written = enabled.records.written.saturating_add(written);
+		let removed = enabled.expire();
+		transaction.set_from_vec(columns::META, meta_keys::STATE_LEASES, enabled.records.encode());
+		Some(StateLeasesUpdate { backend: self, leases, previous: Some(previous), removed })
+	}
+
+	fn write_state_leases(&self, records: &LeaseRecords<Block::Hash>) -> ClientResult<()> {
+		let mut transaction = Transaction::new();
+		transaction.set_from_vec(columns::META, meta_keys::STATE_LEASES, records.encode());
+		self.storage.db.commit(transaction)?;
+		Ok(())
+	}
+
+	/// Run `f` on the enabled leases, then persist them and release the removed leases.
+	fn with_state_leases<R>(
+		&self,
+		f: impl FnOnce(&mut Leases<Block::Hash>) -> ClientResult<R>,
+	) -> ClientResult<R> {
+		let mut leases = self.state_leases.lock();
+		let leases = leases
+			.as_mut()
+			.ok_or_else(|| ClientError::Backend("State leases are disabled".into()))?;
+
+		let removed = leases.expire();
+		let result = f(leases);
+		self.write_state_leases(&leases.records)?;
+		for lease in removed {
+			self.unpin_block(lease.hash);
+		}
+		leases.report();
+		result
+	}
+}
+
+/// An update of the state leases written to an import transaction, see
+/// [`Backend::update_state_leases`].
+pub(crate) struct StateLeasesUpdate<'a, Block: BlockT> {
+	backend: &'a Backend<Block>,
+	leases: MutexGuard<'a, Option<Leases<Block::Hash>>>,
+	/// The leases before the update, restored if the transaction isn't committed.
+	previous: Option<LeaseRecords<Block::Hash>>,
+	removed: Vec<LeaseRecord<Block::Hash>>,
+}
+
+impl<'a, Block: BlockT> StateLeasesUpdate<'a, Block> {
+	/// Release the removed leases, once the transaction is committed.
+	pub(crate) fn committed(mut self) {
+		self.previous = None;
+		for lease in std::mem::take(&mut self.removed) {
+			self.backend.unpin_block(lease.hash);
+		}
+		if let Some(leases) = self.leases.as_ref() {
+			leases.report();
+		}
+	}
+}
+
+impl<'a, Block: BlockT> Drop for StateLeasesUpdate<'a, Block> {
+	fn drop(&mut self) {
+		if let (Some(previous), Some(leases)) = (self.previous.take(), self.leases.as_mut()) {
+			leases.records = previous;
+		}
+	}
+}
+
+impl<Block: BlockT> StateLeases<Block> for Backend<Block> {
+	fn acquire_lease(
+		&self,
+		owner: &str,
+		hash: Block::Hash,
+		duration: Duration,
+	) -> ClientResult<StateLease<Block::Hash>> {
+		self.with_state_leases(|leases| {
+			if duration > leases.settings.max_duration {
+				return Err(ClientError::Backend(format!(
+					"State leases last at most {} seconds",
+					leases.settings.max_duration.as_secs(),
+				)))
+			}
+			let owned = leases.records.leases.iter().filter(|l| l.owner == owner).count();
+			if owned >= leases.settings.max_leases_per_owner {
+				return Err(ClientError::Backend(format!(
+					"{} already holds {} state leases",
+					owner, owned,
+				)))
+			}
+			self.pin_block(hash)?;
+
+			let lease = LeaseRecord {
+				id: leases.records.next_id,
+				owner: owner.into(),
+				hash,
+				expires_at: now().saturating_add(duration.as_secs()),
+				written_at: leases.records.written,
+			};
+			debug!(target: "db", "State lease {} of {} pins {:?}", lease.id, owner, hash);
+			leases.records.next_id += 1;
+			leases.records.leases.push(lease.clone());
+			Ok(lease.lease())
+		})
+	}
+
+	fn renew_lease(&self, id: u64, duration: Duration) -> ClientResult<StateLease<Block::Hash>> {
+		self.with_state_leases(|leases| {
+			if duration > leases.settings.max_duration {
+				return Err(ClientError::Backend(format!(
+					"State leases last at most {} seconds",
+					leases.settings.max_duration.as_secs(),
+				)))
+			}
+			let lease = leases
+				.records
+				.leases
+				.iter_mut()
+				.find(|lease| lease.id == id)
+				.ok_or_else(|| ClientError::Backend(format!("Unknown state lease {}", id)))?;
+			lease.expires_at = now().saturating_add(duration.as_secs());
+			Ok(lease.lease())
+		})
+	}
+
+	fn revoke_lease(&self, id: u64) -> ClientResult<bool> {
+		self.with_state_leases(|leases| {
+			let Some(position) = leases.records.leases.iter().position(|lease| lease.id == id)
+			else {
+				return Ok(false)
+			};
+			let lease = leases.records.leases.remove(position);
+			debug!(target: "db", "Revoked the state lease {} of {}", lease.id, lease.owner);
+			self.unpin_block(lease.hash);
+			Ok(true)
+		})
+	}
+
+	fn leases(&self) -> ClientResult<Vec<StateLease<Block::Hash>>> {
+		self.with_state_leases(|leases| {
+			Ok(leases.records.leases.iter().map(LeaseRecord::lease).collect())
+		})
+	}
+}
@@ -0,0 +1,149 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Opt-in record of the storage changes of the imported blocks.
+//!
+//! Once enabled with [`Backend::set_storage_diffs`], the main and child trie changes of every
+//! block imported with its state are stored as a [`StorageDiff`] under the lookup key of the
+//! block. The diff of a block is removed along with its body when it's pruned.
+//!
+//! The first block from which the diffs of all the canonical blocks are recorded is kept in the
+//! `meta` column. Only the diffs of the blocks from it on are returned, and the record starts
+//! over whenever a block is imported while it's disabled, or without its state.
+
+use std::sync::atomic::Ordering;
+
+use codec::{Decode, Encode};
+use sc_client_api::backend::{BlockStorageDiff, StorageDiff};
+use sp_blockchain::{Error as ClientError, HeaderBackend, Result as ClientResult};
+use sp_database::Transaction;
+use sp_runtime::traits::{Block as BlockT, NumberFor, One, Saturating};
+use sp_state_machine::{ChildStorageCollection, StorageCollection};
+
+use crate::{
+	columns,
+	utils::{self, meta_keys},
+	Backend, DbHash,
+};
+
+impl<Block: BlockT> Backend<Block> {
+	/// Record the storage changes of the blocks imported from now on, enabling
+	/// [`sc_client_api::Backend::storage_diff`] and [`sc_client_api::Backend::storage_diffs`].
+	///
+	/// Blocks imported before the record was enabled, or while it was disabled, have no recorded
+	/// changes.
+	pub fn set_storage_diffs(&self, enabled: bool) {
+		self.storage_diffs.store(enabled, Ordering::Relaxed);
+	}
+
+	/// Record the storage changes of the block `number`, if it's imported with its state.
+	pub(crate) fn record_storage_diff(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		number: NumberFor<Block>,
+		lookup_key: &[u8],
+		changes: Option<(&StorageCollection, &ChildStorageCollection)>,
+	) {
+		let enabled = self.storage_diffs.load(Ordering::Relaxed);
+		self.update_index_start(
+			transaction,
+			&self.storage_diffs_start,
+			meta_keys::STORAGE_DIFFS_START,
+			enabled && changes.is_some(),
+			number,
+		);
+		if let (true, Some((main, children))) = (enabled, changes) {
+			// Encoded as a `StorageDiff`, without cloning the collections.
+			transaction.set_from_vec(columns::STORAGE_DIFF, lookup_key, (main, children).encode());
+		}
+	}
+
+	/// The first block from which the storage changes of all the canonical blocks are recorded,
+	/// if they're being recorded. Until a block is recorded, that's the block following the best
+	/// block.
+	fn storage_diffs_coverage(&self) -> Option<NumberFor<Block>> {
+		if !self.storage_diffs.load(Ordering::Relaxed) {
+			return None
+		}
+		let start = *self.storage_diffs_start.read();
+		Some(start.unwrap_or_else(|| self.blockchain.info().best_number.saturating_add(One::one())))
+	}
+
+	pub(crate) fn remove_storage_diff(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		lookup_key: &[u8],
+	) {
+		transaction.remove(columns::STORAGE_DIFF, lookup_key);
+	}
+
+	fn read_storage_diff(&self, lookup_key: &[u8]) -> ClientResult<Option<StorageDiff>> {
+		let Some(diff) = self.storage.db.get(columns::STORAGE_DIFF, lookup_key) else {
+			return Ok(None)
+		};
+		StorageDiff::decode(&mut &diff[..])
+			.map(Some)
+			.map_err(|err| ClientError::Backend(format!("Error decoding storage diff: {}", err)))
+	}
+
+	pub(crate) fn query_storage_diff(
+		&self,
+		hash: Block::Hash,
+		prefix: &[u8],
+	) -> ClientResult<Option<StorageDiff>> {
+		let Some(start) = self.storage_diffs_coverage() else { return Ok(None) };
+		let Some(number) = self.blockchain.number(hash)? else { return Ok(None) };
+		if number < start {
+			return Ok(None)
+		}
+		let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+		let mut diff = self.read_storage_diff(&lookup_key)?;
+		if let Some(diff) = diff.as_mut() {
+			diff.retain_prefix(prefix);
+		}
+		Ok(diff)
+	}
+
+	pub(crate) fn query_storage_diffs(
+		&self,
+		from: NumberFor<Block>,
+		to: NumberFor<Block>,
+		prefix: &[u8],
+	) -> ClientResult<Option<Vec<BlockStorageDiff<Block>>>> {
+		let Some(start) = self.storage_diffs_coverage() else { return Ok(None) };
+
+		// Only the recorded blocks are visited.
+		let to = to.min(self.blockchain.info().best_number);
+		let mut diffs = Vec::new();
+		let mut number = from.max(start);
+		while number <= to {
+			if let Some(hash) = self.blockchain.hash(number)? {
+				let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+				if let Some(mut diff) = self.read_storage_diff(&lookup_key)? {
+					diff.retain_prefix(prefix);
+					if !diff.is_empty() {
+						diffs.push(BlockStorageDiff { hash, number, diff });
+					}
+				}
+			}
+			number = number.saturating_add(One::one());
+		}
+		Ok(Some(diffs))
+	}
+}
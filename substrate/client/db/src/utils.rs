@@ -35,7 +35,7 @@ use sp_trie::DBValue;
 
 /// Number of columns in the db. Must be the same for both full && light dbs.
 /// Otherwise RocksDb will fail to open database && check its type.
-pub const NUM_COLUMNS: u32 = 13;
+pub const NUM_COLUMNS: u32 = 16;
 /// Meta column. The set of keys in the column is shared by full && light storages.
 pub const COLUMN_META: u32 = 0;
 
@@ -57,6 +57,22 @@ pub mod meta_keys {
 	pub const LEAF_PREFIX: &[u8; 4] = b"leaf";
 	/// Children prefix list key.
 	pub const CHILDREN_PREFIX: &[u8; 8] = b"children";
+	/// Progress of the time and size based blocks pruning.
+	pub const BLOCKS_RETENTION: &[u8; 9] = b"retention";
+	/// The next block to move to the cold database.
+	pub const COLD_BLOCKS: &[u8; 4] = b"cold";
+	/// Dictionary compressing the block bodies.
+	pub const COMPRESSION_DICTIONARY: &[u8; 5] = b"zdict";
+	/// The columns whose values may be compressed.
+	pub const COMPRESSED_COLUMNS: &[u8; 5] = b"zcols";
+	/// The state leases.
+	pub const STATE_LEASES: &[u8; 6] = b"leases";
+	/// The first block covered by the extrinsic indexes.
+	pub const EXTRINSIC_INDEX_START: &[u8; 7] = b"xtindex";
+	/// The first block covered by the storage diffs.
+	pub const STORAGE_DIFFS_START: &[u8; 5] = b"diffs";
+	/// The first block covered by the key history index.
+	pub const KEY_HISTORY_START: &[u8; 7] = b"keyhist";
 }
 
 /// Database metadata.
@@ -546,6 +562,23 @@ pub fn read_genesis_hash<Hash: Decode>(
 	}
 }
 
+/// Read the number of the first block covered by an index, stored under `key`.
+pub fn read_index_start<N: Decode>(
+	db: &dyn Database<DbHash>,
+	key: &[u8],
+) -> sp_blockchain::Result<Option<N>> {
+	match db.get(COLUMN_META, key) {
+		Some(n) => match Decode::decode(&mut &n[..]) {
+			Ok(n) => Ok(Some(n)),
+			Err(err) => Err(sp_blockchain::Error::Backend(format!(
+				"Error decoding the start of an index: {}",
+				err
+			))),
+		},
+		None => Ok(None),
+	}
+}
+
 impl DatabaseType {
 	/// Returns str representation of the type.
 	pub fn as_str(&self) -> &'static str {
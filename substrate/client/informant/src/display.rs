@@ -94,54 +94,66 @@ impl<B: BlockT> InformantDisplay<B> {
 			(diff_bytes_inbound, diff_bytes_outbound)
 		};
 
-		let (level, status, target) =
-			match (sync_status.state, sync_status.state_sync, sync_status.warp_sync) {
-				// Do not set status to "Block history" when we are doing a major sync.
-				//
-				// A node could for example have been warp synced to the tip of the chain and
-				// shutdown. At the next start we still need to download the block history, but
-				// first will sync to the tip of the chain.
-				(
-					sync_status,
-					_,
-					Some(WarpSyncProgress { phase: WarpSyncPhase::DownloadingBlocks(n), .. }),
-				) if !sync_status.is_major_syncing() => ("", "Block history".into(), format!(", #{}", n)),
+		let (level, status, target) = match (
+			sync_status.state,
+			sync_status.state_sync,
+			sync_status.warp_sync,
+			sync_status.gap_sync,
+		) {
+			// Do not set status to "Block history" when we are doing a major sync.
+			//
+			// A node could for example have been warp synced to the tip of the chain and
+			// shutdown. At the next start we still need to download the block history, but
+			// first will sync to the tip of the chain.
+			(sync_status, _, _, Some(gap)) if !sync_status.is_major_syncing() => (
+				"",
+				"Block history".into(),
+				format!(
+					", #{} down to #{}, {:.2} Mib",
+					gap.lowest_queued,
+					gap.target,
+					(gap.total_bytes as f32) / (1024f32 * 1024f32)
+				),
+			),
+			(
+				sync_status,
+				_,
+				Some(WarpSyncProgress { phase: WarpSyncPhase::DownloadingBlocks(n), .. }),
+				_,
+			) if !sync_status.is_major_syncing() => ("", "Block history".into(), format!(", #{}", n)),
+			(_, _, Some(WarpSyncProgress { phase: WarpSyncPhase::AwaitingTargetBlock, .. }), _) =>
+				("", "Waiting for pending target block".into(), "".into()),
+			// Handle all phases besides the two phases we already handle above.
+			(_, _, Some(warp), _)
+				if !matches!(
+					warp.phase,
+					WarpSyncPhase::AwaitingTargetBlock | WarpSyncPhase::DownloadingBlocks(_)
+				) =>
 				(
-					_,
-					_,
-					Some(WarpSyncProgress { phase: WarpSyncPhase::AwaitingTargetBlock, .. }),
-				) => ("", "Waiting for pending target block".into(), "".into()),
-				// Handle all phases besides the two phases we already handle above.
-				(_, _, Some(warp))
-					if !matches!(
-						warp.phase,
-						WarpSyncPhase::AwaitingTargetBlock | WarpSyncPhase::DownloadingBlocks(_)
-					) =>
-					(
-						"",
-						"Warping".into(),
-						format!(
-							", {}, {:.2} Mib",
-							warp.phase,
-							(warp.total_bytes as f32) / (1024f32 * 1024f32)
-						),
-					),
-				(_, Some(state), _) => (
-					" ",
-					"State sync".into(),
+					"",
+					"Warping".into(),
 					format!(
-						", {}, {}%, {:.2} Mib",
-						state.phase,
-						state.percentage,
-						(state.size as f32) / (1024f32 * 1024f32)
+						", {}, {:.2} Mib",
+						warp.phase,
+						(warp.total_bytes as f32) / (1024f32 * 1024f32)
 					),
 				),
-				(SyncState::Idle, _, _) => ("", "Idle".into(), "".into()),
-				(SyncState::Downloading { target }, _, _) =>
-					("", format!("Syncing{}", speed), format!(", target=#{target}")),
-				(SyncState::Importing { target }, _, _) =>
-					("", format!("Preparing{}", speed), format!(", target=#{target}")),
-			};
+			(_, Some(state), _, _) => (
+				" ",
+				"State sync".into(),
+				format!(
+					", {}, {}%, {:.2} Mib",
+					state.phase,
+					state.percentage,
+					(state.size as f32) / (1024f32 * 1024f32)
+				),
+			),
+			(SyncState::Idle, _, _, _) => ("", "Idle".into(), "".into()),
+			(SyncState::Downloading { target }, _, _, _) =>
+				("", format!("Syncing{}", speed), format!(", target=#{target}")),
+			(SyncState::Importing { target }, _, _, _) =>
+				("", format!("Preparing{}", speed), format!(", target=#{target}")),
+		};
 
 		info!(
 			target: "substrate",
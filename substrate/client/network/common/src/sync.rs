@@ -54,3 +54,17 @@ impl Default for SyncMode {
 		Self::Full
 	}
 }
+
+/// Configuration of the download of the block history missing after warp or state sync.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GapSyncConfig {
+	/// Only download the block history once requested, instead of as soon as the gap appears.
+	pub on_demand: bool,
+	/// Only download the blocks of the gap within the given number of blocks of the best block.
+	///
+	/// The blocks below them are never downloaded, even by a later run, and remain recorded as a
+	/// gap by the database.
+	pub depth: Option<u32>,
+	/// Maximum number of bytes of block history to download per second.
+	pub max_bytes_per_second: Option<u64>,
+}
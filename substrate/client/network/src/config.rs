@@ -45,7 +45,7 @@ use zeroize::Zeroize;
 
 pub use sc_network_common::{
 	role::{Role, Roles},
-	sync::SyncMode,
+	sync::{GapSyncConfig, SyncMode},
 	ExHashT,
 };
 
@@ -570,6 +570,21 @@ impl NonDefaultSetConfig {
 	}
 }
 
+/// Trusted block to use as the target of warp sync, instead of the block proved by warp proofs.
+///
+/// It allows warp syncing networks which don't run a finality gadget providing warp proofs.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct WarpSyncCheckpoint {
+	/// Hash of the block.
+	pub hash: sp_core::Bytes,
+	/// SCALE-encoded header of the block.
+	pub header: sp_core::Bytes,
+	/// SCALE-encoded justifications of the block, if any.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub justifications: Option<sp_core::Bytes>,
+}
+
 /// Network service configuration.
 #[derive(Clone, Debug)]
 pub struct NetworkConfiguration {
@@ -615,6 +630,19 @@ pub struct NetworkConfiguration {
 	/// Initial syncing mode.
 	pub sync_mode: SyncMode,
 
+	/// Download of the block history missing after warp or state sync.
+	pub gap_sync: GapSyncConfig,
+
+	/// Trusted checkpoint to warp sync to, overriding the warp sync provider and the checkpoint of
+	/// the chain spec.
+	pub warp_sync_checkpoint: Option<WarpSyncCheckpoint>,
+
+	/// Trusted block authoring peers the private transactions are sent to.
+	///
+	/// If not empty, and no private transactions policy is given to the service, all the
+	/// transactions of the pool are private: they're only sent to those peers, never gossiped.
+	pub private_transactions_peers: Vec<MultiaddrWithPeerId>,
+
 	/// True if Kademlia random discovery should be enabled.
 	///
 	/// If true, the node will automatically randomly walk the DHT in order to find new peers.
@@ -678,6 +706,9 @@ impl NetworkConfiguration {
 			max_parallel_downloads: 5,
 			max_blocks_per_request: 64,
 			sync_mode: SyncMode::Full,
+			gap_sync: GapSyncConfig::default(),
+			warp_sync_checkpoint: None,
+			private_transactions_peers: Vec::new(),
 			enable_dht_random_walk: true,
 			allow_non_globals_in_dht: false,
 			kademlia_disjoint_query_paths: false,
@@ -19,18 +19,25 @@
 
 //! [`PeerStore`] manages peer reputations and provides connection candidates to
 //! [`crate::protocol_controller::ProtocolController`].
+//!
+//! The latest changes of the reputation of every peer are kept along with their reasons, and the
+//! reputations can be persisted to a file to survive restarts.
 
 use libp2p::PeerId;
 use log::trace;
 use parking_lot::Mutex;
 use partial_sort::PartialSort;
 use sc_network_common::{role::ObservedRole, types::ReputationChange};
+use serde::{Deserialize, Serialize};
 use std::{
+	borrow::Cow,
 	cmp::{Ord, Ordering, PartialOrd},
-	collections::{hash_map::Entry, HashMap, HashSet},
+	collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
 	fmt::Debug,
+	fs, io,
+	path::{Path, PathBuf},
 	sync::Arc,
-	time::{Duration, Instant},
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use wasm_timer::Delay;
 
@@ -52,6 +59,10 @@ const INVERSE_DECREMENT: i32 = 50;
 /// Amount of time between the moment we last updated the [`PeerStore`] entry and the moment we
 /// remove it, once the reputation value reaches 0.
 const FORGET_AFTER: Duration = Duration::from_secs(3600);
+/// Maximum number of reputation changes kept in the history of a peer.
+pub const MAX_REPUTATION_HISTORY: usize = 32;
+/// Amount of time between two writes of the reputations to the persistence file, if any.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Trait providing peer reputation management and connection candidates.
 pub trait PeerStoreProvider: Debug + Send {
@@ -133,9 +144,71 @@ impl PeerStoreHandle {
 	pub fn add_known_peer(&mut self, peer_id: PeerId) {
 		self.inner.lock().add_known_peer(peer_id);
 	}
+
+	/// Get the reputation of a peer along with its latest changes, if the peer is known.
+	pub fn peer_reputation_info(&self, peer_id: &PeerId) -> Option<PeerReputation> {
+		self.inner.lock().peers.get(peer_id).map(PeerInfo::reputation_info)
+	}
+
+	/// Get the peers which are currently banned, along with their reputation.
+	pub fn banned_peers(&self) -> Vec<(PeerId, PeerReputation)> {
+		self.inner
+			.lock()
+			.peers
+			.iter()
+			.filter(|(_, info)| info.is_banned())
+			.map(|(peer_id, info)| (*peer_id, info.reputation_info()))
+			.collect()
+	}
+
+	/// Unban a peer by resetting its reputation to 0.
+	///
+	/// Returns `false` if the peer isn't banned.
+	pub fn unban_peer(&mut self, peer_id: &PeerId) -> bool {
+		self.inner.lock().unban_peer(peer_id)
+	}
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Change of the reputation of a peer, as recorded in its history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReputationEvent {
+	/// Milliseconds since the UNIX epoch at which the change happened.
+	pub timestamp: u64,
+	/// Value of the change.
+	pub value: i32,
+	/// Reason of the change.
+	pub reason: Cow<'static, str>,
+	/// Reputation of the peer after the change.
+	pub reputation: i32,
+}
+
+/// Reputation of a peer, along with its latest changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerReputation {
+	/// Current reputation of the peer.
+	pub reputation: i32,
+	/// Whether the reputation of the peer is below [`BANNED_THRESHOLD`].
+	pub banned: bool,
+	/// Latest changes of the reputation, oldest first.
+	///
+	/// At most [`MAX_REPUTATION_HISTORY`] changes are kept. The decay of the reputation over time
+	/// isn't recorded.
+	pub history: Vec<ReputationEvent>,
+}
+
+impl PeerReputation {
+	/// Get the latest change which brought the reputation of the peer below
+	/// [`BANNED_THRESHOLD`], if still in the history.
+	pub fn ban_event(&self) -> Option<&ReputationEvent> {
+		self.history.iter().rev().find(|event| {
+			event.reputation < BANNED_THRESHOLD &&
+				event.reputation.saturating_sub(event.value) >= BANNED_THRESHOLD
+		})
+	}
+}
+
+#[derive(Debug, Clone)]
 struct PeerInfo {
 	/// Reputation of the peer.
 	reputation: i32,
@@ -145,11 +218,14 @@ struct PeerInfo {
 
 	/// Role of the peer, if known.
 	role: Option<ObservedRole>,
+
+	/// Latest changes of the reputation of the peer.
+	history: VecDeque<ReputationEvent>,
 }
 
 impl Default for PeerInfo {
 	fn default() -> Self {
-		Self { reputation: 0, last_updated: Instant::now(), role: None }
+		Self { reputation: 0, last_updated: Instant::now(), role: None, history: VecDeque::new() }
 	}
 }
 
@@ -179,9 +255,34 @@ impl PeerInfo {
 		self.reputation < BANNED_THRESHOLD
 	}
 
-	fn add_reputation(&mut self, increment: i32) {
+	fn add_reputation(&mut self, increment: i32, reason: Cow<'static, str>) {
 		self.reputation = self.reputation.saturating_add(increment);
 		self.bump_last_updated();
+		self.record(increment, reason);
+	}
+
+	fn record(&mut self, value: i32, reason: Cow<'static, str>) {
+		let timestamp = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |since_epoch| since_epoch.as_millis() as u64);
+
+		while self.history.len() >= MAX_REPUTATION_HISTORY {
+			self.history.pop_front();
+		}
+		self.history.push_back(ReputationEvent {
+			timestamp,
+			value,
+			reason,
+			reputation: self.reputation,
+		});
+	}
+
+	fn reputation_info(&self) -> PeerReputation {
+		PeerReputation {
+			reputation: self.reputation,
+			banned: self.is_banned(),
+			history: self.history.iter().cloned().collect(),
+		}
 	}
 
 	fn decay_reputation(&mut self, seconds_passed: u64) {
@@ -212,6 +313,7 @@ impl PeerInfo {
 struct PeerStoreInner {
 	peers: HashMap<PeerId, PeerInfo>,
 	protocols: Vec<ProtocolHandle>,
+	persistence_path: Option<PathBuf>,
 }
 
 impl PeerStoreInner {
@@ -225,7 +327,7 @@ impl PeerStoreInner {
 
 	fn report_disconnect(&mut self, peer_id: PeerId) {
 		let peer_info = self.peers.entry(peer_id).or_default();
-		peer_info.add_reputation(DISCONNECT_REPUTATION_CHANGE);
+		peer_info.add_reputation(DISCONNECT_REPUTATION_CHANGE, Cow::Borrowed("Disconnected"));
 
 		log::trace!(
 			target: LOG_TARGET,
@@ -238,7 +340,8 @@ impl PeerStoreInner {
 
 	fn report_peer(&mut self, peer_id: PeerId, change: ReputationChange) {
 		let peer_info = self.peers.entry(peer_id).or_default();
-		peer_info.add_reputation(change.value);
+		let was_banned = peer_info.is_banned();
+		peer_info.add_reputation(change.value, Cow::Borrowed(change.reason));
 
 		if peer_info.reputation < BANNED_THRESHOLD {
 			self.protocols.iter().for_each(|handle| handle.disconnect_peer(peer_id));
@@ -251,6 +354,12 @@ impl PeerStoreInner {
 				peer_info.reputation,
 				change.reason,
 			);
+
+			// Bans are persisted right away, so they're kept if the node stops before the next
+			// periodic write.
+			if !was_banned {
+				self.persist();
+			}
 		} else {
 			log::trace!(
 				target: LOG_TARGET,
@@ -263,6 +372,22 @@ impl PeerStoreInner {
 		}
 	}
 
+	fn unban_peer(&mut self, peer_id: &PeerId) -> bool {
+		let Some(peer_info) = self.peers.get_mut(peer_id).filter(|info| info.is_banned()) else {
+			return false
+		};
+
+		let change = peer_info.reputation.saturating_neg();
+		peer_info.reputation = 0;
+		peer_info.bump_last_updated();
+		peer_info.record(change, Cow::Borrowed("Unbanned"));
+
+		log::info!(target: LOG_TARGET, "Unbanned {peer_id}, reputation: {change:+} to 0.");
+		self.persist();
+
+		true
+	}
+
 	fn set_peer_role(&mut self, peer_id: &PeerId, role: ObservedRole) {
 		log::trace!(target: LOG_TARGET, "Set {peer_id} role to {role:?}");
 
@@ -289,7 +414,7 @@ impl PeerStoreInner {
 			.peers
 			.iter()
 			.filter_map(|(peer_id, info)| {
-				(!info.is_banned() && !ignored.contains(peer_id)).then_some((*peer_id, *info))
+				(!info.is_banned() && !ignored.contains(peer_id)).then_some((*peer_id, info))
 			})
 			.collect::<Vec<_>>();
 		let count = std::cmp::min(count, candidates.len());
@@ -315,6 +440,31 @@ impl PeerStoreInner {
 			.retain(|_, info| info.reputation != 0 || info.last_updated + FORGET_AFTER > now);
 	}
 
+	/// Write the reputations of the peers to the persistence file, if any.
+	fn persist(&self) {
+		let Some(path) = &self.persistence_path else { return };
+
+		if let Err(e) = write_persisted_peers(path, &self.persisted_peers()) {
+			log::warn!(
+				target: LOG_TARGET,
+				"Failed to persist the peer reputations to {}: {e}",
+				path.display(),
+			);
+		}
+	}
+
+	fn persisted_peers(&self) -> Vec<PersistedPeer> {
+		self.peers
+			.iter()
+			.filter(|(_, info)| info.reputation != 0 || !info.history.is_empty())
+			.map(|(peer_id, info)| PersistedPeer {
+				peer_id: peer_id.to_base58(),
+				reputation: info.reputation,
+				history: info.history.clone(),
+			})
+			.collect()
+	}
+
 	fn add_known_peer(&mut self, peer_id: PeerId) {
 		match self.peers.entry(peer_id) {
 			Entry::Occupied(mut e) => {
@@ -332,6 +482,34 @@ impl PeerStoreInner {
 	}
 }
 
+/// Entry of a peer in the file the reputations are persisted to.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PersistedPeer {
+	/// Base58-encoded `PeerId` of the peer.
+	peer_id: String,
+	/// Reputation of the peer.
+	reputation: i32,
+	/// Latest changes of the reputation of the peer.
+	history: VecDeque<ReputationEvent>,
+}
+
+fn load_persisted_peers(path: &Path) -> io::Result<Vec<PersistedPeer>> {
+	let file = fs::File::open(path)?;
+	Ok(serde_json::from_reader(io::BufReader::new(file))?)
+}
+
+fn write_persisted_peers(path: &Path, peers: &[PersistedPeer]) -> io::Result<()> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	// Write to a temporary file first so a crash doesn't leave a truncated file behind.
+	let tmp_path = path.with_extension("tmp");
+	fs::write(&tmp_path, serde_json::to_vec(peers)?)?;
+	fs::rename(tmp_path, path)
+}
+
 /// Worker part of [`PeerStoreHandle`]
 #[derive(Debug)]
 pub struct PeerStore {
@@ -348,19 +526,70 @@ impl PeerStore {
 					.map(|peer_id| (peer_id, PeerInfo::default()))
 					.collect(),
 				protocols: Vec::new(),
+				persistence_path: None,
 			})),
 		}
 	}
 
+	/// Persist the reputations of the peers to the file at `path`, and restore the ones
+	/// persisted there by a previous run.
+	///
+	/// The reputations don't decay while the node isn't running, so a peer banned before a
+	/// restart is still banned after it.
+	pub fn with_persistence(self, path: PathBuf) -> Self {
+		let mut inner = self.inner.lock();
+		match load_persisted_peers(&path) {
+			Ok(persisted) => {
+				let mut restored = 0;
+				for peer in persisted {
+					let Ok(peer_id) = peer.peer_id.parse::<PeerId>() else {
+						log::warn!(
+							target: LOG_TARGET,
+							"Ignoring the persisted reputation of invalid peer {}.",
+							peer.peer_id,
+						);
+						continue
+					};
+
+					let peer_info = inner.peers.entry(peer_id).or_default();
+					peer_info.reputation = peer.reputation;
+					peer_info.history = peer.history;
+					restored += 1;
+				}
+
+				log::debug!(
+					target: LOG_TARGET,
+					"Restored the reputations of {restored} peers from {}.",
+					path.display(),
+				);
+			},
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {},
+			Err(e) => log::warn!(
+				target: LOG_TARGET,
+				"Failed to load the persisted peer reputations from {}: {e}",
+				path.display(),
+			),
+		}
+
+		inner.persistence_path = Some(path);
+		drop(inner);
+		self
+	}
+
 	/// Get `PeerStoreHandle`.
 	pub fn handle(&self) -> PeerStoreHandle {
 		PeerStoreHandle { inner: self.inner.clone() }
 	}
 
 	/// Drive the `PeerStore`, decaying reputation values over time and removing expired entries.
+	///
+	/// The reputations are also periodically persisted, if enabled with
+	/// [`PeerStore::with_persistence`]. Bans and unbans are persisted right away, and the
+	/// reputations are persisted a last time once the peer store is dropped.
 	pub async fn run(self) {
 		let started = Instant::now();
 		let mut latest_time_update = started;
+		let mut latest_persist = started;
 
 		loop {
 			let now = Instant::now();
@@ -374,14 +603,28 @@ impl PeerStore {
 			};
 
 			self.inner.lock().progress_time(seconds_passed);
+
+			if now - latest_persist >= PERSIST_INTERVAL {
+				latest_persist = now;
+				self.inner.lock().persist();
+			}
+
 			let _ = Delay::new(Duration::from_secs(1)).await;
 		}
 	}
 }
 
+impl Drop for PeerStore {
+	fn drop(&mut self) {
+		self.inner.lock().persist();
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::PeerInfo;
+	use super::{PeerInfo, PeerStore, PeerStoreProvider, BANNED_THRESHOLD, MAX_REPUTATION_HISTORY};
+	use libp2p::PeerId;
+	use sc_network_common::types::ReputationChange;
 
 	#[test]
 	fn decaying_zero_reputation_yields_zero() {
@@ -448,4 +691,116 @@ mod tests {
 		peer_info.decay_reputation(SECONDS / 2);
 		assert_eq!(peer_info.reputation, 0);
 	}
+
+	#[test]
+	fn reputation_history_is_bounded() {
+		let peer_store = PeerStore::new(Vec::new());
+		let mut handle = peer_store.handle();
+		let peer_id = PeerId::random();
+
+		for _ in 0..MAX_REPUTATION_HISTORY {
+			handle.report_peer(peer_id, ReputationChange::new(-1, "Old"));
+		}
+		handle.report_peer(peer_id, ReputationChange::new(10, "New"));
+
+		let info = handle.peer_reputation_info(&peer_id).unwrap();
+		assert_eq!(info.reputation, 10 - MAX_REPUTATION_HISTORY as i32);
+		assert_eq!(info.history.len(), MAX_REPUTATION_HISTORY);
+		let latest = info.history.last().unwrap();
+		assert_eq!(
+			(latest.value, &*latest.reason, latest.reputation),
+			(10, "New", info.reputation)
+		);
+		assert!(handle.peer_reputation_info(&PeerId::random()).is_none());
+	}
+
+	#[test]
+	fn banned_peer_can_be_unbanned() {
+		let peer_store = PeerStore::new(Vec::new());
+		let mut handle = peer_store.handle();
+		let peer_id = PeerId::random();
+
+		handle.report_peer(peer_id, ReputationChange::new(-10, "Bad"));
+		handle.report_peer(peer_id, ReputationChange::new_fatal("Very bad"));
+		assert!(handle.is_banned(&peer_id));
+
+		let banned = handle.banned_peers();
+		assert_eq!(banned.len(), 1);
+		assert_eq!(banned[0].0, peer_id);
+		assert_eq!(&*banned[0].1.ban_event().unwrap().reason, "Very bad");
+
+		assert!(handle.unban_peer(&peer_id));
+		assert!(!handle.is_banned(&peer_id));
+		assert!(handle.banned_peers().is_empty());
+		assert!(!handle.unban_peer(&peer_id));
+
+		let info = handle.peer_reputation_info(&peer_id).unwrap();
+		assert_eq!(info.reputation, 0);
+		assert!(info.reputation > BANNED_THRESHOLD);
+		assert_eq!(&*info.history.last().unwrap().reason, "Unbanned");
+	}
+
+	#[test]
+	fn reputations_are_restored_after_restart() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("network").join("peer_reputations.json");
+		let banned_peer = PeerId::random();
+		let known_peer = PeerId::random();
+
+		let peer_store = PeerStore::new(Vec::new()).with_persistence(path.clone());
+		let mut handle = peer_store.handle();
+		handle.report_peer(banned_peer, ReputationChange::new_fatal("Very bad"));
+		handle.report_peer(known_peer, ReputationChange::new(100, "Good"));
+		handle.add_known_peer(PeerId::random());
+		let before = (
+			handle.peer_reputation_info(&banned_peer).unwrap(),
+			handle.peer_reputation_info(&known_peer).unwrap(),
+		);
+		// The reputations are persisted once the peer store is dropped on shutdown.
+		drop(peer_store);
+
+		let peer_store = PeerStore::new(Vec::new()).with_persistence(path);
+		let handle = peer_store.handle();
+		assert_eq!(handle.num_known_peers(), 2);
+		assert!(handle.is_banned(&banned_peer));
+		assert_eq!(
+			(
+				handle.peer_reputation_info(&banned_peer).unwrap(),
+				handle.peer_reputation_info(&known_peer).unwrap(),
+			),
+			before,
+		);
+	}
+
+	#[test]
+	fn bans_and_unbans_are_persisted_right_away() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("peer_reputations.json");
+		let banned_peer = PeerId::random();
+		let unbanned_peer = PeerId::random();
+
+		let peer_store = PeerStore::new(Vec::new()).with_persistence(path.clone());
+		let mut handle = peer_store.handle();
+		handle.report_peer(unbanned_peer, ReputationChange::new_fatal("Very bad"));
+		handle.report_peer(banned_peer, ReputationChange::new_fatal("Very bad"));
+		assert!(handle.unban_peer(&unbanned_peer));
+
+		// The node is killed before the periodic write, without dropping the peer store.
+		std::mem::forget(peer_store);
+
+		let peer_store = PeerStore::new(Vec::new()).with_persistence(path);
+		let handle = peer_store.handle();
+		assert!(handle.is_banned(&banned_peer));
+		assert!(!handle.is_banned(&unbanned_peer));
+		assert_eq!(
+			&*handle
+				.peer_reputation_info(&unbanned_peer)
+				.unwrap()
+				.history
+				.last()
+				.unwrap()
+				.reason,
+			"Unbanned",
+		);
+	}
 }
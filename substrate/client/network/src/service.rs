@@ -37,7 +37,7 @@ use crate::{
 	network_state::{
 		NetworkState, NotConnectedPeer as NetworkStateNotConnectedPeer, Peer as NetworkStatePeer,
 	},
-	peer_store::{PeerStoreHandle, PeerStoreProvider},
+	peer_store::{PeerReputation, PeerStoreHandle, PeerStoreProvider},
 	protocol::{self, NotifsHandlerError, Protocol, Ready},
 	protocol_controller::{self, ProtoSetConfig, ProtocolController, SetId},
 	request_responses::{IfDisconnected, RequestFailure},
@@ -744,6 +744,23 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		rx.await.map_err(|_| ())
 	}
 
+	/// Get the reputation of a peer along with its latest changes, if the peer is known.
+	pub fn peer_reputation_info(&self, peer_id: &PeerId) -> Option<PeerReputation> {
+		self.peer_store_handle.peer_reputation_info(peer_id)
+	}
+
+	/// Get the peers which are currently banned, along with their reputation.
+	pub fn banned_peers(&self) -> Vec<(PeerId, PeerReputation)> {
+		self.peer_store_handle.banned_peers()
+	}
+
+	/// Unban a peer by resetting its reputation to 0.
+	///
+	/// Returns `false` if the peer isn't banned.
+	pub fn unban_peer(&self, peer_id: &PeerId) -> bool {
+		self.peer_store_handle.clone().unban_peer(peer_id)
+	}
+
 	/// Utility function to extract `PeerId` from each `Multiaddr` for peer set updates.
 	///
 	/// Returns an `Err` if one of the given addresses is invalid or contains an
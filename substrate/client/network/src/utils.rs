@@ -59,6 +59,13 @@ impl<T: Hash + Eq> LruHashSet<T> {
 		}
 		false
 	}
+
+	/// Remove element from the set.
+	///
+	/// Returns `true` if the element was in the set.
+	pub fn remove(&mut self, e: &T) -> bool {
+		self.set.remove(e)
+	}
 }
 
 #[cfg(test)]
@@ -86,4 +93,15 @@ mod tests {
 		assert!(set.insert(3));
 		assert_eq!(vec![&1, &3], set.set.iter().collect::<Vec<_>>());
 	}
+
+	#[test]
+	fn remove() {
+		let mut set = LruHashSet::<u8>::new(NonZeroUsize::new(3).unwrap());
+		set.insert(1);
+		set.insert(2);
+
+		assert!(set.remove(&1));
+		assert!(!set.remove(&1));
+		assert_eq!(vec![&2], set.set.iter().collect::<Vec<_>>());
+	}
 }
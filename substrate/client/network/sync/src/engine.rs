@@ -57,7 +57,7 @@ use prost::Message;
 use schnellru::{ByLength, LruMap};
 use tokio::time::{Interval, MissedTickBehavior};
 
-use sc_client_api::{BlockBackend, HeaderBackend, ProofProvider};
+use sc_client_api::{AuxStore, BlockBackend, HeaderBackend, ProofProvider};
 use sc_consensus::{import_queue::ImportQueueService, IncomingBlock};
 use sc_network::{
 	config::{
@@ -328,6 +328,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -365,6 +366,7 @@ where
 			mode,
 			max_parallel_downloads,
 			max_blocks_per_request,
+			gap_sync: net_config.network_config.gap_sync.clone(),
 			metrics_registry: metrics_registry.cloned(),
 		};
 		let cache_capacity = (net_config.network_config.default_peers_set.in_peers +
@@ -881,6 +883,9 @@ where
 			},
 			ToServiceCommand::OnBlockFinalized(hash, header) =>
 				self.strategy.on_block_finalized(&hash, *header.number()),
+			ToServiceCommand::StartGapSync(tx) => {
+				let _ = tx.send(self.strategy.start_gap_sync());
+			},
 		}
 	}
 
@@ -979,7 +984,8 @@ where
 		}
 
 		if !self.default_peers_set_no_slot_connected_peers.remove(&peer_id) &&
-			info.inbound && info.info.roles.is_full()
+			info.inbound &&
+			info.info.roles.is_full()
 		{
 			match self.num_in_peers.checked_sub(1) {
 				Some(value) => {
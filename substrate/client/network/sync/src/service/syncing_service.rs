@@ -59,6 +59,7 @@ pub enum ToServiceCommand<B: BlockT> {
 	NumSyncRequests(oneshot::Sender<usize>),
 	PeersInfo(oneshot::Sender<Vec<(PeerId, ExtendedPeerInfo<B>)>>),
 	OnBlockFinalized(B::Hash, B::Header),
+	StartGapSync(oneshot::Sender<bool>),
 	// Status {
 	// 	pending_response: oneshot::Sender<SyncStatus<B>>,
 	// },
@@ -147,6 +148,16 @@ impl<B: BlockT> SyncingService<B> {
 		let _ = self.tx.unbounded_send(ToServiceCommand::OnBlockFinalized(hash, header));
 	}
 
+	/// Start downloading the block history missing after warp or state sync.
+	///
+	/// Returns `false` if there's no block history to download.
+	pub async fn start_gap_sync(&self) -> Result<bool, oneshot::Canceled> {
+		let (tx, rx) = oneshot::channel();
+		let _ = self.tx.unbounded_send(ToServiceCommand::StartGapSync(tx));
+
+		rx.await
+	}
+
 	/// Get sync status
 	///
 	/// Returns an error if `SyncingEngine` has terminated.
@@ -19,7 +19,9 @@
 //! [`SyncingStrategy`] is a proxy between [`crate::engine::SyncingEngine`]
 //! and specific syncing algorithms.
 
+mod aux_schema;
 pub mod chain_sync;
+pub mod gap_sync;
 mod state;
 pub mod state_sync;
 pub mod warp;
@@ -32,11 +34,11 @@ use chain_sync::{ChainSync, ChainSyncAction, ChainSyncMode};
 use libp2p::PeerId;
 use log::{error, info};
 use prometheus_endpoint::Registry;
-use sc_client_api::{BlockBackend, ProofProvider};
+use sc_client_api::{AuxStore, BlockBackend, ProofProvider};
 use sc_consensus::{BlockImportError, BlockImportStatus, IncomingBlock};
 use sc_network_common::sync::{
 	message::{BlockAnnounce, BlockData, BlockRequest},
-	SyncMode,
+	GapSyncConfig, SyncMode,
 };
 use sp_blockchain::{Error as ClientError, HeaderBackend, HeaderMetadata};
 use sp_consensus::BlockOrigin;
@@ -67,6 +69,8 @@ pub struct SyncingConfig {
 	pub max_parallel_downloads: u32,
 	/// Maximum number of blocks to request.
 	pub max_blocks_per_request: u32,
+	/// Download of the block history missing after warp or state sync.
+	pub gap_sync: GapSyncConfig,
 	/// Prometheus metrics registry.
 	pub metrics_registry: Option<Registry>,
 }
@@ -110,6 +114,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -130,6 +135,7 @@ where
 				client.clone(),
 				config.max_parallel_downloads,
 				config.max_blocks_per_request,
+				config.gap_sync,
 				config.metrics_registry,
 			)?))
 		}
@@ -321,6 +327,17 @@ where
 		}
 	}
 
+	/// Start downloading the block history missing after warp or state sync.
+	///
+	/// Returns `false` if there's no block history to download yet.
+	pub fn start_gap_sync(&mut self) -> bool {
+		match self {
+			SyncingStrategy::WarpSyncStrategy(_) => false,
+			SyncingStrategy::StateSyncStrategy(_) => false,
+			SyncingStrategy::ChainSyncStrategy(strategy) => strategy.start_gap_sync(),
+		}
+	}
+
 	/// Get the total number of downloaded blocks.
 	pub fn num_downloaded_blocks(&self) -> usize {
 		match self {
@@ -434,6 +451,7 @@ where
 							client,
 							config.max_parallel_downloads,
 							config.max_blocks_per_request,
+							config.gap_sync,
 							config.metrics_registry,
 						) {
 							Ok(chain_sync) => chain_sync,
@@ -464,6 +482,7 @@ where
 					client,
 					config.max_parallel_downloads,
 					config.max_blocks_per_request,
+					config.gap_sync,
 					config.metrics_registry,
 				) {
 					Ok(chain_sync) => chain_sync,
@@ -0,0 +1,223 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Schema for the progress of warp sync in the aux-db, so that a restarted node resumes warp sync
+//! where it left off.
+
+use crate::strategy::warp::EncodedVerifierState;
+use codec::{Decode, Encode};
+use sc_client_api::backend::AuxStore;
+use sp_blockchain::{Error as ClientError, Result as ClientResult};
+
+const WARP_PROOF_PROGRESS_KEY: &[u8] = b"warp_sync_proof_progress";
+const STATE_SYNC_PROGRESS_KEY: &[u8] = b"warp_sync_state_progress";
+const STATE_SYNC_CHUNK_PREFIX: &[u8] = b"warp_sync_state_chunk";
+
+/// State received in a response, as the key values and the storage keys of each trie, by the
+/// root of the trie.
+pub(crate) type StateChunk = Vec<(Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>))>;
+
+/// Progress of the warp proofs download: the proofs are verified up to `last_hash`, from which
+/// the next proof is verified against `verifier_state`.
+///
+/// The target block of a complete proof isn't persisted, as it would be stale after a restart.
+#[derive(Debug, Encode, Decode, PartialEq)]
+pub(crate) struct PersistedWarpProof<Hash> {
+	pub verifier_state: EncodedVerifierState,
+	pub last_hash: Hash,
+}
+
+/// Progress of the download of a key range of the state.
+#[derive(Debug, Encode, Decode, PartialEq)]
+pub(crate) struct PersistedKeyRange {
+	/// Last key received, followed by the last key received of its child trie, if any.
+	pub last_key: Vec<Vec<u8>>,
+	/// Whether the whole range has been received.
+	pub complete: bool,
+	/// Number of state chunks received for the range.
+	pub chunks: u32,
+}
+
+/// Progress of the state download.
+#[derive(Debug, Encode, Decode, PartialEq)]
+pub(crate) struct PersistedStateSync<Hash> {
+	/// Block the state is downloaded at.
+	pub target: Hash,
+	/// Progress of the key ranges of the state.
+	pub ranges: Vec<PersistedKeyRange>,
+}
+
+fn load_decode<B: AuxStore, T: Decode>(backend: &B, key: &[u8]) -> ClientResult<Option<T>> {
+	match backend.get_aux(key)? {
+		None => Ok(None),
+		Some(t) => T::decode(&mut &t[..])
+			.map_err(|e| ClientError::Backend(format!("Warp sync DB is corrupted: {}", e)))
+			.map(Some),
+	}
+}
+
+fn state_chunk_key(range: usize, index: u32) -> Vec<u8> {
+	(STATE_SYNC_CHUNK_PREFIX, range as u32, index).encode()
+}
+
+/// Load the progress of the warp proofs download, if any.
+pub(crate) fn load_warp_proof<B: AuxStore, Hash: Decode>(
+	backend: &B,
+) -> ClientResult<Option<PersistedWarpProof<Hash>>> {
+	load_decode(backend, WARP_PROOF_PROGRESS_KEY)
+}
+
+/// Write the progress of the warp proofs download.
+pub(crate) fn write_warp_proof<B: AuxStore, Hash: Encode>(
+	backend: &B,
+	progress: &PersistedWarpProof<Hash>,
+) -> ClientResult<()> {
+	backend.insert_aux(&[(WARP_PROOF_PROGRESS_KEY, progress.encode().as_slice())], &[])
+}
+
+/// Clear the progress of the warp proofs download.
+pub(crate) fn clear_warp_proof<B: AuxStore>(backend: &B) -> ClientResult<()> {
+	backend.insert_aux(&[], &[WARP_PROOF_PROGRESS_KEY])
+}
+
+/// Load the progress of the state download, if any.
+pub(crate) fn load_state_sync<B: AuxStore, Hash: Decode>(
+	backend: &B,
+) -> ClientResult<Option<PersistedStateSync<Hash>>> {
+	load_decode(backend, STATE_SYNC_PROGRESS_KEY)
+}
+
+/// Load the state chunks received for the key range `range`, whose progress is `progress`.
+pub(crate) fn load_state_chunks<B: AuxStore>(
+	backend: &B,
+	range: usize,
+	progress: &PersistedKeyRange,
+) -> ClientResult<Vec<StateChunk>> {
+	(0..progress.chunks)
+		.map(|index| {
+			load_decode::<_, StateChunk>(backend, &state_chunk_key(range, index))?.ok_or_else(
+				|| {
+					ClientError::Backend(format!(
+						"Missing state chunk {index} of key range {range}"
+					))
+				},
+			)
+		})
+		.collect()
+}
+
+/// Write the progress of the state download, along with the state `chunk` last received for the
+/// key range `range`.
+///
+/// Both are written at once, so the progress never refers to a chunk which isn't written.
+pub(crate) fn write_state_sync<B: AuxStore, Hash: Encode>(
+	backend: &B,
+	progress: &PersistedStateSync<Hash>,
+	range: usize,
+	chunk: &StateChunk,
+) -> ClientResult<()> {
+	let index = progress.ranges[range].chunks.checked_sub(1).ok_or_else(|| {
+		ClientError::Backend(format!("No state chunk received for key range {range}"))
+	})?;
+	let chunk_key = state_chunk_key(range, index);
+	let chunk = chunk.encode();
+	let progress = progress.encode();
+	backend.insert_aux(
+		&[(STATE_SYNC_PROGRESS_KEY, progress.as_slice()), (chunk_key.as_slice(), chunk.as_slice())],
+		&[],
+	)
+}
+
+/// Clear the progress of the state download, along with the state chunks received.
+pub(crate) fn clear_state_sync<B: AuxStore, Hash: Decode>(backend: &B) -> ClientResult<()> {
+	// A corrupted progress is cleared as well, leaving its chunks behind.
+	let ranges = load_state_sync::<_, Hash>(backend)
+		.ok()
+		.flatten()
+		.map(|progress| progress.ranges)
+		.unwrap_or_default();
+	let chunk_keys = ranges
+		.iter()
+		.enumerate()
+		.flat_map(|(range, progress)| {
+			(0..progress.chunks).map(move |index| state_chunk_key(range, index))
+		})
+		.collect::<Vec<_>>();
+	let keys = std::iter::once(STATE_SYNC_PROGRESS_KEY)
+		.chain(chunk_keys.iter().map(Vec::as_slice))
+		.collect::<Vec<_>>();
+	backend.insert_aux(&[], &keys)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use substrate_test_runtime_client::{
+		runtime::Hash, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+	};
+
+	#[test]
+	fn warp_proof_progress_is_written_and_cleared() {
+		let client = TestClientBuilder::new().build();
+		assert_eq!(load_warp_proof::<_, Hash>(&client).unwrap(), None);
+
+		let progress = PersistedWarpProof {
+			verifier_state: EncodedVerifierState(vec![3]),
+			last_hash: Hash::random(),
+		};
+		write_warp_proof(&client, &progress).unwrap();
+		assert_eq!(load_warp_proof(&client).unwrap(), Some(progress));
+
+		clear_warp_proof(&client).unwrap();
+		assert_eq!(load_warp_proof::<_, Hash>(&client).unwrap(), None);
+	}
+
+	#[test]
+	fn state_chunks_are_written_and_cleared() {
+		let client = TestClientBuilder::new().build();
+		let target = Hash::random();
+		let mut progress = PersistedStateSync {
+			target,
+			ranges: vec![
+				PersistedKeyRange { last_key: Vec::new(), complete: false, chunks: 0 },
+				PersistedKeyRange { last_key: Vec::new(), complete: false, chunks: 0 },
+			],
+		};
+		let chunks = (0..2u8)
+			.map(|i| vec![(Vec::new(), (vec![(vec![0x80, i], vec![i])], Vec::new()))])
+			.collect::<Vec<StateChunk>>();
+
+		// No chunk was received for the range yet.
+		assert!(write_state_sync(&client, &progress, 1, &chunks[0]).is_err());
+
+		for (i, chunk) in chunks.iter().enumerate() {
+			progress.ranges[1].last_key = vec![vec![0x80, i as u8]];
+			progress.ranges[1].chunks += 1;
+			write_state_sync(&client, &progress, 1, chunk).unwrap();
+		}
+		assert_eq!(load_state_sync(&client).unwrap(), Some(progress));
+		let loaded = load_state_sync::<_, Hash>(&client).unwrap().unwrap();
+		assert_eq!(load_state_chunks(&client, 0, &loaded.ranges[0]).unwrap(), Vec::new());
+		assert_eq!(load_state_chunks(&client, 1, &loaded.ranges[1]).unwrap(), chunks);
+
+		clear_state_sync::<_, Hash>(&client).unwrap();
+		assert_eq!(load_state_sync::<_, Hash>(&client).unwrap(), None);
+		assert!(client.get_aux(&state_chunk_key(1, 0)).unwrap().is_none());
+		assert!(client.get_aux(&state_chunk_key(1, 1)).unwrap().is_none());
+	}
+}
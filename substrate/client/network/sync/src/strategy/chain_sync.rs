@@ -34,8 +34,8 @@ use crate::{
 	extra_requests::ExtraRequests,
 	schema::v1::StateResponse,
 	strategy::{
+		gap_sync::GapSync,
 		state_sync::{ImportResult, StateSync, StateSyncProvider},
-		warp::{WarpSyncPhase, WarpSyncProgress},
 	},
 	types::{BadPeer, OpaqueStateRequest, OpaqueStateResponse, SyncState, SyncStatus},
 	LOG_TARGET,
@@ -45,10 +45,14 @@ use codec::Encode;
 use libp2p::PeerId;
 use log::{debug, error, info, trace, warn};
 use prometheus_endpoint::{register, Gauge, GaugeVec, Opts, PrometheusError, Registry, U64};
-use sc_client_api::{BlockBackend, ProofProvider};
+use sc_client_api::{AuxStore, BlockBackend, ProofProvider};
 use sc_consensus::{BlockImportError, BlockImportStatus, IncomingBlock};
-use sc_network_common::sync::message::{
-	BlockAnnounce, BlockAttributes, BlockData, BlockRequest, BlockResponse, Direction, FromBlock,
+use sc_network_common::sync::{
+	message::{
+		BlockAnnounce, BlockAttributes, BlockData, BlockRequest, BlockResponse, Direction,
+		FromBlock,
+	},
+	GapSyncConfig,
 };
 use sp_arithmetic::traits::Saturating;
 use sp_blockchain::{Error as ClientError, HeaderBackend, HeaderMetadata};
@@ -74,7 +78,7 @@ mod test;
 const MAX_IMPORTING_BLOCKS: usize = 2048;
 
 /// Maximum blocks to download ahead of any gap.
-const MAX_DOWNLOAD_AHEAD: u32 = 2048;
+pub(crate) const MAX_DOWNLOAD_AHEAD: u32 = 2048;
 
 /// Maximum blocks to look backwards. The gap is the difference between the highest block and the
 /// common block of a node.
@@ -202,12 +206,6 @@ impl Default for AllowedRequests {
 	}
 }
 
-struct GapSync<B: BlockT> {
-	blocks: BlockCollection<B>,
-	best_queued_number: NumberFor<B>,
-	target: NumberFor<B>,
-}
-
 /// Action that the parent of [`ChainSync`] should perform after reporting a network or block event.
 #[derive(Debug)]
 pub enum ChainSyncAction<B: BlockT> {
@@ -281,6 +279,10 @@ pub struct ChainSync<B: BlockT, Client> {
 	import_existing: bool,
 	/// Gap download process.
 	gap_sync: Option<GapSync<B>>,
+	/// Gap download configuration.
+	gap_sync_config: GapSyncConfig,
+	/// Whether the gap download was requested, if it's only started on demand.
+	gap_sync_requested: bool,
 	/// Pending actions.
 	actions: Vec<ChainSyncAction<B>>,
 	/// Prometheus metrics.
@@ -363,6 +365,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -373,6 +376,7 @@ where
 		client: Arc<Client>,
 		max_parallel_downloads: u32,
 		max_blocks_per_request: u32,
+		gap_sync_config: GapSyncConfig,
 		metrics_registry: Option<Registry>,
 	) -> Result<Self, ClientError> {
 		let mut sync = Self {
@@ -392,6 +396,8 @@ where
 			state_sync: None,
 			import_existing: false,
 			gap_sync: None,
+			gap_sync_config,
+			gap_sync_requested: false,
 			actions: Vec::new(),
 			metrics: metrics_registry.and_then(|r| match Metrics::register(&r) {
 				Ok(metrics) => Some(metrics),
@@ -433,11 +439,6 @@ where
 			SyncState::Idle
 		};
 
-		let warp_sync_progress = self.gap_sync.as_ref().map(|gap_sync| WarpSyncProgress {
-			phase: WarpSyncPhase::DownloadingBlocks(gap_sync.best_queued_number),
-			total_bytes: 0,
-		});
-
 		SyncStatus {
 			state: sync_state,
 			best_seen_block,
@@ -445,8 +446,25 @@ where
 			num_connected_peers: 0u32,
 			queued_blocks: self.queue_blocks.len() as u32,
 			state_sync: self.state_sync.as_ref().map(|s| s.progress()),
-			warp_sync: warp_sync_progress,
+			warp_sync: None,
+			gap_sync: self.gap_sync.as_ref().map(|s| s.progress()),
+		}
+	}
+
+	/// Start downloading the block history missing after warp or state sync, if not already
+	/// downloading it.
+	///
+	/// Returns `false` if there's no block history to download.
+	pub fn start_gap_sync(&mut self) -> bool {
+		if self.gap_sync.is_none() {
+			let info = self.client.info();
+			if let Some((start, end)) = info.block_gap {
+				self.gap_sync_requested = true;
+				self.gap_sync = GapSync::new(start, end, info.best_number, &self.gap_sync_config);
+				self.allowed_requests.set_all();
+			}
 		}
+		self.gap_sync.is_some()
 	}
 
 	/// Get an estimate of the number of parallel sync requests.
@@ -706,16 +724,9 @@ where
 					PeerSyncState::DownloadingGap(_) => {
 						peer.state = PeerSyncState::Available;
 						if let Some(gap_sync) = &mut self.gap_sync {
-							gap_sync.blocks.clear_peer_download(peer_id);
-							if let Some(start_block) =
-								validate_blocks::<B>(&blocks, peer_id, Some(request))?
-							{
-								gap_sync.blocks.insert(start_block, blocks, *peer_id);
-							}
 							gap = true;
-							let blocks: Vec<_> = gap_sync
-								.blocks
-								.ready_blocks(gap_sync.best_queued_number + One::one())
+							gap_sync
+								.on_block_response(peer_id, request, blocks)?
 								.into_iter()
 								.map(|block_data| {
 									let justifications =
@@ -737,14 +748,7 @@ where
 										state: None,
 									}
 								})
-								.collect();
-							debug!(
-								target: LOG_TARGET,
-								"Drained {} gap blocks from {}",
-								blocks.len(),
-								gap_sync.best_queued_number,
-							);
-							blocks
+								.collect()
 						} else {
 							debug!(target: LOG_TARGET, "Unexpected gap block response from {peer_id}");
 							return Err(BadPeer(*peer_id, rep::NO_BLOCK))
@@ -1135,7 +1139,10 @@ where
 	pub fn remove_peer(&mut self, peer_id: &PeerId) {
 		self.blocks.clear_peer_download(peer_id);
 		if let Some(gap_sync) = &mut self.gap_sync {
-			gap_sync.blocks.clear_peer_download(peer_id)
+			gap_sync.remove_peer(peer_id)
+		}
+		if let Some(state_sync) = &mut self.state_sync {
+			state_sync.remove_peer(peer_id)
 		}
 		self.peers.remove(peer_id);
 		self.extra_justifications.peer_disconnected(peer_id);
@@ -1265,11 +1272,6 @@ where
 		if self.fork_targets.remove(hash).is_some() {
 			trace!(target: LOG_TARGET, "Completed fork sync {hash:?}");
 		}
-		if let Some(gap_sync) = &mut self.gap_sync {
-			if number > gap_sync.best_queued_number && number <= gap_sync.target {
-				gap_sync.best_queued_number = number;
-			}
-		}
 		if number > self.best_queued_number {
 			self.best_queued_number = number;
 			self.best_queued_hash = *hash;
@@ -1377,12 +1379,11 @@ where
 		}
 
 		if let Some((start, end)) = info.block_gap {
-			debug!(target: LOG_TARGET, "Starting gap sync #{start} - #{end}");
-			self.gap_sync = Some(GapSync {
-				best_queued_number: start - One::one(),
-				target: end,
-				blocks: BlockCollection::new(),
-			});
+			if self.gap_sync_config.on_demand && !self.gap_sync_requested {
+				debug!(target: LOG_TARGET, "Gap #{start} - #{end} is only downloaded on demand");
+			} else {
+				self.gap_sync = GapSync::new(start, end, info.best_number, &self.gap_sync_config);
+			}
 		}
 		trace!(
 			target: LOG_TARGET,
@@ -1604,17 +1605,10 @@ where
 					trace!(target: LOG_TARGET, "Downloading fork {hash:?} from {id}");
 					peer.state = PeerSyncState::DownloadingStale(hash);
 					Some((id, req))
-				} else if let Some((range, req)) = gap_sync.as_mut().and_then(|sync| {
-					peer_gap_block_request(
-						&id,
-						peer,
-						&mut sync.blocks,
-						attrs,
-						sync.target,
-						sync.best_queued_number,
-						max_blocks_per_request,
-					)
-				}) {
+				} else if let Some((range, req)) = gap_sync
+					.as_mut()
+					.and_then(|sync| sync.block_request(&id, peer, attrs, max_blocks_per_request))
+				{
 					peer.state = PeerSyncState::DownloadingGap(range.start);
 					trace!(
 						target: LOG_TARGET,
@@ -1632,33 +1626,31 @@ where
 			.collect()
 	}
 
-	/// Get a state request scheduled by sync to be sent out (if any).
-	fn state_request(&mut self) -> Option<(PeerId, OpaqueStateRequest)> {
+	/// Get the state requests scheduled by sync to be sent out, each for a different key range.
+	fn state_requests(&mut self) -> Vec<(PeerId, OpaqueStateRequest)> {
 		if self.allowed_requests.is_empty() {
-			return None
-		}
-		if self.state_sync.is_some() &&
-			self.peers.iter().any(|(_, peer)| peer.state == PeerSyncState::DownloadingState)
-		{
-			// Only one pending state request is allowed.
-			return None
+			return Vec::new()
 		}
-		if let Some(sync) = &self.state_sync {
+		let mut requests = Vec::new();
+		if let Some(sync) = &mut self.state_sync {
 			if sync.is_complete() {
-				return None
+				return Vec::new()
 			}
 
 			for (id, peer) in self.peers.iter_mut() {
 				if peer.state.is_available() && peer.common_number >= sync.target_number() {
-					peer.state = PeerSyncState::DownloadingState;
-					let request = sync.next_request();
-					trace!(target: LOG_TARGET, "New StateRequest for {}: {:?}", id, request);
-					self.allowed_requests.clear();
-					return Some((*id, OpaqueStateRequest(Box::new(request))))
+					if let Some(request) = sync.next_request(id) {
+						peer.state = PeerSyncState::DownloadingState;
+						trace!(target: LOG_TARGET, "New StateRequest for {}: {:?}", id, request);
+						requests.push((*id, OpaqueStateRequest(Box::new(request))));
+					}
 				}
 			}
 		}
-		None
+		if !requests.is_empty() {
+			self.allowed_requests.clear();
+		}
+		requests
 	}
 
 	#[must_use]
@@ -1690,7 +1682,7 @@ where
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import(*response)
+			sync.import(peer_id, *response)
 		} else {
 			debug!(target: LOG_TARGET, "Ignored obsolete state response from {peer_id}");
 			return Err(BadPeer(*peer_id, rep::NOT_REQUESTED))
@@ -1739,9 +1731,6 @@ where
 		for (_, hash) in &results {
 			self.queue_blocks.remove(hash);
 			self.blocks.clear_queued(hash);
-			if let Some(gap_sync) = &mut self.gap_sync {
-				gap_sync.blocks.clear_queued(hash);
-			}
 		}
 		for (result, hash) in results {
 			if has_error {
@@ -1798,13 +1787,14 @@ where
 						self.restart();
 					}
 					let gap_sync_complete =
-						self.gap_sync.as_ref().map_or(false, |s| s.target == number);
+						self.gap_sync.as_ref().map_or(false, |s| s.target() == number);
 					if gap_sync_complete {
 						info!(
 							target: LOG_TARGET,
 							"Block history download is complete."
 						);
 						self.gap_sync = None;
+						self.gap_sync_requested = false;
 					}
 				},
 				Err(BlockImportError::IncompleteHeader(peer_id)) =>
@@ -1874,11 +1864,16 @@ where
 			.map(|(peer_id, request)| ChainSyncAction::SendBlockRequest { peer_id, request });
 		self.actions.extend(justification_requests);
 
-		let state_request = self
-			.state_request()
+		let state_requests = self
+			.state_requests()
 			.into_iter()
 			.map(|(peer_id, request)| ChainSyncAction::SendStateRequest { peer_id, request });
-		self.actions.extend(state_request);
+		self.actions.extend(state_requests);
+
+		// Peers left idle by the gap sync bandwidth budget are retried once it refills.
+		if self.gap_sync.as_ref().map_or(false, |s| s.is_throttled()) {
+			self.allowed_requests.set_all();
+		}
 
 		std::mem::take(&mut self.actions).into_iter()
 	}
@@ -2031,39 +2026,6 @@ fn peer_block_request<B: BlockT>(
 	Some((range, request))
 }
 
-/// Get a new block request for the peer if any.
-fn peer_gap_block_request<B: BlockT>(
-	id: &PeerId,
-	peer: &PeerSync<B>,
-	blocks: &mut BlockCollection<B>,
-	attrs: BlockAttributes,
-	target: NumberFor<B>,
-	common_number: NumberFor<B>,
-	max_blocks_per_request: u32,
-) -> Option<(Range<NumberFor<B>>, BlockRequest<B>)> {
-	let range = blocks.needed_blocks(
-		*id,
-		max_blocks_per_request,
-		std::cmp::min(peer.best_number, target),
-		common_number,
-		1,
-		MAX_DOWNLOAD_AHEAD,
-	)?;
-
-	// The end is not part of the range.
-	let last = range.end.saturating_sub(One::one());
-	let from = FromBlock::Number(last);
-
-	let request = BlockRequest::<B> {
-		id: 0,
-		fields: attrs,
-		from,
-		direction: Direction::Descending,
-		max: Some((range.end - range.start).saturated_into::<u32>()),
-	};
-	Some((range, request))
-}
-
 /// Get pending fork sync targets for a peer.
 fn fork_sync_request<B: BlockT>(
 	id: &PeerId,
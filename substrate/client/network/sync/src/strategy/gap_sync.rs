@@ -0,0 +1,428 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Download of the block history missing after warp or state sync.
+//!
+//! Warp and state sync import a recent block along with its state, leaving a gap in the block
+//! history below it which is recorded by the database. [`GapSync`] downloads the blocks of this
+//! gap in the background of the regular block sync, without executing them.
+//!
+//! The blocks are downloaded and imported downwards, from the end of the gap, so every imported
+//! block is anchored on the known block above it: the client only accepts a block of the gap
+//! whose child is known. As the database shrinks the gap from its end with every imported block,
+//! an interrupted download resumes where it stopped, and the history which isn't downloaded
+//! because of the configured depth remains recorded as a gap.
+
+use crate::{
+	blocks::BlockData,
+	strategy::chain_sync::{validate_blocks, PeerSync, MAX_DOWNLOAD_AHEAD},
+	types::BadPeer,
+	LOG_TARGET,
+};
+
+use codec::Encode;
+use libp2p::PeerId;
+use log::{debug, trace};
+use sc_network_common::sync::{
+	message::{self, BlockAttributes, BlockRequest, Direction, FromBlock},
+	GapSyncConfig,
+};
+use sp_arithmetic::traits::Saturating;
+use sp_runtime::traits::{Block as BlockT, NumberFor, One, SaturatedConversion};
+use std::{collections::BTreeMap, ops::Range, time::Instant};
+
+/// Progress of the block history download.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct GapSyncProgress<B: BlockT> {
+	/// First block of the history to download, the highest one.
+	pub start: NumberFor<B>,
+	/// Lowest block of the history queued for import.
+	pub lowest_queued: NumberFor<B>,
+	/// Last block of the history to download, the lowest one.
+	pub target: NumberFor<B>,
+	/// Total bytes downloaded so far.
+	pub total_bytes: u64,
+}
+
+/// Token bucket limiting the bandwidth used by the block history download.
+struct Budget {
+	/// Bytes allowed per second, which is also the largest burst allowed.
+	max_bytes_per_second: u64,
+	/// Bytes which may still be downloaded. Negative once a response exceeded the budget.
+	available: i128,
+	/// When the budget was last refilled.
+	refilled_at: Instant,
+}
+
+impl Budget {
+	fn new(max_bytes_per_second: u64, now: Instant) -> Self {
+		Self { max_bytes_per_second, available: max_bytes_per_second.into(), refilled_at: now }
+	}
+
+	/// Refill the budget for the time elapsed since the last refill.
+	fn refill(&mut self, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.refilled_at);
+		let refill = elapsed.as_micros() * u128::from(self.max_bytes_per_second) / 1_000_000;
+		self.available = self
+			.available
+			.saturating_add(refill.try_into().unwrap_or(i128::MAX))
+			.min(self.max_bytes_per_second.into());
+		self.refilled_at = now;
+	}
+
+	fn is_exhausted(&self) -> bool {
+		self.available <= 0
+	}
+
+	fn spend(&mut self, bytes: u64) {
+		self.available = self.available.saturating_sub(bytes.into());
+	}
+}
+
+/// A range of blocks of the history, below the blocks queued for import.
+enum GapRange<B: BlockT> {
+	/// The blocks up to `end`, excluded, are being downloaded from the peer.
+	Downloading { end: NumberFor<B>, peer_id: PeerId },
+	/// The blocks were downloaded from the peer, in ascending order.
+	Downloaded { blocks: Vec<message::BlockData<B>>, peer_id: PeerId },
+}
+
+/// State of the block history download.
+pub(crate) struct GapSync<B: BlockT> {
+	/// Ranges of blocks being downloaded or downloaded, by their first block.
+	ranges: BTreeMap<NumberFor<B>, GapRange<B>>,
+	/// First block of the history to download, the highest one.
+	start: NumberFor<B>,
+	/// Lowest block of the history queued for import.
+	lowest_queued: NumberFor<B>,
+	/// Last block of the history to download, the lowest one.
+	target: NumberFor<B>,
+	/// Bandwidth budget, if limited.
+	budget: Option<Budget>,
+	/// Total bytes downloaded so far.
+	total_bytes: u64,
+}
+
+impl<B: BlockT> GapSync<B> {
+	/// Create a new instance downloading the blocks `start..=end`, limited as configured.
+	///
+	/// The depth is counted from the best block, so that the history isn't extended further once
+	/// a limited download completed. Returns `None` if the depth leaves no block to download.
+	pub fn new(
+		start: NumberFor<B>,
+		end: NumberFor<B>,
+		best_number: NumberFor<B>,
+		config: &GapSyncConfig,
+	) -> Option<Self> {
+		let target = match config.depth {
+			Some(depth) => std::cmp::max(start, best_number.saturating_sub(depth.into())),
+			None => start,
+		};
+		if target > end {
+			debug!(target: LOG_TARGET, "Skipping gap sync #{start} - #{end}, beyond the depth");
+			return None
+		}
+		debug!(target: LOG_TARGET, "Starting gap sync #{end} down to #{target}");
+
+		Some(Self {
+			ranges: BTreeMap::new(),
+			start: end,
+			lowest_queued: end + One::one(),
+			target,
+			budget: config.max_bytes_per_second.map(|max| Budget::new(max, Instant::now())),
+			total_bytes: 0,
+		})
+	}
+
+	/// Last block of the history to download, the lowest one.
+	pub fn target(&self) -> NumberFor<B> {
+		self.target
+	}
+
+	/// Returns the current progress.
+	pub fn progress(&self) -> GapSyncProgress<B> {
+		GapSyncProgress {
+			start: self.start,
+			lowest_queued: self.lowest_queued,
+			target: self.target,
+			total_bytes: self.total_bytes,
+		}
+	}
+
+	/// Whether requests are held back until the bandwidth budget refills.
+	pub fn is_throttled(&self) -> bool {
+		self.budget.as_ref().map_or(false, |budget| budget.is_exhausted())
+	}
+
+	/// Get a new block request for the peer, if any.
+	pub fn block_request(
+		&mut self,
+		id: &PeerId,
+		peer: &PeerSync<B>,
+		attrs: BlockAttributes,
+		max_blocks_per_request: u32,
+	) -> Option<(Range<NumberFor<B>>, BlockRequest<B>)> {
+		if let Some(budget) = &mut self.budget {
+			budget.refill(Instant::now());
+			if budget.is_exhausted() {
+				trace!(target: LOG_TARGET, "Gap sync bandwidth budget exhausted");
+				return None
+			}
+		}
+
+		// Find the highest blocks neither queued, downloaded nor being downloaded.
+		let mut end = self.lowest_queued;
+		let mut lowest = self.target;
+		for (first, range) in self.ranges.iter().rev() {
+			let range_end = match range {
+				GapRange::Downloading { end, .. } => *end,
+				GapRange::Downloaded { blocks, .. } => *first + (blocks.len() as u32).into(),
+			};
+			if range_end < end {
+				lowest = range_end;
+				break
+			}
+			end = *first;
+		}
+		if end <= lowest || self.lowest_queued - end >= MAX_DOWNLOAD_AHEAD.into() {
+			return None
+		}
+		let last = end - One::one();
+		if peer.best_number < last {
+			return None
+		}
+		let first = std::cmp::max(lowest, end.saturating_sub(max_blocks_per_request.into()));
+
+		self.ranges.insert(first, GapRange::Downloading { end, peer_id: *id });
+		let request = BlockRequest::<B> {
+			id: 0,
+			fields: attrs,
+			from: FromBlock::Number(last),
+			direction: Direction::Descending,
+			max: Some((end - first).saturated_into::<u32>()),
+		};
+		Some((first..end, request))
+	}
+
+	/// Submit a block response for processing, returning the blocks ready to be imported.
+	///
+	/// The blocks are expected in ascending order, and are returned in descending order.
+	pub fn on_block_response(
+		&mut self,
+		peer_id: &PeerId,
+		request: BlockRequest<B>,
+		blocks: Vec<message::BlockData<B>>,
+	) -> Result<Vec<BlockData<B>>, BadPeer> {
+		let bytes = blocks.iter().map(|block| block.encoded_size() as u64).sum();
+		self.total_bytes = self.total_bytes.saturating_add(bytes);
+		if let Some(budget) = &mut self.budget {
+			budget.spend(bytes);
+		}
+
+		let Some(end) = self.remove_download(peer_id) else {
+			debug!(target: LOG_TARGET, "Ignoring unexpected gap blocks from {peer_id}");
+			return Ok(Vec::new())
+		};
+		if let Some(first) = validate_blocks::<B>(&blocks, peer_id, Some(request))? {
+			// A response skipping blocks is dropped, for the range to be requested again.
+			if first + (blocks.len() as u32).into() == end {
+				self.ranges.insert(first, GapRange::Downloaded { blocks, peer_id: *peer_id });
+			} else {
+				debug!(target: LOG_TARGET, "Ignoring non-contiguous gap blocks from {peer_id}");
+			}
+		}
+
+		let mut ready = Vec::new();
+		while let Some(entry) = self.ranges.last_entry() {
+			let first = *entry.key();
+			let ends_at_lowest_queued = match entry.get() {
+				GapRange::Downloaded { blocks, .. } =>
+					first + (blocks.len() as u32).into() == self.lowest_queued,
+				GapRange::Downloading { .. } => false,
+			};
+			if !ends_at_lowest_queued {
+				break
+			}
+			if let GapRange::Downloaded { blocks, peer_id } = entry.remove() {
+				ready.extend(
+					blocks
+						.into_iter()
+						.rev()
+						.map(|block| BlockData { block, origin: Some(peer_id) }),
+				);
+			}
+			self.lowest_queued = first;
+		}
+		debug!(
+			target: LOG_TARGET,
+			"Drained {} gap blocks down to {}",
+			ready.len(),
+			self.lowest_queued,
+		);
+		Ok(ready)
+	}
+
+	/// Forget the range being downloaded from the peer, returning its end.
+	fn remove_download(&mut self, peer_id: &PeerId) -> Option<NumberFor<B>> {
+		let (first, end) = self.ranges.iter().find_map(|(first, range)| match range {
+			GapRange::Downloading { end, peer_id: id } if id == peer_id => Some((*first, *end)),
+			_ => None,
+		})?;
+		self.ranges.remove(&first);
+		Some(end)
+	}
+
+	/// Notify that a peer has disconnected.
+	pub fn remove_peer(&mut self, peer_id: &PeerId) {
+		self.remove_download(peer_id);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::strategy::chain_sync::PeerSyncState;
+	use sp_runtime::traits::{Header as HeaderT, Zero};
+	use std::time::Duration;
+	use substrate_test_runtime_client::runtime::{Block, Hash, Header};
+
+	fn peer(best_number: u64) -> PeerSync<Block> {
+		PeerSync {
+			peer_id: PeerId::random(),
+			common_number: Zero::zero(),
+			best_hash: Default::default(),
+			best_number,
+			state: PeerSyncState::Available,
+		}
+	}
+
+	/// Blocks answering the request, in ascending order.
+	fn blocks(request: &BlockRequest<Block>) -> Vec<message::BlockData<Block>> {
+		let FromBlock::Number(last) = request.from else { panic!("Gap requests are by number") };
+		(last + 1 - u64::from(request.max.unwrap())..=last)
+			.map(|number| {
+				let header = Header::new(
+					number,
+					Default::default(),
+					Default::default(),
+					Hash::repeat_byte(number as u8),
+					Default::default(),
+				);
+				message::BlockData::<Block> {
+					hash: header.hash(),
+					header: Some(header),
+					body: None,
+					indexed_body: None,
+					receipt: None,
+					message_queue: None,
+					justification: None,
+					justifications: None,
+				}
+			})
+			.collect()
+	}
+
+	fn numbers(blocks: &[BlockData<Block>]) -> Vec<u64> {
+		blocks
+			.iter()
+			.map(|block| *block.block.header.as_ref().unwrap().number())
+			.collect()
+	}
+
+	#[test]
+	fn depth_limits_downloaded_history() {
+		let config = GapSyncConfig { depth: Some(100), ..Default::default() };
+		let gap_sync = GapSync::<Block>::new(1, 1000, 1001, &config).unwrap();
+		assert_eq!(
+			gap_sync.progress(),
+			GapSyncProgress { start: 1000, lowest_queued: 1001, target: 901, total_bytes: 0 },
+		);
+
+		// A gap shorter than the depth is downloaded entirely.
+		let gap_sync = GapSync::<Block>::new(950, 1000, 1001, &config).unwrap();
+		assert_eq!(gap_sync.target(), 950);
+
+		// The history isn't extended any further once the chain moved past the depth.
+		assert!(GapSync::<Block>::new(1, 900, 1001, &config).is_none());
+
+		let config = GapSyncConfig { depth: Some(0), ..Default::default() };
+		assert!(GapSync::<Block>::new(1, 1000, 1001, &config).is_none());
+	}
+
+	#[test]
+	fn downloads_and_queues_history_downwards() {
+		let mut gap_sync = GapSync::<Block>::new(1, 10, 11, &Default::default()).unwrap();
+		let (first, second) = (peer(11), peer(11));
+
+		let (range, first_request) = gap_sync
+			.block_request(&first.peer_id, &first, BlockAttributes::HEADER, 4)
+			.unwrap();
+		assert_eq!(range, 7..11);
+		let (range, second_request) = gap_sync
+			.block_request(&second.peer_id, &second, BlockAttributes::HEADER, 4)
+			.unwrap();
+		assert_eq!(range, 3..7);
+
+		// The lower blocks aren't queued before the blocks above them.
+		let ready = gap_sync
+			.on_block_response(&second.peer_id, second_request.clone(), blocks(&second_request))
+			.unwrap();
+		assert!(ready.is_empty());
+		let ready = gap_sync
+			.on_block_response(&first.peer_id, first_request.clone(), blocks(&first_request))
+			.unwrap();
+		assert_eq!(numbers(&ready), vec![10, 9, 8, 7, 6, 5, 4, 3]);
+		assert_eq!(gap_sync.progress().lowest_queued, 3);
+
+		// The blocks of a disconnected peer are requested again.
+		let (range, _) = gap_sync
+			.block_request(&first.peer_id, &first, BlockAttributes::HEADER, 4)
+			.unwrap();
+		assert_eq!(range, 1..3);
+		gap_sync.remove_peer(&first.peer_id);
+		let (range, request) = gap_sync
+			.block_request(&second.peer_id, &second, BlockAttributes::HEADER, 4)
+			.unwrap();
+		assert_eq!(range, 1..3);
+		let ready = gap_sync.on_block_response(&second.peer_id, request.clone(), blocks(&request));
+		assert_eq!(numbers(&ready.unwrap()), vec![2, 1]);
+		assert!(gap_sync
+			.block_request(&first.peer_id, &first, BlockAttributes::HEADER, 4)
+			.is_none());
+	}
+
+	#[test]
+	fn budget_refills_over_time() {
+		let now = Instant::now();
+		let mut budget = Budget::new(1000, now);
+		assert!(!budget.is_exhausted());
+
+		budget.spend(1500);
+		assert!(budget.is_exhausted());
+
+		budget.refill(now + Duration::from_millis(250));
+		assert!(budget.is_exhausted());
+
+		budget.refill(now + Duration::from_millis(750));
+		assert!(!budget.is_exhausted());
+
+		// The budget never exceeds a second worth of bytes.
+		budget.refill(now + Duration::from_secs(60));
+		assert_eq!(budget.available, 1000);
+	}
+}
@@ -26,7 +26,7 @@ use crate::{
 };
 use libp2p::PeerId;
 use log::{debug, error, trace};
-use sc_client_api::ProofProvider;
+use sc_client_api::{AuxStore, ProofProvider};
 use sc_consensus::{BlockImportError, BlockImportStatus, IncomingBlock};
 use sc_network_common::sync::message::BlockAnnounce;
 use sp_consensus::BlockOrigin;
@@ -93,7 +93,7 @@ impl<B: BlockT> StateStrategy<B> {
 		initial_peers: impl Iterator<Item = (PeerId, NumberFor<B>)>,
 	) -> Self
 	where
-		Client: ProofProvider<B> + Send + Sync + 'static,
+		Client: ProofProvider<B> + AuxStore + Send + Sync + 'static,
 	{
 		let peers = initial_peers
 			.map(|(peer_id, best_number)| {
@@ -140,7 +140,9 @@ impl<B: BlockT> StateStrategy<B> {
 
 	/// Notify that a peer has disconnected.
 	pub fn remove_peer(&mut self, peer_id: &PeerId) {
-		self.peers.remove(peer_id);
+		if let Some(Peer { state: PeerState::DownloadingState, .. }) = self.peers.remove(peer_id) {
+			self.state_sync.remove_peer(peer_id);
+		}
 	}
 
 	/// Submit a validated block announcement.
@@ -198,7 +200,7 @@ impl<B: BlockT> StateStrategy<B> {
 			response.proof.len(),
 		);
 
-		match self.state_sync.import(*response) {
+		match self.state_sync.import(&peer_id, *response) {
 			ImportResult::Import(hash, header, state, body, justifications) => {
 				let origin = BlockOrigin::NetworkInitialSync;
 				let block = IncomingBlock {
@@ -221,6 +223,8 @@ impl<B: BlockT> StateStrategy<B> {
 			ImportResult::Continue => Ok(()),
 			ImportResult::BadResponse => {
 				debug!(target: LOG_TARGET, "Bad state data received from {peer_id}");
+				// Don't schedule requests to the peer until it's disconnected.
+				self.peers.remove(&peer_id);
 				Err(BadPeer(peer_id, rep::BAD_STATE))
 			},
 		}
@@ -266,23 +270,22 @@ impl<B: BlockT> StateStrategy<B> {
 	}
 
 	/// Produce state request.
+	///
+	/// Each peer downloads a different key range of the state, in parallel.
 	fn state_request(&mut self) -> Option<(PeerId, OpaqueStateRequest)> {
 		if self.state_sync.is_complete() {
 			return None
 		}
 
-		if self
-			.peers
-			.values()
-			.any(|peer| matches!(peer.state, PeerState::DownloadingState))
-		{
-			// Only one state request at a time is possible.
-			return None
-		}
-
 		let peer_id =
 			self.schedule_next_peer(PeerState::DownloadingState, self.state_sync.target_number())?;
-		let request = self.state_sync.next_request();
+		let Some(request) = self.state_sync.next_request(&peer_id) else {
+			// All the key ranges left are being downloaded.
+			if let Some(peer) = self.peers.get_mut(&peer_id) {
+				peer.state = PeerState::Available;
+			}
+			return None
+		};
 		trace!(
 			target: LOG_TARGET,
 			"New state request to {peer_id}: {request:?}.",
@@ -327,6 +330,7 @@ impl<B: BlockT> StateStrategy<B> {
 			queued_blocks: 0,
 			state_sync: Some(self.state_sync.progress()),
 			warp_sync: None,
+			gap_sync: None,
 		}
 	}
 
@@ -338,11 +342,10 @@ impl<B: BlockT> StateStrategy<B> {
 	/// Get actions that should be performed by the owner on [`WarpSync`]'s behalf
 	#[must_use]
 	pub fn actions(&mut self) -> impl Iterator<Item = StateStrategyAction<B>> {
-		let state_request = self
-			.state_request()
-			.into_iter()
-			.map(|(peer_id, request)| StateStrategyAction::SendStateRequest { peer_id, request });
-		self.actions.extend(state_request);
+		let state_requests = std::iter::from_fn(|| self.state_request())
+			.map(|(peer_id, request)| StateStrategyAction::SendStateRequest { peer_id, request })
+			.collect::<Vec<_>>();
+		self.actions.extend(state_requests);
 
 		std::mem::take(&mut self.actions).into_iter()
 	}
@@ -366,6 +369,7 @@ mod test {
 	use sc_client_api::KeyValueStates;
 	use sc_consensus::{ImportedAux, ImportedState};
 	use sp_runtime::traits::Zero;
+	use std::collections::HashSet;
 	use substrate_test_runtime_client::{
 		runtime::{Block, Hash},
 		BlockBuilderExt, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
@@ -375,8 +379,9 @@ mod test {
 		pub StateSync<B: BlockT> {}
 
 		impl<B: BlockT> StateSyncProvider<B> for StateSync<B> {
-			fn import(&mut self, response: StateResponse) -> ImportResult<B>;
-			fn next_request(&self) -> StateRequest;
+			fn import(&mut self, peer_id: &PeerId, response: StateResponse) -> ImportResult<B>;
+			fn next_request(&mut self, peer_id: &PeerId) -> Option<StateRequest>;
+			fn remove_peer(&mut self, peer_id: &PeerId);
 			fn is_complete(&self) -> bool;
 			fn target_number(&self) -> NumberFor<B>;
 			fn target_hash(&self) -> B::Hash;
@@ -501,7 +506,7 @@ mod test {
 	}
 
 	#[test]
-	fn no_parallel_state_requests() {
+	fn parallel_state_requests_download_different_key_ranges() {
 		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
 		let target_block = BlockBuilderBuilder::new(&*client)
 			.on_parent_block(client.chain_info().best_hash)
@@ -523,17 +528,63 @@ mod test {
 			initial_peers,
 		);
 
-		// First request is sent.
-		assert!(state_strategy.state_request().is_some());
+		let requests = std::iter::from_fn(|| state_strategy.state_request())
+			.map(|(peer_id, mut opaque_request)| {
+				let request: &mut StateRequest = opaque_request.0.downcast_mut().unwrap();
+				(peer_id, request.start.clone())
+			})
+			.collect::<Vec<_>>();
 
-		// No parallel request is sent.
-		assert!(state_strategy.state_request().is_none());
+		// Peers synced at least as much as the median are sent requests for different ranges.
+		assert_eq!(requests.len(), 5);
+		let peers = requests.iter().map(|(peer_id, _)| *peer_id).collect::<HashSet<_>>();
+		assert_eq!(peers.len(), 5);
+		let starts = requests.iter().map(|(_, start)| start.clone()).collect::<HashSet<_>>();
+		assert_eq!(starts.len(), 5);
+		assert!(starts.contains(&Vec::new()));
+	}
+
+	#[test]
+	fn key_range_of_disconnected_peer_is_requested_again() {
+		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
+		let target_block = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(client.chain_info().best_hash)
+			.with_parent_block_number(client.chain_info().best_number)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+
+		let peer_id = PeerId::random();
+		let mut state_strategy = StateStrategy::new(
+			client.clone(),
+			target_block.header().clone(),
+			None,
+			None,
+			false,
+			std::iter::once((peer_id, 10)),
+		);
+
+		let (_peer_id, mut opaque_request) = state_strategy.state_request().unwrap();
+		let request: &mut StateRequest = opaque_request.0.downcast_mut().unwrap();
+		let start = request.start.clone();
+
+		// The range is requested from another peer once the first one disconnects.
+		state_strategy.remove_peer(&peer_id);
+		let other_peer_id = PeerId::random();
+		state_strategy.add_peer(other_peer_id, Hash::random(), 10);
+
+		let (peer_id, mut opaque_request) = state_strategy.state_request().unwrap();
+		let request: &mut StateRequest = opaque_request.0.downcast_mut().unwrap();
+		assert_eq!(peer_id, other_peer_id);
+		assert_eq!(request.start, start);
 	}
 
 	#[test]
 	fn received_state_response_makes_peer_available_again() {
 		let mut state_sync_provider = MockStateSync::<Block>::new();
-		state_sync_provider.expect_import().return_once(|_| ImportResult::Continue);
+		state_sync_provider.expect_import().return_once(|_, _| ImportResult::Continue);
 		let peer_id = PeerId::random();
 		let initial_peers = std::iter::once((peer_id, 10));
 		let mut state_strategy =
@@ -551,7 +602,9 @@ mod test {
 	fn bad_state_response_drops_peer() {
 		let mut state_sync_provider = MockStateSync::<Block>::new();
 		// Provider says that state response is bad.
-		state_sync_provider.expect_import().return_once(|_| ImportResult::BadResponse);
+		state_sync_provider
+			.expect_import()
+			.return_once(|_, _| ImportResult::BadResponse);
 		let peer_id = PeerId::random();
 		let initial_peers = std::iter::once((peer_id, 10));
 		let mut state_strategy =
@@ -570,7 +623,7 @@ mod test {
 	fn partial_state_response_doesnt_generate_actions() {
 		let mut state_sync_provider = MockStateSync::<Block>::new();
 		// Sync provider says that the response is partial.
-		state_sync_provider.expect_import().return_once(|_| ImportResult::Continue);
+		state_sync_provider.expect_import().return_once(|_, _| ImportResult::Continue);
 		let peer_id = PeerId::random();
 		let initial_peers = std::iter::once((peer_id, 10));
 		let mut state_strategy =
@@ -611,7 +664,7 @@ mod test {
 			body.clone(),
 			justifications.clone(),
 		);
-		state_sync_provider.expect_import().return_once(move |_| import);
+		state_sync_provider.expect_import().return_once(move |_, _| import);
 
 		// Reference values to check against.
 		let expected_origin = BlockOrigin::NetworkInitialSync;
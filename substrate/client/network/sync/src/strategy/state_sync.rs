@@ -18,14 +18,23 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! State sync support.
+//!
+//! The keys of the top trie are partitioned into ranges which are downloaded from different peers
+//! in parallel, each response being verified against the state root on its own. A range whose
+//! download fails is requested again from another peer, resuming at the last key received.
+//!
+//! The progress of each range, along with the state received, is persisted in the aux-db, so a
+//! restarted node only downloads the ranges which weren't completed.
 
 use crate::{
 	schema::v1::{StateEntry, StateRequest, StateResponse},
+	strategy::aux_schema::{self, PersistedKeyRange, PersistedStateSync, StateChunk},
 	LOG_TARGET,
 };
 use codec::{Decode, Encode};
-use log::debug;
-use sc_client_api::{CompactProof, ProofProvider};
+use libp2p::PeerId;
+use log::{debug, info, warn};
+use sc_client_api::{AuxStore, CompactProof, ProofProvider};
 use sc_consensus::ImportedState;
 use smallvec::SmallVec;
 use sp_core::storage::well_known_keys;
@@ -33,14 +42,24 @@ use sp_runtime::{
 	traits::{Block as BlockT, Header, NumberFor},
 	Justifications,
 };
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{
+	collections::{HashMap, HashSet},
+	fmt,
+	sync::Arc,
+};
+
+/// Number of ranges the keys of the top trie are partitioned into.
+const KEY_RANGES: usize = 16;
 
 /// Generic state sync provider. Used for mocking in tests.
 pub trait StateSyncProvider<B: BlockT>: Send + Sync {
-	/// Validate and import a state response.
-	fn import(&mut self, response: StateResponse) -> ImportResult<B>;
-	/// Produce next state request.
-	fn next_request(&self) -> StateRequest;
+	/// Validate and import a state response from a peer.
+	fn import(&mut self, peer_id: &PeerId, response: StateResponse) -> ImportResult<B>;
+	/// Produce the next state request for a peer, if there's a key range left for it.
+	fn next_request(&mut self, peer_id: &PeerId) -> Option<StateRequest>;
+	/// Notify that a peer has disconnected, so the key range it was downloading is requested
+	/// from another peer.
+	fn remove_peer(&mut self, peer_id: &PeerId);
 	/// Check if the state is complete.
 	fn is_complete(&self) -> bool;
 	/// Returns target block number.
@@ -50,7 +69,6 @@ pub trait StateSyncProvider<B: BlockT>: Send + Sync {
 	/// Returns state sync estimated progress.
 	fn progress(&self) -> StateSyncProgress;
 }
-
 // Reported state sync phase.
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum StateSyncPhase {
@@ -90,6 +108,84 @@ pub enum ImportResult<B: BlockT> {
 	BadResponse,
 }
 
+/// A range of keys of the top trie, along with the child tries of the keys in it.
+struct KeyRange {
+	/// The keys of the range are above this one, if any.
+	start: Option<Vec<u8>>,
+	/// The keys of the range are at most this one, if any.
+	end: Option<Vec<u8>>,
+	/// Last key received, followed by the last key received of its child trie, if any.
+	last_key: SmallVec<[Vec<u8>; 2]>,
+	/// Peer the range is being downloaded from.
+	peer: Option<PeerId>,
+	/// Whether the whole range has been received.
+	complete: bool,
+	/// Number of state chunks received for the range, as persisted.
+	chunks: u32,
+}
+
+impl KeyRange {
+	/// Partition the keys of the top trie into `count` ranges, by their first byte.
+	fn partition(count: usize) -> Vec<Self> {
+		let bounds = (1..count).map(|i| vec![(i * 256 / count) as u8]);
+		let starts = std::iter::once(None).chain(bounds.clone().map(Some));
+		let ends = bounds.map(Some).chain(std::iter::once(None));
+		starts
+			.zip(ends)
+			.map(|(start, end)| Self {
+				start,
+				end,
+				last_key: SmallVec::default(),
+				peer: None,
+				complete: false,
+				chunks: 0,
+			})
+			.collect()
+	}
+
+	/// Progress of the range to persist.
+	fn persisted(&self) -> PersistedKeyRange {
+		PersistedKeyRange {
+			last_key: self.last_key.to_vec(),
+			complete: self.complete,
+			chunks: self.chunks,
+		}
+	}
+
+	/// Key the next request for the range starts after.
+	fn next_start(&self) -> Vec<Vec<u8>> {
+		if !self.last_key.is_empty() {
+			self.last_key.clone().into_vec()
+		} else {
+			self.start.iter().cloned().collect()
+		}
+	}
+
+	/// Whether the keys received went past the end of the range, which are then also received by
+	/// the following range.
+	fn is_past_end(&self) -> bool {
+		match (&self.end, self.last_key.get(0)) {
+			(Some(end), Some(last)) => last > end,
+			_ => false,
+		}
+	}
+
+	/// Estimated progress of the download of the range, from 0 to 256 divided by the number of
+	/// ranges.
+	fn progress(&self) -> u32 {
+		let first_byte = |key: Option<&Vec<u8>>, default| {
+			key.and_then(|key| key.get(0)).map_or(default, |byte| *byte as u32)
+		};
+		let start = first_byte(self.start.as_ref(), 0);
+		let end = first_byte(self.end.as_ref(), 256);
+		if self.complete {
+			end - start
+		} else {
+			first_byte(self.last_key.get(0), start).clamp(start, end) - start
+		}
+	}
+}
+
 /// State sync state machine. Accumulates partial state data until it
 /// is ready to be imported.
 pub struct StateSync<B: BlockT, Client> {
@@ -98,7 +194,8 @@ pub struct StateSync<B: BlockT, Client> {
 	target_root: B::Hash,
 	target_body: Option<Vec<B::Extrinsic>>,
 	target_justifications: Option<Justifications>,
-	last_key: SmallVec<[Vec<u8>; 2]>,
+	ranges: Vec<KeyRange>,
+	bad_peers: HashSet<PeerId>,
 	state: HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>,
 	complete: bool,
 	client: Arc<Client>,
@@ -109,7 +206,7 @@ pub struct StateSync<B: BlockT, Client> {
 impl<B, Client> StateSync<B, Client>
 where
 	B: BlockT,
-	Client: ProofProvider<B> + Send + Sync + 'static,
+	Client: ProofProvider<B> + AuxStore + Send + Sync + 'static,
 {
 	///  Create a new instance.
 	pub fn new(
@@ -119,37 +216,112 @@ where
 		target_justifications: Option<Justifications>,
 		skip_proof: bool,
 	) -> Self {
-		Self {
+		let mut state_sync = Self {
 			client,
 			target_block: target_header.hash(),
 			target_root: *target_header.state_root(),
 			target_header,
 			target_body,
 			target_justifications,
-			last_key: SmallVec::default(),
+			ranges: KeyRange::partition(KEY_RANGES),
+			bad_peers: HashSet::new(),
 			state: HashMap::default(),
 			complete: false,
 			imported_bytes: 0,
 			skip_proof,
+		};
+		state_sync.resume();
+		state_sync
+	}
+
+	/// Resume the download of the state from the persisted progress, if it's at the same block.
+	fn resume(&mut self) {
+		let persisted = match aux_schema::load_state_sync::<_, B::Hash>(&*self.client) {
+			Ok(Some(persisted)) => persisted,
+			Ok(None) => return,
+			Err(e) => {
+				warn!(target: LOG_TARGET, "Failed to load state sync progress: {e}");
+				return self.clear_persisted()
+			},
+		};
+		if persisted.target != self.target_block || persisted.ranges.len() != self.ranges.len() {
+			return self.clear_persisted()
+		}
+
+		let chunks = persisted
+			.ranges
+			.iter()
+			.enumerate()
+			.map(|(index, range)| aux_schema::load_state_chunks(&*self.client, index, range))
+			.collect::<Result<Vec<_>, _>>();
+		let chunks = match chunks {
+			Ok(chunks) => chunks,
+			Err(e) => {
+				warn!(target: LOG_TARGET, "Failed to load state sync progress: {e}");
+				return self.clear_persisted()
+			},
+		};
+		for (range, persisted) in self.ranges.iter_mut().zip(persisted.ranges) {
+			range.last_key = persisted.last_key.into();
+			range.complete = persisted.complete;
+			range.chunks = persisted.chunks;
 		}
+		for chunk in chunks.into_iter().flatten() {
+			self.merge(chunk);
+		}
+		info!(
+			target: LOG_TARGET,
+			"Resuming state sync at #{} ({}), {} of {} key ranges already downloaded",
+			self.target_header.number(),
+			self.target_block,
+			self.ranges.iter().filter(|range| range.complete).count(),
+			self.ranges.len(),
+		);
 	}
-}
 
-impl<B, Client> StateSyncProvider<B> for StateSync<B, Client>
-where
-	B: BlockT,
-	Client: ProofProvider<B> + Send + Sync + 'static,
-{
-	///  Validate and import a state response.
-	fn import(&mut self, response: StateResponse) -> ImportResult<B> {
+	/// Persist the progress of the state download, along with the `chunk` received for the key
+	/// range `range`.
+	fn persist(&self, range: usize, chunk: &StateChunk) {
+		let progress = PersistedStateSync {
+			target: self.target_block,
+			ranges: self.ranges.iter().map(KeyRange::persisted).collect(),
+		};
+		if let Err(e) = aux_schema::write_state_sync(&*self.client, &progress, range, chunk) {
+			warn!(target: LOG_TARGET, "Failed to persist state sync progress: {e}");
+		}
+	}
+
+	/// Clear the persisted progress of the state download.
+	fn clear_persisted(&self) {
+		if let Err(e) = aux_schema::clear_state_sync::<_, B::Hash>(&*self.client) {
+			warn!(target: LOG_TARGET, "Failed to clear state sync progress: {e}");
+		}
+	}
+
+	/// Add a state chunk to the downloaded state.
+	fn merge(&mut self, chunk: StateChunk) {
+		for (state_root, (key_values, storage_keys)) in chunk {
+			self.imported_bytes += key_values.iter().map(|(key, _)| key.len() as u64).sum::<u64>();
+			let entry = self.state.entry(state_root).or_default();
+			entry.0.extend(key_values);
+			entry.1.extend(storage_keys);
+		}
+	}
+
+	/// Validate and import a state response for the key range `range`, returning the state
+	/// received.
+	fn import_range(&mut self, range: usize, response: StateResponse) -> Result<StateChunk, ()> {
 		if response.entries.is_empty() && response.proof.is_empty() {
 			debug!(target: LOG_TARGET, "Bad state response");
-			return ImportResult::BadResponse
+			return Err(())
 		}
 		if !self.skip_proof && response.proof.is_empty() {
 			debug!(target: LOG_TARGET, "Missing proof");
-			return ImportResult::BadResponse
+			return Err(())
 		}
+		let start = self.ranges[range].next_start();
+		let last_key = &mut self.ranges[range].last_key;
+		let mut chunk = HashMap::<_, (Vec<_>, Vec<_>)>::new();
 		let complete = if !self.skip_proof {
 			debug!(target: LOG_TARGET, "Importing state from {} trie nodes", response.proof.len());
 			let proof_size = response.proof.len() as u64;
@@ -157,28 +329,25 @@ where
 				Ok(proof) => proof,
 				Err(e) => {
 					debug!(target: LOG_TARGET, "Error decoding proof: {:?}", e);
-					return ImportResult::BadResponse
-				},
-			};
-			let (values, completed) = match self.client.verify_range_proof(
-				self.target_root,
-				proof,
-				self.last_key.as_slice(),
-			) {
-				Err(e) => {
-					debug!(
-						target: LOG_TARGET,
-						"StateResponse failed proof verification: {}",
-						e,
-					);
-					return ImportResult::BadResponse
+					return Err(())
 				},
-				Ok(values) => values,
 			};
+			let (values, completed) =
+				match self.client.verify_range_proof(self.target_root, proof, start.as_slice()) {
+					Err(e) => {
+						debug!(
+							target: LOG_TARGET,
+							"StateResponse failed proof verification: {}",
+							e,
+						);
+						return Err(())
+					},
+					Ok(values) => values,
+				};
 			debug!(target: LOG_TARGET, "Imported with {} keys", values.len());
 
 			let complete = completed == 0;
-			if !complete && !values.update_last_key(completed, &mut self.last_key) {
+			if !complete && !values.update_last_key(completed, last_key) {
 				debug!(target: LOG_TARGET, "Error updating key cursor, depth: {}", completed);
 			};
 
@@ -190,7 +359,7 @@ where
 						.into_iter()
 						.filter(|key_value| {
 							if well_known_keys::is_child_storage_key(key_value.0.as_slice()) {
-								self.state
+								chunk
 									.entry(key_value.1.clone())
 									.or_default()
 									.1
@@ -204,22 +373,9 @@ where
 				} else {
 					values.key_values
 				};
-				let entry = self.state.entry(values.state_root).or_default();
-				if entry.0.len() > 0 && entry.1.len() > 1 {
-					// Already imported child_trie with same root.
-					// Warning this will not work with parallel download.
-				} else if entry.0.is_empty() {
-					for (key, _value) in key_values.iter() {
-						self.imported_bytes += key.len() as u64;
-					}
-
-					entry.0 = key_values;
-				} else {
-					for (key, value) in key_values {
-						self.imported_bytes += key.len() as u64;
-						entry.0.push((key, value))
-					}
-				}
+				// Child tries with the same root, or keys past the end of a range, may be
+				// received more than once. They're deduplicated once the state is complete.
+				chunk.entry(values.state_root).or_default().0.extend(key_values);
 			}
 			self.imported_bytes += proof_size;
 			complete
@@ -229,11 +385,11 @@ where
 			// the parent cursor stays valid.
 			// Empty parent trie content only happens when all the response content
 			// is part of a single child trie.
-			if self.last_key.len() == 2 && response.entries[0].entries.is_empty() {
+			if last_key.len() == 2 && response.entries[0].entries.is_empty() {
 				// Do not remove the parent trie position.
-				self.last_key.pop();
+				last_key.pop();
 			} else {
-				self.last_key.clear();
+				last_key.clear();
 			}
 			for state in response.entries {
 				debug!(
@@ -245,41 +401,84 @@ where
 
 				if !state.complete {
 					if let Some(e) = state.entries.last() {
-						self.last_key.push(e.key.clone());
+						last_key.push(e.key.clone());
 					}
 					complete = false;
 				}
 				let is_top = state.state_root.is_empty();
-				let entry = self.state.entry(state.state_root).or_default();
-				if entry.0.len() > 0 && entry.1.len() > 1 {
-					// Already imported child trie with same root.
-				} else {
-					let mut child_roots = Vec::new();
-					for StateEntry { key, value } in state.entries {
-						// Skip all child key root (will be recalculated on import).
-						if is_top && well_known_keys::is_child_storage_key(key.as_slice()) {
-							child_roots.push((value, key));
-						} else {
-							self.imported_bytes += key.len() as u64;
-							entry.0.push((key, value))
-						}
-					}
-					for (root, storage_key) in child_roots {
-						self.state.entry(root).or_default().1.push(storage_key);
+				let entry = chunk.entry(state.state_root).or_default();
+				let mut child_roots = Vec::new();
+				for StateEntry { key, value } in state.entries {
+					// Skip all child key root (will be recalculated on import).
+					if is_top && well_known_keys::is_child_storage_key(key.as_slice()) {
+						child_roots.push((value, key));
+					} else {
+						entry.0.push((key, value))
 					}
 				}
+				for (root, storage_key) in child_roots {
+					chunk.entry(root).or_default().1.push(storage_key);
+				}
 			}
 			complete
 		};
+
+		let range = &mut self.ranges[range];
+		range.complete = complete || range.is_past_end();
+		Ok(chunk.into_iter().collect())
+	}
+
+	/// Take the downloaded state, without the entries received more than once.
+	fn take_state(&mut self) -> ImportedState<B> {
+		let state = std::mem::take(&mut self.state)
+			.into_iter()
+			.map(|(state_root, (mut key_values, mut storage_keys))| {
+				key_values.sort_by(|a, b| a.0.cmp(&b.0));
+				key_values.dedup_by(|a, b| a.0 == b.0);
+				storage_keys.sort();
+				storage_keys.dedup();
+				(state_root, (key_values, storage_keys))
+			})
+			.collect::<Vec<_>>();
+		ImportedState { block: self.target_block, state: state.into() }
+	}
+}
+
+impl<B, Client> StateSyncProvider<B> for StateSync<B, Client>
+where
+	B: BlockT,
+	Client: ProofProvider<B> + AuxStore + Send + Sync + 'static,
+{
+	///  Validate and import a state response from a peer.
+	fn import(&mut self, peer_id: &PeerId, response: StateResponse) -> ImportResult<B> {
+		let Some(range) = self.ranges.iter().position(|range| range.peer == Some(*peer_id)) else {
+			debug!(target: LOG_TARGET, "Unexpected state response from {peer_id}");
+			return ImportResult::BadResponse
+		};
+		self.ranges[range].peer = None;
+
+		let Ok(chunk) = self.import_range(range, response) else {
+			// Request the range from another peer.
+			self.bad_peers.insert(*peer_id);
+			return ImportResult::BadResponse
+		};
+
+		let complete = self.ranges.iter().all(|range| range.complete);
+		if complete {
+			// The state is imported as a whole, and downloaded again if the import is interrupted.
+			self.clear_persisted();
+		} else {
+			self.ranges[range].chunks += 1;
+			self.persist(range, &chunk);
+		}
+		self.merge(chunk);
+
 		if complete {
 			self.complete = true;
 			ImportResult::Import(
 				self.target_block,
 				self.target_header.clone(),
-				ImportedState {
-					block: self.target_block,
-					state: std::mem::take(&mut self.state).into(),
-				},
+				self.take_state(),
 				self.target_body.clone(),
 				self.target_justifications.clone(),
 			)
@@ -288,12 +487,26 @@ where
 		}
 	}
 
-	/// Produce next state request.
-	fn next_request(&self) -> StateRequest {
-		StateRequest {
+	/// Produce the next state request for a peer, if there's a key range left for it.
+	fn next_request(&mut self, peer_id: &PeerId) -> Option<StateRequest> {
+		if self.bad_peers.contains(peer_id) ||
+			self.ranges.iter().any(|range| range.peer == Some(*peer_id))
+		{
+			return None
+		}
+		let range = self.ranges.iter_mut().find(|range| !range.complete && range.peer.is_none())?;
+		range.peer = Some(*peer_id);
+		Some(StateRequest {
 			block: self.target_block.encode(),
-			start: self.last_key.clone().into_vec(),
+			start: range.next_start(),
 			no_proof: self.skip_proof,
+		})
+	}
+
+	/// Notify that a peer has disconnected.
+	fn remove_peer(&mut self, peer_id: &PeerId) {
+		for range in self.ranges.iter_mut().filter(|range| range.peer == Some(*peer_id)) {
+			range.peer = None;
 		}
 	}
 
@@ -314,8 +527,8 @@ where
 
 	/// Returns state sync estimated progress.
 	fn progress(&self) -> StateSyncProgress {
-		let cursor = *self.last_key.get(0).and_then(|last| last.get(0)).unwrap_or(&0u8);
-		let percent_done = cursor as u32 * 100 / 256;
+		let done: u32 = self.ranges.iter().map(|range| range.progress()).sum();
+		let percent_done = done * 100 / 256;
 		StateSyncProgress {
 			percentage: percent_done,
 			size: self.imported_bytes,
@@ -327,3 +540,128 @@ where
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use futures::executor::block_on;
+	use sc_block_builder::BlockBuilderBuilder;
+	use sc_client_api::StorageProvider;
+	use sc_consensus::{
+		BlockImport, BlockImportParams, ForkChoiceStrategy, StateAction, StorageChanges,
+	};
+	use sp_blockchain::HeaderBackend;
+	use sp_consensus::BlockOrigin;
+	use sp_core::storage::{ChildInfo, StorageKey};
+	use substrate_test_runtime_client::{
+		runtime::Hash, ClientBlockImportExt, DefaultTestClientBuilderExt, TestClientBuilder,
+		TestClientBuilderExt,
+	};
+
+	#[test]
+	fn key_ranges_partition_keys() {
+		let ranges = KeyRange::partition(4);
+		assert_eq!(ranges.len(), 4);
+		assert_eq!(ranges[0].start, None);
+		assert_eq!(ranges[0].end, Some(vec![0x40]));
+		assert_eq!(ranges[1].start, Some(vec![0x40]));
+		assert_eq!(ranges[1].end, Some(vec![0x80]));
+		assert_eq!(ranges[3].start, Some(vec![0xc0]));
+		assert_eq!(ranges[3].end, None);
+
+		assert_eq!(ranges[0].next_start(), Vec::<Vec<u8>>::new());
+		assert_eq!(ranges[2].next_start(), vec![vec![0x80]]);
+	}
+
+	#[test]
+	fn key_range_resumes_after_last_key() {
+		let mut range = KeyRange::partition(4).remove(1);
+		range.last_key.push(vec![0x50, 1]);
+		range.last_key.push(vec![2]);
+		assert_eq!(range.next_start(), vec![vec![0x50, 1], vec![2]]);
+		assert!(!range.is_past_end());
+		assert_eq!(range.progress(), 0x10);
+
+		range.last_key.clear();
+		range.last_key.push(vec![0x80, 0]);
+		assert!(range.is_past_end());
+	}
+
+	#[test]
+	fn state_is_downloaded_in_ranges_from_several_peers() {
+		let child_info = ChildInfo::new_default(b"child");
+		// Keys spread over all the key ranges, so responses go past the end of their range.
+		let mut builder = TestClientBuilder::new();
+		for byte in 0..=u8::MAX {
+			builder = builder.add_extra_storage(vec![byte, 1], vec![byte; 64]);
+		}
+		for byte in 0..16u8 {
+			builder = builder.add_extra_child_storage(&child_info, vec![byte], vec![byte; 64]);
+		}
+		let mut source = builder.build();
+		let block = BlockBuilderBuilder::new(&source)
+			.on_parent_block(source.chain_info().best_hash)
+			.with_parent_block_number(source.chain_info().best_number)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+		block_on(source.import(BlockOrigin::Own, block.clone())).unwrap();
+
+		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
+		let mut state_sync =
+			StateSync::new(client.clone(), block.header().clone(), None, None, false);
+		let peers = (0..4).map(|_| PeerId::random()).collect::<Vec<_>>();
+		let mut requests = 0;
+		let state = 'download: loop {
+			let pending = peers
+				.iter()
+				.filter_map(|peer| Some((*peer, state_sync.next_request(peer)?)))
+				.collect::<Vec<_>>();
+			assert!(!pending.is_empty());
+			for (peer, request) in pending {
+				requests += 1;
+				let hash = Hash::decode(&mut request.block.as_ref()).unwrap();
+				let (proof, _) = source.read_proof_collection(hash, &request.start, 1024).unwrap();
+				let response = StateResponse { entries: Vec::new(), proof: proof.encode() };
+				match state_sync.import(&peer, response) {
+					ImportResult::Import(_, _, state, _, _) => break 'download state,
+					ImportResult::Continue => {},
+					ImportResult::BadResponse => panic!("Valid state response rejected"),
+				}
+			}
+		};
+		assert!(state_sync.is_complete());
+		assert!(requests > KEY_RANGES);
+
+		// Entries received by more than one range are only imported once.
+		for level in &state.state.0 {
+			assert!(level.key_values.windows(2).all(|pair| pair[0].0 < pair[1].0));
+		}
+		assert!(state
+			.state
+			.0
+			.iter()
+			.any(|level| level.state_root.is_empty() && level.key_values.len() > 256));
+
+		// The state is only imported if it matches the state root of the target block.
+		let hash = block.header().hash();
+		let mut import = BlockImportParams::new(BlockOrigin::NetworkInitialSync, block.header);
+		import.state_action = StateAction::ApplyChanges(StorageChanges::Import(state));
+		import.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+		block_on(client.clone().import_block(import)).unwrap();
+
+		assert_eq!(
+			client.storage(hash, &StorageKey(vec![0x80, 1])).unwrap().map(|data| data.0),
+			Some(vec![0x80; 64]),
+		);
+		assert_eq!(
+			client
+				.child_storage(hash, &child_info, &StorageKey(vec![15]))
+				.unwrap()
+				.map(|data| data.0),
+			Some(vec![15; 64]),
+		);
+	}
+}
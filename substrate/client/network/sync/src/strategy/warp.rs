@@ -17,18 +17,23 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! Warp syncing strategy. Bootstraps chain by downloading warp proofs and state.
-
-pub use sp_consensus_grandpa::{AuthorityList, SetId};
+//!
+//! The progress of the warp proofs download is persisted in the aux-db, so a restarted node
+//! resumes from the last verified proof.
 
 use crate::{
-	strategy::chain_sync::validate_blocks,
+	strategy::{
+		aux_schema::{self, PersistedWarpProof},
+		chain_sync::validate_blocks,
+	},
 	types::{BadPeer, SyncState, SyncStatus},
 	LOG_TARGET,
 };
 use codec::{Decode, Encode};
 use futures::channel::oneshot;
 use libp2p::PeerId;
-use log::{debug, error, trace};
+use log::{debug, error, info, trace, warn};
+use sc_client_api::AuxStore;
 use sc_network_common::sync::message::{
 	BlockAnnounce, BlockAttributes, BlockData, BlockRequest, Direction, FromBlock,
 };
@@ -45,6 +50,12 @@ const MIN_PEERS_TO_START_WARP_SYNC: usize = 3;
 /// Scale-encoded warp sync proof response.
 pub struct EncodedProof(pub Vec<u8>);
 
+/// Scale-encoded state warp proofs are verified against, such as the id and the authorities of
+/// the authority set of a finality gadget. It's opaque to warp sync, only the
+/// [`WarpSyncProvider`] decodes it.
+#[derive(Encode, Decode, Debug, Clone, Default, PartialEq, Eq)]
+pub struct EncodedVerifierState(pub Vec<u8>);
+
 /// Warp sync request
 #[derive(Encode, Decode, Debug, Clone)]
 pub struct WarpProofRequest<B: BlockT> {
@@ -54,10 +65,11 @@ pub struct WarpProofRequest<B: BlockT> {
 
 /// Proof verification result.
 pub enum VerificationResult<Block: BlockT> {
-	/// Proof is valid, but the target was not reached.
-	Partial(SetId, AuthorityList, Block::Hash),
+	/// Proof is valid, but the target was not reached. The next proof starts at the given block
+	/// and is verified against the given state.
+	Partial(EncodedVerifierState, Block::Hash),
 	/// Target finality is proved.
-	Complete(SetId, AuthorityList, Block::Header),
+	Complete(Block::Header),
 }
 
 /// Warp sync backend. Handles retrieving and verifying warp sync proofs.
@@ -68,16 +80,14 @@ pub trait WarpSyncProvider<Block: BlockT>: Send + Sync {
 		&self,
 		start: Block::Hash,
 	) -> Result<EncodedProof, Box<dyn std::error::Error + Send + Sync>>;
-	/// Verify warp proof against current set of authorities.
+	/// Verify warp proof against the state the previous proof was verified to.
 	fn verify(
 		&self,
 		proof: &EncodedProof,
-		set_id: SetId,
-		authorities: AuthorityList,
+		verifier_state: &EncodedVerifierState,
 	) -> Result<VerificationResult<Block>, Box<dyn std::error::Error + Send + Sync>>;
-	/// Get current list of authorities. This is supposed to be genesis authorities when starting
-	/// sync.
-	fn current_authorities(&self) -> AuthorityList;
+	/// Get the state the first proof, starting at the genesis, is verified against.
+	fn initial_verifier_state(&self) -> EncodedVerifierState;
 }
 
 mod rep {
@@ -106,7 +116,7 @@ pub enum WarpSyncPhase<Block: BlockT> {
 	AwaitingPeers { required_peers: usize },
 	/// Waiting for target block to be received.
 	AwaitingTargetBlock,
-	/// Downloading and verifying grandpa warp proofs.
+	/// Downloading and verifying warp proofs.
 	DownloadingWarpProofs,
 	/// Downloading target block.
 	DownloadingTargetBlock,
@@ -145,6 +155,33 @@ pub struct WarpSyncProgress<Block: BlockT> {
 	pub total_bytes: u64,
 }
 
+/// Trusted block to warp sync to, without downloading warp proofs.
+#[derive(Clone, Debug)]
+pub struct WarpSyncCheckpoint<Block: BlockT> {
+	/// Header of the block.
+	pub header: Block::Header,
+	/// Justifications of the block, if any.
+	pub justifications: Option<Justifications>,
+}
+
+impl<Block: BlockT> WarpSyncCheckpoint<Block> {
+	/// Decode the checkpoint of the network configuration, checking its header has its hash.
+	pub fn decode(checkpoint: &sc_network::config::WarpSyncCheckpoint) -> Result<Self, String> {
+		let header = Block::Header::decode(&mut &checkpoint.header[..])
+			.map_err(|e| format!("Invalid header: {e}"))?;
+		if header.hash().as_ref() != &checkpoint.hash[..] {
+			return Err(format!("Header hash {} is not the hash of the checkpoint", header.hash()))
+		}
+		let justifications = checkpoint
+			.justifications
+			.as_ref()
+			.map(|justifications| Justifications::decode(&mut &justifications[..]))
+			.transpose()
+			.map_err(|e| format!("Invalid justifications: {e}"))?;
+		Ok(Self { header, justifications })
+	}
+}
+
 /// The different types of warp syncing, passed to `build_network`.
 pub enum WarpSyncParams<Block: BlockT> {
 	/// Standard warp sync for the chain.
@@ -153,6 +190,8 @@ pub enum WarpSyncParams<Block: BlockT> {
 	///
 	/// It is expected that the header provider ensures that the header is trusted.
 	WaitForTarget(oneshot::Receiver<<Block as BlockT>::Header>),
+	/// Skip downloading proofs and download the state of a trusted checkpoint.
+	WithCheckpoint(WarpSyncCheckpoint<Block>),
 }
 
 /// Warp sync configuration as accepted by [`WarpSync`].
@@ -163,6 +202,8 @@ pub enum WarpSyncConfig<Block: BlockT> {
 	///
 	/// It is expected that the header provider ensures that the header is trusted.
 	WaitForTarget,
+	/// Skip downloading proofs and download the state of a trusted checkpoint.
+	WithCheckpoint(WarpSyncCheckpoint<Block>),
 }
 
 impl<Block: BlockT> WarpSyncParams<Block> {
@@ -174,18 +215,23 @@ impl<Block: BlockT> WarpSyncParams<Block> {
 			WarpSyncParams::WithProvider(provider) =>
 				(WarpSyncConfig::WithProvider(provider), None),
 			WarpSyncParams::WaitForTarget(rx) => (WarpSyncConfig::WaitForTarget, Some(rx)),
+			WarpSyncParams::WithCheckpoint(checkpoint) =>
+				(WarpSyncConfig::WithCheckpoint(checkpoint), None),
 		}
 	}
 }
 
 /// Warp sync phase used by warp sync state machine.
 enum Phase<B: BlockT> {
-	/// Waiting for enough peers to connect.
-	WaitingForPeers { warp_sync_provider: Arc<dyn WarpSyncProvider<B>> },
+	/// Waiting for enough peers to connect, before downloading warp proofs from the genesis or
+	/// from the persisted last verified proof.
+	WaitingForPeers {
+		warp_sync_provider: Arc<dyn WarpSyncProvider<B>>,
+		resume_from: Option<(EncodedVerifierState, B::Hash)>,
+	},
 	/// Downloading warp proofs.
 	WarpProof {
-		set_id: SetId,
-		authorities: AuthorityList,
+		verifier_state: EncodedVerifierState,
 		last_hash: B::Hash,
 		warp_sync_provider: Arc<dyn WarpSyncProvider<B>>,
 	},
@@ -242,22 +288,31 @@ pub struct WarpSync<B: BlockT, Client> {
 	peers: HashMap<PeerId, Peer<B>>,
 	actions: Vec<WarpSyncAction<B>>,
 	result: Option<WarpSyncResult<B>>,
+	/// Justifications of the target block, if known without downloading them.
+	target_justifications: Option<Justifications>,
 }
 
 impl<B, Client> WarpSync<B, Client>
 where
 	B: BlockT,
-	Client: HeaderBackend<B> + 'static,
+	Client: HeaderBackend<B> + AuxStore + 'static,
 {
-	/// Create a new instance. When passing a warp sync provider we will be checking for proof and
-	/// authorities. Alternatively we can pass a target block when we want to skip downloading
-	/// proofs, in this case we will continue polling until the target block is known.
+	/// Create a new instance. When passing a warp sync provider we will be checking for warp
+	/// proofs. Alternatively we can pass a target block when we want to skip downloading
+	/// proofs, in this case we will continue polling until the target block is known, or a trusted
+	/// checkpoint to download the state of.
+	///
+	/// Warp proofs are downloaded from the persisted progress if there's any.
 	pub fn new(client: Arc<Client>, warp_sync_config: WarpSyncConfig<B>) -> Self {
 		if client.info().finalized_state.is_some() {
 			error!(
 				target: LOG_TARGET,
 				"Can't use warp sync mode with a partially synced database. Reverting to full sync mode."
 			);
+			// Any persisted progress is from before the state was imported.
+			if let Err(e) = aux_schema::clear_warp_proof(&*client) {
+				warn!(target: LOG_TARGET, "Failed to clear warp sync progress: {e}");
+			}
 			return Self {
 				client,
 				phase: Phase::Complete,
@@ -266,13 +321,39 @@ where
 				peers: HashMap::new(),
 				actions: vec![WarpSyncAction::Finished],
 				result: None,
+				target_justifications: None,
 			}
 		}
 
+		let mut target_justifications = None;
+
 		let phase = match warp_sync_config {
 			WarpSyncConfig::WithProvider(warp_sync_provider) =>
-				Phase::WaitingForPeers { warp_sync_provider },
+				match aux_schema::load_warp_proof::<_, B::Hash>(&*client) {
+					Ok(None) => Phase::WaitingForPeers { warp_sync_provider, resume_from: None },
+					Ok(Some(PersistedWarpProof { verifier_state, last_hash })) => {
+						info!(target: LOG_TARGET, "Resuming warp sync from {last_hash}.");
+						Phase::WaitingForPeers {
+							warp_sync_provider,
+							resume_from: Some((verifier_state, last_hash)),
+						}
+					},
+					Err(e) => {
+						warn!(target: LOG_TARGET, "Failed to load warp sync progress: {e}");
+						Phase::WaitingForPeers { warp_sync_provider, resume_from: None }
+					},
+				},
 			WarpSyncConfig::WaitForTarget => Phase::PendingTargetBlock,
+			WarpSyncConfig::WithCheckpoint(WarpSyncCheckpoint { header, justifications }) => {
+				info!(
+					target: LOG_TARGET,
+					"Warp syncing to checkpoint {} ({}).",
+					header.hash(),
+					header.number(),
+				);
+				target_justifications = justifications;
+				Phase::TargetBlock(header)
+			},
 		};
 
 		Self {
@@ -283,6 +364,7 @@ where
 			peers: HashMap::new(),
 			actions: Vec::new(),
 			result: None,
+			target_justifications,
 		}
 	}
 
@@ -335,16 +417,20 @@ where
 
 	/// Start warp sync as soon as we have enough peers.
 	fn try_to_start_warp_sync(&mut self) {
-		let Phase::WaitingForPeers { warp_sync_provider } = &self.phase else { return };
+		let Phase::WaitingForPeers { warp_sync_provider, resume_from } = &mut self.phase else {
+			return
+		};
 
 		if self.peers.len() < MIN_PEERS_TO_START_WARP_SYNC {
 			return
 		}
 
+		let (verifier_state, last_hash) = resume_from.take().unwrap_or_else(|| {
+			(warp_sync_provider.initial_verifier_state(), self.client.info().genesis_hash)
+		});
 		self.phase = Phase::WarpProof {
-			set_id: 0,
-			authorities: warp_sync_provider.current_authorities(),
-			last_hash: self.client.info().genesis_hash,
+			verifier_state,
+			last_hash,
 			warp_sync_provider: Arc::clone(warp_sync_provider),
 		};
 		trace!(target: LOG_TARGET, "Started warp sync with {} peers.", self.peers.len());
@@ -356,8 +442,7 @@ where
 			peer.state = PeerState::Available;
 		}
 
-		let Phase::WarpProof { set_id, authorities, last_hash, warp_sync_provider } =
-			&mut self.phase
+		let Phase::WarpProof { verifier_state, last_hash, warp_sync_provider } = &mut self.phase
 		else {
 			debug!(target: LOG_TARGET, "Unexpected warp proof response");
 			self.actions
@@ -365,33 +450,45 @@ where
 			return
 		};
 
-		match warp_sync_provider.verify(&response, *set_id, authorities.clone()) {
+		match warp_sync_provider.verify(&response, verifier_state) {
 			Err(e) => {
 				debug!(target: LOG_TARGET, "Bad warp proof response: {}", e);
 				self.actions
 					.push(WarpSyncAction::DropPeer(BadPeer(*peer_id, rep::BAD_WARP_PROOF)))
 			},
-			Ok(VerificationResult::Partial(new_set_id, new_authorities, new_last_hash)) => {
-				log::debug!(target: LOG_TARGET, "Verified partial proof, set_id={:?}", new_set_id);
-				*set_id = new_set_id;
-				*authorities = new_authorities;
+			Ok(VerificationResult::Partial(new_verifier_state, new_last_hash)) => {
+				log::debug!(target: LOG_TARGET, "Verified partial proof up to {}", new_last_hash);
+				*verifier_state = new_verifier_state;
 				*last_hash = new_last_hash;
 				self.total_proof_bytes += response.0.len() as u64;
+				let progress = PersistedWarpProof {
+					verifier_state: verifier_state.clone(),
+					last_hash: new_last_hash,
+				};
+				self.persist(&progress);
 			},
-			Ok(VerificationResult::Complete(new_set_id, _, header)) => {
+			Ok(VerificationResult::Complete(header)) => {
 				log::debug!(
 					target: LOG_TARGET,
-					"Verified complete proof, set_id={:?}. Continuing with target block download: {} ({}).",
-					new_set_id,
+					"Verified complete proof. Continuing with target block download: {} ({}).",
 					header.hash(),
 					header.number(),
 				);
 				self.total_proof_bytes += response.0.len() as u64;
+				// The target block isn't persisted, so after a restart the last proof is requested
+				// again, for a fresh target block.
 				self.phase = Phase::TargetBlock(header);
 			},
 		}
 	}
 
+	/// Persist the progress of the warp proofs download.
+	fn persist(&self, progress: &PersistedWarpProof<B::Hash>) {
+		if let Err(e) = aux_schema::write_warp_proof(&*self.client, progress) {
+			warn!(target: LOG_TARGET, "Failed to persist warp sync progress: {e}");
+		}
+	}
+
 	/// Process (target) block response.
 	pub fn on_block_response(
 		&mut self,
@@ -467,7 +564,7 @@ where
 		self.result = Some(WarpSyncResult {
 			target_header: header.clone(),
 			target_body: block.body,
-			target_justifications: block.justifications,
+			target_justifications: self.target_justifications.take().or(block.justifications),
 		});
 		self.phase = Phase::Complete;
 		self.actions.push(WarpSyncAction::Finished);
@@ -616,6 +713,7 @@ where
 			queued_blocks: 0,
 			state_sync: None,
 			warp_sync: Some(self.progress()),
+			gap_sync: None,
 		}
 	}
 
@@ -649,7 +747,6 @@ mod test {
 	use super::*;
 	use sc_block_builder::BlockBuilderBuilder;
 	use sp_blockchain::{BlockStatus, Error as BlockchainError, HeaderBackend, Info};
-	use sp_consensus_grandpa::{AuthorityList, SetId};
 	use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
 	use std::{io::ErrorKind, sync::Arc};
 	use substrate_test_runtime_client::{
@@ -683,10 +780,30 @@ mod test {
 			fn verify(
 				&self,
 				proof: &EncodedProof,
-				set_id: SetId,
-				authorities: AuthorityList,
+				verifier_state: &EncodedVerifierState,
 			) -> Result<VerificationResult<B>, Box<dyn std::error::Error + Send + Sync>>;
-			fn current_authorities(&self) -> AuthorityList;
+			fn initial_verifier_state(&self) -> EncodedVerifierState;
+		}
+	}
+
+	// Mocked clients don't persist the warp sync progress.
+	impl<B: BlockT> AuxStore for MockClient<B> {
+		fn insert_aux<
+			'a,
+			'b: 'a,
+			'c: 'a,
+			I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+			D: IntoIterator<Item = &'a &'b [u8]>,
+		>(
+			&self,
+			_insert: I,
+			_delete: D,
+		) -> sp_blockchain::Result<()> {
+			Ok(())
+		}
+
+		fn get_aux(&self, _key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(None)
 		}
 	}
 
@@ -782,9 +899,9 @@ mod test {
 		let client = mock_client_without_state();
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(Arc::new(client), config);
 
@@ -824,9 +941,9 @@ mod test {
 			let client = mock_client_without_state();
 			let mut provider = MockWarpSyncProvider::<Block>::new();
 			provider
-				.expect_current_authorities()
+				.expect_initial_verifier_state()
 				.once()
-				.return_const(AuthorityList::default());
+				.return_const(EncodedVerifierState::default());
 			let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 			let mut warp_sync = WarpSync::new(Arc::new(client), config);
 
@@ -845,9 +962,9 @@ mod test {
 			let client = mock_client_without_state();
 			let mut provider = MockWarpSyncProvider::<Block>::new();
 			provider
-				.expect_current_authorities()
+				.expect_initial_verifier_state()
 				.once()
-				.return_const(AuthorityList::default());
+				.return_const(EncodedVerifierState::default());
 			let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 			let mut warp_sync = WarpSync::new(Arc::new(client), config);
 
@@ -865,9 +982,9 @@ mod test {
 		let client = mock_client_without_state();
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(Arc::new(client), config);
 
@@ -888,9 +1005,9 @@ mod test {
 		let client = mock_client_without_state();
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(Arc::new(client), config);
 
@@ -919,9 +1036,9 @@ mod test {
 		let client = mock_client_without_state();
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(Arc::new(client), config);
 
@@ -942,11 +1059,11 @@ mod test {
 		let client = mock_client_without_state();
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		// Warp proof verification fails.
-		provider.expect_verify().return_once(|_proof, _set_id, _authorities| {
+		provider.expect_verify().return_once(|_proof, _verifier_state| {
 			Err(Box::new(std::io::Error::new(ErrorKind::Other, "test-verification-failure")))
 		});
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
@@ -983,12 +1100,12 @@ mod test {
 		let client = mock_client_without_state();
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		// Warp proof is partial.
-		provider.expect_verify().return_once(|_proof, set_id, authorities| {
-			Ok(VerificationResult::Partial(set_id, authorities, Hash::random()))
+		provider.expect_verify().return_once(|_proof, verifier_state| {
+			Ok(VerificationResult::Partial(verifier_state.clone(), Hash::random()))
 		});
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(Arc::new(client), config);
@@ -1018,9 +1135,9 @@ mod test {
 		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let target_block = BlockBuilderBuilder::new(&*client)
 			.on_parent_block(client.chain_info().best_hash)
 			.with_parent_block_number(client.chain_info().best_number)
@@ -1031,8 +1148,8 @@ mod test {
 			.block;
 		let target_header = target_block.header().clone();
 		// Warp proof is complete.
-		provider.expect_verify().return_once(move |_proof, set_id, authorities| {
-			Ok(VerificationResult::Complete(set_id, authorities, target_header))
+		provider.expect_verify().return_once(move |_proof, _verifier_state| {
+			Ok(VerificationResult::Complete(target_header))
 		});
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(client, config);
@@ -1059,14 +1176,111 @@ mod test {
 		);
 	}
 
+	#[test]
+	fn partial_warp_proof_is_resumed_after_restart() {
+		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
+		let mut provider = MockWarpSyncProvider::<Block>::new();
+		provider
+			.expect_initial_verifier_state()
+			.once()
+			.return_const(EncodedVerifierState::default());
+		let last_hash = Hash::random();
+		// Warp proof is partial.
+		provider.expect_verify().return_once(move |_proof, _verifier_state| {
+			Ok(VerificationResult::Partial(EncodedVerifierState(vec![1]), last_hash))
+		});
+		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
+		let mut warp_sync = WarpSync::new(client.clone(), config);
+
+		for best_number in 1..11 {
+			warp_sync.add_peer(PeerId::random(), Hash::random(), best_number);
+		}
+		let (request_peer_id, _request) = warp_sync.warp_proof_request().unwrap();
+		warp_sync.on_warp_proof_response(&request_peer_id, EncodedProof(Vec::new()));
+
+		// After a restart, proofs are requested from the last verified one.
+		let mut provider = MockWarpSyncProvider::<Block>::new();
+		provider.expect_initial_verifier_state().never();
+		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
+		let mut warp_sync = WarpSync::new(client, config);
+
+		for best_number in 1..11 {
+			warp_sync.add_peer(PeerId::random(), Hash::random(), best_number);
+		}
+		assert!(matches!(
+			&warp_sync.phase,
+			Phase::WarpProof { verifier_state, .. } if *verifier_state == EncodedVerifierState(vec![1])
+		));
+		let (_peer_id, request) = warp_sync.warp_proof_request().unwrap();
+		assert_eq!(request.begin, last_hash);
+	}
+
+	#[test]
+	fn complete_warp_proof_is_requested_again_after_restart() {
+		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
+		let target_block = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(client.chain_info().best_hash)
+			.with_parent_block_number(client.chain_info().best_number)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+		let target_header = target_block.header().clone();
+		let mut provider = MockWarpSyncProvider::<Block>::new();
+		provider
+			.expect_initial_verifier_state()
+			.once()
+			.return_const(EncodedVerifierState::default());
+		let last_hash = Hash::random();
+		// Warp proof is partial, then complete.
+		provider
+			.expect_verify()
+			.withf(|_proof, verifier_state| verifier_state.0.is_empty())
+			.return_once(move |_proof, _verifier_state| {
+				Ok(VerificationResult::Partial(EncodedVerifierState(vec![1]), last_hash))
+			});
+		provider
+			.expect_verify()
+			.withf(|_proof, verifier_state| *verifier_state == EncodedVerifierState(vec![1]))
+			.return_once(move |_proof, _verifier_state| {
+				Ok(VerificationResult::Complete(target_header))
+			});
+		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
+		let mut warp_sync = WarpSync::new(client.clone(), config);
+
+		for best_number in 1..11 {
+			warp_sync.add_peer(PeerId::random(), Hash::random(), best_number);
+		}
+		for _ in 0..2 {
+			let (request_peer_id, _request) = warp_sync.warp_proof_request().unwrap();
+			warp_sync.on_warp_proof_response(&request_peer_id, EncodedProof(Vec::new()));
+		}
+		assert!(
+			matches!(warp_sync.phase, Phase::TargetBlock(header) if header == *target_block.header())
+		);
+
+		// After a restart, the last proof is requested again instead of downloading a possibly
+		// stale target block.
+		let mut provider = MockWarpSyncProvider::<Block>::new();
+		provider.expect_initial_verifier_state().never();
+		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
+		let warp_sync = WarpSync::new(client, config);
+		assert!(matches!(
+			&warp_sync.phase,
+			Phase::WaitingForPeers { resume_from: Some((verifier_state, hash)), .. }
+				if *verifier_state == EncodedVerifierState(vec![1]) && *hash == last_hash
+		));
+	}
+
 	#[test]
 	fn no_target_block_requests_in_another_phase() {
 		let client = mock_client_without_state();
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(Arc::new(client), config);
 
@@ -1086,9 +1300,9 @@ mod test {
 		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let target_block = BlockBuilderBuilder::new(&*client)
 			.on_parent_block(client.chain_info().best_hash)
 			.with_parent_block_number(client.chain_info().best_number)
@@ -1099,8 +1313,8 @@ mod test {
 			.block;
 		let target_header = target_block.header().clone();
 		// Warp proof is complete.
-		provider.expect_verify().return_once(move |_proof, set_id, authorities| {
-			Ok(VerificationResult::Complete(set_id, authorities, target_header))
+		provider.expect_verify().return_once(move |_proof, _verifier_state| {
+			Ok(VerificationResult::Complete(target_header))
 		});
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(client, config);
@@ -1162,9 +1376,9 @@ mod test {
 		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let target_block = BlockBuilderBuilder::new(&*client)
 			.on_parent_block(client.chain_info().best_hash)
 			.with_parent_block_number(client.chain_info().best_number)
@@ -1175,8 +1389,8 @@ mod test {
 			.block;
 		let target_header = target_block.header().clone();
 		// Warp proof is complete.
-		provider.expect_verify().return_once(move |_proof, set_id, authorities| {
-			Ok(VerificationResult::Complete(set_id, authorities, target_header))
+		provider.expect_verify().return_once(move |_proof, _verifier_state| {
+			Ok(VerificationResult::Complete(target_header))
 		});
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(client, config);
@@ -1200,9 +1414,9 @@ mod test {
 		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let target_block = BlockBuilderBuilder::new(&*client)
 			.on_parent_block(client.chain_info().best_hash)
 			.with_parent_block_number(client.chain_info().best_number)
@@ -1213,8 +1427,8 @@ mod test {
 			.block;
 		let target_header = target_block.header().clone();
 		// Warp proof is complete.
-		provider.expect_verify().return_once(move |_proof, set_id, authorities| {
-			Ok(VerificationResult::Complete(set_id, authorities, target_header))
+		provider.expect_verify().return_once(move |_proof, _verifier_state| {
+			Ok(VerificationResult::Complete(target_header))
 		});
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(client, config);
@@ -1243,9 +1457,9 @@ mod test {
 		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let target_block = BlockBuilderBuilder::new(&*client)
 			.on_parent_block(client.chain_info().best_hash)
 			.with_parent_block_number(client.chain_info().best_number)
@@ -1267,8 +1481,8 @@ mod test {
 
 		let target_header = target_block.header().clone();
 		// Warp proof is complete.
-		provider.expect_verify().return_once(move |_proof, set_id, authorities| {
-			Ok(VerificationResult::Complete(set_id, authorities, target_header))
+		provider.expect_verify().return_once(move |_proof, _verifier_state| {
+			Ok(VerificationResult::Complete(target_header))
 		});
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(client, config);
@@ -1320,9 +1534,9 @@ mod test {
 		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let target_block = BlockBuilderBuilder::new(&*client)
 			.on_parent_block(client.chain_info().best_hash)
 			.with_parent_block_number(client.chain_info().best_number)
@@ -1344,8 +1558,8 @@ mod test {
 
 		let target_header = target_block.header().clone();
 		// Warp proof is complete.
-		provider.expect_verify().return_once(move |_proof, set_id, authorities| {
-			Ok(VerificationResult::Complete(set_id, authorities, target_header))
+		provider.expect_verify().return_once(move |_proof, _verifier_state| {
+			Ok(VerificationResult::Complete(target_header))
 		});
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(client, config);
@@ -1383,9 +1597,9 @@ mod test {
 		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
 		let mut provider = MockWarpSyncProvider::<Block>::new();
 		provider
-			.expect_current_authorities()
+			.expect_initial_verifier_state()
 			.once()
-			.return_const(AuthorityList::default());
+			.return_const(EncodedVerifierState::default());
 		let mut target_block_builder = BlockBuilderBuilder::new(&*client)
 			.on_parent_block(client.chain_info().best_hash)
 			.with_parent_block_number(client.chain_info().best_number)
@@ -1397,8 +1611,8 @@ mod test {
 		let target_block = target_block_builder.build().unwrap().block;
 		let target_header = target_block.header().clone();
 		// Warp proof is complete.
-		provider.expect_verify().return_once(move |_proof, set_id, authorities| {
-			Ok(VerificationResult::Complete(set_id, authorities, target_header))
+		provider.expect_verify().return_once(move |_proof, _verifier_state| {
+			Ok(VerificationResult::Complete(target_header))
 		});
 		let config = WarpSyncConfig::WithProvider(Arc::new(provider));
 		let mut warp_sync = WarpSync::new(client, config);
@@ -1440,4 +1654,74 @@ mod test {
 		assert_eq!(result.target_body, body);
 		assert_eq!(result.target_justifications, justifications);
 	}
+
+	#[test]
+	fn warp_sync_checkpoint_is_decoded_only_with_its_hash() {
+		let header = <Block as BlockT>::Header::new(
+			1,
+			Hash::random(),
+			Hash::random(),
+			Hash::random(),
+			Default::default(),
+		);
+		let justifications = Justifications::from((*b"FRNK", vec![1, 2, 3]));
+		let mut checkpoint = sc_network::config::WarpSyncCheckpoint {
+			hash: header.hash().as_ref().to_vec().into(),
+			header: header.encode().into(),
+			justifications: Some(justifications.encode().into()),
+		};
+
+		let decoded = WarpSyncCheckpoint::<Block>::decode(&checkpoint).unwrap();
+		assert_eq!(decoded.header, header);
+		assert_eq!(decoded.justifications, Some(justifications));
+
+		checkpoint.hash = Hash::random().as_ref().to_vec().into();
+		assert!(WarpSyncCheckpoint::<Block>::decode(&checkpoint).is_err());
+	}
+
+	#[test]
+	fn warp_sync_to_checkpoint_downloads_target_block() {
+		let client = Arc::new(TestClientBuilder::new().set_no_genesis().build());
+		let target_block = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(client.chain_info().best_hash)
+			.with_parent_block_number(client.chain_info().best_number)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+		let justifications = Some(Justifications::from((*b"FRNK", vec![1, 2, 3])));
+		let config = WarpSyncConfig::WithCheckpoint(WarpSyncCheckpoint {
+			header: target_block.header().clone(),
+			justifications: justifications.clone(),
+		});
+		let mut warp_sync = WarpSync::new(client, config);
+
+		// No warp proofs are downloaded.
+		assert!(
+			matches!(&warp_sync.phase, Phase::TargetBlock(header) if header == target_block.header())
+		);
+		warp_sync.add_peer(PeerId::random(), Hash::random(), 10);
+		let (peer_id, request) = warp_sync.target_block_request().unwrap();
+
+		// Block received without justifications.
+		let body = Some(target_block.extrinsics().iter().cloned().collect::<Vec<_>>());
+		let response = vec![BlockData::<Block> {
+			hash: target_block.header().hash(),
+			header: Some(target_block.header().clone()),
+			body: body.clone(),
+			indexed_body: None,
+			receipt: None,
+			message_queue: None,
+			justification: None,
+			justifications: None,
+		}];
+		assert!(warp_sync.on_block_response_inner(peer_id, request, response).is_ok());
+
+		// The justifications of the checkpoint are used.
+		let result = warp_sync.take_result().unwrap();
+		assert_eq!(result.target_header, *target_block.header());
+		assert_eq!(result.target_body, body);
+		assert_eq!(result.target_justifications, justifications);
+	}
 }
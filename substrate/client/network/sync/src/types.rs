@@ -24,7 +24,9 @@ use sc_network_common::{role::Roles, types::ReputationChange};
 
 use libp2p::PeerId;
 
-use crate::strategy::{state_sync::StateSyncProgress, warp::WarpSyncProgress};
+use crate::strategy::{
+	gap_sync::GapSyncProgress, state_sync::StateSyncProgress, warp::WarpSyncProgress,
+};
 use sc_network_common::sync::message::BlockRequest;
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 
@@ -76,6 +78,8 @@ pub struct SyncStatus<Block: BlockT> {
 	pub state_sync: Option<StateSyncProgress>,
 	/// Warp sync in progress, if any.
 	pub warp_sync: Option<WarpSyncProgress<Block>>,
+	/// Block history download in progress, if any.
+	pub gap_sync: Option<GapSyncProgress<Block>>,
 }
 
 /// A peer did not behave as expected and should be reported.
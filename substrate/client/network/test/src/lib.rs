@@ -52,8 +52,8 @@ use sc_consensus::{
 };
 use sc_network::{
 	config::{
-		FullNetworkConfiguration, MultiaddrWithPeerId, NetworkConfiguration, NonDefaultSetConfig,
-		NonReservedPeerMode, ProtocolId, Role, SyncMode, TransportConfig,
+		FullNetworkConfiguration, GapSyncConfig, MultiaddrWithPeerId, NetworkConfiguration,
+		NonDefaultSetConfig, NonReservedPeerMode, ProtocolId, Role, SyncMode, TransportConfig,
 	},
 	peer_store::PeerStore,
 	request_responses::ProtocolConfig as RequestResponseConfig,
@@ -68,7 +68,7 @@ use sc_network_sync::{
 	service::{network::NetworkServiceProvider, syncing_service::SyncingService},
 	state_request_handler::StateRequestHandler,
 	strategy::warp::{
-		AuthorityList, EncodedProof, SetId, VerificationResult, WarpSyncParams, WarpSyncProvider,
+		EncodedProof, EncodedVerifierState, VerificationResult, WarpSyncParams, WarpSyncProvider,
 	},
 	warp_request_handler,
 };
@@ -661,14 +661,13 @@ impl<B: BlockT> WarpSyncProvider<B> for TestWarpSyncProvider<B> {
 	fn verify(
 		&self,
 		proof: &EncodedProof,
-		_set_id: SetId,
-		_authorities: AuthorityList,
+		_verifier_state: &EncodedVerifierState,
 	) -> Result<VerificationResult<B>, Box<dyn std::error::Error + Send + Sync>> {
 		let EncodedProof(encoded) = proof;
 		let header = B::Header::decode(&mut encoded.as_slice()).unwrap();
-		Ok(VerificationResult::Complete(0, Default::default(), header))
+		Ok(VerificationResult::Complete(header))
 	}
-	fn current_authorities(&self) -> AuthorityList {
+	fn initial_verifier_state(&self) -> EncodedVerifierState {
 		Default::default()
 	}
 }
@@ -694,6 +693,8 @@ pub struct FullPeerConfig {
 	pub is_authority: bool,
 	/// Syncing mode
 	pub sync_mode: SyncMode,
+	/// Download of the block history missing after warp or state sync.
+	pub gap_sync: GapSyncConfig,
 	/// Extra genesis storage.
 	pub extra_storage: Option<sp_core::storage::Storage>,
 	/// Enable transaction indexing.
@@ -790,6 +791,7 @@ pub trait TestNetFactory: Default + Sized + Send {
 		let mut network_config =
 			NetworkConfiguration::new("test-node", "test-client", Default::default(), None);
 		network_config.sync_mode = config.sync_mode;
+		network_config.gap_sync = config.gap_sync;
 		network_config.transport = TransportConfig::MemoryOnly;
 		network_config.listen_addresses = vec![listen_addr.clone()];
 		network_config.allow_non_globals_in_dht = true;
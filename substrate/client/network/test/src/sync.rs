@@ -1252,6 +1252,41 @@ async fn warp_sync() {
 	.await;
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn warp_sync_with_gap_sync_depth() {
+	sp_tracing::try_init_simple();
+	let mut net = TestNet::new(0);
+	// Create 3 synced peers and 1 peer warp syncing only the 16 most recent blocks of history.
+	net.add_full_peer_with_config(Default::default());
+	net.add_full_peer_with_config(Default::default());
+	net.add_full_peer_with_config(Default::default());
+	net.add_full_peer_with_config(FullPeerConfig {
+		sync_mode: SyncMode::Warp,
+		gap_sync: GapSyncConfig { depth: Some(16), ..Default::default() },
+		..Default::default()
+	});
+	let blocks = net.peer(0).push_blocks(64, false);
+	net.peer(1).push_blocks(64, false);
+	net.peer(2).push_blocks(64, false);
+	net.run_until_sync().await;
+	assert!(net.peer(3).client().has_state_at(&BlockId::Number(64)));
+
+	// Wait for peer 3 to download the blocks #63 down to #48, anchored on the warp sync target.
+	futures::future::poll_fn::<(), _>(|cx| {
+		net.poll(cx);
+		if net.peer(3).has_body(blocks[62]) && net.peer(3).has_body(blocks[47]) {
+			Poll::Ready(())
+		} else {
+			Poll::Pending
+		}
+	})
+	.await;
+
+	// The older history isn't downloaded, and remains recorded as a gap.
+	assert!(!net.peer(3).has_body(blocks[46]));
+	assert_eq!(net.peer(3).client().info().block_gap, Some((1, 47)));
+}
+
 /// If there is a finalized state in the DB, warp sync falls back to full sync.
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn warp_sync_failover_to_full_sync() {
@@ -20,6 +20,7 @@
 //! Configuration of the transaction protocol
 
 use futures::prelude::*;
+use sc_network::config::MultiaddrWithPeerId;
 use sc_network_common::ExHashT;
 use sp_runtime::traits::Block as BlockT;
 use std::{collections::HashMap, future::Future, pin::Pin, time};
@@ -38,6 +39,15 @@ pub(crate) const MAX_TRANSACTIONS_SIZE: u64 = 16 * 1024 * 1024;
 /// Maximum number of transaction validation request we keep at any moment.
 pub(crate) const MAX_PENDING_TRANSACTIONS: usize = 8192;
 
+/// Maximum allowed size for a private transactions request.
+pub(crate) const MAX_PRIVATE_TRANSACTIONS_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Maximum number of private transactions requests we queue.
+pub(crate) const MAX_PRIVATE_TRANSACTIONS_REQUESTS: usize = 64;
+
+/// Duration after which private transactions requests are considered timed out.
+pub(crate) const PRIVATE_TRANSACTIONS_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+
 /// Result of the transaction import.
 #[derive(Clone, Copy, Debug)]
 pub enum TransactionImport {
@@ -64,6 +74,12 @@ pub trait TransactionPool<H: ExHashT, B: BlockT>: Send + Sync {
 	///
 	/// This will return future.
 	fn import(&self, transaction: B::Extrinsic) -> TransactionImportFuture;
+	/// Import a transaction received privately into the pool, so it's never gossiped.
+	///
+	/// This will return future.
+	fn import_private(&self, transaction: B::Extrinsic) -> TransactionImportFuture;
+	/// Whether the transaction with the given hash was received privately.
+	fn is_private(&self, hash: &H) -> bool;
 	/// Notify the pool about transactions broadcast.
 	fn on_broadcasted(&self, propagations: HashMap<H, Vec<String>>);
 	/// Get transaction by hash.
@@ -91,9 +107,40 @@ impl<H: ExHashT + Default, B: BlockT> TransactionPool<H, B> for EmptyTransaction
 		Box::pin(future::ready(TransactionImport::KnownGood))
 	}
 
+	fn import_private(&self, _transaction: B::Extrinsic) -> TransactionImportFuture {
+		Box::pin(future::ready(TransactionImport::KnownGood))
+	}
+
+	fn is_private(&self, _hash: &H) -> bool {
+		false
+	}
+
 	fn on_broadcasted(&self, _: HashMap<H, Vec<String>>) {}
 
 	fn transaction(&self, _h: &H) -> Option<B::Extrinsic> {
 		None
 	}
 }
+
+/// Policy deciding which transactions of the pool are private.
+///
+/// Private transactions aren't gossiped, but only sent to the trusted peers over the private
+/// transactions protocol.
+pub trait PrivateTransactionPolicy<B: BlockT>: Send + Sync {
+	/// Whether the given transaction is private.
+	fn is_private(&self, transaction: &B::Extrinsic) -> bool;
+}
+
+impl<B: BlockT, F: Fn(&B::Extrinsic) -> bool + Send + Sync> PrivateTransactionPolicy<B> for F {
+	fn is_private(&self, transaction: &B::Extrinsic) -> bool {
+		self(transaction)
+	}
+}
+
+/// Configuration of the private transactions.
+pub struct PrivateTransactionsConfig<B: BlockT> {
+	/// Which transactions of the pool are private.
+	pub policy: Box<dyn PrivateTransactionPolicy<B>>,
+	/// The trusted block authoring peers the private transactions are sent to.
+	pub trusted_peers: Vec<MultiaddrWithPeerId>,
+}
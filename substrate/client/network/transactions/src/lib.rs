@@ -26,22 +26,29 @@
 //!   network configuration as an extra peers set.
 //! - Use [`TransactionsHandlerPrototype::build`] then [`TransactionsHandler::run`] to obtain a
 //! `Future` that processes transactions.
+//!
+//! Transactions deemed private by a [`config::PrivateTransactionPolicy`] aren't gossiped, but
+//! only sent to trusted peers over a request-response protocol, enabled with
+//! [`TransactionsHandlerPrototype::enable_private_transactions`].
 
 use crate::config::*;
 
 use codec::{Decode, Encode};
-use futures::{prelude::*, stream::FuturesUnordered};
+use futures::{channel::oneshot, prelude::*, stream::FuturesUnordered};
 use libp2p::{multiaddr, PeerId};
 use log::{debug, trace, warn};
 
 use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
 use sc_network::{
-	config::{NonDefaultSetConfig, NonReservedPeerMode, ProtocolId, SetConfig},
+	config::{
+		IncomingRequest, MultiaddrWithPeerId, NonDefaultSetConfig, NonReservedPeerMode,
+		OutgoingResponse, ProtocolId, RequestResponseConfig, SetConfig,
+	},
 	error,
 	service::traits::{NotificationEvent, NotificationService, ValidationResult},
 	types::ProtocolName,
 	utils::{interval, LruHashSet},
-	NetworkEventStream, NetworkNotification, NetworkPeers,
+	IfDisconnected, NetworkEventStream, NetworkNotification, NetworkPeers, NetworkRequest,
 };
 use sc_network_common::{role::ObservedRole, ExHashT};
 use sc_network_sync::{SyncEvent, SyncEventStream};
@@ -75,10 +82,14 @@ mod rep {
 	pub const GOOD_TRANSACTION: Rep = Rep::new(1 << 7, "Good transaction");
 	/// Reputation change when a peer sends us a bad transaction.
 	pub const BAD_TRANSACTION: Rep = Rep::new(-(1 << 12), "Bad transaction");
+	/// Reputation change when a peer we don't trust sends us private transactions.
+	pub const UNEXPECTED_PRIVATE_TRANSACTIONS: Rep =
+		Rep::new(-(1 << 10), "Unexpected private transactions");
 }
 
 struct Metrics {
 	propagated_transactions: Counter<U64>,
+	private_transactions: Counter<U64>,
 }
 
 impl Metrics {
@@ -91,6 +102,13 @@ impl Metrics {
 				)?,
 				r,
 			)?,
+			private_transactions: register(
+				Counter::new(
+					"substrate_sync_private_transactions",
+					"Number of private transactions sent to trusted peers",
+				)?,
+				r,
+			)?,
 		})
 	}
 }
@@ -114,11 +132,20 @@ impl<H: ExHashT> Future for PendingTransaction<H> {
 	}
 }
 
+/// Private transactions request to a trusted peer, resolving to whether they were sent.
+type PendingPrivateRequest<H> = Pin<Box<dyn Future<Output = (PeerId, Vec<H>, bool)> + Send>>;
+
 /// Prototype for a [`TransactionsHandler`].
 pub struct TransactionsHandlerPrototype {
 	/// Name of the transaction protocol.
 	protocol_name: ProtocolName,
 
+	/// Name of the private transactions protocol.
+	private_protocol_name: ProtocolName,
+
+	/// Receiver of the private transactions requests, if the protocol is enabled.
+	private_requests: Option<async_channel::Receiver<IncomingRequest>>,
+
 	/// Handle that is used to communicate with `sc_network::Notifications`.
 	notification_service: Box<dyn NotificationService>,
 }
@@ -137,6 +164,16 @@ impl TransactionsHandlerPrototype {
 			format!("/{}/transactions/1", array_bytes::bytes2hex("", genesis_hash))
 		}
 		.into();
+		let private_protocol_name: ProtocolName = if let Some(fork_id) = fork_id {
+			format!(
+				"/{}/{}/private-transactions/1",
+				array_bytes::bytes2hex("", genesis_hash),
+				fork_id
+			)
+		} else {
+			format!("/{}/private-transactions/1", array_bytes::bytes2hex("", genesis_hash))
+		}
+		.into();
 		let (config, notification_service) = NonDefaultSetConfig::new(
 			protocol_name.clone(),
 			vec![format!("/{}/transactions/1", protocol_id.as_ref()).into()],
@@ -150,7 +187,35 @@ impl TransactionsHandlerPrototype {
 			},
 		);
 
-		(Self { protocol_name, notification_service }, config)
+		(
+			Self {
+				protocol_name,
+				private_protocol_name,
+				private_requests: None,
+				notification_service,
+			},
+			config,
+		)
+	}
+
+	/// Enable the private transactions protocol. Returns its configuration, to add to the
+	/// network configuration.
+	///
+	/// The protocol is required to send the private transactions to the trusted peers, and to
+	/// receive private transactions. The transactions received over it are imported into the pool
+	/// and never gossiped, only sent to our own trusted peers.
+	pub fn enable_private_transactions(&mut self) -> RequestResponseConfig {
+		let (tx, rx) = async_channel::bounded(MAX_PRIVATE_TRANSACTIONS_REQUESTS);
+		self.private_requests = Some(rx);
+
+		RequestResponseConfig {
+			name: self.private_protocol_name.clone(),
+			fallback_names: Vec::new(),
+			max_request_size: MAX_PRIVATE_TRANSACTIONS_SIZE,
+			max_response_size: 0,
+			request_timeout: PRIVATE_TRANSACTIONS_TIMEOUT,
+			inbound_queue: Some(tx),
+		}
 	}
 
 	/// Turns the prototype into the actual handler. Returns a controller that allows controlling
@@ -158,10 +223,13 @@ impl TransactionsHandlerPrototype {
 	///
 	/// Important: the transactions handler is initially disabled and doesn't gossip transactions.
 	/// Gossiping is enabled when major syncing is done.
+	///
+	/// The transactions deemed private by the `private_transactions` policy are only sent to its
+	/// trusted peers, which requires the private transactions protocol to be enabled.
 	pub fn build<
 		B: BlockT + 'static,
 		H: ExHashT,
-		N: NetworkPeers + NetworkEventStream + NetworkNotification,
+		N: NetworkPeers + NetworkEventStream + NetworkNotification + NetworkRequest,
 		S: SyncEventStream + sp_consensus::SyncOracle,
 	>(
 		self,
@@ -169,10 +237,40 @@ impl TransactionsHandlerPrototype {
 		sync: S,
 		transaction_pool: Arc<dyn TransactionPool<H, B>>,
 		metrics_registry: Option<&Registry>,
+		private_transactions: Option<PrivateTransactionsConfig<B>>,
 	) -> error::Result<(TransactionsHandler<B, H, N, S>, TransactionsHandlerController<H>)> {
 		let sync_event_stream = sync.event_stream("transactions-handler-sync");
 		let (to_handler, from_controller) = tracing_unbounded("mpsc_transactions_handler", 100_000);
 
+		let (private_policy, trusted_peers) = match private_transactions {
+			Some(config) => {
+				let trusted_peers = if self.private_requests.is_some() {
+					config
+						.trusted_peers
+						.into_iter()
+						.map(|MultiaddrWithPeerId { multiaddr, peer_id }| {
+							network.add_known_address(peer_id, multiaddr);
+							(peer_id, new_known_transactions())
+						})
+						.collect()
+				} else {
+					warn!(
+						target: "sub-libp2p",
+						"Private transactions protocol not enabled, private transactions won't be \
+						 sent to the trusted peers",
+					);
+					HashMap::new()
+				};
+				(Some(config.policy), trusted_peers)
+			},
+			None => (None, HashMap::new()),
+		};
+		let private_requests = match self.private_requests {
+			Some(private_requests) =>
+				Box::pin(private_requests) as Pin<Box<dyn Stream<Item = IncomingRequest> + Send>>,
+			None => Box::pin(stream::pending()),
+		};
+
 		let handler = TransactionsHandler {
 			protocol_name: self.protocol_name,
 			notification_service: self.notification_service,
@@ -187,6 +285,11 @@ impl TransactionsHandlerPrototype {
 			peers: HashMap::new(),
 			transaction_pool,
 			from_controller,
+			private_protocol_name: self.private_protocol_name,
+			private_requests: private_requests.fuse(),
+			private_policy,
+			trusted_peers,
+			pending_private_requests: FuturesUnordered::new(),
 			metrics: if let Some(r) = metrics_registry {
 				Some(Metrics::register(r)?)
 			} else {
@@ -232,7 +335,7 @@ enum ToHandler<H: ExHashT> {
 pub struct TransactionsHandler<
 	B: BlockT + 'static,
 	H: ExHashT,
-	N: NetworkPeers + NetworkEventStream + NetworkNotification,
+	N: NetworkPeers + NetworkEventStream + NetworkNotification + NetworkRequest,
 	S: SyncEventStream + sp_consensus::SyncOracle,
 > {
 	protocol_name: ProtocolName,
@@ -255,6 +358,16 @@ pub struct TransactionsHandler<
 	peers: HashMap<PeerId, Peer<H>>,
 	transaction_pool: Arc<dyn TransactionPool<H, B>>,
 	from_controller: TracingUnboundedReceiver<ToHandler<H>>,
+	/// Name of the private transactions protocol.
+	private_protocol_name: ProtocolName,
+	/// Incoming private transactions requests.
+	private_requests: stream::Fuse<Pin<Box<dyn Stream<Item = IncomingRequest> + Send>>>,
+	/// Which transactions of the pool are private, if any.
+	private_policy: Option<Box<dyn PrivateTransactionPolicy<B>>>,
+	/// The trusted peers, with the private transactions known to them.
+	trusted_peers: HashMap<PeerId, LruHashSet<H>>,
+	/// Private transactions requests to the trusted peers.
+	pending_private_requests: FuturesUnordered<PendingPrivateRequest<H>>,
 	/// Prometheus metrics.
 	metrics: Option<Metrics>,
 	/// Handle that is used to communicate with `sc_network::Notifications`.
@@ -269,11 +382,16 @@ struct Peer<H: ExHashT> {
 	role: ObservedRole,
 }
 
+/// Set of the transactions known to a peer.
+fn new_known_transactions<H: ExHashT>() -> LruHashSet<H> {
+	LruHashSet::new(NonZeroUsize::new(MAX_KNOWN_TRANSACTIONS).expect("Constant is nonzero"))
+}
+
 impl<B, H, N, S> TransactionsHandler<B, H, N, S>
 where
 	B: BlockT + 'static,
 	H: ExHashT,
-	N: NetworkPeers + NetworkEventStream + NetworkNotification,
+	N: NetworkPeers + NetworkEventStream + NetworkNotification + NetworkRequest,
 	S: SyncEventStream + sp_consensus::SyncOracle,
 {
 	/// Turns the [`TransactionsHandler`] into a future that should run forever and not be
@@ -312,7 +430,13 @@ where
 						// `Notifications` has seemingly closed. Closing as well.
 						return
 					}
-				}
+				},
+				request = self.private_requests.select_next_some() => {
+					self.on_private_transactions_request(request);
+				},
+				(who, hashes, sent) = self.pending_private_requests.select_next_some() => {
+					self.on_private_transactions_sent(who, hashes, sent);
+				},
 			}
 		}
 	}
@@ -333,15 +457,9 @@ where
 					return
 				};
 
-				let _was_in = self.peers.insert(
-					peer,
-					Peer {
-						known_transactions: LruHashSet::new(
-							NonZeroUsize::new(MAX_KNOWN_TRANSACTIONS).expect("Constant is nonzero"),
-						),
-						role,
-					},
-				);
+				let _was_in = self
+					.peers
+					.insert(peer, Peer { known_transactions: new_known_transactions(), role });
 				debug_assert!(_was_in.is_none());
 			},
 			NotificationEvent::NotificationStreamClosed { peer } => {
@@ -394,38 +512,142 @@ where
 		}
 
 		trace!(target: "sync", "Received {} transactions from {}", transactions.len(), who);
-		if let Some(ref mut peer) = self.peers.get_mut(&who) {
-			for t in transactions {
-				if self.pending_transactions.len() > MAX_PENDING_TRANSACTIONS {
+		if !self.peers.contains_key(&who) {
+			return
+		}
+
+		for t in transactions {
+			if self.pending_transactions.len() > MAX_PENDING_TRANSACTIONS {
+				debug!(
+					target: "sync",
+					"Ignoring any further transactions that exceed `MAX_PENDING_TRANSACTIONS`({}) limit",
+					MAX_PENDING_TRANSACTIONS,
+				);
+				break
+			}
+
+			let hash = self.transaction_pool.hash_of(&t);
+			if let Some(peer) = self.peers.get_mut(&who) {
+				peer.known_transactions.insert(hash.clone());
+			}
+
+			self.import_transaction(who, hash, t, false);
+		}
+	}
+
+	/// Called when a peer sends us a private transactions request.
+	fn on_private_transactions_request(&mut self, request: IncomingRequest) {
+		let IncomingRequest { peer, payload, pending_response } = request;
+
+		let (result, reputation_changes) = if !self.trusted_peers.contains_key(&peer) {
+			debug!(target: "sub-libp2p", "Rejecting private transactions from untrusted {}", peer);
+			(Err(()), vec![rep::UNEXPECTED_PRIVATE_TRANSACTIONS])
+		} else {
+			match <Transactions<B::Extrinsic> as Decode>::decode(&mut payload.as_ref()) {
+				Ok(transactions) => {
+					self.on_private_transactions(peer, transactions);
+					(Ok(Vec::new()), Vec::new())
+				},
+				Err(e) => {
 					debug!(
-						target: "sync",
-						"Ignoring any further transactions that exceed `MAX_PENDING_TRANSACTIONS`({}) limit",
-						MAX_PENDING_TRANSACTIONS,
+						target: "sub-libp2p",
+						"Failed to decode private transactions list from {}: {}", peer, e,
 					);
-					break
-				}
+					(Err(()), vec![rep::BAD_TRANSACTION])
+				},
+			}
+		};
 
-				let hash = self.transaction_pool.hash_of(&t);
-				peer.known_transactions.insert(hash.clone());
+		let response = OutgoingResponse { result, reputation_changes, sent_feedback: None };
+		if pending_response.send(response).is_err() {
+			debug!(target: "sub-libp2p", "Failed to answer private transactions request from {}", peer);
+		}
+	}
 
-				self.network.report_peer(who, rep::ANY_TRANSACTION);
-
-				match self.pending_transactions_peers.entry(hash.clone()) {
-					Entry::Vacant(entry) => {
-						self.pending_transactions.push(PendingTransaction {
-							validation: self.transaction_pool.import(t),
-							tx_hash: hash,
-						});
-						entry.insert(vec![who]);
-					},
-					Entry::Occupied(mut entry) => {
-						entry.get_mut().push(who);
-					},
-				}
+	/// Called when peer sends us private transactions.
+	fn on_private_transactions(&mut self, who: PeerId, transactions: Transactions<B::Extrinsic>) {
+		// Accept transactions only when node is not major syncing
+		if self.sync.is_major_syncing() {
+			trace!(target: "sync", "{} Ignoring private transactions while major syncing", who);
+			return
+		}
+
+		trace!(target: "sync", "Received {} private transactions from {}", transactions.len(), who);
+		for t in transactions {
+			if self.pending_transactions.len() > MAX_PENDING_TRANSACTIONS {
+				debug!(
+					target: "sync",
+					"Ignoring any further private transactions that exceed `MAX_PENDING_TRANSACTIONS`({}) limit",
+					MAX_PENDING_TRANSACTIONS,
+				);
+				break
+			}
+
+			let hash = self.transaction_pool.hash_of(&t);
+			if let Some(known_transactions) = self.trusted_peers.get_mut(&who) {
+				known_transactions.insert(hash.clone());
 			}
+
+			self.import_transaction(who, hash, t, true);
+		}
+	}
+
+	/// Import a transaction sent by a peer into the pool, unless it's already being imported.
+	///
+	/// Transactions received `private`ly are recorded as such by the pool, so they're never
+	/// gossiped.
+	fn import_transaction(
+		&mut self,
+		who: PeerId,
+		hash: H,
+		transaction: B::Extrinsic,
+		private: bool,
+	) {
+		self.network.report_peer(who, rep::ANY_TRANSACTION);
+
+		match self.pending_transactions_peers.entry(hash.clone()) {
+			Entry::Vacant(entry) => {
+				let validation = if private {
+					self.transaction_pool.import_private(transaction)
+				} else {
+					self.transaction_pool.import(transaction)
+				};
+				self.pending_transactions.push(PendingTransaction { validation, tx_hash: hash });
+				entry.insert(vec![who]);
+			},
+			Entry::Occupied(mut entry) => {
+				entry.get_mut().push(who);
+			},
 		}
 	}
 
+	/// Called when a private transactions request to a trusted peer finished.
+	fn on_private_transactions_sent(&mut self, who: PeerId, hashes: Vec<H>, sent: bool) {
+		if sent {
+			return
+		}
+
+		debug!(
+			target: "sync",
+			"Failed to send {} private transactions to {}, retrying on the next propagation",
+			hashes.len(),
+			who,
+		);
+		if let Some(known_transactions) = self.trusted_peers.get_mut(&who) {
+			for hash in &hashes {
+				known_transactions.remove(hash);
+			}
+		}
+	}
+
+	/// Whether the given transaction is private, and so must not be gossiped.
+	fn is_private(&self, hash: &H, transaction: &B::Extrinsic) -> bool {
+		self.transaction_pool.is_private(hash) ||
+			self.private_policy
+				.as_ref()
+				.map_or(false, |policy| policy.is_private(transaction))
+	}
+
 	fn on_handle_transaction_import(&mut self, who: PeerId, import: TransactionImport) {
 		match import {
 			TransactionImport::KnownGood =>
@@ -457,6 +679,12 @@ where
 		let mut propagated_to = HashMap::<_, Vec<_>>::new();
 		let mut propagated_transactions = 0;
 
+		let (private, transactions): (Vec<_>, Vec<_>) = transactions
+			.iter()
+			.cloned()
+			.partition(|(hash, transaction)| self.is_private(hash, transaction));
+		self.send_private_transactions(&private, &mut propagated_to);
+
 		for (who, peer) in self.peers.iter_mut() {
 			// never send transactions to the light node
 			if matches!(peer.role, ObservedRole::Light) {
@@ -500,6 +728,51 @@ where
 		propagated_to
 	}
 
+	/// Send the given private transactions to the trusted peers which don't know them yet.
+	fn send_private_transactions(
+		&mut self,
+		transactions: &[(H, B::Extrinsic)],
+		propagated_to: &mut HashMap<H, Vec<String>>,
+	) {
+		let mut private_transactions = 0;
+
+		for (who, known_transactions) in self.trusted_peers.iter_mut() {
+			let (hashes, to_send): (Vec<_>, Vec<_>) = transactions
+				.iter()
+				.filter(|(hash, _)| known_transactions.insert(hash.clone()))
+				.cloned()
+				.unzip();
+
+			if to_send.is_empty() {
+				continue
+			}
+
+			private_transactions += hashes.len();
+			for hash in &hashes {
+				propagated_to.entry(hash.clone()).or_default().push(who.to_base58());
+			}
+
+			trace!(target: "sync", "Sending {} private transactions to {}", to_send.len(), who);
+			let (tx, rx) = oneshot::channel();
+			self.network.start_request(
+				*who,
+				self.private_protocol_name.clone(),
+				to_send.encode(),
+				None,
+				tx,
+				IfDisconnected::TryConnect,
+			);
+
+			let who = *who;
+			self.pending_private_requests
+				.push(Box::pin(async move { (who, hashes, matches!(rx.await, Ok(Ok(_)))) }));
+		}
+
+		if let Some(ref metrics) = self.metrics {
+			metrics.private_transactions.inc_by(private_transactions as _)
+		}
+	}
+
 	/// Call when we must propagate ready transactions to peers.
 	fn propagate_transactions(&mut self) {
 		// Accept transactions only when node is not major syncing
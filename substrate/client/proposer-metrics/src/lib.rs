@@ -45,6 +45,7 @@ impl MetricsLink {
 }
 
 /// The reason why proposing a block ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EndProposingReason {
 	NoMoreTransactions,
 	HitDeadline,
@@ -84,6 +84,8 @@ const POOL_NO_TAGS: i32 = POOL_INVALID_TX + 9;
 const POOL_INVALID_BLOCK_ID: i32 = POOL_INVALID_TX + 10;
 /// The pool is not accepting future transactions.
 const POOL_FUTURE_TX: i32 = POOL_INVALID_TX + 11;
+/// The bundle is empty or too long.
+const POOL_INVALID_BUNDLE_LENGTH: i32 = POOL_INVALID_TX + 12;
 /// Other error.
 const OTHER_ERR: i32 = BASE_ERROR + 40;
 
@@ -176,6 +178,13 @@ impl From<Error> for ErrorObjectOwned {
 					None::<()>,
 				)
 			},
+			Error::Pool(PoolError::InvalidBundleLength(length)) => {
+				ErrorObject::owned(
+					POOL_INVALID_BUNDLE_LENGTH,
+					"Invalid bundle length",
+					Some(format!("The bundle has {} extrinsics", length)),
+				)
+			},
 			Error::UnsafeRpcCalled(e) => e.into(),
 			other => ErrorObject::owned(
 				OTHER_ERR,
@@ -0,0 +1,168 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Structure of the transaction pool, as returned by the author RPC module.
+
+use sc_transaction_pool_api::{
+	InspectedFutureTransaction, InspectedTransaction, MissingTag as PoolMissingTag,
+	PoolInspection as Inspection, TransactionSource,
+};
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+
+/// Structure of the transaction pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolInspection<Hash> {
+	/// Sizes of the queues.
+	pub status: PoolStatus,
+	/// Transactions in the ready queue, in the order they would be included in a block.
+	pub ready: Vec<Transaction<Hash>>,
+	/// Transactions in the future queue.
+	pub future: Vec<FutureTransaction<Hash>>,
+	/// Hashes of the transactions currently banned from the pool.
+	pub banned: Vec<Hash>,
+}
+
+/// Sizes of the queues of the transaction pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStatus {
+	/// Number of transactions in the ready queue.
+	pub ready: usize,
+	/// Sum of bytes of ready transaction encodings.
+	pub ready_bytes: usize,
+	/// Number of transactions in the future queue.
+	pub future: usize,
+	/// Sum of bytes of future transaction encodings.
+	pub future_bytes: usize,
+}
+
+/// A transaction in the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction<Hash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// Size of the transaction's encoding.
+	pub bytes: usize,
+	/// Priority of the transaction.
+	pub priority: u64,
+	/// Block number at which the transaction becomes invalid.
+	pub valid_till: u64,
+	/// Tags required by the transaction.
+	pub requires: Vec<Bytes>,
+	/// Tags provided by the transaction.
+	pub provides: Vec<Bytes>,
+	/// Where the transaction comes from.
+	pub source: Source,
+	/// Whether the transaction is propagated to the other peers.
+	pub propagate: bool,
+	/// How long the transaction has been in the pool, in milliseconds.
+	pub in_pool_ms: u64,
+}
+
+/// A transaction in the future queue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FutureTransaction<Hash> {
+	/// The transaction.
+	#[serde(flatten)]
+	pub transaction: Transaction<Hash>,
+	/// The required tags which aren't provided yet, and block the transaction.
+	pub missing_tags: Vec<MissingTag<Hash>>,
+}
+
+/// A tag a future transaction is waiting for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingTag<Hash> {
+	/// The tag.
+	pub tag: Bytes,
+	/// The transaction of the future queue providing the tag, if any.
+	pub provider: Option<Hash>,
+}
+
+/// Where a transaction comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Source {
+	/// The transaction was part of a block which got retracted.
+	InBlock,
+	/// The transaction was submitted by the node itself.
+	Local,
+	/// The transaction was received from the network or over RPC.
+	External,
+}
+
+impl From<TransactionSource> for Source {
+	fn from(source: TransactionSource) -> Self {
+		match source {
+			TransactionSource::InBlock => Source::InBlock,
+			TransactionSource::Local => Source::Local,
+			TransactionSource::External => Source::External,
+		}
+	}
+}
+
+impl<Hash> From<InspectedTransaction<Hash>> for Transaction<Hash> {
+	fn from(tx: InspectedTransaction<Hash>) -> Self {
+		Transaction {
+			hash: tx.hash,
+			bytes: tx.bytes,
+			priority: tx.priority,
+			valid_till: tx.valid_till,
+			requires: tx.requires.into_iter().map(Into::into).collect(),
+			provides: tx.provides.into_iter().map(Into::into).collect(),
+			source: tx.source.into(),
+			propagate: tx.propagate,
+			in_pool_ms: tx.in_pool.as_millis().try_into().unwrap_or(u64::MAX),
+		}
+	}
+}
+
+impl<Hash> From<InspectedFutureTransaction<Hash>> for FutureTransaction<Hash> {
+	fn from(tx: InspectedFutureTransaction<Hash>) -> Self {
+		FutureTransaction {
+			transaction: tx.transaction.into(),
+			missing_tags: tx
+				.missing_tags
+				.into_iter()
+				.map(|PoolMissingTag { tag, provider }| MissingTag { tag: tag.into(), provider })
+				.collect(),
+		}
+	}
+}
+
+impl<Hash> From<Inspection<Hash>> for PoolInspection<Hash> {
+	fn from(inspection: Inspection<Hash>) -> Self {
+		let status = inspection.status;
+		PoolInspection {
+			status: PoolStatus {
+				ready: status.ready,
+				ready_bytes: status.ready_bytes,
+				future: status.future,
+				future_bytes: status.future_bytes,
+			},
+			ready: inspection.ready.into_iter().map(Into::into).collect(),
+			future: inspection.future.into_iter().map(Into::into).collect(),
+			banned: inspection.banned,
+		}
+	}
+}
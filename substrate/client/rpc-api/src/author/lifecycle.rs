@@ -0,0 +1,128 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Lifecycle of a transaction, as returned by the author RPC module.
+
+use sc_transaction_pool_api::{
+	LifecycleEvent as PoolLifecycleEvent, LifecycleRecord as PoolLifecycleRecord,
+	TransactionLifecycle as Lifecycle,
+};
+use serde::{Deserialize, Serialize};
+
+use super::inspect::Source;
+
+/// Recorded lifecycle of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionLifecycle<Hash, BlockHash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// The most recent events of the transaction, in the order they happened.
+	pub events: Vec<LifecycleRecord<Hash, BlockHash>>,
+}
+
+/// An event of the lifecycle of a transaction, along with when it happened.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifecycleRecord<Hash, BlockHash> {
+	/// When the event happened, in milliseconds since the UNIX epoch.
+	pub timestamp_ms: u64,
+	/// The event.
+	pub event: LifecycleEvent<Hash, BlockHash>,
+}
+
+/// An event of the lifecycle of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LifecycleEvent<Hash, BlockHash> {
+	/// Transaction was submitted to the pool, to be validated at the given block.
+	Submitted {
+		/// Where the transaction comes from.
+		source: Source,
+		/// Block the transaction is validated at.
+		at: BlockHash,
+	},
+	/// Transaction was rejected by the validation, with the given error.
+	Rejected(String),
+	/// Transaction entered the future queue.
+	Future,
+	/// Transaction entered the ready queue.
+	Ready,
+	/// Transaction has been propagated to the given peers.
+	Propagated(Vec<String>),
+	/// Transaction has been included in the proposal of the block with the given hash.
+	Proposed(BlockHash),
+	/// Transaction has been included in the imported block with the given hash.
+	InBlock(BlockHash),
+	/// The block this transaction was included in has been retracted.
+	Retracted(BlockHash),
+	/// The block this transaction was included in wasn't finalized in time.
+	FinalityTimeout(BlockHash),
+	/// The block this transaction was included in has been finalized.
+	Finalized(BlockHash),
+	/// Transaction has been replaced in the pool by the one with the given hash.
+	Usurped(Hash),
+	/// Transaction has been dropped from the pool because of the limit.
+	Dropped,
+	/// Transaction has been removed from the pool as invalid.
+	Invalid,
+}
+
+impl<Hash, BlockHash> From<PoolLifecycleEvent<Hash, BlockHash>>
+	for LifecycleEvent<Hash, BlockHash>
+{
+	fn from(event: PoolLifecycleEvent<Hash, BlockHash>) -> Self {
+		match event {
+			PoolLifecycleEvent::Submitted { source, at } =>
+				LifecycleEvent::Submitted { source: source.into(), at },
+			PoolLifecycleEvent::Rejected(error) => LifecycleEvent::Rejected(error),
+			PoolLifecycleEvent::Future => LifecycleEvent::Future,
+			PoolLifecycleEvent::Ready => LifecycleEvent::Ready,
+			PoolLifecycleEvent::Propagated(peers) => LifecycleEvent::Propagated(peers),
+			PoolLifecycleEvent::Proposed(block) => LifecycleEvent::Proposed(block),
+			PoolLifecycleEvent::InBlock(block) => LifecycleEvent::InBlock(block),
+			PoolLifecycleEvent::Retracted(block) => LifecycleEvent::Retracted(block),
+			PoolLifecycleEvent::FinalityTimeout(block) => LifecycleEvent::FinalityTimeout(block),
+			PoolLifecycleEvent::Finalized(block) => LifecycleEvent::Finalized(block),
+			PoolLifecycleEvent::Usurped(by) => LifecycleEvent::Usurped(by),
+			PoolLifecycleEvent::Dropped => LifecycleEvent::Dropped,
+			PoolLifecycleEvent::Invalid => LifecycleEvent::Invalid,
+		}
+	}
+}
+
+impl<Hash, BlockHash> From<PoolLifecycleRecord<Hash, BlockHash>>
+	for LifecycleRecord<Hash, BlockHash>
+{
+	fn from(record: PoolLifecycleRecord<Hash, BlockHash>) -> Self {
+		LifecycleRecord {
+			timestamp_ms: record.time.as_millis().try_into().unwrap_or(u64::MAX),
+			event: record.event.into(),
+		}
+	}
+}
+
+impl<Hash, BlockHash> From<Lifecycle<Hash, BlockHash>> for TransactionLifecycle<Hash, BlockHash> {
+	fn from(lifecycle: Lifecycle<Hash, BlockHash>) -> Self {
+		TransactionLifecycle {
+			hash: lifecycle.hash,
+			events: lifecycle.events.into_iter().map(Into::into).collect(),
+		}
+	}
+}
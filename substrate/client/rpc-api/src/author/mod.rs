@@ -21,6 +21,8 @@
 
 pub mod error;
 pub mod hash;
+pub mod inspect;
+pub mod lifecycle;
 
 use error::Error;
 use jsonrpsee::proc_macros::rpc;
@@ -34,6 +36,13 @@ pub trait AuthorApi<Hash, BlockHash> {
 	#[method(name = "author_submitExtrinsic")]
 	async fn submit_extrinsic(&self, extrinsic: Bytes) -> Result<Hash, Error>;
 
+	/// Submit a bundle of hex-encoded extrinsics, included in a block contiguously, in order, and
+	/// either all of them or none.
+	///
+	/// Returns the hash of the bundle. This method is only active with unsafe RPC methods exposed.
+	#[method(name = "author_submitBundle")]
+	async fn submit_bundle(&self, extrinsics: Vec<Bytes>) -> Result<Hash, Error>;
+
 	/// Insert a key into the keystore.
 	#[method(name = "author_insertKey")]
 	fn insert_key(&self, key_type: String, suri: String, public: Bytes) -> Result<(), Error>;
@@ -67,6 +76,22 @@ pub trait AuthorApi<Hash, BlockHash> {
 		bytes_or_hash: Vec<hash::ExtrinsicOrHash<Hash>>,
 	) -> Result<Vec<Hash>, Error>;
 
+	/// Returns the structure of the transaction pool: the transactions of the ready and future
+	/// queues, what the future ones are waiting for, and the banned transactions.
+	#[method(name = "author_inspectPool")]
+	fn inspect_pool(&self) -> Result<inspect::PoolInspection<Hash>, Error>;
+
+	/// Returns the recorded lifecycle of a transaction: its submission, validation, propagation,
+	/// inclusion in a proposal, in an imported block, and finalization.
+	///
+	/// Only a bounded number of the most recently active transactions is tracked, null is
+	/// returned for the others.
+	#[method(name = "author_transactionLifecycle")]
+	fn transaction_lifecycle(
+		&self,
+		hash: Hash,
+	) -> Result<Option<lifecycle::TransactionLifecycle<Hash, BlockHash>>, Error>;
+
 	/// Submit an extrinsic to watch.
 	///
 	/// See [`TransactionStatus`](sc_transaction_pool_api::TransactionStatus) for details on
@@ -77,4 +102,15 @@ pub trait AuthorApi<Hash, BlockHash> {
 		item = TransactionStatus<Hash, BlockHash>,
 	)]
 	fn watch_extrinsic(&self, bytes: Bytes);
+
+	/// Submit a bundle of extrinsics to watch.
+	///
+	/// The index of the block events is the index of the bundle's first extrinsic in the block.
+	/// This method is only active with unsafe RPC methods exposed.
+	#[subscription(
+		name = "author_submitAndWatchBundle" => "author_bundleUpdate",
+		unsubscribe = "author_unwatchBundle",
+		item = TransactionStatus<Hash, BlockHash>,
+	)]
+	fn watch_bundle(&self, extrinsics: Vec<Bytes>);
 }
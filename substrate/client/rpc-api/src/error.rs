@@ -27,4 +27,6 @@ pub mod base {
 	pub const DEV: i32 = 6000;
 	#[cfg(feature = "mixnet")]
 	pub const MIXNET: i32 = 8000;
+	pub const STATE_LEASE: i32 = 9000;
+	pub const SNAPSHOT: i32 = 10000;
 }
@@ -35,5 +35,7 @@ pub mod dev;
 #[cfg(feature = "mixnet")]
 pub mod mixnet;
 pub mod offchain;
+pub mod snapshot;
 pub mod state;
+pub mod state_lease;
 pub mod system;
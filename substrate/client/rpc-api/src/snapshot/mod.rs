@@ -0,0 +1,51 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate snapshot API.
+//!
+//! Takes snapshots of the database of a running node, to be restored with the
+//! `import-snapshot` command. All the methods are unsafe.
+
+pub mod error;
+
+use error::Error;
+use jsonrpsee::proc_macros::rpc;
+use serde::{Deserialize, Serialize};
+
+/// A snapshot written by `snapshot_export`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo<Hash, Number> {
+	/// The hash of the block the snapshot was taken at.
+	pub block_hash: Hash,
+	/// The number of the block the snapshot was taken at.
+	pub block_number: Number,
+}
+
+/// Substrate snapshot API
+#[rpc(client, server)]
+pub trait SnapshotApi<Hash, Number> {
+	/// Write a snapshot of the database at the last finalized block to the file `path` of the
+	/// node.
+	///
+	/// If `base` isn't empty, it's the paths of the chain of snapshots, a full snapshot followed
+	/// by the incremental ones, an incremental snapshot is taken against.
+	#[method(name = "snapshot_export", blocking)]
+	fn export(&self, path: String, base: Vec<String>) -> Result<SnapshotInfo<Hash, Number>, Error>;
+}
@@ -0,0 +1,54 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! State lease RPC errors.
+
+use jsonrpsee::types::error::{ErrorObject, ErrorObjectOwned};
+
+/// State lease RPC Result type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// State lease RPC errors.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// The node doesn't keep state leases.
+	#[error("State leases are not enabled on this node")]
+	Disabled,
+	/// The lease couldn't be acquired or updated.
+	#[error("State lease error: {0}")]
+	Client(Box<dyn std::error::Error + Send + Sync>),
+	/// Call to an unsafe RPC was denied.
+	#[error(transparent)]
+	UnsafeRpcCalled(#[from] crate::policy::UnsafeRpcError),
+}
+
+/// Base error code for all state lease errors.
+const BASE_ERROR: i32 = crate::error::base::STATE_LEASE;
+
+impl From<Error> for ErrorObjectOwned {
+	fn from(e: Error) -> Self {
+		let msg = e.to_string();
+
+		match e {
+			Error::Disabled => ErrorObject::owned(BASE_ERROR + 1, msg, None::<()>),
+			Error::Client(_) => ErrorObject::owned(BASE_ERROR + 2, msg, None::<()>),
+			Error::UnsafeRpcCalled(e) => e.into(),
+		}
+	}
+}
@@ -0,0 +1,68 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate state lease API.
+//!
+//! The leases pin the state of blocks across restarts of the node, until they expire or are
+//! revoked. All the methods are unsafe.
+
+pub mod error;
+
+use error::Error;
+use jsonrpsee::proc_macros::rpc;
+use serde::{Deserialize, Serialize};
+
+/// A state lease returned by the `stateLease_*` RPCs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateLeaseInfo<Hash> {
+	/// The identifier of the lease.
+	pub id: u64,
+	/// The owner the lease is accounted to.
+	pub owner: String,
+	/// The hash of the block whose state is pinned.
+	pub block_hash: Hash,
+	/// When the lease expires, in seconds since the UNIX epoch.
+	pub expires_at: u64,
+}
+
+/// Substrate state lease RPC API
+#[rpc(client, server)]
+pub trait StateLeaseApi<Hash> {
+	/// Pin the state of the block `hash` for `duration` seconds, on behalf of `owner`.
+	#[method(name = "stateLease_acquire")]
+	fn acquire(
+		&self,
+		owner: String,
+		hash: Hash,
+		duration: u64,
+	) -> Result<StateLeaseInfo<Hash>, Error>;
+
+	/// Extend the lease `id` to expire `duration` seconds from now.
+	#[method(name = "stateLease_renew")]
+	fn renew(&self, id: u64, duration: u64) -> Result<StateLeaseInfo<Hash>, Error>;
+
+	/// Release the lease `id`. Returns `false` if there was no such lease.
+	#[method(name = "stateLease_revoke")]
+	fn revoke(&self, id: u64) -> Result<bool, Error>;
+
+	/// List the leases which didn't expire.
+	#[method(name = "stateLease_list")]
+	fn list(&self) -> Result<Vec<StateLeaseInfo<Hash>>, Error>;
+}
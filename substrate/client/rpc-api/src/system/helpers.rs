@@ -91,6 +91,63 @@ pub struct SyncState<Number> {
 	pub current_block: Number,
 	/// Height of the highest block in the network.
 	pub highest_block: Number,
+	/// Download of the block history missing after warp or state sync, if in progress.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub gap_sync: Option<GapSyncState<Number>>,
+}
+
+/// The state of the download of the block history missing after warp or state sync.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GapSyncState<Number> {
+	/// Height of the first block of the history to download, the highest one, as the history is
+	/// downloaded downwards.
+	pub starting_block: Number,
+	/// Height of the lowest block of the history queued for import.
+	pub current_block: Number,
+	/// Height of the last block of the history to download, the lowest one.
+	pub target_block: Number,
+	/// Number of bytes downloaded so far.
+	pub downloaded_bytes: u64,
+}
+
+/// Reputation of a peer, along with its latest changes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerReputation {
+	/// Current reputation of the peer.
+	pub reputation: i32,
+	/// Whether the peer is banned because of its reputation.
+	pub banned: bool,
+	/// Latest changes of the reputation, oldest first.
+	pub history: Vec<ReputationChange>,
+}
+
+/// Change of the reputation of a peer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReputationChange {
+	/// Milliseconds since the UNIX epoch at which the change happened.
+	pub timestamp: u64,
+	/// Value of the change.
+	pub value: i32,
+	/// Reason of the change.
+	pub reason: String,
+	/// Reputation of the peer after the change.
+	pub reputation: i32,
+}
+
+/// Peer banned because of its reputation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BannedPeer {
+	/// Base58-encoded `PeerId` of the peer.
+	pub peer_id: String,
+	/// Current reputation of the peer.
+	pub reputation: i32,
+	/// Change which got the peer banned, if still known.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub ban: Option<ReputationChange>,
 }
 
 #[cfg(test)]
@@ -124,6 +181,24 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn should_serialize_banned_peer() {
+		assert_eq!(
+			::serde_json::to_string(&BannedPeer {
+				peer_id: "2".into(),
+				reputation: -5,
+				ban: Some(ReputationChange {
+					timestamp: 1,
+					value: -4,
+					reason: "a".into(),
+					reputation: -5,
+				}),
+			})
+			.unwrap(),
+			r#"{"peerId":"2","reputation":-5,"ban":{"timestamp":1,"value":-4,"reason":"a","reputation":-5}}"#,
+		);
+	}
+
 	#[test]
 	fn should_serialize_sync_state() {
 		assert_eq!(
@@ -131,6 +206,7 @@ mod tests {
 				starting_block: 12u32,
 				current_block: 50u32,
 				highest_block: 128u32,
+				gap_sync: None,
 			})
 			.unwrap(),
 			r#"{"startingBlock":12,"currentBlock":50,"highestBlock":128}"#,
@@ -141,9 +217,26 @@ mod tests {
 				starting_block: 12u32,
 				current_block: 50u32,
 				highest_block: 50u32,
+				gap_sync: None,
 			})
 			.unwrap(),
 			r#"{"startingBlock":12,"currentBlock":50,"highestBlock":50}"#,
 		);
+
+		assert_eq!(
+			::serde_json::to_string(&SyncState {
+				starting_block: 12u32,
+				current_block: 50u32,
+				highest_block: 50u32,
+				gap_sync: Some(GapSyncState {
+					starting_block: 10u32,
+					current_block: 5u32,
+					target_block: 1u32,
+					downloaded_bytes: 1024,
+				}),
+			})
+			.unwrap(),
+			r#"{"startingBlock":12,"currentBlock":50,"highestBlock":50,"gapSync":{"startingBlock":10,"currentBlock":5,"targetBlock":1,"downloadedBytes":1024}}"#,
+		);
 	}
 }
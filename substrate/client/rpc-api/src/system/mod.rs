@@ -24,7 +24,10 @@ pub mod helpers;
 
 use jsonrpsee::{core::JsonValue, proc_macros::rpc};
 
-pub use self::helpers::{Health, NodeRole, PeerInfo, SyncState, SystemInfo};
+pub use self::helpers::{
+	BannedPeer, GapSyncState, Health, NodeRole, PeerInfo, PeerReputation, ReputationChange,
+	SyncState, SystemInfo,
+};
 pub use error::Error;
 
 /// Substrate system RPC API
@@ -99,6 +102,29 @@ pub trait SystemApi<Hash, Number> {
 	#[method(name = "system_reservedPeers")]
 	async fn system_reserved_peers(&self) -> Result<Vec<String>, Error>;
 
+	/// Returns the reputation of a peer, along with the latest changes of it and their reasons.
+	/// The string should encode only the PeerId e.g.
+	/// `QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV`.
+	///
+	/// Returns `null` if the peer isn't known.
+	#[method(name = "system_peerReputation")]
+	async fn system_peer_reputation(
+		&self,
+		peer_id: String,
+	) -> Result<Option<PeerReputation>, Error>;
+
+	/// Returns the peers banned because of their reputation, along with the change which got
+	/// them banned.
+	#[method(name = "system_bannedPeers")]
+	async fn system_banned_peers(&self) -> Result<Vec<BannedPeer>, Error>;
+
+	/// Unbans a peer by resetting its reputation. The string should encode only the PeerId e.g.
+	/// `QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV`.
+	///
+	/// Returns `false` if the peer isn't banned.
+	#[method(name = "system_unbanPeer")]
+	async fn system_unban_peer(&self, peer_id: String) -> Result<bool, Error>;
+
 	/// Returns the roles the node is running as.
 	#[method(name = "system_nodeRoles")]
 	async fn system_node_roles(&self) -> Result<Vec<NodeRole>, Error>;
@@ -108,6 +134,13 @@ pub trait SystemApi<Hash, Number> {
 	#[method(name = "system_syncState")]
 	async fn system_sync_state(&self) -> Result<SyncState<Number>, Error>;
 
+	/// Starts downloading the block history missing after warp or state sync, if it's only
+	/// downloaded on demand.
+	///
+	/// Returns `false` if there's no block history to download.
+	#[method(name = "system_startGapSync")]
+	async fn system_start_gap_sync(&self) -> Result<bool, Error>;
+
 	/// Adds the supplied directives to the current log filter
 	///
 	/// The syntax is identical to the CLI `<target>=<level>`:
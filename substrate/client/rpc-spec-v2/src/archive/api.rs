@@ -20,7 +20,10 @@
 //! API trait of the archive methods.
 
 use crate::{
-	common::events::{ArchiveStorageResult, PaginatedStorageQuery},
+	common::events::{
+		ArchiveExtrinsicsResult, ArchiveStorageHistoryResult, ArchiveStorageResult,
+		BlockStorageDiffResult, PaginatedStorageQuery, StorageDiffResult,
+	},
 	MethodResult,
 };
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
@@ -105,4 +108,106 @@ pub trait ArchiveApi<Hash> {
 		items: Vec<PaginatedStorageQuery<String>>,
 		child_trie: Option<String>,
 	) -> RpcResult<ArchiveStorageResult>;
+
+	/// Get the extrinsics signed by an account, starting from the given height.
+	///
+	/// The signer is the hexadecimal-encoded SCALE-codec encoding of the account's address.
+	/// Returns at most `limit` extrinsics of the blocks of the best chain, unless the extrinsics of
+	/// the last block returned are more, and the height from which to resume the query.
+	///
+	/// Requires the node to index the extrinsics, starting at the blocks it imports. The result
+	/// holds the height of the first block the indexes cover.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "archive_unstable_extrinsicsBySigner", blocking)]
+	fn archive_unstable_extrinsics_by_signer(
+		&self,
+		signer: String,
+		from_height: u64,
+		limit: usize,
+	) -> RpcResult<ArchiveExtrinsicsResult>;
+
+	/// Get the extrinsics dispatching a call, starting from the given height.
+	///
+	/// The call is identified by the index of its pallet and its index within the pallet.
+	/// Returns at most `limit` extrinsics of the blocks of the best chain, unless the extrinsics of
+	/// the last block returned are more, and the height from which to resume the query.
+	///
+	/// Requires the node to index the extrinsics, starting at the blocks it imports. The result
+	/// holds the height of the first block the indexes cover.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "archive_unstable_extrinsicsByCall", blocking)]
+	fn archive_unstable_extrinsics_by_call(
+		&self,
+		pallet: u8,
+		call: u8,
+		from_height: u64,
+		limit: usize,
+	) -> RpcResult<ArchiveExtrinsicsResult>;
+
+	/// Get the storage changes made by a block.
+	///
+	/// Returns the changes of the main trie and of the child tries whose key starts with the
+	/// hexadecimal-encoded `key_prefix`, if provided. A removed entry has a null value. If the
+	/// changes of the block weren't recorded, null.
+	///
+	/// Requires the node to record the storage changes, starting at the blocks it imports.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "archive_unstable_storageDiff", blocking)]
+	fn archive_unstable_storage_diff(
+		&self,
+		hash: Hash,
+		key_prefix: Option<String>,
+	) -> RpcResult<Option<StorageDiffResult>>;
+
+	/// Get the storage changes made by the blocks of the best chain from `from_height` to
+	/// `to_height` inclusive.
+	///
+	/// Returns the blocks which changed a key starting with the hexadecimal-encoded `key_prefix`,
+	/// if provided, in ascending order, along with these changes.
+	///
+	/// Requires the node to record the storage changes, starting at the blocks it imports.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "archive_unstable_storageDiffs", blocking)]
+	fn archive_unstable_storage_diffs(
+		&self,
+		from_height: u64,
+		to_height: u64,
+		key_prefix: Option<String>,
+	) -> RpcResult<Vec<BlockStorageDiffResult>>;
+
+	/// Get the changes of the value of a storage key made by the blocks of the best chain from
+	/// `from_height` to `to_height` inclusive.
+	///
+	/// The key is hexadecimal-encoded, and looked up in the child trie whose hexadecimal-encoded
+	/// storage key is `child_trie` if provided. Returns at most `limit` changes, ordered by
+	/// height, and the height from which to resume the query. A removal has a null value.
+	///
+	/// Requires the node to index the history of the storage keys, starting at the blocks it
+	/// imports. The result holds the height of the first block the index covers, and only the
+	/// changes made from it on are returned.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "archive_unstable_storageHistory", blocking)]
+	fn archive_unstable_storage_history(
+		&self,
+		key: String,
+		child_trie: Option<String>,
+		from_height: u64,
+		to_height: u64,
+		limit: usize,
+	) -> RpcResult<ArchiveStorageHistoryResult>;
 }
@@ -21,15 +21,19 @@
 
 use crate::{
 	archive::{error::Error as ArchiveError, ArchiveApiServer},
-	common::events::{ArchiveStorageResult, PaginatedStorageQuery},
+	common::events::{
+		ArchiveExtrinsicsResult, ArchiveStorageHistoryResult, ArchiveStorageResult,
+		BlockStorageDiffResult, ChildTrieDiff, IndexedExtrinsicResult, KeyChangeResult,
+		PaginatedStorageQuery, StorageDiffItem, StorageDiffResult,
+	},
 	hex_string, MethodResult,
 };
 
 use codec::Encode;
 use jsonrpsee::core::{async_trait, RpcResult};
 use sc_client_api::{
-	Backend, BlockBackend, BlockchainEvents, CallExecutor, ChildInfo, ExecutorProvider, StorageKey,
-	StorageProvider,
+	Backend, BlockBackend, BlockchainEvents, CallExecutor, ChildInfo, ExecutorProvider,
+	ExtrinsicIndexKey, StorageDiff, StorageKey, StorageProvider,
 };
 use sp_api::{CallApiAt, CallContext};
 use sp_blockchain::{
@@ -51,6 +55,12 @@ pub struct ArchiveConfig {
 	pub max_descendant_responses: usize,
 	/// The maximum number of queried items allowed for the `archive_storage` at a time.
 	pub max_queried_items: usize,
+	/// The maximum number of extrinsics requested from the extrinsic indexes at a time.
+	pub max_indexed_extrinsics: usize,
+	/// The maximum number of blocks whose storage changes are requested at a time.
+	pub max_storage_diff_blocks: usize,
+	/// The maximum number of changes of a storage key requested at a time.
+	pub max_key_changes: usize,
 }
 
 /// The maximum number of items the `archive_storage` can return for a descendant query before
@@ -65,11 +75,23 @@ const MAX_DESCENDANT_RESPONSES: usize = 5;
 /// `MAX_DESCENDANT_RESPONSES`.
 const MAX_QUERIED_ITEMS: usize = 8;
 
+/// The maximum number of extrinsics requested from the extrinsic indexes at a time.
+const MAX_INDEXED_EXTRINSICS: usize = 256;
+
+/// The maximum number of blocks whose storage changes are requested at a time.
+const MAX_STORAGE_DIFF_BLOCKS: usize = 256;
+
+/// The maximum number of changes of a storage key requested at a time.
+const MAX_KEY_CHANGES: usize = 256;
+
 impl Default for ArchiveConfig {
 	fn default() -> Self {
 		Self {
 			max_descendant_responses: MAX_DESCENDANT_RESPONSES,
 			max_queried_items: MAX_QUERIED_ITEMS,
+			max_indexed_extrinsics: MAX_INDEXED_EXTRINSICS,
+			max_storage_diff_blocks: MAX_STORAGE_DIFF_BLOCKS,
+			max_key_changes: MAX_KEY_CHANGES,
 		}
 	}
 }
@@ -87,6 +109,12 @@ pub struct Archive<BE: Backend<Block>, Block: BlockT, Client> {
 	storage_max_descendant_responses: usize,
 	/// The maximum number of queried items allowed for the `archive_storage` at a time.
 	storage_max_queried_items: usize,
+	/// The maximum number of extrinsics requested from the extrinsic indexes at a time.
+	max_indexed_extrinsics: usize,
+	/// The maximum number of blocks whose storage changes are requested at a time.
+	max_storage_diff_blocks: usize,
+	/// The maximum number of changes of a storage key requested at a time.
+	max_key_changes: usize,
 	/// Phantom member to pin the block type.
 	_phantom: PhantomData<Block>,
 }
@@ -106,9 +134,93 @@ impl<BE: Backend<Block>, Block: BlockT, Client> Archive<BE, Block, Client> {
 			genesis_hash,
 			storage_max_descendant_responses: config.max_descendant_responses,
 			storage_max_queried_items: config.max_queried_items,
+			max_indexed_extrinsics: config.max_indexed_extrinsics,
+			max_storage_diff_blocks: config.max_storage_diff_blocks,
+			max_key_changes: config.max_key_changes,
 			_phantom: PhantomData,
 		}
 	}
+
+	/// Query the extrinsic indexes of the backend.
+	fn indexed_extrinsics(
+		&self,
+		key: ExtrinsicIndexKey,
+		from_height: u64,
+		limit: usize,
+	) -> Result<ArchiveExtrinsicsResult, ArchiveError> {
+		if limit == 0 {
+			return Err(ArchiveError::InvalidParam("The limit must be positive".into()))
+		}
+		let limit = limit.min(self.max_indexed_extrinsics);
+		let from: NumberFor<Block> = U256::from(from_height).try_into().map_err(|_| {
+			ArchiveError::InvalidParam(format!("Invalid block height: {}", from_height))
+		})?;
+
+		let extrinsics = self
+			.backend
+			.indexed_extrinsics(&key, from, limit)
+			.map_err(|error| ArchiveError::FetchIndexedExtrinsics(error.to_string()))?
+			.ok_or(ArchiveError::ExtrinsicIndexDisabled)?;
+
+		// The last block returned is complete, so the query resumes from the next one.
+		let next_height = extrinsics
+			.last()
+			.filter(|_| extrinsics.len() >= limit)
+			.map(|last| last.number.saturated_into::<u64>() + 1);
+		let result = extrinsics
+			.into_iter()
+			.map(|extrinsic| IndexedExtrinsicResult {
+				block_hash: hex_string(&extrinsic.hash.as_ref()),
+				height: extrinsic.number.saturated_into(),
+				index: extrinsic.index,
+			})
+			.collect();
+		let indexed_from =
+			self.backend.extrinsic_index_start().map(|start| start.saturated_into::<u64>());
+		Ok(ArchiveExtrinsicsResult { result, next_height, indexed_from })
+	}
+
+	/// Query the storage changes of the blocks of the best chain from `from_height` to
+	/// `to_height`.
+	fn storage_diffs(
+		&self,
+		from_height: u64,
+		to_height: u64,
+		key_prefix: Option<String>,
+	) -> Result<Vec<BlockStorageDiffResult>, ArchiveError> {
+		if to_height < from_height {
+			return Err(ArchiveError::InvalidParam(
+				"The end height precedes the start height".into(),
+			))
+		}
+		if to_height - from_height >= self.max_storage_diff_blocks as u64 {
+			return Err(ArchiveError::InvalidParam(format!(
+				"At most {} blocks can be queried at a time",
+				self.max_storage_diff_blocks,
+			)))
+		}
+		let height = |height: u64| -> Result<NumberFor<Block>, ArchiveError> {
+			U256::from(height).try_into().map_err(|_| {
+				ArchiveError::InvalidParam(format!("Invalid block height: {}", height))
+			})
+		};
+		let (from, to) = (height(from_height)?, height(to_height)?);
+		let key_prefix = key_prefix.map(parse_hex_param).transpose()?.unwrap_or_default();
+
+		let diffs = self
+			.backend
+			.storage_diffs(from, to, &key_prefix)
+			.map_err(|error| ArchiveError::FetchStorageDiffs(error.to_string()))?
+			.ok_or(ArchiveError::StorageDiffDisabled)?;
+		Ok(diffs
+			.into_iter()
+			.map(|diff| BlockStorageDiffResult {
+				block_hash: hex_string(&diff.hash.as_ref()),
+				height: diff.number.saturated_into(),
+				diff: storage_diff_result(diff.diff),
+			})
+			.collect())
+	}
 }
 
 /// Parse hex-encoded string parameter as raw bytes.
@@ -123,6 +235,30 @@ fn parse_hex_param(param: String) -> Result<Vec<u8>, ArchiveError> {
 	array_bytes::hex2bytes(&param).map_err(|_| ArchiveError::InvalidParam(param))
 }
 
+/// Convert the storage changes of a block to their RPC representation.
+fn storage_diff_result(diff: StorageDiff) -> StorageDiffResult {
+	let items = |changes: Vec<(Vec<u8>, Option<Vec<u8>>)>| {
+		changes
+			.into_iter()
+			.map(|(key, value)| StorageDiffItem {
+				key: hex_string(&key),
+				value: value.map(|value| hex_string(&value)),
+			})
+			.collect()
+	};
+	StorageDiffResult {
+		items: items(diff.main),
+		child_tries: diff
+			.children
+			.into_iter()
+			.map(|(child_trie, changes)| ChildTrieDiff {
+				child_trie: hex_string(&child_trie),
+				items: items(changes),
+			})
+			.collect(),
+	}
+}
+
 #[async_trait]
 impl<BE, Block, Client> ArchiveApiServer<Block::Hash> for Archive<BE, Block, Client>
 where
@@ -278,4 +414,89 @@ where
 		);
 		Ok(storage_client.handle_query(hash, items, child_trie))
 	}
+
+	fn archive_unstable_extrinsics_by_signer(
+		&self,
+		signer: String,
+		from_height: u64,
+		limit: usize,
+	) -> RpcResult<ArchiveExtrinsicsResult> {
+		let signer = parse_hex_param(signer)?;
+		Ok(self.indexed_extrinsics(ExtrinsicIndexKey::Signer(signer), from_height, limit)?)
+	}
+
+	fn archive_unstable_extrinsics_by_call(
+		&self,
+		pallet: u8,
+		call: u8,
+		from_height: u64,
+		limit: usize,
+	) -> RpcResult<ArchiveExtrinsicsResult> {
+		Ok(self.indexed_extrinsics(ExtrinsicIndexKey::Call { pallet, call }, from_height, limit)?)
+	}
+
+	fn archive_unstable_storage_diff(
+		&self,
+		hash: Block::Hash,
+		key_prefix: Option<String>,
+	) -> RpcResult<Option<StorageDiffResult>> {
+		let key_prefix = key_prefix.map(parse_hex_param).transpose()?.unwrap_or_default();
+		let diff = self
+			.backend
+			.storage_diff(hash, &key_prefix)
+			.map_err(|error| ArchiveError::FetchStorageDiffs(error.to_string()))?;
+		Ok(diff.map(storage_diff_result))
+	}
+
+	fn archive_unstable_storage_diffs(
+		&self,
+		from_height: u64,
+		to_height: u64,
+		key_prefix: Option<String>,
+	) -> RpcResult<Vec<BlockStorageDiffResult>> {
+		Ok(self.storage_diffs(from_height, to_height, key_prefix)?)
+	}
+
+	fn archive_unstable_storage_history(
+		&self,
+		key: String,
+		child_trie: Option<String>,
+		from_height: u64,
+		to_height: u64,
+		limit: usize,
+	) -> RpcResult<ArchiveStorageHistoryResult> {
+		if limit == 0 {
+			return Err(ArchiveError::InvalidParam("The limit must be positive".into()).into())
+		}
+		let limit = limit.min(self.max_key_changes);
+		let key = StorageKey(parse_hex_param(key)?);
+		let child_trie = child_trie
+			.map(|child_trie| parse_hex_param(child_trie))
+			.transpose()?
+			.map(ChildInfo::new_default_from_vec);
+		let from: NumberFor<Block> = from_height.saturated_into();
+		let to: NumberFor<Block> = to_height.saturated_into();
+
+		let changes = self
+			.client
+			.storage_history(child_trie.as_ref(), &key, from, to, limit)
+			.map_err(|error| ArchiveError::FetchKeyHistory(error.to_string()))?
+			.ok_or(ArchiveError::KeyHistoryDisabled)?;
+
+		let next_height = changes
+			.last()
+			.filter(|last| changes.len() >= limit && last.number < to)
+			.map(|last| last.number.saturated_into::<u64>() + 1);
+		let result = changes
+			.into_iter()
+			.map(|change| KeyChangeResult {
+				block_hash: hex_string(&change.hash.as_ref()),
+				height: change.number.saturated_into(),
+				value: change.value.map(|value| hex_string(&value.0)),
+			})
+			.collect();
+		let indexed_from =
+			self.backend.key_history_start().map(|start| start.saturated_into::<u64>());
+		Ok(ArchiveStorageHistoryResult { result, next_height, indexed_from })
+	}
 }
@@ -33,6 +33,24 @@ pub enum Error {
 	/// Failed to fetch leaves.
 	#[error("Failed to fetch leaves of the chain: {0}")]
 	FetchLeaves(String),
+	/// The extrinsics aren't indexed.
+	#[error("Extrinsics are not indexed by this node")]
+	ExtrinsicIndexDisabled,
+	/// Failed to query the extrinsic indexes.
+	#[error("Failed to query the extrinsic indexes: {0}")]
+	FetchIndexedExtrinsics(String),
+	/// The storage changes aren't recorded.
+	#[error("Storage changes are not recorded by this node")]
+	StorageDiffDisabled,
+	/// Failed to query the storage changes.
+	#[error("Failed to query the storage changes: {0}")]
+	FetchStorageDiffs(String),
+	/// The history of the storage keys isn't indexed.
+	#[error("The history of the storage keys is not indexed by this node")]
+	KeyHistoryDisabled,
+	/// Failed to query the history of a storage key.
+	#[error("Failed to query the history of the storage key: {0}")]
+	FetchKeyHistory(String),
 }
 
 // Base code for all `archive` errors.
@@ -43,6 +61,18 @@ const INVALID_PARAM_ERROR: i32 = BASE_ERROR + 1;
 const RUNTIME_CALL_ERROR: i32 = BASE_ERROR + 2;
 /// Failed to fetch leaves.
 const FETCH_LEAVES_ERROR: i32 = BASE_ERROR + 3;
+/// The extrinsics aren't indexed.
+const EXTRINSIC_INDEX_DISABLED_ERROR: i32 = BASE_ERROR + 4;
+/// Failed to query the extrinsic indexes.
+const FETCH_INDEXED_EXTRINSICS_ERROR: i32 = BASE_ERROR + 5;
+/// The storage changes aren't recorded.
+const STORAGE_DIFF_DISABLED_ERROR: i32 = BASE_ERROR + 6;
+/// Failed to query the storage changes.
+const FETCH_STORAGE_DIFFS_ERROR: i32 = BASE_ERROR + 7;
+/// The history of the storage keys isn't indexed.
+const KEY_HISTORY_DISABLED_ERROR: i32 = BASE_ERROR + 8;
+/// Failed to query the history of a storage key.
+const FETCH_KEY_HISTORY_ERROR: i32 = BASE_ERROR + 9;
 
 impl From<Error> for ErrorObject<'static> {
 	fn from(e: Error) -> Self {
@@ -52,6 +82,18 @@ impl From<Error> for ErrorObject<'static> {
 			Error::InvalidParam(_) => ErrorObject::owned(INVALID_PARAM_ERROR, msg, None::<()>),
 			Error::RuntimeCall(_) => ErrorObject::owned(RUNTIME_CALL_ERROR, msg, None::<()>),
 			Error::FetchLeaves(_) => ErrorObject::owned(FETCH_LEAVES_ERROR, msg, None::<()>),
+			Error::ExtrinsicIndexDisabled =>
+				ErrorObject::owned(EXTRINSIC_INDEX_DISABLED_ERROR, msg, None::<()>),
+			Error::FetchIndexedExtrinsics(_) =>
+				ErrorObject::owned(FETCH_INDEXED_EXTRINSICS_ERROR, msg, None::<()>),
+			Error::StorageDiffDisabled =>
+				ErrorObject::owned(STORAGE_DIFF_DISABLED_ERROR, msg, None::<()>),
+			Error::FetchStorageDiffs(_) =>
+				ErrorObject::owned(FETCH_STORAGE_DIFFS_ERROR, msg, None::<()>),
+			Error::KeyHistoryDisabled =>
+				ErrorObject::owned(KEY_HISTORY_DISABLED_ERROR, msg, None::<()>),
+			Error::FetchKeyHistory(_) =>
+				ErrorObject::owned(FETCH_KEY_HISTORY_ERROR, msg, None::<()>),
 		}
 		.into()
 	}
@@ -19,7 +19,9 @@
 
 use crate::{
 	common::events::{
-		ArchiveStorageMethodOk, ArchiveStorageResult, PaginatedStorageQuery, StorageQueryType,
+		ArchiveExtrinsicsResult, ArchiveStorageHistoryResult, ArchiveStorageMethodOk,
+		ArchiveStorageResult, BlockStorageDiffResult, IndexedExtrinsicResult, KeyChangeResult,
+		PaginatedStorageQuery, StorageDiffItem, StorageDiffResult, StorageQueryType,
 		StorageResultType,
 	},
 	hex_string, MethodResult,
@@ -37,7 +39,7 @@ use jsonrpsee::{
 	rpc_params, RpcModule,
 };
 use sc_block_builder::BlockBuilderBuilder;
-use sc_client_api::ChildInfo;
+use sc_client_api::{ChildInfo, ExtrinsicIndexKey};
 use sp_blockchain::HeaderBackend;
 use sp_consensus::BlockOrigin;
 use sp_core::{Blake2Hasher, Hasher};
@@ -80,7 +82,7 @@ fn setup_api(
 		client.clone(),
 		backend,
 		CHAIN_GENESIS,
-		ArchiveConfig { max_descendant_responses, max_queried_items },
+		ArchiveConfig { max_descendant_responses, max_queried_items, ..Default::default() },
 	)
 	.into_rpc();
 
@@ -840,3 +842,282 @@ async fn archive_storage_discarded_items() {
 		_ => panic!("Unexpected result"),
 	};
 }
+
+#[tokio::test]
+async fn archive_indexed_extrinsics() {
+	// Without an extrinsic indexer.
+	let (_client, api) = setup_api(MAX_PAGINATION_LIMIT, MAX_QUERIED_LIMIT);
+	let err = api
+		.call::<_, serde_json::Value>("archive_unstable_extrinsicsByCall", rpc_params![0, 0, 0, 10])
+		.await
+		.unwrap_err();
+	assert_matches!(err, Error::Call(err) if err.code() == 3004);
+
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	backend.set_extrinsic_indexer(Arc::new(|extrinsic: &runtime::Extrinsic| {
+		let call = extrinsic.function.encode();
+		let mut keys = vec![ExtrinsicIndexKey::Call { pallet: call[0], call: call[1] }];
+		if let Some((signer, _, _)) = &extrinsic.signature {
+			keys.push(ExtrinsicIndexKey::Signer(signer.encode()));
+		}
+		keys
+	}));
+	let mut client = Arc::new(builder.build());
+	let api =
+		Archive::new(client.clone(), backend, CHAIN_GENESIS, ArchiveConfig::default()).into_rpc();
+
+	// Block 1 includes a transfer from Alice, block 2 a transfer from Bob then one from Alice.
+	let mut parent = client.chain_info().genesis_hash;
+	let mut blocks = Vec::new();
+	for (number, transfers) in [
+		vec![(AccountKeyring::Alice, 0)],
+		vec![(AccountKeyring::Bob, 0), (AccountKeyring::Alice, 1)],
+	]
+	.into_iter()
+	.enumerate()
+	{
+		let mut builder = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(parent)
+			.with_parent_block_number(number as u64)
+			.build()
+			.unwrap();
+		for (from, nonce) in transfers {
+			builder
+				.push_transfer(runtime::Transfer {
+					from: from.into(),
+					to: AccountKeyring::Ferdie.into(),
+					amount: 42,
+					nonce,
+				})
+				.unwrap();
+		}
+		let block = builder.build().unwrap().block;
+		parent = block.header.hash();
+		client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+		blocks.push(block);
+	}
+	let extrinsic = |block: usize, height: u64, index: u32| IndexedExtrinsicResult {
+		block_hash: hex_string(&blocks[block].header.hash().as_ref()),
+		height,
+		index,
+	};
+
+	let alice = hex_string(&AccountKeyring::Alice.public().encode());
+	let result: ArchiveExtrinsicsResult = api
+		.call("archive_unstable_extrinsicsBySigner", rpc_params![&alice, 0, 10])
+		.await
+		.unwrap();
+	assert_eq!(result.result, vec![extrinsic(0, 1, 0), extrinsic(1, 2, 1)]);
+	assert_eq!(result.next_height, None);
+	assert_eq!(result.indexed_from, Some(0));
+
+	// The extrinsics of a block aren't split across pages.
+	let call = blocks[0].extrinsics[0].function.encode();
+	let result: ArchiveExtrinsicsResult = api
+		.call("archive_unstable_extrinsicsByCall", rpc_params![call[0], call[1], 0, 1])
+		.await
+		.unwrap();
+	assert_eq!(result.result, vec![extrinsic(0, 1, 0)]);
+	assert_eq!(result.next_height, Some(2));
+	let result: ArchiveExtrinsicsResult = api
+		.call("archive_unstable_extrinsicsByCall", rpc_params![call[0], call[1], 2, 1])
+		.await
+		.unwrap();
+	assert_eq!(result.result, vec![extrinsic(1, 2, 0), extrinsic(1, 2, 1)]);
+	assert_eq!(result.next_height, Some(3));
+
+	// Invalid limit.
+	let err = api
+		.call::<_, ArchiveExtrinsicsResult>(
+			"archive_unstable_extrinsicsBySigner",
+			rpc_params![&alice, 0, 0],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err, Error::Call(err) if err.code() == 3001 && err.message().contains("Invalid parameter"));
+}
+
+#[tokio::test]
+async fn archive_storage_diff() {
+	// Without the record of the storage changes.
+	let (_client, api) = setup_api(MAX_PAGINATION_LIMIT, MAX_QUERIED_LIMIT);
+	let err = api
+		.call::<_, serde_json::Value>(
+			"archive_unstable_storageDiffs",
+			rpc_params![0, 10, None::<String>],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err, Error::Call(err) if err.code() == 3006);
+
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	backend.set_storage_diffs(true);
+	let mut client = Arc::new(builder.build());
+	let genesis_hash = client.chain_info().genesis_hash;
+	let api = Archive::new(
+		client.clone(),
+		backend,
+		CHAIN_GENESIS,
+		ArchiveConfig { max_storage_diff_blocks: 4, ..Default::default() },
+	)
+	.into_rpc();
+
+	// Block 1 writes the mock key, block 2 removes it.
+	let mut parent = genesis_hash;
+	let mut blocks = Vec::new();
+	for (number, value) in [Some(VALUE.to_vec()), None].into_iter().enumerate() {
+		let mut builder = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(parent)
+			.with_parent_block_number(number as u64)
+			.build()
+			.unwrap();
+		builder.push_storage_change(KEY.to_vec(), value).unwrap();
+		let block = builder.build().unwrap().block;
+		parent = block.header.hash();
+		client.import(BlockOrigin::Own, block).await.unwrap();
+		blocks.push(hex_string(&parent.as_ref()));
+	}
+	let written = StorageDiffItem { key: hex_string(&KEY), value: Some(hex_string(&VALUE)) };
+	let removed = StorageDiffItem { key: hex_string(&KEY), value: None };
+
+	// The changes of the genesis block aren't recorded.
+	let diff: Option<StorageDiffResult> = api
+		.call(
+			"archive_unstable_storageDiff",
+			rpc_params![hex_string(&genesis_hash.as_ref()), None::<String>],
+		)
+		.await
+		.unwrap();
+	assert_eq!(diff, None);
+
+	let diff: Option<StorageDiffResult> = api
+		.call("archive_unstable_storageDiff", rpc_params![&blocks[0], None::<String>])
+		.await
+		.unwrap();
+	assert!(diff.unwrap().items.contains(&written));
+	let diff: Option<StorageDiffResult> = api
+		.call("archive_unstable_storageDiff", rpc_params![&blocks[1], hex_string(&KEY)])
+		.await
+		.unwrap();
+	assert_eq!(diff, Some(StorageDiffResult { items: vec![removed.clone()], child_tries: vec![] }));
+
+	let diffs: Vec<BlockStorageDiffResult> = api
+		.call("archive_unstable_storageDiffs", rpc_params![0, 3, hex_string(&KEY)])
+		.await
+		.unwrap();
+	assert_eq!(
+		diffs,
+		vec![
+			BlockStorageDiffResult {
+				block_hash: blocks[0].clone(),
+				height: 1,
+				diff: StorageDiffResult { items: vec![written], child_tries: vec![] },
+			},
+			BlockStorageDiffResult {
+				block_hash: blocks[1].clone(),
+				height: 2,
+				diff: StorageDiffResult { items: vec![removed], child_tries: vec![] },
+			},
+		],
+	);
+
+	// Too many blocks.
+	let err = api
+		.call::<_, Vec<BlockStorageDiffResult>>(
+			"archive_unstable_storageDiffs",
+			rpc_params![0, 4, None::<String>],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err, Error::Call(err) if err.code() == 3001 && err.message().contains("Invalid parameter"));
+}
+
+#[tokio::test]
+async fn archive_storage_history() {
+	// Without the key history index.
+	let (_client, api) = setup_api(MAX_PAGINATION_LIMIT, MAX_QUERIED_LIMIT);
+	let err = api
+		.call::<_, serde_json::Value>(
+			"archive_unstable_storageHistory",
+			rpc_params![hex_string(&KEY), None::<String>, 0, 10, 10],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err, Error::Call(err) if err.code() == 3008);
+
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	backend.set_key_history(true);
+	let mut client = Arc::new(builder.build());
+	let api =
+		Archive::new(client.clone(), backend, CHAIN_GENESIS, ArchiveConfig::default()).into_rpc();
+
+	// Block 1 writes the mock key, block 2 removes it and block 3 writes it again.
+	let mut parent = client.chain_info().genesis_hash;
+	let mut blocks = Vec::new();
+	for (number, value) in
+		[Some(VALUE.to_vec()), None, Some(CHILD_VALUE.to_vec())].into_iter().enumerate()
+	{
+		let mut builder = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(parent)
+			.with_parent_block_number(number as u64)
+			.build()
+			.unwrap();
+		builder.push_storage_change(KEY.to_vec(), value).unwrap();
+		let block = builder.build().unwrap().block;
+		parent = block.header.hash();
+		client.import(BlockOrigin::Own, block).await.unwrap();
+		blocks.push(hex_string(&parent.as_ref()));
+	}
+	let change = |block: usize, value: Option<&[u8]>| KeyChangeResult {
+		block_hash: blocks[block].clone(),
+		height: block as u64 + 1,
+		value: value.map(|value| hex_string(&value)),
+	};
+
+	let result: ArchiveStorageHistoryResult = api
+		.call(
+			"archive_unstable_storageHistory",
+			rpc_params![hex_string(&KEY), None::<String>, 0, 10, 10],
+		)
+		.await
+		.unwrap();
+	assert_eq!(
+		result.result,
+		vec![change(0, Some(VALUE)), change(1, None), change(2, Some(CHILD_VALUE))],
+	);
+	assert_eq!(result.next_height, None);
+	assert_eq!(result.indexed_from, Some(1));
+
+	let result: ArchiveStorageHistoryResult = api
+		.call(
+			"archive_unstable_storageHistory",
+			rpc_params![hex_string(&KEY), None::<String>, 2, 10, 1],
+		)
+		.await
+		.unwrap();
+	assert_eq!(result.result, vec![change(1, None)]);
+	assert_eq!(result.next_height, Some(3));
+
+	// The key isn't changed in any child trie.
+	let result: ArchiveStorageHistoryResult = api
+		.call(
+			"archive_unstable_storageHistory",
+			rpc_params![hex_string(&KEY), hex_string(&CHILD_STORAGE_KEY), 0, 10, 10],
+		)
+		.await
+		.unwrap();
+	assert!(result.result.is_empty());
+
+	// Invalid limit.
+	let err = api
+		.call::<_, ArchiveStorageHistoryResult>(
+			"archive_unstable_storageHistory",
+			rpc_params![hex_string(&KEY), None::<String>, 0, 10, 0],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err, Error::Call(err) if err.code() == 3001 && err.message().contains("Invalid parameter"));
+}
@@ -143,6 +143,107 @@ pub struct ArchiveStorageMethodErr {
 	pub error: String,
 }
 
+/// An extrinsic found through the extrinsic indexes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedExtrinsicResult {
+	/// The hex-encoded hash of the block including the extrinsic.
+	pub block_hash: String,
+	/// The height of the block including the extrinsic.
+	pub height: u64,
+	/// The position of the extrinsic within the block body.
+	pub index: u32,
+}
+
+/// The result of an extrinsic index query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveExtrinsicsResult {
+	/// The extrinsics found, ordered by height and by position within the block.
+	pub result: Vec<IndexedExtrinsicResult>,
+	/// The height from which the query should resume, if more extrinsics may be found.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(default)]
+	pub next_height: Option<u64>,
+	/// The height of the first block the indexes cover, if any. The extrinsics of older blocks
+	/// may be missing.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(default)]
+	pub indexed_from: Option<u64>,
+}
+
+/// A change of the value of a storage key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyChangeResult {
+	/// The hex-encoded hash of the block changing the value.
+	pub block_hash: String,
+	/// The height of the block changing the value.
+	pub height: u64,
+	/// The hex-encoded value set by the block, or `None` if the key was removed.
+	pub value: Option<String>,
+}
+
+/// The result of a storage history query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveStorageHistoryResult {
+	/// The changes found, ordered by height.
+	pub result: Vec<KeyChangeResult>,
+	/// The height from which the query should resume, if more changes may be found.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(default)]
+	pub next_height: Option<u64>,
+	/// The height of the first block the index covers, if any. The changes made by older blocks
+	/// aren't returned.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(default)]
+	pub indexed_from: Option<u64>,
+}
+
+/// A change of a storage entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDiffItem {
+	/// The hex-encoded key of the entry.
+	pub key: String,
+	/// The hex-encoded value written to the entry, or `None` if the entry was removed.
+	pub value: Option<String>,
+}
+
+/// The changes of the entries of a child trie.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChildTrieDiff {
+	/// The hex-encoded storage key of the child trie.
+	pub child_trie: String,
+	/// The changes of the entries of the child trie.
+	pub items: Vec<StorageDiffItem>,
+}
+
+/// The storage changes of a block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageDiffResult {
+	/// The changes of the entries of the main trie.
+	pub items: Vec<StorageDiffItem>,
+	/// The changes of the entries of the child tries.
+	pub child_tries: Vec<ChildTrieDiff>,
+}
+
+/// The storage changes of a block of a range.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStorageDiffResult {
+	/// The hex-encoded hash of the block.
+	pub block_hash: String,
+	/// The height of the block.
+	pub height: u64,
+	/// The storage changes of the block.
+	#[serde(flatten)]
+	pub diff: StorageDiffResult,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -105,6 +105,23 @@ where
 		})
 	}
 
+	async fn submit_bundle(&self, extrinsics: Vec<Bytes>) -> Result<TxHash<P>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let xts = extrinsics
+			.iter()
+			.map(|ext| Decode::decode(&mut &ext[..]))
+			.collect::<std::result::Result<Vec<_>, _>>()
+			.map_err(|err| Error::Client(Box::new(err)))?;
+		let best_block_hash = self.client.info().best_hash;
+		self.pool.submit_bundle(best_block_hash, TX_SOURCE, xts).await.map_err(|e| {
+			e.into_pool_error()
+				.map(|e| Error::Pool(e))
+				.unwrap_or_else(|e| Error::Verification(Box::new(e)))
+				.into()
+		})
+	}
+
 	fn insert_key(&self, key_type: String, suri: String, public: Bytes) -> Result<()> {
 		self.deny_unsafe.check_if_safe()?;
 
@@ -178,6 +195,19 @@ where
 			.collect())
 	}
 
+	fn inspect_pool(&self) -> Result<inspect::PoolInspection<TxHash<P>>> {
+		self.deny_unsafe.check_if_safe()?;
+		Ok(self.pool.inspect().into())
+	}
+
+	fn transaction_lifecycle(
+		&self,
+		hash: TxHash<P>,
+	) -> Result<Option<lifecycle::TransactionLifecycle<TxHash<P>, BlockHash<P>>>> {
+		self.deny_unsafe.check_if_safe()?;
+		Ok(self.pool.lifecycle(&hash).map(Into::into))
+	}
+
 	fn watch_extrinsic(&self, pending: PendingSubscriptionSink, xt: Bytes) {
 		let best_block_hash = self.client.info().best_hash;
 		let dxt = match TransactionFor::<P>::decode(&mut &xt[..]).map_err(|e| Error::from(e)) {
@@ -208,4 +238,48 @@ where
 
 		spawn_subscription_task(&self.executor, fut);
 	}
+
+	fn watch_bundle(&self, pending: PendingSubscriptionSink, xts: Vec<Bytes>) {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			spawn_subscription_task(&self.executor, pending.reject(ErrorObject::from(err)));
+			return
+		}
+
+		let best_block_hash = self.client.info().best_hash;
+		let dxts = match xts
+			.iter()
+			.map(|xt| TransactionFor::<P>::decode(&mut &xt[..]))
+			.collect::<std::result::Result<Vec<_>, _>>()
+			.map_err(|e| Error::from(e))
+		{
+			Ok(dxts) => dxts,
+			Err(e) => {
+				spawn_subscription_task(&self.executor, pending.reject(e));
+				return
+			},
+		};
+
+		let submit =
+			self.pool
+				.submit_and_watch_bundle(best_block_hash, TX_SOURCE, dxts)
+				.map_err(|e| {
+					e.into_pool_error()
+						.map(error::Error::from)
+						.unwrap_or_else(|e| error::Error::Verification(Box::new(e)))
+				});
+
+		let fut = async move {
+			let stream = match submit.await {
+				Ok(stream) => stream,
+				Err(err) => {
+					let _ = pending.reject(ErrorObject::from(err)).await;
+					return
+				},
+			};
+
+			pipe_from_stream(pending, stream).await;
+		};
+
+		spawn_subscription_task(&self.executor, fut);
+	}
 }
@@ -108,6 +108,40 @@ async fn author_submit_transaction_should_not_cause_error() {
 	);
 }
 
+#[tokio::test]
+async fn author_should_submit_bundle() {
+	let setup = TestSetup::default();
+	let api = setup.author().into_rpc();
+	let xts = vec![uxt(AccountKeyring::Alice, 0).encode(), uxt(AccountKeyring::Alice, 1).encode()];
+	let bundle_hash: H256 =
+		blake2_256(&xts.iter().map(|xt| H256::from(blake2_256(xt))).collect::<Vec<_>>().encode())
+			.into();
+	let xts = xts.into_iter().map(Bytes::from).collect::<Vec<_>>();
+
+	let response: H256 = api.call("author_submitBundle", [xts]).await.unwrap();
+
+	assert_eq!(response, bundle_hash);
+	assert_eq!(setup.pool.ready_bundles(setup.client.info().best_hash).len(), 1);
+	assert_eq!(setup.pool.status().ready, 0);
+
+	assert_matches!(
+		api.call::<_, H256>("author_submitBundle", [Vec::<Bytes>::new()]).await,
+		Err(RpcError::Call(err)) if err.message().contains("Invalid bundle length") && err.code() == 1022
+	);
+}
+
+#[tokio::test]
+async fn author_submit_bundle_should_deny_unsafe() {
+	let author = Author { deny_unsafe: DenyUnsafe::Yes, ..TestSetup::default().author() };
+	let api = author.into_rpc();
+	let xts = vec![Bytes::from(uxt(AccountKeyring::Alice, 0).encode())];
+
+	assert_matches!(
+		api.call::<_, H256>("author_submitBundle", [xts]).await,
+		Err(RpcError::Call(err)) if err.message().contains("RPC call is unsafe to be called externally")
+	);
+}
+
 #[tokio::test]
 async fn author_should_watch_extrinsic() {
 	let api = TestSetup::into_rpc();
@@ -215,6 +249,80 @@ async fn author_should_remove_extrinsics() {
 	assert_eq!(removed, vec![xt1_hash, xt2_hash, xt3_hash]);
 }
 
+#[tokio::test]
+async fn author_should_inspect_pool() {
+	let setup = TestSetup::default();
+	let api = setup.author().into_rpc();
+
+	let xt1 = to_hex(&uxt(AccountKeyring::Alice, 0).encode(), true);
+	let xt1_hash: H256 = api.call("author_submitExtrinsic", [xt1]).await.unwrap();
+	// Nonce 1 is missing, so this one waits in the future queue
+	let xt2 = to_hex(&uxt(AccountKeyring::Alice, 2).encode(), true);
+	let xt2_hash: H256 = api.call("author_submitExtrinsic", [xt2]).await.unwrap();
+	let xt3 = to_hex(&uxt(AccountKeyring::Bob, 0).encode(), true);
+	let xt3_hash: H256 = api.call("author_submitExtrinsic", [xt3]).await.unwrap();
+	let _: Vec<H256> = api
+		.call("author_removeExtrinsic", vec![vec![hash::ExtrinsicOrHash::Hash(xt3_hash)]])
+		.await
+		.unwrap();
+
+	let inspection: inspect::PoolInspection<H256> =
+		api.call("author_inspectPool", EmptyParams::new()).await.unwrap();
+
+	assert_eq!(inspection.status.ready, 1);
+	assert_eq!(inspection.status.future, 1);
+	assert_eq!(inspection.ready.len(), 1);
+	assert_eq!(inspection.ready[0].hash, xt1_hash);
+	assert_eq!(inspection.ready[0].source, inspect::Source::External);
+	assert_eq!(inspection.future.len(), 1);
+	assert_eq!(inspection.future[0].transaction.hash, xt2_hash);
+	assert_eq!(
+		inspection.future[0].transaction.requires,
+		inspection.future[0]
+			.missing_tags
+			.iter()
+			.map(|missing| missing.tag.clone())
+			.collect::<Vec<_>>()
+	);
+	assert_eq!(inspection.future[0].missing_tags[0].provider, None);
+	assert_eq!(inspection.banned, vec![xt3_hash]);
+}
+
+#[tokio::test]
+async fn author_should_return_transaction_lifecycle() {
+	let setup = TestSetup::default();
+	let api = setup.author().into_rpc();
+	let genesis_hash = setup.client.info().genesis_hash;
+
+	let xt1 = to_hex(&uxt(AccountKeyring::Alice, 0).encode(), true);
+	let xt1_hash: H256 = api.call("author_submitExtrinsic", [xt1]).await.unwrap();
+	// Nonce 1 is missing, so this one waits in the future queue
+	let xt2 = to_hex(&uxt(AccountKeyring::Alice, 2).encode(), true);
+	let xt2_hash: H256 = api.call("author_submitExtrinsic", [xt2]).await.unwrap();
+
+	let events = |lifecycle: Option<lifecycle::TransactionLifecycle<H256, H256>>| {
+		lifecycle
+			.unwrap()
+			.events
+			.into_iter()
+			.map(|record| record.event)
+			.collect::<Vec<_>>()
+	};
+	let submitted = lifecycle::LifecycleEvent::Submitted {
+		source: inspect::Source::External,
+		at: genesis_hash,
+	};
+
+	let xt1_lifecycle = api.call("author_transactionLifecycle", [xt1_hash]).await.unwrap();
+	assert_eq!(events(xt1_lifecycle), vec![submitted.clone(), lifecycle::LifecycleEvent::Ready]);
+	let xt2_lifecycle = api.call("author_transactionLifecycle", [xt2_hash]).await.unwrap();
+	assert_eq!(events(xt2_lifecycle), vec![submitted, lifecycle::LifecycleEvent::Future]);
+
+	let unknown_lifecycle: Option<lifecycle::TransactionLifecycle<H256, H256>> =
+		api.call("author_transactionLifecycle", [H256::repeat_byte(1)]).await.unwrap();
+	assert!(unknown_lifecycle.is_none());
+}
+
 #[tokio::test]
 async fn author_should_insert_key() {
 	let setup = TestSetup::default();
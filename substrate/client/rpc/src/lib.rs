@@ -38,7 +38,9 @@ pub mod dev;
 #[cfg(feature = "mixnet")]
 pub mod mixnet;
 pub mod offchain;
+pub mod snapshot;
 pub mod state;
+pub mod state_lease;
 pub mod system;
 pub mod utils;
 
@@ -0,0 +1,79 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the [`SnapshotApiServer`] trait, exporting snapshots of the backend.
+
+#[cfg(test)]
+mod tests;
+
+use sc_client_api::backend::Backend;
+use sc_rpc_api::{snapshot::error::Error, DenyUnsafe};
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+use std::{
+	fs,
+	io::{self, Read},
+	marker::PhantomData,
+	sync::Arc,
+};
+
+pub use sc_rpc_api::snapshot::{SnapshotApiServer, SnapshotInfo};
+
+/// The snapshot API. All methods are unsafe.
+pub struct Snapshots<Block: BlockT, BE> {
+	backend: Arc<BE>,
+	deny_unsafe: DenyUnsafe,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block: BlockT, BE> Snapshots<Block, BE> {
+	/// Create a new snapshot API.
+	pub fn new(backend: Arc<BE>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { backend, deny_unsafe, _phantom: PhantomData }
+	}
+}
+
+impl<Block, BE> SnapshotApiServer<Block::Hash, NumberFor<Block>> for Snapshots<Block, BE>
+where
+	Block: BlockT + 'static,
+	BE: Backend<Block> + 'static,
+{
+	fn export(
+		&self,
+		path: String,
+		base: Vec<String>,
+	) -> Result<SnapshotInfo<Block::Hash, NumberFor<Block>>, Error> {
+		self.deny_unsafe.check_if_safe()?;
+		let snapshots = self.backend.snapshots().ok_or(Error::Unsupported)?;
+
+		let base = base
+			.iter()
+			.map(|path| Ok(Box::new(fs::File::open(path)?) as Box<dyn Read + Send>))
+			.collect::<io::Result<Vec<_>>>()?;
+		let mut output = io::BufWriter::new(fs::File::create(&path)?);
+		match snapshots.export_snapshot(base, &mut output) {
+			Ok((block_hash, block_number)) => Ok(SnapshotInfo { block_hash, block_number }),
+			Err(err) => {
+				// Don't leave an incomplete snapshot behind.
+				drop(output);
+				let _ = fs::remove_file(&path);
+				Err(Error::Client(Box::new(err)))
+			},
+		}
+	}
+}
@@ -0,0 +1,68 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use assert_matches::assert_matches;
+use sp_blockchain::HeaderBackend;
+use substrate_test_runtime_client::{prelude::*, runtime::Block, sc_client_db::SnapshotChain};
+
+#[test]
+fn snapshot_export_works() {
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	let client = builder.build();
+	let api = <Snapshots<Block, _>>::new(backend, DenyUnsafe::No);
+
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("full.snapshot");
+	let info = api.export(path.display().to_string(), vec![]).unwrap();
+	assert_eq!(info.block_hash, client.info().genesis_hash);
+	assert_eq!(info.block_number, 0);
+
+	let chain = SnapshotChain::<Block, _>::open([fs::File::open(&path).unwrap()], |_, _, _| Ok(()))
+		.unwrap();
+	assert_eq!(chain.header().hash, info.block_hash);
+
+	// A failed export doesn't leave a file behind.
+	let base = dir.path().join("invalid.snapshot");
+	fs::write(&base, b"invalid").unwrap();
+	let path = dir.path().join("incremental.snapshot");
+	assert_matches!(
+		api.export(path.display().to_string(), vec![base.display().to_string()]),
+		Err(Error::Client(_))
+	);
+	assert!(!path.exists());
+	assert_matches!(
+		api.export(path.display().to_string(), vec!["missing".into()]),
+		Err(Error::Io(_))
+	);
+}
+
+#[test]
+fn snapshot_calls_considered_unsafe() {
+	let backend = TestClientBuilder::new().backend();
+	let api = <Snapshots<Block, _>>::new(backend, DenyUnsafe::Yes);
+
+	assert_matches!(
+		api.export("snapshot".into(), vec![]),
+		Err(Error::UnsafeRpcCalled(e)) => {
+			assert_eq!(e.to_string(), "RPC call is unsafe to be called externally")
+		}
+	);
+}
@@ -0,0 +1,104 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the [`StateLeaseApiServer`] trait, managing the state leases of the backend.
+
+#[cfg(test)]
+mod tests;
+
+use sc_client_api::backend::{Backend, StateLease};
+use sc_rpc_api::{state_lease::error::Error, DenyUnsafe};
+use sp_runtime::traits::Block as BlockT;
+use std::{marker::PhantomData, sync::Arc, time::Duration};
+
+pub use sc_rpc_api::state_lease::{StateLeaseApiServer, StateLeaseInfo};
+
+/// The state lease API. All methods are unsafe.
+pub struct StateLeases<Block: BlockT, BE> {
+	backend: Arc<BE>,
+	deny_unsafe: DenyUnsafe,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block: BlockT, BE> StateLeases<Block, BE> {
+	/// Create a new state lease API.
+	pub fn new(backend: Arc<BE>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { backend, deny_unsafe, _phantom: PhantomData }
+	}
+}
+
+fn lease_info<Hash>(lease: StateLease<Hash>) -> StateLeaseInfo<Hash> {
+	StateLeaseInfo {
+		id: lease.id,
+		owner: lease.owner,
+		block_hash: lease.hash,
+		expires_at: lease.expires_at,
+	}
+}
+
+fn client_err(err: sp_blockchain::Error) -> Error {
+	Error::Client(Box::new(err))
+}
+
+impl<Block, BE> StateLeases<Block, BE>
+where
+	Block: BlockT,
+	BE: Backend<Block>,
+{
+	fn leases(&self) -> Result<&dyn sc_client_api::backend::StateLeases<Block>, Error> {
+		self.deny_unsafe.check_if_safe()?;
+		self.backend.state_leases().ok_or(Error::Disabled)
+	}
+}
+
+impl<Block, BE> StateLeaseApiServer<Block::Hash> for StateLeases<Block, BE>
+where
+	Block: BlockT + 'static,
+	BE: Backend<Block> + 'static,
+{
+	fn acquire(
+		&self,
+		owner: String,
+		hash: Block::Hash,
+		duration: u64,
+	) -> Result<StateLeaseInfo<Block::Hash>, Error> {
+		self.leases()?
+			.acquire_lease(&owner, hash, Duration::from_secs(duration))
+			.map(lease_info)
+			.map_err(client_err)
+	}
+
+	fn renew(&self, id: u64, duration: u64) -> Result<StateLeaseInfo<Block::Hash>, Error> {
+		self.leases()?
+			.renew_lease(id, Duration::from_secs(duration))
+			.map(lease_info)
+			.map_err(client_err)
+	}
+
+	fn revoke(&self, id: u64) -> Result<bool, Error> {
+		self.leases()?.revoke_lease(id).map_err(client_err)
+	}
+
+	fn list(&self) -> Result<Vec<StateLeaseInfo<Block::Hash>>, Error> {
+		self.leases()?
+			.leases()
+			.map(|leases| leases.into_iter().map(lease_info).collect())
+			.map_err(client_err)
+	}
+}
@@ -0,0 +1,79 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use assert_matches::assert_matches;
+use sc_block_builder::BlockBuilderBuilder;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use substrate_test_runtime_client::{prelude::*, runtime::Block, sc_client_db::StateLeaseSettings};
+
+#[tokio::test]
+async fn state_leases_work() {
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	let mut client = Arc::new(builder.build());
+	let api = <StateLeases<Block, _>>::new(backend.clone(), DenyUnsafe::No);
+
+	assert_matches!(api.list(), Err(Error::Disabled));
+	backend.enable_state_leases(StateLeaseSettings::default(), None).unwrap();
+
+	let genesis_hash = client.info().genesis_hash;
+	let lease = api.acquire("signer".into(), genesis_hash, 60).unwrap();
+	assert_eq!(lease.owner, "signer");
+	assert_eq!(lease.block_hash, genesis_hash);
+
+	let block = BlockBuilderBuilder::new(&*client)
+		.on_parent_block(genesis_hash)
+		.with_parent_block_number(0)
+		.build()
+		.unwrap()
+		.build()
+		.unwrap()
+		.block;
+	client.import(BlockOrigin::Own, block).await.unwrap();
+
+	let renewed = api.renew(lease.id, 120).unwrap();
+	assert!(renewed.expires_at >= lease.expires_at);
+	assert_eq!(api.list().unwrap(), vec![renewed]);
+
+	// The lease can't be longer than a day.
+	assert_matches!(api.renew(lease.id, 2 * 24 * 60 * 60), Err(Error::Client(_)));
+	assert_matches!(api.acquire("signer".into(), Default::default(), 60), Err(Error::Client(_)));
+
+	assert_matches!(api.revoke(lease.id), Ok(true));
+	assert_matches!(api.revoke(lease.id), Ok(false));
+	assert_eq!(api.list().unwrap(), vec![]);
+}
+
+#[test]
+fn state_lease_calls_considered_unsafe() {
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	backend.enable_state_leases(StateLeaseSettings::default(), None).unwrap();
+	let api = <StateLeases<Block, _>>::new(backend, DenyUnsafe::Yes);
+
+	assert_matches!(
+		api.list(),
+		Err(Error::UnsafeRpcCalled(e)) => {
+			assert_eq!(e.to_string(), "RPC call is unsafe to be called externally")
+		}
+	);
+	assert_matches!(api.revoke(0), Err(Error::UnsafeRpcCalled(_)));
+}
@@ -58,10 +58,18 @@ pub enum Request<B: traits::Block> {
 	NetworkRemoveReservedPeer(String, oneshot::Sender<error::Result<()>>),
 	/// Must return the list of reserved peers
 	NetworkReservedPeers(oneshot::Sender<Vec<String>>),
+	/// Must return the reputation of the peer, or any potential parse error.
+	PeerReputation(String, oneshot::Sender<error::Result<Option<PeerReputation>>>),
+	/// Must return the peers banned because of their reputation.
+	BannedPeers(oneshot::Sender<Vec<BannedPeer>>),
+	/// Must return whether the peer was banned, or any potential parse error.
+	UnbanPeer(String, oneshot::Sender<error::Result<bool>>),
 	/// Must return the node role.
 	NodeRoles(oneshot::Sender<Vec<NodeRole>>),
 	/// Must return the state of the node syncing.
 	SyncState(oneshot::Sender<SyncState<<B::Header as HeaderT>::Number>>),
+	/// Must start the download of the block history and return whether there's one to download.
+	StartGapSync(oneshot::Sender<bool>),
 }
 
 impl<B: traits::Block> System<B> {
@@ -162,6 +170,36 @@ impl<B: traits::Block> SystemApiServer<B::Hash, <B::Header as HeaderT>::Number>
 		rx.await.map_err(|e| Error::Internal(e.to_string()))
 	}
 
+	async fn system_peer_reputation(
+		&self,
+		peer_id: String,
+	) -> Result<Option<PeerReputation>, Error> {
+		self.deny_unsafe.check_if_safe()?;
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::PeerReputation(peer_id, tx));
+		match rx.await {
+			Ok(result) => result,
+			Err(e) => Err(Error::Internal(e.to_string())),
+		}
+	}
+
+	async fn system_banned_peers(&self) -> Result<Vec<BannedPeer>, Error> {
+		self.deny_unsafe.check_if_safe()?;
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::BannedPeers(tx));
+		rx.await.map_err(|e| Error::Internal(e.to_string()))
+	}
+
+	async fn system_unban_peer(&self, peer_id: String) -> Result<bool, Error> {
+		self.deny_unsafe.check_if_safe()?;
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::UnbanPeer(peer_id, tx));
+		match rx.await {
+			Ok(result) => result,
+			Err(e) => Err(Error::Internal(e.to_string())),
+		}
+	}
+
 	async fn system_node_roles(&self) -> Result<Vec<NodeRole>, Error> {
 		let (tx, rx) = oneshot::channel();
 		let _ = self.send_back.unbounded_send(Request::NodeRoles(tx));
@@ -174,6 +212,13 @@ impl<B: traits::Block> SystemApiServer<B::Hash, <B::Header as HeaderT>::Number>
 		rx.await.map_err(|e| Error::Internal(e.to_string()))
 	}
 
+	async fn system_start_gap_sync(&self) -> Result<bool, Error> {
+		self.deny_unsafe.check_if_safe()?;
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::StartGapSync(tx));
+		rx.await.map_err(|e| Error::Internal(e.to_string()))
+	}
+
 	fn system_add_log_filter(&self, directives: String) -> Result<(), Error> {
 		self.deny_unsafe.check_if_safe()?;
 
@@ -116,6 +116,36 @@ fn api<T: Into<Option<Status>>>(sync: T) -> RpcModule<System<Block>> {
 					let _ = sender
 						.send(vec!["QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV".to_string()]);
 				},
+				Request::PeerReputation(peer, sender) => {
+					let _ = match peer.parse::<PeerId>() {
+						Ok(_) => sender.send(Ok(Some(PeerReputation {
+							reputation: -10,
+							banned: false,
+							history: vec![ReputationChange {
+								timestamp: 1,
+								value: -10,
+								reason: "Bad".into(),
+								reputation: -10,
+							}],
+						}))),
+						Err(s) =>
+							sender.send(Err(error::Error::MalformattedPeerArg(s.to_string()))),
+					};
+				},
+				Request::BannedPeers(sender) => {
+					let _ = sender.send(vec![BannedPeer {
+						peer_id: "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV".to_string(),
+						reputation: i32::MIN,
+						ban: None,
+					}]);
+				},
+				Request::UnbanPeer(peer, sender) => {
+					let _ = match peer.parse::<PeerId>() {
+						Ok(_) => sender.send(Ok(true)),
+						Err(s) =>
+							sender.send(Err(error::Error::MalformattedPeerArg(s.to_string()))),
+					};
+				},
 				Request::NodeRoles(sender) => {
 					let _ = sender.send(vec![NodeRole::Authority]);
 				},
@@ -124,8 +154,12 @@ fn api<T: Into<Option<Status>>>(sync: T) -> RpcModule<System<Block>> {
 						starting_block: 1,
 						current_block: 2,
 						highest_block: 3,
+						gap_sync: None,
 					});
 				},
+				Request::StartGapSync(sender) => {
+					let _ = sender.send(true);
+				},
 			};
 
 			future::ready(())
@@ -297,7 +331,16 @@ async fn system_node_roles() {
 async fn system_sync_state() {
 	let sync_state: SyncState<i32> =
 		api(None).call("system_syncState", EmptyParams::new()).await.unwrap();
-	assert_eq!(sync_state, SyncState { starting_block: 1, current_block: 2, highest_block: 3 });
+	assert_eq!(
+		sync_state,
+		SyncState { starting_block: 1, current_block: 2, highest_block: 3, gap_sync: None }
+	);
+}
+
+#[tokio::test]
+async fn system_start_gap_sync() {
+	let started: bool = api(None).call("system_startGapSync", EmptyParams::new()).await.unwrap();
+	assert!(started);
 }
 
 #[tokio::test]
@@ -338,6 +381,55 @@ async fn system_network_reserved_peers() {
 	assert_eq!(reserved_peers, vec!["QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV".to_string()],);
 }
 
+#[tokio::test]
+async fn system_peer_reputation() {
+	let reputation: Option<PeerReputation> = api(None)
+		.call("system_peerReputation", ["QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV"])
+		.await
+		.unwrap();
+	assert_eq!(
+		reputation,
+		Some(PeerReputation {
+			reputation: -10,
+			banned: false,
+			history: vec![ReputationChange {
+				timestamp: 1,
+				value: -10,
+				reason: "Bad".into(),
+				reputation: -10,
+			}],
+		}),
+	);
+
+	assert_matches!(
+		api(None).call::<_, Option<PeerReputation>>("system_peerReputation", ["/ip4/198.51.100.19"]).await,
+		Err(RpcError::Call(err)) if err.message().contains("base-58 decode error")
+	);
+}
+
+#[tokio::test]
+async fn system_banned_peers() {
+	let banned: Vec<BannedPeer> =
+		api(None).call("system_bannedPeers", EmptyParams::new()).await.unwrap();
+	assert_eq!(
+		banned,
+		vec![BannedPeer {
+			peer_id: "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV".to_string(),
+			reputation: i32::MIN,
+			ban: None,
+		}],
+	);
+}
+
+#[tokio::test]
+async fn system_unban_peer() {
+	let unbanned: bool = api(None)
+		.call("system_unbanPeer", ["QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV"])
+		.await
+		.unwrap();
+	assert!(unbanned);
+}
+
 #[test]
 fn test_add_reset_log_filter() {
 	const EXPECTED_BEFORE_ADD: &'static str = "EXPECTED_BEFORE_ADD";
@@ -32,10 +32,10 @@ use log::info;
 use prometheus_endpoint::Registry;
 use sc_chain_spec::get_extension;
 use sc_client_api::{
-	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, BadBlocks,
+	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, AuxStore, BadBlocks,
 	BlockBackend, BlockchainEvents, ExecutorProvider, ForkBlocks, StorageProvider, UsageProvider,
 };
-use sc_client_db::{Backend, DatabaseSettings};
+use sc_client_db::{Backend, BlockRetention, BlocksPruning, DatabaseSettings, ExtrinsicIndexFn};
 use sc_consensus::import_queue::ImportQueue;
 use sc_executor::{
 	sp_wasm_interface::HostFunctions, HeapAllocStrategy, RuntimeVersionOf, WasmExecutor,
@@ -53,8 +53,10 @@ use sc_network_sync::{
 	block_relay_protocol::BlockRelayParams, block_request_handler::BlockRequestHandler,
 	engine::SyncingEngine, service::network::NetworkServiceProvider,
 	state_request_handler::StateRequestHandler,
-	warp_request_handler::RequestHandler as WarpSyncRequestHandler, SyncingService, WarpSyncParams,
+	warp_request_handler::RequestHandler as WarpSyncRequestHandler, SyncingService,
+	WarpSyncCheckpoint, WarpSyncParams,
 };
+use sc_network_transactions::config::PrivateTransactionsConfig;
 use sc_rpc::{
 	author::AuthorApiServer,
 	chain::ChainApiServer,
@@ -143,6 +145,11 @@ where
 	TExec: CodeExecutor + RuntimeVersionOf + Clone,
 {
 	let backend = new_db_backend(config.db_config())?;
+	backend.set_storage_diffs(config.storage_diffs);
+	backend.set_key_history(config.key_history);
+	if let Some(settings) = &config.state_leases {
+		backend.enable_state_leases(settings.clone(), config.prometheus_registry())?;
+	}
 
 	let genesis_block_builder = GenesisBlockBuilder::new(
 		config.chain_spec.as_storage_builder(),
@@ -258,6 +265,40 @@ where
 	Ok((client, backend, keystore_container, task_manager))
 }
 
+/// Set the hooks of `backend` deciding which finalized blocks are pruned, see
+/// [`sc_client_db::Backend::set_block_retention`].
+///
+/// Returns an error if the blocks pruning of `config` requires a hook which isn't provided, as it
+/// wouldn't prune any block without it.
+pub fn set_block_retention<TBl: BlockT>(
+	config: &Configuration,
+	backend: &TFullBackend<TBl>,
+	retention: BlockRetention<TBl>,
+) -> Result<(), Error> {
+	if matches!(config.blocks_pruning, BlocksPruning::MaxAge(_)) && retention.timestamp.is_none() {
+		return Err(Error::Other(
+			"Pruning blocks by age isn't supported by this node, as it doesn't provide the \
+			 timestamp of the blocks."
+				.into(),
+		))
+	}
+
+	backend.set_block_retention(retention);
+	Ok(())
+}
+
+/// Index the extrinsics of the imported blocks with `indexer` if enabled by `config`, see
+/// [`sc_client_db::Backend::set_extrinsic_indexer`].
+pub fn set_extrinsic_indexer<TBl: BlockT>(
+	config: &Configuration,
+	backend: &TFullBackend<TBl>,
+	indexer: ExtrinsicIndexFn<TBl>,
+) {
+	if config.extrinsic_index {
+		backend.set_extrinsic_indexer(indexer);
+	}
+}
+
 /// Creates a [`WasmExecutor`] according to [`Configuration`].
 pub fn new_wasm_executor<H: HostFunctions>(config: &Configuration) -> WasmExecutor<H> {
 	let strategy = config
@@ -735,6 +776,10 @@ pub struct BuildNetworkParams<'a, TBl: BlockT, TExPool, TImpQu, TCl> {
 	/// User specified block relay params. If not specified, the default
 	/// block request handler will be used.
 	pub block_relay: Option<BlockRelayParams<TBl>>,
+	/// Optional private transactions params. If specified, the private transactions protocol is
+	/// enabled, to send the transactions deemed private to the trusted peers only, and to receive
+	/// the private transactions of other nodes.
+	pub private_transactions: Option<PrivateTransactionsConfig<TBl>>,
 }
 
 /// Build the network service, the network status sinks and an RPC sender.
@@ -760,6 +805,7 @@ where
 		+ ProofProvider<TBl>
 		+ HeaderBackend<TBl>
 		+ BlockchainEvents<TBl>
+		+ AuxStore
 		+ 'static,
 	TExPool: TransactionPool<Block = TBl, Hash = <TBl as BlockT>::Hash> + 'static,
 	TImpQu: ImportQueue<TBl> + 'static,
@@ -774,10 +820,38 @@ where
 		block_announce_validator_builder,
 		warp_sync_params,
 		block_relay,
+		private_transactions,
 	} = params;
 
-	if warp_sync_params.is_none() && config.network.sync_mode.is_warp() {
-		return Err("Warp sync enabled, but no warp sync provider configured.".into())
+	// Without a policy from the service, the trusted peers of the configuration receive all the
+	// transactions of the pool privately.
+	let private_transactions = private_transactions.or_else(|| {
+		let trusted_peers = config.network.private_transactions_peers.clone();
+		(!trusted_peers.is_empty()).then(|| PrivateTransactionsConfig {
+			policy: Box::new(|_: &TBl::Extrinsic| true),
+			trusted_peers,
+		})
+	});
+
+	// A trusted checkpoint is warp synced to instead of the block proved by warp proofs. The one
+	// of the chain spec is only used for chains without a warp sync provider, while the one given
+	// on the CLI is an explicit opt-in.
+	let chain_spec_checkpoint =
+		config.chain_spec.warp_sync_checkpoint().filter(|_| warp_sync_params.is_none());
+	let warp_sync_checkpoint =
+		match config.network.warp_sync_checkpoint.as_ref().or(chain_spec_checkpoint) {
+			Some(checkpoint) => Some(
+				WarpSyncCheckpoint::<TBl>::decode(checkpoint)
+					.map_err(|e| format!("Invalid warp sync checkpoint: {e}"))?,
+			),
+			None => None,
+		};
+
+	if warp_sync_params.is_none() &&
+		warp_sync_checkpoint.is_none() &&
+		config.network.sync_mode.is_warp()
+	{
+		return Err("Warp sync enabled, but no warp sync provider or checkpoint configured.".into())
 	}
 
 	if client.requires_full_sync() {
@@ -877,16 +951,21 @@ where
 	}
 
 	// create transactions protocol and add it to the list of supported protocols of
-	let (transactions_handler_proto, transactions_config) =
+	let (mut transactions_handler_proto, transactions_config) =
 		sc_network_transactions::TransactionsHandlerPrototype::new(
 			protocol_id.clone(),
 			genesis_hash,
 			config.chain_spec.fork_id(),
 		);
 	net_config.add_notification_protocol(transactions_config);
+	if private_transactions.is_some() {
+		net_config.add_request_response_protocol(
+			transactions_handler_proto.enable_private_transactions(),
+		);
+	}
 
 	// Create `PeerStore` and initialize it with bootnode peer ids.
-	let peer_store = PeerStore::new(
+	let mut peer_store = PeerStore::new(
 		net_config
 			.network_config
 			.boot_nodes
@@ -894,9 +973,15 @@ where
 			.map(|bootnode| bootnode.peer_id)
 			.collect(),
 	);
+	// Persist the peer reputations alongside the network key, so they survive restarts.
+	if let Some(net_config_path) = &net_config.network_config.net_config_path {
+		peer_store = peer_store.with_persistence(net_config_path.join("peer_reputations.json"));
+	}
 	let peer_store_handle = peer_store.handle();
 	spawn_handle.spawn("peer-store", Some("networking"), peer_store.run());
 
+	let warp_sync_params =
+		warp_sync_checkpoint.map(WarpSyncParams::WithCheckpoint).or(warp_sync_params);
 	let (engine, sync_service, block_announce_config) = SyncingEngine::new(
 		Roles::from(&config.role),
 		client.clone(),
@@ -943,6 +1028,7 @@ where
 		sync_service.clone(),
 		Arc::new(TransactionPoolAdapter { pool: transaction_pool, client: client.clone() }),
 		config.prometheus_config.as_ref().map(|config| &config.registry),
+		private_transactions,
 	)?;
 	spawn_handle.spawn("network-transactions-handler", Some("networking"), tx_handler.run());
 
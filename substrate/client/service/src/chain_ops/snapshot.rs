@@ -0,0 +1,69 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::error::Error;
+use log::info;
+use sc_chain_spec::resolve_state_version_from_wasm;
+use sc_client_db::{Backend, SnapshotChain};
+use sc_executor::RuntimeVersionOf;
+use sp_runtime::traits::Block as BlockT;
+use std::{
+	io::{Read, Seek, Write},
+	sync::Arc,
+};
+
+/// Exports a snapshot of the database at the last finalized block.
+///
+/// If `bases` isn't empty, it's the chain of snapshots, a full snapshot followed by the incremental
+/// ones, the exported snapshot is based on. Only the changes since the last of them are exported.
+pub fn export_snapshot<B, R>(
+	backend: Arc<Backend<B>>,
+	mut output: impl Write,
+	bases: Vec<R>,
+) -> Result<(), Error>
+where
+	B: BlockT,
+	R: Read,
+{
+	let base =
+		if bases.is_empty() { None } else { Some(SnapshotChain::open(bases, |_, _, _| Ok(()))?) };
+	let header = backend.export_snapshot(base, &mut output)?;
+	info!("Exported snapshot at #{} ({})", header.number, header.hash);
+	Ok(())
+}
+
+/// Restores a chain of snapshots into the empty database of `backend`.
+///
+/// The state root of the restored state is verified before anything is written.
+pub fn import_snapshot<B, R, E>(
+	backend: Arc<Backend<B>>,
+	executor: &E,
+	mut inputs: Vec<R>,
+) -> Result<(), Error>
+where
+	B: BlockT,
+	R: Read + Seek,
+	E: RuntimeVersionOf,
+{
+	let header = backend.import_snapshot(&mut inputs, |storage| {
+		resolve_state_version_from_wasm(storage, executor)
+	})?;
+	info!("Restored snapshot at #{} ({})", header.number, header.hash);
+	Ok(())
+}
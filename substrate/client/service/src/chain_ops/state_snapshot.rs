@@ -0,0 +1,347 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Verifiable snapshots of the state of a finalized block.
+//!
+//! A state snapshot is keyed by the hash of its block and carries the header of the block,
+//! followed by the state as chunks of compact range proofs against the state root of the header,
+//! as served to state sync. The header is proven final by the warp sync proofs from genesis it
+//! comes with. Importing a snapshot verifies the proofs, checks every chunk against the proven
+//! header, and imports the block with its state as the target of a warp sync would be.
+
+use crate::error::Error;
+use codec::{Decode, Encode, IoReader as CodecIoReader};
+use futures::{future, prelude::*};
+use log::info;
+use sc_client_api::{BlockBackend, CompactProof, HeaderBackend, ProofProvider};
+use sc_consensus::{
+	import_queue::{BlockImportError, BlockImportStatus, ImportQueue, IncomingBlock, Link},
+	ImportedState,
+};
+use sc_network_sync::strategy::warp::{EncodedProof, VerificationResult, WarpSyncProvider};
+use smallvec::SmallVec;
+use sp_consensus::BlockOrigin;
+use sp_core::storage::well_known_keys;
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT, NumberFor},
+	Justifications,
+};
+use std::{
+	collections::HashMap,
+	io::{BufReader, Read, Write},
+	pin::Pin,
+	sync::Arc,
+	task::Poll,
+};
+
+/// The magic bytes every state snapshot starts with.
+pub const STATE_SNAPSHOT_MAGIC: [u8; 8] = *b"sbststat";
+
+/// The version of the state snapshot format.
+pub const STATE_SNAPSHOT_VERSION: u32 = 2;
+
+/// Size limit of the proof of a chunk. The actual size may be bigger.
+const CHUNK_SIZE: usize = 2 * 1024 * 1024;
+
+/// The header of a state snapshot.
+#[derive(Debug, Encode, Decode)]
+pub struct StateSnapshotHeader<B: BlockT> {
+	/// The version of the state snapshot format.
+	pub version: u32,
+	/// The hash of the block the snapshot is of.
+	pub hash: B::Hash,
+	/// The header of the block the snapshot is of.
+	pub header: B::Header,
+	/// The justifications of the block, if any.
+	pub justifications: Option<Justifications>,
+	/// The warp sync proofs, starting at genesis, proving the finality of the block.
+	pub finality_proof: Vec<Vec<u8>>,
+}
+
+/// A chunk of a state snapshot, proving the entries following `start`.
+#[derive(Encode, Decode)]
+struct StateSnapshotChunk {
+	start: Vec<Vec<u8>>,
+	proof: CompactProof,
+}
+
+/// Exports a verifiable snapshot of the state of the block `header`, whose finality is proven by
+/// the warp sync proofs `finality_proof` starting at genesis.
+pub fn export_state_snapshot<B, C>(
+	client: Arc<C>,
+	finality_proof: Vec<EncodedProof>,
+	header: B::Header,
+	mut output: impl Write,
+) -> Result<(), Error>
+where
+	B: BlockT,
+	C: HeaderBackend<B> + BlockBackend<B> + ProofProvider<B>,
+{
+	let hash = header.hash();
+	let number = *header.number();
+	if number > client.info().finalized_number || client.hash(number)? != Some(hash) {
+		return Err(Error::Other(format!("Block #{} ({}) isn't finalized", number, hash)))
+	}
+	let root = *header.state_root();
+
+	info!("Exporting the state of #{} ({})", number, hash);
+	let justifications = client.justifications(hash)?;
+	output.write_all(&STATE_SNAPSHOT_MAGIC)?;
+	output.write_all(
+		&StateSnapshotHeader::<B> {
+			version: STATE_SNAPSHOT_VERSION,
+			hash,
+			header,
+			justifications,
+			finality_proof: finality_proof.into_iter().map(|EncodedProof(proof)| proof).collect(),
+		}
+		.encode(),
+	)?;
+
+	let mut start = SmallVec::<[Vec<u8>; 2]>::new();
+	let mut chunks = 0u64;
+	let mut keys = 0u64;
+	loop {
+		let (proof, count) = client.read_proof_collection(hash, start.as_slice(), CHUNK_SIZE)?;
+		// The proof is checked to find the key the next chunk starts at, as state sync does.
+		let (values, completed) =
+			client.verify_range_proof(root, proof.clone(), start.as_slice())?;
+		output.write_all(&Some(StateSnapshotChunk { start: start.to_vec(), proof }).encode())?;
+		chunks += 1;
+		keys += u64::from(count);
+
+		if completed == 0 {
+			break
+		}
+		if !values.update_last_key(completed, &mut start) {
+			return Err(Error::Other("Failed to find the start of the next chunk".into()))
+		}
+	}
+	output.write_all(&None::<StateSnapshotChunk>.encode())?;
+	output.flush()?;
+
+	info!("Exported {} keys in {} chunks", keys, chunks);
+	Ok(())
+}
+
+/// Verifies the warp sync proofs `finality_proof`, starting at genesis, returning the header of
+/// the block they prove the finality of.
+fn verify_finality_proof<B: BlockT>(
+	warp_sync_provider: &dyn WarpSyncProvider<B>,
+	finality_proof: Vec<Vec<u8>>,
+) -> Result<B::Header, Error> {
+	let mut verifier_state = warp_sync_provider.initial_verifier_state();
+	let proof_count = finality_proof.len();
+	for (index, proof) in finality_proof.into_iter().enumerate() {
+		let result = warp_sync_provider
+			.verify(&EncodedProof(proof), &verifier_state)
+			.map_err(|e| Error::Other(format!("Invalid finality proof: {}", e)))?;
+		match result {
+			VerificationResult::Partial(next_verifier_state, _) =>
+				verifier_state = next_verifier_state,
+			VerificationResult::Complete(header) if index + 1 == proof_count => return Ok(header),
+			VerificationResult::Complete(..) =>
+				return Err(Error::Other("Invalid finality proof: proofs past its end".into())),
+		}
+	}
+	Err(Error::Other("Invalid finality proof: the proof is incomplete".into()))
+}
+
+/// Reads a state snapshot, verifying the finality of its block and every chunk against its header.
+///
+/// If `trusted_hash` is given, the snapshot must be of this block.
+fn read_state_snapshot<B, C>(
+	client: &C,
+	warp_sync_provider: &dyn WarpSyncProvider<B>,
+	input: impl Read,
+	trusted_hash: Option<B::Hash>,
+) -> Result<IncomingBlock<B>, Error>
+where
+	B: BlockT,
+	C: ProofProvider<B>,
+{
+	let mut input = CodecIoReader(BufReader::new(input));
+	let decode_err = |e: codec::Error| Error::Other(format!("Invalid state snapshot: {}", e));
+
+	let mut magic = [0; 8];
+	input.0.read_exact(&mut magic)?;
+	if magic != STATE_SNAPSHOT_MAGIC {
+		return Err(Error::Other("Invalid state snapshot: unknown format".into()))
+	}
+	let snapshot = StateSnapshotHeader::<B>::decode(&mut input).map_err(decode_err)?;
+	if snapshot.version != STATE_SNAPSHOT_VERSION {
+		return Err(Error::Other(format!(
+			"Invalid state snapshot: unsupported version {}",
+			snapshot.version
+		)))
+	}
+	let hash = snapshot.header.hash();
+	if snapshot.hash != hash {
+		return Err(Error::Other("Invalid state snapshot: the header isn't of its block".into()))
+	}
+	if let Some(trusted_hash) = trusted_hash.filter(|trusted_hash| *trusted_hash != hash) {
+		return Err(Error::Other(format!(
+			"The state snapshot is of block {}, not of the trusted block {}",
+			hash, trusted_hash
+		)))
+	}
+	let finalized = verify_finality_proof(warp_sync_provider, snapshot.finality_proof)?;
+	if finalized != snapshot.header {
+		return Err(Error::Other(format!(
+			"The finality proof of the state snapshot is of block {}, not of its block {}",
+			finalized.hash(),
+			hash
+		)))
+	}
+	let root = *snapshot.header.state_root();
+	info!("Verified the finality of #{} ({}), importing its state", snapshot.header.number(), hash);
+
+	let mut cursor = SmallVec::<[Vec<u8>; 2]>::new();
+	let mut state: HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)> = HashMap::new();
+	loop {
+		let chunk = Option::<StateSnapshotChunk>::decode(&mut input)
+			.map_err(decode_err)?
+			.ok_or_else(|| Error::Other("The state snapshot ends before the state does".into()))?;
+		if chunk.start != cursor.as_slice() {
+			return Err(Error::Other("Invalid state snapshot: chunks are out of order".into()))
+		}
+		let (values, completed) =
+			client.verify_range_proof(root, chunk.proof, cursor.as_slice())?;
+		if completed != 0 && !values.update_last_key(completed, &mut cursor) {
+			return Err(Error::Other("Failed to find the start of the next chunk".into()))
+		}
+
+		// Collected as state sync does, with the child tries keyed by their root.
+		for values in values.0 {
+			let key_values = if values.state_root.is_empty() {
+				values
+					.key_values
+					.into_iter()
+					.filter(|(key, value)| {
+						if well_known_keys::is_child_storage_key(key) {
+							state.entry(value.clone()).or_default().1.push(key.clone());
+							false
+						} else {
+							true
+						}
+					})
+					.collect()
+			} else {
+				values.key_values
+			};
+			let entry = state.entry(values.state_root).or_default();
+			// A child trie with the same root may already have been collected.
+			if entry.0.is_empty() || entry.1.len() <= 1 {
+				entry.0.extend(key_values);
+			}
+		}
+
+		if completed == 0 {
+			break
+		}
+	}
+	if Option::<StateSnapshotChunk>::decode(&mut input).map_err(decode_err)?.is_some() {
+		return Err(Error::Other("Invalid state snapshot: chunks past the end of the state".into()))
+	}
+
+	Ok(IncomingBlock {
+		hash,
+		header: Some(snapshot.header),
+		body: None,
+		indexed_body: None,
+		justifications: snapshot.justifications,
+		origin: None,
+		allow_missing_state: true,
+		import_existing: true,
+		skip_execution: true,
+		state: Some(ImportedState { block: hash, state: state.into() }),
+	})
+}
+
+/// Imports a state snapshot into a new database, once the finality of its block is verified by
+/// `warp_sync_provider`, starting from the genesis.
+///
+/// If `trusted_hash` is given, the snapshot must be of this block. The block is imported with its
+/// state as the target of a warp sync. The blocks before it are left to be downloaded once the
+/// node is started.
+pub fn import_state_snapshot<B, IQ, C>(
+	client: Arc<C>,
+	mut import_queue: IQ,
+	warp_sync_provider: Arc<dyn WarpSyncProvider<B>>,
+	input: impl Read + Send + 'static,
+	trusted_hash: Option<B::Hash>,
+) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>
+where
+	B: BlockT,
+	C: HeaderBackend<B> + ProofProvider<B> + Send + Sync + 'static,
+	IQ: ImportQueue<B> + 'static,
+{
+	struct WaitLink {
+		result: Option<Result<(), String>>,
+	}
+
+	impl<B: BlockT> Link<B> for WaitLink {
+		fn blocks_processed(
+			&mut self,
+			_imported: usize,
+			_num_expected_blocks: usize,
+			results: Vec<(Result<BlockImportStatus<NumberFor<B>>, BlockImportError>, B::Hash)>,
+		) {
+			for (result, hash) in results {
+				self.result = Some(
+					result
+						.map(|_| ())
+						.map_err(|e| format!("Failed to import block {:?}: {}", hash, e)),
+				);
+			}
+		}
+	}
+
+	if client.info().finalized_state.is_some() {
+		return future::ready(Err(Error::Other(
+			"The database already has a state, state snapshots can only be imported into a new one"
+				.into(),
+		)))
+		.boxed()
+	}
+	let block = match read_state_snapshot(&*client, &*warp_sync_provider, input, trusted_hash) {
+		Ok(block) => block,
+		Err(e) => return future::ready(Err(e)).boxed(),
+	};
+	import_queue.service_ref().import_blocks(BlockOrigin::File, vec![block]);
+
+	let mut link = WaitLink { result: None };
+	future::poll_fn(move |cx| {
+		import_queue.poll_actions(cx, &mut link);
+		match link.result.take() {
+			Some(result) => {
+				if result.is_ok() {
+					info!(
+						"Imported the state of #{} ({})",
+						client.info().finalized_number,
+						client.info().finalized_hash
+					);
+				}
+				Poll::Ready(result.map_err(Error::Other))
+			},
+			None => Poll::Pending,
+		}
+	})
+	.boxed()
+}
@@ -29,7 +29,8 @@ use sc_chain_spec::{resolve_state_version_from_wasm, BuildGenesisBlock};
 use sc_client_api::{
 	backend::{
 		self, apply_aux, BlockImportOperation, ClientImportOperation, FinalizeSummary, Finalizer,
-		ImportNotificationAction, ImportSummary, LockImportRun, NewBlockState, StorageProvider,
+		ImportNotificationAction, ImportSummary, KeyChange, LockImportRun, NewBlockState,
+		StorageProvider,
 	},
 	client::{
 		BadBlocks, BlockBackend, BlockImportNotification, BlockOf, BlockchainEvents, ClientInfo,
@@ -601,9 +602,10 @@ where
 		}
 
 		let info = self.backend.blockchain().info();
-		let gap_block = info
-			.block_gap
-			.map_or(false, |(start, _)| *import_headers.post().number() == start);
+		let gap_block = info.block_gap.map_or(false, |(start, end)| {
+			let number = *import_headers.post().number();
+			number >= start && number <= end
+		});
 
 		assert!(justifications.is_some() && finalized || justifications.is_none() || gap_block);
 
@@ -819,6 +821,25 @@ where
 		Ok(ImportResult::imported(is_new_best))
 	}
 
+	/// Whether the block is at the end of the block gap, and is the parent of the known block
+	/// above it.
+	///
+	/// Gap sync imports the history downwards, the parent of such a block being unknown. Anchoring
+	/// every block on its child this way also lets the history below the blocks downloaded remain
+	/// missing.
+	fn is_anchored_gap_block(
+		&self,
+		hash: Block::Hash,
+		number: NumberFor<Block>,
+	) -> sp_blockchain::Result<bool> {
+		let blockchain = self.backend.blockchain();
+		if !blockchain.info().block_gap.map_or(false, |(_, end)| number == end) {
+			return Ok(false)
+		}
+		let Some(child) = blockchain.hash(number + One::one())? else { return Ok(false) };
+		Ok(blockchain.header(child)?.map_or(false, |header| *header.parent_hash() == hash))
+	}
+
 	/// Prepares the storage changes for a block.
 	///
 	/// It checks if the state should be enacted and if the `import_block` maybe already provides
@@ -838,6 +859,12 @@ where
 		{
 			(BlockStatus::KnownBad, _) =>
 				return Ok(PrepareStorageChangesResult::Discard(ImportResult::KnownBad)),
+			(BlockStatus::Unknown, StateAction::Skip)
+				if self.is_anchored_gap_block(
+					import_block.post_hash(),
+					*import_block.header.number(),
+				)? =>
+				(false, None),
 			(
 				BlockStatus::InChainPruned,
 				StateAction::ApplyChanges(sc_consensus::StorageChanges::Changes(_)),
@@ -1538,6 +1565,17 @@ where
 			.child_closest_merkle_value(child_info, &key.0)
 			.map_err(|e| sp_blockchain::Error::from_state(Box::new(e)))
 	}
+
+	fn storage_history(
+		&self,
+		child_info: Option<&ChildInfo>,
+		key: &StorageKey,
+		from: NumberFor<Block>,
+		to: NumberFor<Block>,
+		limit: usize,
+	) -> blockchain::Result<Option<Vec<KeyChange<Block>>>> {
+		self.backend.storage_history(child_info, key, from, to, limit)
+	}
 }
 
 impl<B, E, Block, RA> HeaderMetadata<Block> for Client<B, E, Block, RA>
@@ -1825,6 +1863,10 @@ where
 		{
 			BlockStatus::InChainWithState | BlockStatus::Queued => {},
 			BlockStatus::Unknown if allow_missing_parent => {},
+			BlockStatus::Unknown
+				if self
+					.is_anchored_gap_block(hash, number)
+					.map_err(|e| ConsensusError::ClientImport(e.to_string()))? => {},
 			BlockStatus::Unknown => return Ok(ImportResult::UnknownParent),
 			BlockStatus::InChainPruned if allow_missing_state => {},
 			BlockStatus::InChainPruned => return Ok(ImportResult::MissingState),
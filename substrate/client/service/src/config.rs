@@ -21,7 +21,10 @@
 
 use prometheus_endpoint::Registry;
 use sc_chain_spec::ChainSpec;
-pub use sc_client_db::{BlocksPruning, Database, DatabaseSource, PruningMode};
+pub use sc_client_db::{
+	BlockRetention, BlocksPruning, ColdStorageSettings, CompressionSettings, Database,
+	DatabaseSource, ExtrinsicIndexFn, PruningMode, StateLeaseSettings,
+};
 pub use sc_executor::{WasmExecutionMethod, WasmtimeInstantiationStrategy};
 pub use sc_informant::OutputFormat;
 pub use sc_network::{
@@ -35,7 +38,10 @@ pub use sc_network::{
 	Multiaddr,
 };
 pub use sc_telemetry::TelemetryEndpoints;
-pub use sc_transaction_pool::Options as TransactionPoolOptions;
+pub use sc_transaction_pool::{
+	JournalOptions as TransactionPoolJournalOptions, JournalScope as TransactionPoolJournalScope,
+	Options as TransactionPoolOptions,
+};
 use sp_core::crypto::SecretString;
 use std::{
 	io, iter,
@@ -63,6 +69,18 @@ pub struct Configuration {
 	pub keystore: KeystoreConfig,
 	/// Configuration for the database.
 	pub database: DatabaseSource,
+	/// Compression of the block bodies and justifications.
+	pub database_compression: CompressionSettings,
+	/// Where to move the bodies and justifications of the old finalized blocks, if anywhere.
+	pub cold_database: Option<ColdStorageSettings>,
+	/// Whether to index the extrinsics of the imported blocks by signer and by call.
+	pub extrinsic_index: bool,
+	/// Whether to record the storage changes of the imported blocks.
+	pub storage_diffs: bool,
+	/// Whether to index the history of the storage keys changed by the imported blocks.
+	pub key_history: bool,
+	/// The settings of the state leases, if the state of blocks can be leased over RPC.
+	pub state_leases: Option<StateLeaseSettings>,
 	/// Maximum size of internal trie cache in bytes.
 	///
 	/// If `None` is given the cache is disabled.
@@ -244,6 +262,8 @@ impl Configuration {
 			state_pruning: self.state_pruning.clone(),
 			source: self.database.clone(),
 			blocks_pruning: self.blocks_pruning,
+			compression: self.database_compression.clone(),
+			cold_storage: self.cold_database.clone(),
 		}
 	}
 }
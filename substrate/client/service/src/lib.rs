@@ -55,8 +55,9 @@ pub use self::{
 	builder::{
 		build_network, new_client, new_db_backend, new_full_client, new_full_parts,
 		new_full_parts_record_import, new_full_parts_with_genesis_builder, new_wasm_executor,
-		spawn_tasks, BuildNetworkParams, KeystoreContainer, NetworkStarter, SpawnTasksParams,
-		TFullBackend, TFullCallExecutor, TFullClient,
+		set_block_retention, set_extrinsic_indexer, spawn_tasks, BuildNetworkParams,
+		KeystoreContainer, NetworkStarter, SpawnTasksParams, TFullBackend, TFullCallExecutor,
+		TFullClient,
 	},
 	client::{ClientConfig, LocalCallExecutor},
 	error::Error,
@@ -68,7 +69,8 @@ pub use sc_chain_spec::{
 };
 
 pub use config::{
-	BasePath, BlocksPruning, Configuration, DatabaseSource, PruningMode, Role, RpcMethods, TaskType,
+	BasePath, BlockRetention, BlocksPruning, Configuration, DatabaseSource, ExtrinsicIndexFn,
+	PruningMode, Role, RpcMethods, TaskType,
 };
 pub use sc_chain_spec::{
 	ChainSpec, ChainType, Extension as ChainSpecExtension, GenericChainSpec, NoExtension,
@@ -76,7 +78,10 @@ pub use sc_chain_spec::{
 };
 
 pub use sc_consensus::ImportQueue;
-pub use sc_network_sync::WarpSyncParams;
+pub use sc_network_sync::{
+	strategy::warp::{EncodedProof, WarpSyncProvider},
+	WarpSyncParams,
+};
 #[doc(hidden)]
 pub use sc_network_transactions::config::{TransactionImport, TransactionImportFuture};
 pub use sc_rpc::{
@@ -321,6 +326,40 @@ pub async fn build_system_rpc_future<
 					break
 				}
 			},
+			sc_rpc::system::Request::PeerReputation(peer_id, sender) => {
+				let _ = match peer_id.parse::<PeerId>() {
+					Ok(peer_id) => sender.send(Ok(network_service
+						.peer_reputation_info(&peer_id)
+						.map(|info| sc_rpc::system::PeerReputation {
+							reputation: info.reputation,
+							banned: info.banned,
+							history: info.history.into_iter().map(reputation_change).collect(),
+						}))),
+					Err(e) => sender.send(Err(sc_rpc::system::error::Error::MalformattedPeerArg(
+						e.to_string(),
+					))),
+				};
+			},
+			sc_rpc::system::Request::BannedPeers(sender) => {
+				let banned_peers = network_service
+					.banned_peers()
+					.into_iter()
+					.map(|(peer_id, info)| sc_rpc::system::BannedPeer {
+						peer_id: peer_id.to_base58(),
+						reputation: info.reputation,
+						ban: info.ban_event().cloned().map(reputation_change),
+					})
+					.collect();
+				let _ = sender.send(banned_peers);
+			},
+			sc_rpc::system::Request::UnbanPeer(peer_id, sender) => {
+				let _ = match peer_id.parse::<PeerId>() {
+					Ok(peer_id) => sender.send(Ok(network_service.unban_peer(&peer_id))),
+					Err(e) => sender.send(Err(sc_rpc::system::error::Error::MalformattedPeerArg(
+						e.to_string(),
+					))),
+				};
+			},
 			sc_rpc::system::Request::NodeRoles(sender) => {
 				use sc_rpc::system::NodeRole;
 
@@ -332,26 +371,51 @@ pub async fn build_system_rpc_future<
 				let _ = sender.send(vec![node_role]);
 			},
 			sc_rpc::system::Request::SyncState(sender) => {
-				use sc_rpc::system::SyncState;
+				use sc_rpc::system::{GapSyncState, SyncState};
 
-				match sync_service.best_seen_block().await {
-					Ok(best_seen_block) => {
+				match sync_service.status().await {
+					Ok(status) => {
 						let best_number = client.info().best_number;
 						let _ = sender.send(SyncState {
 							starting_block,
 							current_block: best_number,
-							highest_block: best_seen_block.unwrap_or(best_number),
+							highest_block: status.best_seen_block.unwrap_or(best_number),
+							gap_sync: status.gap_sync.map(|gap_sync| GapSyncState {
+								starting_block: gap_sync.start,
+								current_block: gap_sync.lowest_queued,
+								target_block: gap_sync.target,
+								downloaded_bytes: gap_sync.total_bytes,
+							}),
 						});
 					},
 					Err(_) => log::error!("`SyncingEngine` shut down"),
 				}
 			},
+			sc_rpc::system::Request::StartGapSync(sender) =>
+				match sync_service.start_gap_sync().await {
+					Ok(started) => {
+						let _ = sender.send(started);
+					},
+					Err(_) => log::error!("`SyncingEngine` shut down"),
+				},
 		}
 	}
 
 	debug!("`NetworkWorker` has terminated, shutting down the system RPC future.");
 }
 
+/// Convert a change of the reputation of a peer to its RPC representation.
+fn reputation_change(
+	event: sc_network::peer_store::ReputationEvent,
+) -> sc_rpc::system::ReputationChange {
+	sc_rpc::system::ReputationChange {
+		timestamp: event.timestamp,
+		value: event.value,
+		reason: event.reason.into_owned(),
+		reputation: event.reputation,
+	}
+}
+
 // Wrapper for HTTP and WS servers that makes sure they are properly shut down.
 mod waiting {
 	pub struct Server(pub Option<sc_rpc_server::Server>);
@@ -452,6 +516,29 @@ where
 		.collect()
 }
 
+/// Resolve the import of a transaction received from the network.
+async fn transaction_import<H, E: IntoPoolError>(
+	import_future: impl std::future::Future<Output = Result<H, E>>,
+) -> TransactionImport {
+	match import_future.await {
+		Ok(_) => TransactionImport::NewGood,
+		Err(e) => match e.into_pool_error() {
+			Ok(sc_transaction_pool_api::error::Error::AlreadyImported(_)) =>
+				TransactionImport::KnownGood,
+			Ok(e) => {
+				debug!("Error adding transaction to the pool: {:?}", e);
+				TransactionImport::Bad
+			},
+			Err(e) => {
+				debug!("Error converting pool error: {}", e);
+				// it is not bad at least, just some internal node logic error, so peer is
+				// innocent.
+				TransactionImport::KnownGood
+			},
+		},
+	}
+}
+
 impl<B, H, C, Pool, E> sc_network_transactions::config::TransactionPool<H, B>
 	for TransactionPoolAdapter<C, Pool>
 where
@@ -490,25 +577,29 @@ where
 			sc_transaction_pool_api::TransactionSource::External,
 			uxt,
 		);
-		Box::pin(async move {
-			match import_future.await {
-				Ok(_) => TransactionImport::NewGood,
-				Err(e) => match e.into_pool_error() {
-					Ok(sc_transaction_pool_api::error::Error::AlreadyImported(_)) =>
-						TransactionImport::KnownGood,
-					Ok(e) => {
-						debug!("Error adding transaction to the pool: {:?}", e);
-						TransactionImport::Bad
-					},
-					Err(e) => {
-						debug!("Error converting pool error: {}", e);
-						// it is not bad at least, just some internal node logic error, so peer is
-						// innocent.
-						TransactionImport::KnownGood
-					},
-				},
-			}
-		})
+		Box::pin(transaction_import(import_future))
+	}
+
+	fn import_private(&self, transaction: B::Extrinsic) -> TransactionImportFuture {
+		let encoded = transaction.encode();
+		let uxt = match Decode::decode(&mut &encoded[..]) {
+			Ok(uxt) => uxt,
+			Err(e) => {
+				debug!("Private transaction invalid: {:?}", e);
+				return Box::pin(futures::future::ready(TransactionImport::Bad))
+			},
+		};
+
+		let import_future = self.pool.submit_private(
+			self.client.info().best_hash,
+			sc_transaction_pool_api::TransactionSource::External,
+			uxt,
+		);
+		Box::pin(transaction_import(import_future))
+	}
+
+	fn is_private(&self, hash: &H) -> bool {
+		self.pool.ready_transaction(hash).map_or(false, |tx| tx.is_private())
 	}
 
 	fn on_broadcasted(&self, propagations: HashMap<H, Vec<String>>) {
@@ -1461,6 +1461,8 @@ fn doesnt_import_blocks_that_revert_finality() {
 				state_pruning: Some(PruningMode::ArchiveAll),
 				blocks_pruning: BlocksPruning::KeepAll,
 				source: DatabaseSource::RocksDb { path: tmp.path().into(), cache_size: 1024 },
+				compression: Default::default(),
+				cold_storage: None,
 			},
 			u64::MAX,
 		)
@@ -1740,6 +1742,8 @@ fn returns_status_for_pruned_blocks() {
 				state_pruning: Some(PruningMode::blocks_pruning(1)),
 				blocks_pruning: BlocksPruning::KeepFinalized,
 				source: DatabaseSource::RocksDb { path: tmp.path().into(), cache_size: 1024 },
+				compression: Default::default(),
+				cold_storage: None,
 			},
 			u64::MAX,
 		)
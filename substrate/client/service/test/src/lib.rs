@@ -239,6 +239,12 @@ fn node_config<
 		network: network_config,
 		keystore: KeystoreConfig::Path { path: root.join("key"), password: None },
 		database: DatabaseSource::RocksDb { path: root.join("db"), cache_size: 128 },
+		database_compression: Default::default(),
+		cold_database: None,
+		extrinsic_index: false,
+		storage_diffs: false,
+		key_history: false,
+		state_leases: None,
 		trie_cache_maximum_size: Some(16 * 1024 * 1024),
 		state_pruning: Default::default(),
 		blocks_pruning: BlocksPruning::KeepFinalized,
@@ -41,19 +41,28 @@
 //! # Pruning.
 //! See `RefWindow` for pruning algorithm details. `StateDb` prunes on each canonicalization until
 //! pruning constraints are satisfied.
+//!
+//! # Pruning mode migration.
+//! A database created with `PruningMode::ArchiveCanonical` may be switched to a constrained mode
+//! with `StateDb::set_pruning_mode`. The pruning window then starts at the last canonicalized
+//! block, as no earlier block is journaled. The nodes of the states before it are not referenced by
+//! any journal and have to be removed by walking the database, which the caller does through
+//! `StateDb::commit_migration_removals`. Until `StateDb::finish_pruning_migration` is called, the
+//! pruning window is not pruned and every key touched by a canonicalized block is protected from
+//! removal.
 
 mod noncanonical;
 mod pruning;
 #[cfg(test)]
 mod test;
 
-use codec::Codec;
+use codec::{Codec, Decode, Encode};
 use log::trace;
 use noncanonical::NonCanonicalOverlay;
 use parking_lot::RwLock;
 use pruning::{HaveBlock, RefWindow};
 use std::{
-	collections::{hash_map::Entry, HashMap},
+	collections::{hash_map::Entry, HashMap, HashSet},
 	fmt,
 };
 
@@ -63,6 +72,7 @@ const PRUNING_MODE: &[u8] = b"mode";
 const PRUNING_MODE_ARCHIVE: &[u8] = b"archive";
 const PRUNING_MODE_ARCHIVE_CANON: &[u8] = b"archive_canonical";
 const PRUNING_MODE_CONSTRAINED: &[u8] = b"constrained";
+const PRUNING_MIGRATION: &[u8] = b"pruning_migration";
 pub(crate) const DEFAULT_MAX_BLOCK_CONSTRAINT: u32 = 256;
 
 /// Database value type.
@@ -300,12 +310,23 @@ pub enum LastCanonicalized {
 	NotCanonicalizing,
 }
 
+/// An in-progress switch from an archive to a constrained pruning mode.
+struct PruningMigration<BlockHash: Hash, Key: Hash> {
+	/// The block the pruning window was started at.
+	block: (BlockHash, u64),
+	/// Keys inserted or deleted by the blocks canonicalized since the switch.
+	touched: HashSet<Key>,
+	/// Blocks before `block` which were pinned at the time of the switch.
+	legacy_pins: HashSet<BlockHash>,
+}
+
 pub struct StateDbSync<BlockHash: Hash, Key: Hash, D: MetaDb> {
 	mode: PruningMode,
 	non_canonical: NonCanonicalOverlay<BlockHash, Key>,
 	pruning: Option<RefWindow<BlockHash, Key, D>>,
 	pinned: HashMap<BlockHash, u32>,
 	ref_counting: bool,
+	migration: Option<PruningMigration<BlockHash, Key>>,
 }
 
 impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
@@ -317,13 +338,111 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		trace!(target: LOG_TARGET, "StateDb settings: {:?}. Ref-counting: {}", mode, ref_counting);
 
 		let non_canonical: NonCanonicalOverlay<BlockHash, Key> = NonCanonicalOverlay::new(&db)?;
+		let migration = match (&mode, fetch_pruning_migration::<BlockHash, D>(&db)?) {
+			(PruningMode::Constrained(_), Some(block)) => {
+				trace!(target: LOG_TARGET, "Resuming pruning migration started at {:?}", block);
+				Some(PruningMigration {
+					touched: pruning::journaled_keys::<BlockHash, Key, D>(&db, block.1)?,
+					block,
+					legacy_pins: Default::default(),
+				})
+			},
+			_ => None,
+		};
 		let pruning: Option<RefWindow<BlockHash, Key, D>> = match mode {
-			PruningMode::Constrained(Constraints { max_blocks }) =>
-				Some(RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting)?),
+			PruningMode::Constrained(Constraints { max_blocks }) => {
+				let mut pruning = RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting)?;
+				pruning.set_journal_insertions(migration.is_some());
+				Some(pruning)
+			},
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => None,
 		};
 
-		Ok(StateDbSync { mode, non_canonical, pruning, pinned: Default::default(), ref_counting })
+		Ok(StateDbSync {
+			mode,
+			non_canonical,
+			pruning,
+			pinned: Default::default(),
+			ref_counting,
+			migration,
+		})
+	}
+
+	fn set_pruning_mode(
+		&mut self,
+		mode: PruningMode,
+		db: D,
+	) -> Result<CommitSet<Key>, Error<D::Error>> {
+		let mut commit = CommitSet::default();
+		match (self.mode.clone(), mode) {
+			(PruningMode::Constrained(_), PruningMode::Constrained(constraints)) => {
+				self.mode = PruningMode::Constrained(constraints);
+			},
+			(PruningMode::ArchiveCanonical, PruningMode::Constrained(constraints)) => {
+				let window_size = constraints.max_blocks.unwrap_or(0);
+				let pruning = match self.non_canonical.last_canonicalized() {
+					Some((hash, number)) => {
+						let mut pruning = RefWindow::new_from_block(
+							db,
+							window_size,
+							self.ref_counting,
+							&hash,
+							number,
+							&mut commit,
+						)?;
+						pruning.set_journal_insertions(true);
+						commit
+							.meta
+							.inserted
+							.push((to_meta_key(PRUNING_MIGRATION, &()), (&hash, number).encode()));
+						let legacy_pins = self
+							.pinned
+							.keys()
+							.filter(|pinned| {
+								**pinned != hash && !self.non_canonical.have_block(pinned)
+							})
+							.cloned()
+							.collect();
+						trace!(target: LOG_TARGET, "Starting pruning migration at #{}", number);
+						self.migration = Some(PruningMigration {
+							block: (hash, number),
+							touched: Default::default(),
+							legacy_pins,
+						});
+						pruning
+					},
+					// Nothing was canonicalized, so there is no history to remove.
+					None => RefWindow::new(db, window_size, self.ref_counting)?,
+				};
+				self.pruning = Some(pruning);
+				self.mode = PruningMode::Constrained(constraints);
+			},
+			(current, requested) if current == requested => {},
+			(stored, requested) =>
+				return Err(StateDbError::IncompatiblePruningModes { stored, requested }.into()),
+		}
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(PRUNING_MODE, &()), self.mode.id().to_owned()));
+		Ok(commit)
+	}
+
+	fn finish_pruning_migration_commit(&self) -> CommitSet<Key> {
+		let mut commit = CommitSet::default();
+		if self.migration.is_some() {
+			commit.meta.deleted.push(to_meta_key(PRUNING_MIGRATION, &()));
+		}
+		commit
+	}
+
+	fn finish_pruning_migration(&mut self) {
+		if let Some(migration) = self.migration.take() {
+			trace!(target: LOG_TARGET, "Finished pruning migration started at {:?}", migration.block);
+			if let Some(pruning) = self.pruning.as_mut() {
+				pruning.set_journal_insertions(false);
+			}
+		}
 	}
 
 	fn insert_block(
@@ -359,6 +478,12 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		if self.mode == PruningMode::ArchiveCanonical {
 			commit.data.deleted.clear();
 		}
+		if let Some(ref mut migration) = self.migration {
+			migration
+				.touched
+				.extend(commit.data.inserted.iter().map(|(key, _)| key.clone()));
+			migration.touched.extend(commit.data.deleted.iter().cloned());
+		}
 		if let Some(ref mut pruning) = self.pruning {
 			pruning.note_canonical(hash, number, &mut commit)?;
 		}
@@ -409,6 +534,11 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	}
 
 	fn prune(&mut self, commit: &mut CommitSet<Key>) -> Result<(), Error<D::Error>> {
+		// The journal of the window protects the keys touched during a migration, keep it until
+		// the migration is done.
+		if self.migration.is_some() {
+			return Ok(())
+		}
 		if let (&mut Some(ref mut pruning), PruningMode::Constrained(constraints)) =
 			(&mut self.pruning, &self.mode)
 		{
@@ -580,6 +710,71 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 		self.db.read().mode.clone()
 	}
 
+	/// Switch to another pruning mode while the database is open.
+	///
+	/// A constrained window may be resized, and `PruningMode::ArchiveCanonical` may be switched to
+	/// a constrained mode, which starts a pruning migration (see module description). The returned
+	/// commit set stores the new mode and has to be committed to the database.
+	pub fn set_pruning_mode(
+		&self,
+		mode: PruningMode,
+		db: D,
+	) -> Result<CommitSet<Key>, Error<D::Error>> {
+		self.db.write().set_pruning_mode(mode, db)
+	}
+
+	/// Returns the block the pruning window was started at, if a pruning migration is in progress.
+	pub fn pruning_migration(&self) -> Option<(BlockHash, u64)> {
+		self.db.read().migration.as_ref().map(|migration| migration.block.clone())
+	}
+
+	/// Returns `true` if any block before the start of the pruning migration is still pinned. The
+	/// state of such blocks must not be removed.
+	pub fn has_pre_migration_pins(&self) -> bool {
+		let state_db = self.db.read();
+		state_db.migration.as_ref().map_or(false, |migration| {
+			migration.legacy_pins.iter().any(|hash| state_db.pinned.contains_key(hash))
+		})
+	}
+
+	/// Remove historical nodes found by a pruning migration.
+	///
+	/// Keys touched by a block canonicalized since the start of the migration are skipped and
+	/// returned. `commit` is called with the remaining removals while the lock is held, so that no
+	/// block is canonicalized before they are applied to the database. Repeated keys are removed
+	/// once per occurrence.
+	pub fn commit_migration_removals<E>(
+		&self,
+		removals: Vec<Key>,
+		commit: impl FnOnce(CommitSet<Key>) -> Result<(), E>,
+	) -> Result<Vec<Key>, E> {
+		let state_db = self.db.write();
+		let (removals, skipped) = match state_db.migration.as_ref() {
+			Some(migration) =>
+				removals.into_iter().partition(|key| !migration.touched.contains(key)),
+			None => (Vec::new(), removals),
+		};
+		commit(CommitSet {
+			data: ChangeSet { inserted: Vec::new(), deleted: removals },
+			meta: Default::default(),
+		})?;
+		Ok(skipped)
+	}
+
+	/// Mark the pruning migration as complete, which resumes pruning of the window.
+	///
+	/// `commit` is called with the changes to the metadata while the lock is held, and the
+	/// migration is only considered complete once it succeeded.
+	pub fn finish_pruning_migration<E>(
+		&self,
+		commit: impl FnOnce(CommitSet<Key>) -> Result<(), E>,
+	) -> Result<(), E> {
+		let mut state_db = self.db.write();
+		commit(state_db.finish_pruning_migration_commit())?;
+		state_db.finish_pruning_migration();
+		Ok(())
+	}
+
 	/// Add a new non-canonical block.
 	pub fn insert_block(
 		&self,
@@ -653,10 +848,35 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 		self.db.read().is_pruned(hash, number)
 	}
 
+	/// Returns the hash and number of the last canonicalized block, if any.
+	pub fn last_canonicalized_block(&self) -> Option<(BlockHash, u64)> {
+		self.db.read().non_canonical.last_canonicalized()
+	}
+
+	/// Returns the hash, number and parent hash of every block that has not been canonicalized.
+	pub fn non_canonical_blocks(&self) -> Vec<(BlockHash, u64, BlockHash)> {
+		self.db.read().non_canonical.blocks()
+	}
+
+	/// Returns the number of the first block in the pruning window and the size of the window, if
+	/// the state of canonicalized blocks is pruned.
+	pub fn pruning_window(&self) -> Option<(u64, u64)> {
+		self.db
+			.read()
+			.pruning
+			.as_ref()
+			.map(|pruning| (pruning.base(), pruning.window_size()))
+	}
+
 	/// Reset in-memory changes to the last disk-backed state.
 	pub fn reset(&self, db: D) -> Result<(), Error<D::Error>> {
 		let mut state_db = self.db.write();
-		*state_db = StateDbSync::new(state_db.mode.clone(), state_db.ref_counting, db)?;
+		// A switch of the pruning mode may not have been committed.
+		let mode = match fetch_stored_pruning_mode(&db)? {
+			Some(stored) if stored.id() != state_db.mode.id() => stored,
+			_ => state_db.mode.clone(),
+		};
+		*state_db = StateDbSync::new(mode, state_db.ref_counting, db)?;
 		Ok(())
 	}
 }
@@ -689,6 +909,15 @@ fn fetch_stored_pruning_mode<D: MetaDb>(db: &D) -> Result<Option<PruningMode>, E
 	}
 }
 
+fn fetch_pruning_migration<BlockHash: Hash, D: MetaDb>(
+	db: &D,
+) -> Result<Option<(BlockHash, u64)>, Error<D::Error>> {
+	match db.get_meta(&to_meta_key(PRUNING_MIGRATION, &())).map_err(Error::Db)? {
+		Some(buffer) => Ok(Some(Decode::decode(&mut buffer.as_slice())?)),
+		None => Ok(None),
+	}
+}
+
 fn choose_pruning_mode(
 	stored: PruningMode,
 	requested: PruningMode,
@@ -870,6 +1099,119 @@ mod tests {
 		assert!(state_db_open_result.is_err());
 	}
 
+	fn commit_removals(
+		db: &mut TestDb,
+		state_db: &StateDb<H256, H256, TestDb>,
+		keys: &[u64],
+	) -> Vec<H256> {
+		state_db
+			.commit_migration_removals(
+				keys.iter().map(|k| H256::from_low_u64_be(*k)).collect(),
+				|commit| {
+					db.commit(&commit);
+					Ok::<_, ()>(())
+				},
+			)
+			.unwrap()
+	}
+
+	#[test]
+	fn switch_archive_canonical_to_constrained() {
+		let (mut db, state_db) = make_test_db(PruningMode::ArchiveCanonical);
+		db.commit(&state_db.set_pruning_mode(PruningMode::blocks_pruning(1), db.clone()).unwrap());
+		assert_eq!(state_db.pruning_mode(), PruningMode::blocks_pruning(1));
+		assert_eq!(state_db.pruning_migration(), Some((H256::from_low_u64_be(3), 3)));
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(3), 3), IsPruned::NotPruned);
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::Pruned);
+
+		// block 4 deletes 94, so the migration may not remove it anymore
+		db.commit(&state_db.canonicalize_block(&H256::from_low_u64_be(4)).unwrap());
+		assert_eq!(commit_removals(&mut db, &state_db, &[91, 94]), vec![H256::from_low_u64_be(94)]);
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 4, 921, 922, 93, 94])));
+
+		// the window is kept until the migration is finished
+		db.commit(
+			&state_db
+				.insert_block(
+					&H256::from_low_u64_be(5),
+					5,
+					&H256::from_low_u64_be(4),
+					make_changeset(&[], &[]),
+				)
+				.unwrap(),
+		);
+		db.commit(&state_db.canonicalize_block(&H256::from_low_u64_be(5)).unwrap());
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(3), 3), IsPruned::NotPruned);
+
+		state_db
+			.finish_pruning_migration(|commit| {
+				db.commit(&commit);
+				Ok::<_, ()>(())
+			})
+			.unwrap();
+		assert_eq!(state_db.pruning_migration(), None);
+		db.commit(
+			&state_db
+				.insert_block(
+					&H256::from_low_u64_be(6),
+					6,
+					&H256::from_low_u64_be(5),
+					make_changeset(&[], &[]),
+				)
+				.unwrap(),
+		);
+		db.commit(&state_db.canonicalize_block(&H256::from_low_u64_be(6)).unwrap());
+		assert_eq!(state_db.is_pruned(&H256::from_low_u64_be(5), 5), IsPruned::Pruned);
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 4, 921, 922, 93])));
+	}
+
+	#[test]
+	fn pruning_migration_is_restored_on_reopen() {
+		let (mut db, state_db) = make_test_db(PruningMode::ArchiveCanonical);
+		db.commit(&state_db.set_pruning_mode(PruningMode::blocks_pruning(1), db.clone()).unwrap());
+		db.commit(&state_db.canonicalize_block(&H256::from_low_u64_be(4)).unwrap());
+		std::mem::drop(state_db);
+
+		let (_, state_db) = StateDb::<H256, H256, TestDb>::open(
+			db.clone(),
+			Some(PruningMode::blocks_pruning(1)),
+			false,
+			false,
+		)
+		.unwrap();
+		assert_eq!(state_db.pruning_migration(), Some((H256::from_low_u64_be(3), 3)));
+		assert_eq!(
+			commit_removals(&mut db, &state_db, &[4, 94, 921]),
+			vec![H256::from_low_u64_be(4), H256::from_low_u64_be(94)]
+		);
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 4, 91, 922, 93, 94])));
+	}
+
+	#[test]
+	fn set_pruning_mode_compatibility() {
+		let (db, state_db) = make_test_db(PruningMode::blocks_pruning(1));
+		state_db.set_pruning_mode(PruningMode::blocks_pruning(4), db.clone()).unwrap();
+		assert_eq!(state_db.pruning_mode(), PruningMode::blocks_pruning(4));
+		assert_eq!(state_db.pruning_migration(), None);
+		assert!(matches!(
+			state_db.set_pruning_mode(PruningMode::ArchiveCanonical, db),
+			Err(Error::StateDb(StateDbError::IncompatiblePruningModes { .. }))
+		));
+
+		// an uncommitted switch is reverted by a reset
+		let (db, state_db) = make_test_db(PruningMode::ArchiveCanonical);
+		state_db.set_pruning_mode(PruningMode::blocks_pruning(1), db.clone()).unwrap();
+		state_db.reset(db).unwrap();
+		assert_eq!(state_db.pruning_mode(), PruningMode::ArchiveCanonical);
+		assert_eq!(state_db.pruning_migration(), None);
+
+		let (db, state_db) = make_test_db(PruningMode::ArchiveAll);
+		assert!(matches!(
+			state_db.set_pruning_mode(PruningMode::blocks_pruning(1), db),
+			Err(Error::StateDb(StateDbError::IncompatiblePruningModes { .. }))
+		));
+	}
+
 	fn check_stored_and_requested_mode_compatibility(
 		mode_when_created: Option<PruningMode>,
 		mode_when_reopened: Option<PruningMode>,
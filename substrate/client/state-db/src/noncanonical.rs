@@ -370,6 +370,30 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 		self.last_canonicalized.as_ref().map(|&(_, n)| n)
 	}
 
+	pub fn last_canonicalized(&self) -> Option<(BlockHash, u64)> {
+		self.last_canonicalized.clone()
+	}
+
+	/// Returns the hash, number and parent hash of every block in the overlay.
+	pub fn blocks(&self) -> Vec<(BlockHash, u64, BlockHash)> {
+		let front_block_number = self.front_block_number();
+		self.levels
+			.iter()
+			.enumerate()
+			.flat_map(|(index, level)| {
+				level.blocks.iter().map(move |overlay| (overlay.hash.clone(), index as u64))
+			})
+			.map(|(hash, index)| {
+				let parent_hash = self
+					.parents
+					.get(&hash)
+					.expect("there is a parent entry for each entry in levels")
+					.clone();
+				(hash, front_block_number + index, parent_hash)
+			})
+			.collect()
+	}
+
 	/// Confirm that all changes made to commit sets are on disk. Allows for temporarily pinned
 	/// blocks to be released.
 	pub fn sync(&mut self) {
@@ -700,6 +724,26 @@ mod tests {
 		assert_eq!(overlay.last_canonicalized, overlay2.last_canonicalized);
 	}
 
+	#[test]
+	fn lists_blocks() {
+		let h1 = H256::random();
+		let h2 = H256::random();
+		let h3 = H256::random();
+		let mut db = make_db(&[1, 2]);
+		let mut overlay = NonCanonicalOverlay::<H256, H256>::new(&db).unwrap();
+		db.commit(&overlay.insert(&h1, 10, &H256::default(), make_changeset(&[3], &[])).unwrap());
+		db.commit(&overlay.insert(&h2, 11, &h1, make_changeset(&[4], &[])).unwrap());
+		db.commit(&overlay.insert(&h3, 11, &h1, make_changeset(&[5], &[])).unwrap());
+		assert_eq!(overlay.blocks(), vec![(h1, 10, H256::default()), (h2, 11, h1), (h3, 11, h1)]);
+
+		let mut commit = CommitSet::default();
+		overlay.canonicalize(&h1, &mut commit).unwrap();
+		db.commit(&commit);
+		overlay.sync();
+		assert_eq!(overlay.blocks(), vec![(h2, 11, h1), (h3, 11, h1)]);
+		assert_eq!(NonCanonicalOverlay::<H256, H256>::new(&db).unwrap().blocks(), overlay.blocks());
+	}
+
 	#[test]
 	fn restore_from_journal_after_canonicalize() {
 		let h1 = H256::random();
@@ -43,6 +43,8 @@ pub struct RefWindow<BlockHash: Hash, Key: Hash, D: MetaDb> {
 	queue: DeathRowQueue<BlockHash, Key, D>,
 	/// Block number that is next to be pruned.
 	base: u64,
+	/// Whether inserted keys are journaled even if the queue itself doesn't track them.
+	journal_insertions: bool,
 }
 
 /// `DeathRowQueue` used to keep track of blocks in the pruning window, there are two flavors:
@@ -280,6 +282,23 @@ fn to_journal_key(block: u64) -> Vec<u8> {
 	to_meta_key(PRUNING_JOURNAL, &block)
 }
 
+/// Collect all keys inserted or deleted by the journaled blocks, starting at block `from`.
+pub(crate) fn journaled_keys<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	from: u64,
+) -> Result<HashSet<Key>, Error<D::Error>> {
+	let mut keys = HashSet::new();
+	let mut block = from;
+	while let Some(record) = db.get_meta(&to_journal_key(block)).map_err(Error::Db)? {
+		let JournalRecord::<BlockHash, Key> { inserted, deleted, .. } =
+			Decode::decode(&mut record.as_slice())?;
+		keys.extend(inserted);
+		keys.extend(deleted);
+		block += 1;
+	}
+	Ok(keys)
+}
+
 /// The result return by `RefWindow::have_block`
 #[derive(Debug, PartialEq, Eq)]
 pub enum HaveBlock {
@@ -348,7 +367,51 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 			DeathRowQueue::new_db_backed(db, base, last, window_size)?
 		};
 
-		Ok(RefWindow { queue, base })
+		Ok(RefWindow { queue, base, journal_insertions: false })
+	}
+
+	/// Create an empty window starting at the given canonical block, ignoring any journal found in
+	/// the database. Used when switching to a constrained pruning mode from an archive one, where
+	/// none of the blocks before `number` have been journaled. Adds changes to `commit`.
+	pub fn new_from_block(
+		db: D,
+		window_size: u32,
+		count_insertions: bool,
+		hash: &BlockHash,
+		number: u64,
+		commit: &mut CommitSet<Key>,
+	) -> Result<RefWindow<BlockHash, Key, D>, Error<D::Error>> {
+		let queue = if count_insertions {
+			DeathRowQueue::Mem { death_rows: VecDeque::new(), death_index: HashMap::new() }
+		} else {
+			let cache_capacity = window_size.clamp(1, DEFAULT_MAX_BLOCK_CONSTRAINT) as usize;
+			DeathRowQueue::DbBacked {
+				db,
+				cache: VecDeque::with_capacity(cache_capacity),
+				cache_capacity,
+				last: None,
+			}
+		};
+		let mut window = RefWindow { queue, base: number, journal_insertions: false };
+		if number > 0 {
+			commit
+				.meta
+				.inserted
+				.push((to_meta_key(LAST_PRUNED, &()), (number - 1).encode()));
+		}
+		window.note_canonical(hash, number, commit)?;
+		Ok(window)
+	}
+
+	/// Set whether inserted keys should be journaled, even when the database supports reference
+	/// counting and the queue doesn't need them.
+	pub fn set_journal_insertions(&mut self, journal_insertions: bool) {
+		self.journal_insertions = journal_insertions;
+	}
+
+	/// Block number that is next to be pruned.
+	pub fn base(&self) -> u64 {
+		self.base
 	}
 
 	pub fn window_size(&self) -> u64 {
@@ -425,7 +488,8 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 			commit.data.inserted.len(),
 			commit.data.deleted.len(),
 		);
-		let inserted = if matches!(self.queue, DeathRowQueue::Mem { .. }) {
+		let inserted = if self.journal_insertions || matches!(self.queue, DeathRowQueue::Mem { .. })
+		{
 			commit.data.inserted.iter().map(|(k, _)| k.clone()).collect()
 		} else {
 			Default::default()
@@ -70,6 +70,10 @@ pub enum Error {
 
 	#[error("The pool is not accepting future transactions")]
 	RejectedFutureTransaction,
+
+	/// The bundle has no transactions, or more than the pool accepts.
+	#[error("Transaction bundle of {0} transactions is either empty or too long")]
+	InvalidBundleLength(usize),
 }
 
 /// Transaction pool error conversion.
@@ -28,7 +28,9 @@ use futures::{Future, Stream};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sp_core::offchain::TransactionPoolExt;
 use sp_runtime::traits::{Block as BlockT, Member, NumberFor};
-use std::{collections::HashMap, hash::Hash, marker::PhantomData, pin::Pin, sync::Arc};
+use std::{
+	collections::HashMap, hash::Hash, marker::PhantomData, pin::Pin, sync::Arc, time::Duration,
+};
 
 const LOG_TARGET: &str = "txpool::api";
 
@@ -56,6 +58,114 @@ impl PoolStatus {
 	}
 }
 
+/// Structure of the pool, as returned by [`TransactionPool::inspect`].
+#[derive(Debug)]
+pub struct PoolInspection<Hash> {
+	/// Status of the pool.
+	pub status: PoolStatus,
+	/// Transactions in the ready queue, in the order they would be included in a block.
+	pub ready: Vec<InspectedTransaction<Hash>>,
+	/// Transactions in the future queue.
+	pub future: Vec<InspectedFutureTransaction<Hash>>,
+	/// Hashes of the transactions currently banned from the pool.
+	pub banned: Vec<Hash>,
+}
+
+/// A transaction in the pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InspectedTransaction<Hash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// Size of the transaction's encoding.
+	pub bytes: usize,
+	/// Priority of the transaction.
+	pub priority: TransactionPriority,
+	/// Block number at which the transaction becomes invalid.
+	pub valid_till: TransactionLongevity,
+	/// Tags required by the transaction.
+	pub requires: Vec<TransactionTag>,
+	/// Tags provided by the transaction.
+	pub provides: Vec<TransactionTag>,
+	/// Where the transaction comes from.
+	pub source: TransactionSource,
+	/// Whether the transaction is propagated to the other peers.
+	pub propagate: bool,
+	/// How long the transaction has been in the pool.
+	pub in_pool: Duration,
+}
+
+/// A transaction in the future queue, along with what it's waiting for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InspectedFutureTransaction<Hash> {
+	/// The transaction.
+	pub transaction: InspectedTransaction<Hash>,
+	/// The required tags which aren't provided yet.
+	pub missing_tags: Vec<MissingTag<Hash>>,
+}
+
+/// A tag a future transaction is waiting for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingTag<Hash> {
+	/// The tag.
+	pub tag: TransactionTag,
+	/// The transaction of the future queue providing the tag, if any.
+	pub provider: Option<Hash>,
+}
+
+/// An event of the lifecycle of a transaction, as recorded by the pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleEvent<Hash, BlockHash> {
+	/// Transaction was submitted to the pool, to be validated at the given block.
+	Submitted {
+		/// Where the transaction comes from.
+		source: TransactionSource,
+		/// Block the transaction is validated at.
+		at: BlockHash,
+	},
+	/// Transaction was rejected by the validation, with the given error.
+	Rejected(String),
+	/// Transaction entered the future queue.
+	Future,
+	/// Transaction entered the ready queue.
+	Ready,
+	/// Transaction has been propagated to the given peers.
+	Propagated(Vec<String>),
+	/// Transaction has been included in the proposal of the block with the given hash.
+	Proposed(BlockHash),
+	/// Transaction has been included in the imported block with the given hash.
+	InBlock(BlockHash),
+	/// The block this transaction was included in has been retracted.
+	Retracted(BlockHash),
+	/// The block this transaction was included in wasn't finalized in time.
+	FinalityTimeout(BlockHash),
+	/// The block this transaction was included in has been finalized.
+	Finalized(BlockHash),
+	/// Transaction has been replaced in the pool by the one with the given hash.
+	Usurped(Hash),
+	/// Transaction has been dropped from the pool because of the limit.
+	Dropped,
+	/// Transaction has been removed from the pool as invalid.
+	Invalid,
+}
+
+/// An event of the lifecycle of a transaction, along with when it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LifecycleRecord<Hash, BlockHash> {
+	/// When the event happened, since the UNIX epoch.
+	pub time: Duration,
+	/// The event.
+	pub event: LifecycleEvent<Hash, BlockHash>,
+}
+
+/// Lifecycle of a transaction, as returned by [`TransactionPool::lifecycle`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionLifecycle<Hash, BlockHash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// The most recent events of the transaction, in the order they happened.
+	pub events: Vec<LifecycleRecord<Hash, BlockHash>>,
+}
+
 /// Possible transaction status events.
 ///
 /// This events are being emitted by `TransactionPool` watchers,
@@ -179,6 +289,9 @@ pub trait InPoolTransaction {
 	fn provides(&self) -> &[TransactionTag];
 	/// Return a flag indicating if the transaction should be propagated to other peers.
 	fn is_propagable(&self) -> bool;
+	/// Return a flag indicating if the transaction was received privately, so it must only be
+	/// sent to trusted peers.
+	fn is_private(&self) -> bool;
 }
 
 /// Transaction pool interface.
@@ -213,6 +326,16 @@ pub trait TransactionPool: Send + Sync {
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<TxHash<Self>, Self::Error>;
 
+	/// Returns a future that imports one unverified transaction, received privately, to the pool.
+	///
+	/// The transaction is never propagated to others.
+	fn submit_private(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error>;
+
 	/// Returns a future that import a single transaction and starts to watch their progress in the
 	/// pool.
 	fn submit_and_watch(
@@ -222,6 +345,28 @@ pub trait TransactionPool: Send + Sync {
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error>;
 
+	/// Returns a future that imports a bundle of unverified transactions to the pool.
+	///
+	/// The transactions of a bundle are included into a block contiguously, in order, and either
+	/// all of them or none. Returns the hash of the bundle.
+	fn submit_bundle(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<TxHash<Self>, Self::Error>;
+
+	/// Returns a future that imports a bundle of transactions and starts to watch its progress in
+	/// the pool.
+	///
+	/// The index of the events is the index of the bundle's first transaction within the block.
+	fn submit_and_watch_bundle(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error>;
+
 	// *** Block production / Networking
 	/// Get an iterator for ready transactions ordered by priority.
 	///
@@ -238,13 +383,42 @@ pub trait TransactionPool: Send + Sync {
 		>,
 	>;
 
+	/// Get an iterator for ready transactions ordered by priority, on top of the given block.
+	///
+	/// Unlike [`Self::ready_at`], the block doesn't need to be the best block, so a block can be
+	/// built on top of any leaf of the chain. Defaults to the ready transactions of the best
+	/// chain.
+	fn ready_at_block(
+		&self,
+		_at: <Self::Block as BlockT>::Hash,
+		number: NumberFor<Self::Block>,
+	) -> Pin<
+		Box<
+			dyn Future<
+					Output = Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>,
+				> + Send,
+		>,
+	> {
+		self.ready_at(number)
+	}
+
 	/// Get an iterator for ready transactions ordered by priority.
 	fn ready(&self) -> Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>;
 
+	/// Get the bundles awaiting inclusion on top of the given block, ordered by the priority of
+	/// their first transaction.
+	fn ready_bundles(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+	) -> Vec<Vec<Arc<Self::InPoolTransaction>>>;
+
 	// *** Block production
 	/// Remove transactions identified by given hashes (and dependent transactions) from the pool.
 	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>>;
 
+	/// Notify the pool about transactions included in the proposal of a block.
+	fn on_proposed(&self, block: BlockHash<Self>, hashes: &[TxHash<Self>]);
+
 	// *** logging
 	/// Get futures transaction list.
 	fn futures(&self) -> Vec<Self::InPoolTransaction>;
@@ -252,6 +426,18 @@ pub trait TransactionPool: Send + Sync {
 	/// Returns pool status.
 	fn status(&self) -> PoolStatus;
 
+	/// Returns the structure of the pool.
+	fn inspect(&self) -> PoolInspection<TxHash<Self>>;
+
+	/// Returns the recorded lifecycle of a transaction, if it's still tracked.
+	///
+	/// A bounded number of the most recently active transactions is tracked, including the ones
+	/// which left the pool.
+	fn lifecycle(
+		&self,
+		hash: &TxHash<Self>,
+	) -> Option<TransactionLifecycle<TxHash<Self>, BlockHash<Self>>>;
+
 	// *** logging / RPC / networking
 	/// Return an event stream of transactions imported to the pool.
 	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>>;
@@ -299,6 +485,11 @@ pub enum ChainEvent<B: BlockT> {
 		/// If `None`, no re-org happened on import.
 		tree_route: Option<Arc<sp_blockchain::TreeRoute<B>>>,
 	},
+	/// New block which isn't the new best block have been added to the chain.
+	NewBlock {
+		/// Hash of the block.
+		hash: B::Hash,
+	},
 	/// An existing block has been finalized.
 	Finalized {
 		/// Hash of just finalized block.
@@ -312,7 +503,9 @@ impl<B: BlockT> ChainEvent<B> {
 	/// Returns the block hash associated to the event.
 	pub fn hash(&self) -> B::Hash {
 		match self {
-			Self::NewBestBlock { hash, .. } | Self::Finalized { hash, .. } => *hash,
+			Self::NewBestBlock { hash, .. } |
+			Self::NewBlock { hash } |
+			Self::Finalized { hash, .. } => *hash,
 		}
 	}
 
@@ -87,6 +87,7 @@ impl ChainApi for TestApi {
 			provides: vec![to_tag(nonce, from)],
 			longevity: 10,
 			propagate: true,
+			private: false,
 		})))
 	}
 
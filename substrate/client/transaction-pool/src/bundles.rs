@@ -0,0 +1,418 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Bundles of transactions, included into a block contiguously and either all of them or none.
+//!
+//! The transactions of a bundle are kept apart from the queues of the pool, so they are never
+//! included on their own. A bundle is tracked from its submission until the block including it is
+//! finalized, or until it becomes invalid. Once there is no room left, the pending bundle of the
+//! lowest priority is dropped in favor of a bundle of a higher priority.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::{
+	watcher::{Sender, Watcher},
+	BlockHash, ChainApi, ExtrinsicHash, TransactionFor,
+};
+use sc_transaction_pool_api::error;
+
+/// The maximum number of bundles awaiting inclusion or finalization.
+const MAX_BUNDLES: usize = 256;
+
+/// The maximum number of transactions of a bundle.
+pub(crate) const MAX_BUNDLE_LENGTH: usize = 16;
+
+struct Bundle<Api: ChainApi> {
+	transactions: Vec<TransactionFor<Api>>,
+	/// The block including the bundle, and the index of its first transaction within the block.
+	included: Option<(BlockHash<Api>, usize)>,
+	/// The blocks out of the best chain including any of its transactions.
+	fork_blocks: HashSet<BlockHash<Api>>,
+	/// Orders the bundles of the same priority by their submission.
+	insertion_id: u64,
+	watchers: Sender<ExtrinsicHash<Api>, BlockHash<Api>>,
+}
+
+/// The bundles submitted to the pool.
+pub(crate) struct Bundles<Api: ChainApi> {
+	bundles: HashMap<ExtrinsicHash<Api>, Bundle<Api>>,
+	/// The bundle of every transaction of the bundles.
+	transactions: HashMap<ExtrinsicHash<Api>, ExtrinsicHash<Api>>,
+	next_insertion_id: u64,
+}
+
+impl<Api: ChainApi> Default for Bundles<Api> {
+	fn default() -> Self {
+		Self { bundles: Default::default(), transactions: Default::default(), next_insertion_id: 0 }
+	}
+}
+
+impl<Api: ChainApi> Bundles<Api> {
+	/// Imports a validated bundle, returning a watcher of its progress if requested.
+	///
+	/// Fails if a transaction of the bundle is repeated or already part of another bundle.
+	pub(crate) fn import(
+		&mut self,
+		hash: ExtrinsicHash<Api>,
+		transactions: Vec<TransactionFor<Api>>,
+		watch: bool,
+	) -> Result<Option<Watcher<ExtrinsicHash<Api>, BlockHash<Api>>>, error::Error> {
+		if self.bundles.contains_key(&hash) {
+			return Err(error::Error::AlreadyImported(Box::new(hash)))
+		}
+		let mut hashes = HashSet::new();
+		for tx in &transactions {
+			if self.transactions.contains_key(&tx.hash) || !hashes.insert(tx.hash) {
+				return Err(error::Error::AlreadyImported(Box::new(tx.hash)))
+			}
+		}
+		if self.bundles.len() >= MAX_BUNDLES {
+			let priority = transactions[0].priority;
+			let lowest = self
+				.bundles
+				.iter()
+				.filter(|(_, bundle)| bundle.included.is_none())
+				.min_by_key(|(_, bundle)| {
+					(bundle.transactions[0].priority, std::cmp::Reverse(bundle.insertion_id))
+				})
+				.filter(|(_, bundle)| bundle.transactions[0].priority < priority)
+				.map(|(hash, _)| *hash);
+			match lowest {
+				Some(lowest) => self.remove(&lowest).watchers.dropped(),
+				None => return Err(error::Error::ImmediatelyDropped),
+			}
+		}
+
+		let mut watchers = Sender::default();
+		let watcher = watch.then(|| watchers.new_watcher(hash));
+		watchers.ready();
+
+		self.transactions.extend(hashes.into_iter().map(|tx_hash| (tx_hash, hash)));
+		self.bundles.insert(
+			hash,
+			Bundle {
+				transactions,
+				included: None,
+				fork_blocks: Default::default(),
+				insertion_id: self.next_insertion_id,
+				watchers,
+			},
+		);
+		self.next_insertion_id += 1;
+		Ok(watcher)
+	}
+
+	/// Returns true if the transaction is part of a bundle.
+	pub(crate) fn contains(&self, tx_hash: &ExtrinsicHash<Api>) -> bool {
+		self.transactions.contains_key(tx_hash)
+	}
+
+	/// Returns the bundles awaiting inclusion, ordered by the priority of their first transaction.
+	pub(crate) fn ready(&self) -> Vec<Vec<TransactionFor<Api>>> {
+		self.ready_on_fork(&Default::default(), &Default::default())
+	}
+
+	/// Returns the bundles awaiting inclusion on top of a block out of the best chain, given the
+	/// blocks of the best chain retracted and the blocks enacted from the best block to it.
+	pub(crate) fn ready_on_fork(
+		&self,
+		retracted: &HashSet<BlockHash<Api>>,
+		enacted: &HashSet<BlockHash<Api>>,
+	) -> Vec<Vec<TransactionFor<Api>>> {
+		let mut ready = self
+			.bundles
+			.values()
+			.filter(|bundle| bundle.included.map_or(true, |(hash, _)| retracted.contains(&hash)))
+			.filter(|bundle| bundle.fork_blocks.is_disjoint(enacted))
+			.collect::<Vec<_>>();
+		ready.sort_by_key(|bundle| {
+			(std::cmp::Reverse(bundle.transactions[0].priority), bundle.insertion_id)
+		});
+		ready.into_iter().map(|bundle| bundle.transactions.clone()).collect()
+	}
+
+	/// Returns the hash and first transaction of the bundles awaiting inclusion.
+	pub(crate) fn pending(&self) -> Vec<(ExtrinsicHash<Api>, TransactionFor<Api>)> {
+		self.bundles
+			.iter()
+			.filter(|(_, bundle)| bundle.included.is_none())
+			.map(|(hash, bundle)| (*hash, bundle.transactions[0].clone()))
+			.collect()
+	}
+
+	/// Removes the bundles awaiting inclusion which contain any of the given transactions,
+	/// returning their transactions.
+	pub(crate) fn remove_invalid(
+		&mut self,
+		tx_hashes: &[ExtrinsicHash<Api>],
+	) -> Vec<TransactionFor<Api>> {
+		let bundles = tx_hashes
+			.iter()
+			.filter_map(|tx_hash| self.transactions.get(tx_hash).copied())
+			.collect::<HashSet<_>>();
+		self.invalidate(bundles)
+	}
+
+	/// Removes the given bundles awaiting inclusion, notifying their watchers that they are
+	/// invalid, and returns their transactions.
+	pub(crate) fn invalidate(
+		&mut self,
+		hashes: impl IntoIterator<Item = ExtrinsicHash<Api>>,
+	) -> Vec<TransactionFor<Api>> {
+		let mut removed = Vec::new();
+		for hash in hashes {
+			if self.bundles.get(&hash).map_or(false, |bundle| bundle.included.is_none()) {
+				let mut bundle = self.remove(&hash);
+				bundle.watchers.invalid();
+				removed.extend(bundle.transactions);
+			}
+		}
+		removed
+	}
+
+	/// Marks the bundles included by a block of the best chain, given the hashes of its
+	/// extrinsics.
+	///
+	/// A bundle of which only some transactions were included can't be included anymore, and is
+	/// invalidated.
+	pub(crate) fn on_block_enacted(
+		&mut self,
+		block_hash: BlockHash<Api>,
+		extrinsics: &[ExtrinsicHash<Api>],
+	) {
+		let indices = extrinsics
+			.iter()
+			.enumerate()
+			.map(|(index, hash)| (*hash, index))
+			.collect::<HashMap<_, _>>();
+
+		let mut broken = Vec::new();
+		for (hash, bundle) in self.bundles.iter_mut().filter(|(_, b)| b.included.is_none()) {
+			let positions =
+				bundle.transactions.iter().map(|tx| indices.get(&tx.hash)).collect::<Vec<_>>();
+			match positions[0] {
+				Some(&first)
+					if positions
+						.iter()
+						.enumerate()
+						.all(|(offset, index)| *index == Some(&(first + offset))) =>
+				{
+					bundle.included = Some((block_hash, first));
+					bundle.watchers.in_block(block_hash, first);
+				},
+				_ if positions.iter().any(Option::is_some) => broken.push(*hash),
+				_ => {},
+			}
+		}
+		self.invalidate(broken);
+	}
+
+	/// Notifies the bundles included by a retracted block, which await inclusion again.
+	pub(crate) fn on_block_retracted(&mut self, block_hash: BlockHash<Api>) {
+		for bundle in self.bundles.values_mut() {
+			if matches!(bundle.included, Some((hash, _)) if hash == block_hash) {
+				bundle.included = None;
+				bundle.fork_blocks.insert(block_hash);
+				bundle.watchers.retracted(block_hash);
+			}
+		}
+	}
+
+	/// Records the bundles of which a block out of the best chain includes any transaction, so
+	/// they aren't ready on top of it.
+	pub(crate) fn on_fork_block(
+		&mut self,
+		block_hash: BlockHash<Api>,
+		extrinsics: &[ExtrinsicHash<Api>],
+	) {
+		for tx_hash in extrinsics {
+			if let Some(bundle) =
+				self.transactions.get(tx_hash).and_then(|hash| self.bundles.get_mut(hash))
+			{
+				bundle.fork_blocks.insert(block_hash);
+			}
+		}
+	}
+
+	/// Removes the bundles included by a finalized block, notifying their watchers.
+	pub(crate) fn on_block_finalized(&mut self, block_hash: BlockHash<Api>) {
+		let finalized = self
+			.bundles
+			.iter()
+			.filter_map(|(hash, bundle)| match bundle.included {
+				Some((hash_of_block, index)) if hash_of_block == block_hash => Some((*hash, index)),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		for (hash, index) in finalized {
+			self.remove(&hash).watchers.finalized(block_hash, index);
+		}
+	}
+
+	fn remove(&mut self, hash: &ExtrinsicHash<Api>) -> Bundle<Api> {
+		let bundle = self.bundles.remove(hash).expect("Only called for known bundles; qed");
+		for tx in &bundle.transactions {
+			self.transactions.remove(&tx.hash);
+		}
+		bundle
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::graph::base_pool::Transaction;
+	use futures::executor::block_on_stream;
+	use sc_transaction_pool_api::TransactionStatus;
+	use sp_runtime::transaction_validity::TransactionSource;
+	use std::sync::Arc;
+	use substrate_test_runtime::{ExtrinsicBuilder, Hash, H256};
+	use substrate_test_runtime_transaction_pool::TestApi;
+
+	fn transaction(hash: u64, priority: u64) -> TransactionFor<TestApi> {
+		Arc::new(Transaction {
+			data: ExtrinsicBuilder::new_include_data(vec![hash as u8]).build(),
+			bytes: 1,
+			hash: H256::from_low_u64_be(hash),
+			priority,
+			valid_till: 64,
+			requires: vec![],
+			provides: vec![vec![hash as u8]],
+			propagate: true,
+			private: false,
+			source: TransactionSource::External,
+		})
+	}
+
+	fn hash(n: u64) -> Hash {
+		H256::from_low_u64_be(n)
+	}
+
+	#[test]
+	fn should_order_ready_bundles_by_priority() {
+		let mut bundles = Bundles::<TestApi>::default();
+		bundles
+			.import(hash(100), vec![transaction(1, 5), transaction(2, 50)], false)
+			.unwrap();
+		bundles.import(hash(101), vec![transaction(3, 10)], false).unwrap();
+
+		let ready = bundles
+			.ready()
+			.into_iter()
+			.map(|bundle| bundle.iter().map(|tx| tx.hash).collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+		assert_eq!(ready, vec![vec![hash(3)], vec![hash(1), hash(2)]]);
+
+		// A transaction can't be part of two bundles.
+		assert!(bundles.import(hash(102), vec![transaction(2, 1)], false).is_err());
+	}
+
+	#[test]
+	fn should_follow_bundle_inclusion() {
+		let mut bundles = Bundles::<TestApi>::default();
+		let watcher = bundles
+			.import(hash(100), vec![transaction(1, 1), transaction(2, 1)], true)
+			.unwrap()
+			.unwrap();
+
+		bundles.on_block_enacted(hash(10), &[hash(7)]);
+		assert_eq!(bundles.ready().len(), 1);
+		bundles.on_block_enacted(hash(11), &[hash(7), hash(1), hash(2)]);
+		assert!(bundles.ready().is_empty());
+
+		bundles.on_block_retracted(hash(11));
+		assert_eq!(bundles.ready().len(), 1);
+
+		bundles.on_block_enacted(hash(12), &[hash(1), hash(2)]);
+		bundles.on_block_finalized(hash(12));
+		assert!(!bundles.contains(&hash(1)));
+
+		assert_eq!(
+			block_on_stream(watcher.into_stream()).collect::<Vec<_>>(),
+			vec![
+				TransactionStatus::Ready,
+				TransactionStatus::InBlock((hash(11), 1)),
+				TransactionStatus::Retracted(hash(11)),
+				TransactionStatus::InBlock((hash(12), 0)),
+				TransactionStatus::Finalized((hash(12), 0)),
+			],
+		);
+	}
+
+	#[test]
+	fn should_drop_bundles_of_the_lowest_priority_once_full() {
+		let mut bundles = Bundles::<TestApi>::default();
+		let watcher = bundles.import(hash(100), vec![transaction(0, 1)], true).unwrap().unwrap();
+		for n in 1..MAX_BUNDLES as u64 {
+			bundles.import(hash(100 + n), vec![transaction(n, 5)], false).unwrap();
+		}
+
+		// not a higher priority than any pending bundle.
+		assert!(bundles.import(hash(1000), vec![transaction(1000, 1)], false).is_err());
+		bundles.import(hash(1001), vec![transaction(1001, 5)], false).unwrap();
+		assert!(!bundles.contains(&hash(0)));
+		assert_eq!(bundles.ready().len(), MAX_BUNDLES);
+
+		assert_eq!(
+			block_on_stream(watcher.into_stream()).collect::<Vec<_>>(),
+			vec![TransactionStatus::Ready, TransactionStatus::Dropped],
+		);
+	}
+
+	#[test]
+	fn should_only_return_bundles_ready_on_fork() {
+		let mut bundles = Bundles::<TestApi>::default();
+		bundles.import(hash(100), vec![transaction(1, 1)], false).unwrap();
+		bundles.import(hash(101), vec![transaction(2, 1)], false).unwrap();
+
+		bundles.on_block_enacted(hash(10), &[hash(1)]);
+		bundles.on_fork_block(hash(11), &[hash(2)]);
+
+		let ready_on_fork = |retracted: &[Hash], enacted: &[Hash]| {
+			bundles
+				.ready_on_fork(
+					&retracted.iter().copied().collect(),
+					&enacted.iter().copied().collect(),
+				)
+				.into_iter()
+				.map(|bundle| bundle[0].hash)
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(ready_on_fork(&[], &[]), vec![hash(2)]);
+		assert_eq!(ready_on_fork(&[hash(10)], &[hash(11)]), vec![hash(1)]);
+	}
+
+	#[test]
+	fn should_invalidate_partially_included_bundles() {
+		let mut bundles = Bundles::<TestApi>::default();
+		let watcher = bundles
+			.import(hash(100), vec![transaction(1, 1), transaction(2, 1)], true)
+			.unwrap()
+			.unwrap();
+
+		bundles.on_block_enacted(hash(10), &[hash(2), hash(1)]);
+		assert!(bundles.ready().is_empty());
+		assert!(!bundles.contains(&hash(1)));
+
+		assert_eq!(
+			block_on_stream(watcher.into_stream()).collect::<Vec<_>>(),
+			vec![TransactionStatus::Ready, TransactionStatus::Invalid],
+		);
+	}
+}
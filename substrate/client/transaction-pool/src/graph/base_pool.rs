@@ -21,11 +21,20 @@
 //!
 //! For a more full-featured pool, have a look at the `pool` module.
 
-use std::{cmp::Ordering, collections::HashSet, fmt, hash, sync::Arc};
+use std::{
+	cmp::Ordering,
+	collections::{HashMap, HashSet},
+	fmt, hash,
+	sync::Arc,
+	time::Instant,
+};
 
 use crate::LOG_TARGET;
 use log::{debug, trace, warn};
-use sc_transaction_pool_api::{error, InPoolTransaction, PoolStatus};
+use sc_transaction_pool_api::{
+	error, InPoolTransaction, InspectedFutureTransaction, InspectedTransaction, MissingTag,
+	PoolStatus,
+};
 use serde::Serialize;
 use sp_core::hexdisplay::HexDisplay;
 use sp_runtime::{
@@ -103,6 +112,8 @@ pub struct Transaction<Hash, Extrinsic> {
 	pub provides: Vec<Tag>,
 	/// Should that transaction be propagated.
 	pub propagate: bool,
+	/// Was that transaction received privately, so it's never gossiped.
+	pub private: bool,
 	/// Source of that transaction.
 	pub source: Source,
 }
@@ -144,6 +155,10 @@ impl<Hash, Extrinsic> InPoolTransaction for Transaction<Hash, Extrinsic> {
 	fn is_propagable(&self) -> bool {
 		self.propagate
 	}
+
+	fn is_private(&self) -> bool {
+		self.private
+	}
 }
 
 impl<Hash: Clone, Extrinsic: Clone> Transaction<Hash, Extrinsic> {
@@ -163,6 +178,24 @@ impl<Hash: Clone, Extrinsic: Clone> Transaction<Hash, Extrinsic> {
 			requires: self.requires.clone(),
 			provides: self.provides.clone(),
 			propagate: self.propagate,
+			private: self.private,
+		}
+	}
+}
+
+impl<Hash: Clone, Extrinsic> Transaction<Hash, Extrinsic> {
+	/// Describes the transaction, imported to the pool at `imported_at`.
+	fn inspect(&self, imported_at: Instant, now: Instant) -> InspectedTransaction<Hash> {
+		InspectedTransaction {
+			hash: self.hash.clone(),
+			bytes: self.bytes,
+			priority: self.priority,
+			valid_till: self.valid_till,
+			requires: self.requires.clone(),
+			provides: self.provides.clone(),
+			source: self.source,
+			propagate: self.propagate,
+			in_pool: now.saturating_duration_since(imported_at),
 		}
 	}
 }
@@ -186,6 +219,7 @@ where
 		write!(fmt, "valid_till: {:?}, ", &self.valid_till)?;
 		write!(fmt, "bytes: {:?}, ", &self.bytes)?;
 		write!(fmt, "propagate: {:?}, ", &self.propagate)?;
+		write!(fmt, "private: {:?}, ", &self.private)?;
 		write!(fmt, "source: {:?}, ", &self.source)?;
 		write!(fmt, "requires: [{}], ", join_tags(&self.requires))?;
 		write!(fmt, "provides: [{}], ", join_tags(&self.provides))?;
@@ -198,6 +232,125 @@ where
 /// Store last pruned tags for given number of invocations.
 const RECENTLY_PRUNED_TAGS: usize = 2;
 
+/// How transactions are chosen for eviction when a queue exceeds its limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+	/// Evict the transaction with the lowest priority, whoever sent it.
+	#[default]
+	Priority,
+	/// Evict the transaction with the lowest priority among the transactions of the sender with
+	/// the most transactions in the queue.
+	Fair,
+}
+
+/// Fairness rules between the senders of the transactions.
+#[derive(Debug, Clone)]
+pub struct Fairness {
+	/// Number of leading bytes of the first tag a transaction provides which identify its sender.
+	///
+	/// With FRAME's `CheckNonce`, the tags are the encoded account followed by the nonce.
+	/// Transactions providing no tag this long have no sender.
+	pub sender_tag_prefix: usize,
+	/// Limits of the transactions of each sender, in both queues.
+	///
+	/// The newest transactions of a sender exceeding them are dropped.
+	pub sender_limit: Option<Limit>,
+	/// How transactions are chosen for eviction when a queue exceeds its limits.
+	pub eviction: EvictionPolicy,
+	/// Minimum increase of priority, in percent, for a transaction to replace the transactions
+	/// providing the same tags.
+	pub replacement_bump_percent: u64,
+}
+
+impl Default for Fairness {
+	fn default() -> Self {
+		Self {
+			sender_tag_prefix: 32,
+			sender_limit: None,
+			eviction: EvictionPolicy::Priority,
+			replacement_bump_percent: 0,
+		}
+	}
+}
+
+impl Fairness {
+	/// The sender of a transaction, if any.
+	fn sender<'a, Hash, Ex>(&self, tx: &'a Transaction<Hash, Ex>) -> Option<&'a [u8]> {
+		let prefix = self.sender_tag_prefix;
+		tx.provides
+			.first()
+			.filter(|tag| prefix > 0 && tag.len() >= prefix)
+			.map(|tag| &tag[..prefix])
+	}
+}
+
+/// The transactions of a sender in the pool, in both queues.
+#[derive(Debug)]
+struct SenderTransactions<Hash> {
+	/// The size of each transaction.
+	transactions: HashMap<Hash, usize>,
+	bytes: usize,
+}
+
+/// The transactions of each sender in the pool, kept up to date as transactions are imported and
+/// removed.
+#[derive(Debug)]
+struct Senders<Hash> {
+	senders: HashMap<Vec<u8>, SenderTransactions<Hash>>,
+	by_hash: HashMap<Hash, Vec<u8>>,
+}
+
+impl<Hash> Default for Senders<Hash> {
+	fn default() -> Self {
+		Self { senders: Default::default(), by_hash: Default::default() }
+	}
+}
+
+impl<Hash: hash::Hash + Eq + Clone> Senders<Hash> {
+	/// Records a transaction imported to the pool.
+	fn insert<Ex>(&mut self, fairness: &Fairness, tx: &Transaction<Hash, Ex>) {
+		let Some(sender) = fairness.sender(tx) else { return };
+		let transactions = self
+			.senders
+			.entry(sender.to_vec())
+			.or_insert_with(|| SenderTransactions { transactions: HashMap::new(), bytes: 0 });
+		if transactions.transactions.insert(tx.hash.clone(), tx.bytes).is_none() {
+			transactions.bytes += tx.bytes;
+			self.by_hash.insert(tx.hash.clone(), sender.to_vec());
+		}
+	}
+
+	/// Forgets a transaction removed from the pool.
+	fn remove(&mut self, hash: &Hash) {
+		let Some(sender) = self.by_hash.remove(hash) else { return };
+		let Some(transactions) = self.senders.get_mut(&sender) else { return };
+		if let Some(bytes) = transactions.transactions.remove(hash) {
+			transactions.bytes -= bytes;
+		}
+		if transactions.transactions.is_empty() {
+			self.senders.remove(&sender);
+		}
+	}
+
+	/// The senders exceeding `limit`.
+	fn exceeding(&self, limit: &Limit) -> Vec<Vec<u8>> {
+		self.senders
+			.iter()
+			.filter(|(_, txs)| limit.is_exceeded(txs.transactions.len(), txs.bytes))
+			.map(|(sender, _)| sender.clone())
+			.collect()
+	}
+}
+
+/// The sender with the most transactions, if any has more than one.
+fn largest_sender(counts: HashMap<Vec<u8>, usize>) -> Option<Vec<u8>> {
+	counts
+		.into_iter()
+		.filter(|(_, count)| *count > 1)
+		.max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then_with(|| b.cmp(a)))
+		.map(|(sender, _)| sender)
+}
+
 /// Transaction pool.
 ///
 /// Builds a dependency graph for all transactions in the pool and returns
@@ -219,6 +372,8 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	/// transactions to future in case they were just stuck in verification.
 	recently_pruned: [HashSet<Tag>; RECENTLY_PRUNED_TAGS],
 	recently_pruned_index: usize,
+	fairness: Fairness,
+	senders: Senders<Hash>,
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for BasePool<Hash, Ex> {
@@ -236,7 +391,26 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			ready: Default::default(),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
+			fairness: Default::default(),
+			senders: Default::default(),
+		}
+	}
+
+	/// Set the fairness rules between the senders of the transactions.
+	pub fn set_fairness(&mut self, fairness: Fairness) {
+		self.ready.set_replacement_bump(fairness.replacement_bump_percent);
+		self.fairness = fairness;
+
+		// The senders are identified anew.
+		let mut senders = Senders::default();
+		for tx in self.future.all() {
+			senders.insert(&self.fairness, tx);
 		}
+		self.ready.fold(|_: Option<()>, current| {
+			senders.insert(&self.fairness, &current.transaction.transaction);
+			None
+		});
+		self.senders = senders;
 	}
 
 	/// Temporary enables future transactions, runs closure and then restores
@@ -288,11 +462,15 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			}
 
 			let hash = tx.transaction.hash.clone();
+			self.senders.insert(&self.fairness, &tx.transaction);
 			self.future.import(tx);
 			return Ok(Imported::Future { hash })
 		}
 
-		self.import_to_ready(tx)
+		let transaction = tx.transaction.clone();
+		let imported = self.import_to_ready(tx)?;
+		self.senders.insert(&self.fairness, &transaction);
+		Ok(imported)
 	}
 
 	/// Imports transaction to ready queue.
@@ -338,6 +516,13 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			first = false;
 		}
 
+		for tx in &removed {
+			self.senders.remove(&tx.hash);
+		}
+		for hash in &failed {
+			self.senders.remove(hash);
+		}
+
 		// An edge case when importing transaction caused
 		// some future transactions to be imported and that
 		// future transactions pushed out current transaction.
@@ -346,7 +531,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		if removed.iter().any(|tx| tx.hash == hash) {
 			// We still need to remove all transactions that we promoted
 			// since they depend on each other and will never get to the best iterator.
-			self.ready.remove_subtree(&promoted);
+			for tx in self.ready.remove_subtree(&promoted) {
+				self.senders.remove(&tx.hash);
+			}
 
 			debug!(target: LOG_TARGET, "[{:?}] Cycle detected, bailing.", hash);
 			return Err(error::Error::CycleDetected)
@@ -365,6 +552,52 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.future.all()
 	}
 
+	/// Describes the transactions in the queues, as of `now`.
+	///
+	/// Ready transactions are returned in the order they would be included in a block.
+	pub fn inspect(
+		&self,
+		now: Instant,
+	) -> (Vec<InspectedTransaction<Hash>>, Vec<InspectedFutureTransaction<Hash>>) {
+		let ready = self
+			.ready
+			.get()
+			.map(|tx| {
+				let imported_at = self.ready.imported_at(&tx.hash).unwrap_or(now);
+				tx.inspect(imported_at, now)
+			})
+			.collect();
+
+		let providers = self
+			.future
+			.all()
+			.flat_map(|tx| tx.provides.iter().map(|tag| (tag, &tx.hash)))
+			.collect::<HashMap<_, _>>();
+		let future = self
+			.future
+			.waiting()
+			.map(|waiting| {
+				let tx = &waiting.transaction;
+				// keep the order of the requirements, for the output to be stable
+				let missing_tags = tx
+					.requires
+					.iter()
+					.filter(|tag| waiting.missing_tags.contains(*tag))
+					.map(|tag| MissingTag {
+						tag: tag.clone(),
+						provider: providers.get(tag).map(|hash| (*hash).clone()),
+					})
+					.collect();
+				InspectedFutureTransaction {
+					transaction: tx.inspect(waiting.imported_at, now),
+					missing_tags,
+				}
+			})
+			.collect();
+
+		(ready, future)
+	}
+
 	/// Returns pool transactions given list of hashes.
 	///
 	/// Includes both ready and future pool. For every hash in the `hashes`
@@ -387,17 +620,42 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	/// them. Technically the worst transaction should be evaluated by computing the entire pending
 	/// set. We use a simplified approach to remove transactions with the lowest priority first or
 	/// those that occupy the pool for the longest time in case priority is the same.
+	///
+	/// The limits of each sender are enforced first. With [`EvictionPolicy::Fair`], the worst
+	/// transaction is only looked for among the transactions of the sender with the most
+	/// transactions in the queue, and the newest of its ready transactions goes first so the
+	/// older ones it depends on stay in the pool.
 	pub fn enforce_limits(
 		&mut self,
 		ready: &Limit,
 		future: &Limit,
 	) -> Vec<Arc<Transaction<Hash, Ex>>> {
-		let mut removed = vec![];
+		let mut removed = self.enforce_sender_limit();
 
 		while ready.is_exceeded(self.ready.len(), self.ready.bytes()) {
+			let fairness = &self.fairness;
+			let sender = match fairness.eviction {
+				EvictionPolicy::Priority => None,
+				EvictionPolicy::Fair => self
+					.ready
+					.fold(|counts: Option<HashMap<Vec<u8>, usize>>, current| {
+						let mut counts = counts.unwrap_or_default();
+						if let Some(sender) = fairness.sender(&current.transaction.transaction) {
+							*counts.entry(sender.to_vec()).or_default() += 1;
+						}
+						Some(counts)
+					})
+					.and_then(largest_sender),
+			};
+
 			// find the worst transaction
 			let worst = self.ready.fold::<TransactionRef<Hash, Ex>, _>(|worst, current| {
 				let transaction = &current.transaction;
+				if sender.is_some() &&
+					fairness.sender(&transaction.transaction) != sender.as_deref()
+				{
+					return worst
+				}
 				worst
 					.map(|worst| {
 						// Here we don't use `TransactionRef`'s ordering implementation because
@@ -406,8 +664,13 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 						// newer transactions instead and drop the older ones.
 						match worst.transaction.priority.cmp(&transaction.transaction.priority) {
 							Ordering::Less => worst,
+							Ordering::Equal
+								if sender.is_some() &&
+									worst.insertion_id < transaction.insertion_id =>
+								transaction.clone(),
 							Ordering::Equal =>
-								if worst.insertion_id > transaction.insertion_id {
+								if sender.is_none() && worst.insertion_id > transaction.insertion_id
+								{
 									transaction.clone()
 								} else {
 									worst
@@ -426,8 +689,26 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		}
 
 		while future.is_exceeded(self.future.len(), self.future.bytes()) {
+			let fairness = &self.fairness;
+			let sender = match fairness.eviction {
+				EvictionPolicy::Priority => None,
+				EvictionPolicy::Fair => self
+					.future
+					.fold(|counts: Option<HashMap<Vec<u8>, usize>>, current| {
+						let mut counts = counts.unwrap_or_default();
+						if let Some(sender) = fairness.sender(&current.transaction) {
+							*counts.entry(sender.to_vec()).or_default() += 1;
+						}
+						Some(counts)
+					})
+					.and_then(largest_sender),
+			};
+
 			// find the worst transaction
 			let worst = self.future.fold(|worst, current| match worst {
+				_ if sender.is_some() &&
+					fairness.sender(&current.transaction) != sender.as_deref() =>
+					worst,
 				None => Some(current.clone()),
 				Some(ref tx) if tx.imported_at > current.imported_at => Some(current.clone()),
 				other => other,
@@ -443,6 +724,46 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		removed
 	}
 
+	/// Removes the newest transactions of the senders exceeding their limits, the future ones
+	/// first.
+	///
+	/// Only the transactions of the senders exceeding their limits are looked at.
+	fn enforce_sender_limit(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let Some(limit) = self.fairness.sender_limit.clone() else { return vec![] };
+		let mut removed = vec![];
+
+		for sender in self.senders.exceeding(&limit) {
+			while let Some(newest) = self.newest_of_exceeding_sender(&sender, &limit) {
+				removed.append(&mut self.remove_subtree(&[newest]));
+			}
+		}
+
+		removed
+	}
+
+	/// The newest transaction of `sender` if it exceeds `limit`, a future one if any.
+	fn newest_of_exceeding_sender(&self, sender: &[u8], limit: &Limit) -> Option<Hash> {
+		let transactions = self.senders.senders.get(sender)?;
+		if !limit.is_exceeded(transactions.transactions.len(), transactions.bytes) {
+			return None
+		}
+
+		let newest_future = transactions
+			.transactions
+			.keys()
+			.filter_map(|hash| Some((self.future.imported_at(hash)?, hash)))
+			.max_by_key(|(imported_at, _)| *imported_at);
+		let newest_ready = || {
+			transactions
+				.transactions
+				.keys()
+				.filter_map(|hash| Some((self.ready.insertion_id(hash)?, hash)))
+				.max_by_key(|(insertion_id, _)| *insertion_id)
+				.map(|(_, hash)| hash)
+		};
+		newest_future.map(|(_, hash)| hash).or_else(newest_ready).cloned()
+	}
+
 	/// Removes all transactions represented by the hashes and all other transactions
 	/// that depend on them.
 	///
@@ -454,12 +775,19 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	pub fn remove_subtree(&mut self, hashes: &[Hash]) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = self.ready.remove_subtree(hashes);
 		removed.extend(self.future.remove(hashes));
+		for tx in &removed {
+			self.senders.remove(&tx.hash);
+		}
 		removed
 	}
 
 	/// Removes and returns all transactions from the future queue.
 	pub fn clear_future(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
-		self.future.clear()
+		let removed = self.future.clear();
+		for tx in &removed {
+			self.senders.remove(&tx.hash);
+		}
+		removed
 	}
 
 	/// Prunes transactions that provide given list of tags.
@@ -483,6 +811,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			// store the tags for next submission
 			recently_pruned.insert(tag);
 		}
+		for tx in &pruned {
+			self.senders.remove(&tx.hash);
+		}
 
 		let mut promoted = vec![];
 		let mut failed = vec![];
@@ -495,6 +826,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 						target: LOG_TARGET,
 						"[{:?}] Failed to promote during pruning: {:?}", hash, e,
 					);
+					self.senders.remove(&hash);
 					failed.push(hash)
 				},
 			}
@@ -549,9 +881,12 @@ mod tests {
 		requires: vec![],
 		provides: vec![],
 		propagate: true,
+		private: false,
 		source: Source::External,
 	};
 
+	const DEFAULT_LIMIT: Limit = Limit { count: 100, total_bytes: 1024 };
+
 	#[test]
 	fn should_import_transaction_to_ready() {
 		// given
@@ -937,7 +1272,7 @@ mod tests {
 				}
 			),
 			"Transaction { \
-hash: 4, priority: 1000, valid_till: 64, bytes: 1, propagate: true, \
+hash: 4, priority: 1000, valid_till: 64, bytes: 1, propagate: true, private: false, \
 source: TransactionSource::External, requires: [03, 02], provides: [04], data: [4]}"
 				.to_owned()
 		);
@@ -1043,4 +1378,141 @@ source: TransactionSource::External, requires: [03, 02], provides: [04], data: [
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	fn sender_tx(sender: u8, nonce: u8, priority: u64) -> Transaction<Hash, Vec<u8>> {
+		Transaction {
+			data: vec![sender, nonce],
+			hash: ((sender as u64) << 8) | nonce as u64,
+			priority,
+			requires: if nonce == 0 { vec![] } else { vec![vec![sender, nonce - 1]] },
+			provides: vec![vec![sender, nonce]],
+			..DEFAULT_TX.clone()
+		}
+	}
+
+	#[test]
+	fn should_inspect_queues() {
+		// given
+		let mut pool = pool();
+		let now = Instant::now();
+		pool.import(sender_tx(1, 0, 5)).unwrap();
+		pool.import(sender_tx(1, 3, 5)).unwrap();
+		pool.import(sender_tx(1, 2, 5)).unwrap();
+
+		// when
+		let (ready, future) = pool.inspect(now);
+
+		// then
+		assert_eq!(
+			ready.iter().map(|tx| tx.hash).collect::<Vec<_>>(),
+			vec![sender_tx(1, 0, 5).hash]
+		);
+		assert_eq!(future.len(), 2);
+		let blocked =
+			future.iter().find(|tx| tx.transaction.hash == sender_tx(1, 3, 5).hash).unwrap();
+		assert_eq!(
+			blocked.missing_tags,
+			vec![MissingTag { tag: vec![1, 2], provider: Some(sender_tx(1, 2, 5).hash) }]
+		);
+		let waiting =
+			future.iter().find(|tx| tx.transaction.hash == sender_tx(1, 2, 5).hash).unwrap();
+		assert_eq!(waiting.missing_tags, vec![MissingTag { tag: vec![1, 1], provider: None }]);
+	}
+
+	#[test]
+	fn should_enforce_sender_limit() {
+		// given
+		let mut pool = pool();
+		pool.set_fairness(Fairness {
+			sender_tag_prefix: 1,
+			sender_limit: Some(Limit { count: 2, total_bytes: 1024 }),
+			..Default::default()
+		});
+		for nonce in 0..3 {
+			pool.import(sender_tx(1, nonce, 5)).unwrap();
+		}
+		pool.import(sender_tx(1, 5, 5)).unwrap();
+		pool.import(sender_tx(2, 0, 5)).unwrap();
+		assert_eq!(pool.ready.len(), 4);
+		assert_eq!(pool.future.len(), 1);
+
+		// when
+		let removed = pool.enforce_limits(&DEFAULT_LIMIT, &DEFAULT_LIMIT);
+
+		// then
+		let removed = removed.iter().map(|tx| tx.hash).collect::<Vec<_>>();
+		assert_eq!(removed, vec![sender_tx(1, 5, 5).hash, sender_tx(1, 2, 5).hash]);
+		assert_eq!(pool.ready.len(), 3);
+		assert_eq!(pool.future.len(), 0);
+	}
+
+	#[test]
+	fn should_keep_sender_transactions_up_to_date() {
+		// given
+		let mut pool = pool();
+		pool.set_fairness(Fairness {
+			sender_tag_prefix: 1,
+			sender_limit: Some(Limit { count: 2, total_bytes: 1024 }),
+			..Default::default()
+		});
+		for nonce in 0..4 {
+			pool.import(sender_tx(1, nonce, 5)).unwrap();
+		}
+		pool.import(sender_tx(2, 1, 5)).unwrap();
+		let count = |pool: &BasePool<Hash, Vec<u8>>, sender: u8| {
+			pool.senders.senders.get(&vec![sender]).map_or(0, |txs| txs.transactions.len())
+		};
+		assert_eq!((count(&pool, 1), count(&pool, 2)), (4, 1));
+
+		// when
+		pool.prune_tags(vec![vec![1, 0]]);
+		pool.remove_subtree(&[sender_tx(1, 3, 5).hash]);
+		pool.clear_future();
+
+		// then
+		assert_eq!((count(&pool, 1), count(&pool, 2)), (2, 0));
+		assert_eq!(pool.senders.by_hash.len(), 2);
+		assert!(pool.enforce_limits(&DEFAULT_LIMIT, &DEFAULT_LIMIT).is_empty());
+	}
+
+	#[test]
+	fn should_evict_from_the_largest_sender() {
+		// given
+		let mut pool = pool();
+		pool.set_fairness(Fairness {
+			sender_tag_prefix: 1,
+			eviction: EvictionPolicy::Fair,
+			..Default::default()
+		});
+		for nonce in 0..3 {
+			pool.import(sender_tx(1, nonce, 10)).unwrap();
+		}
+		pool.import(sender_tx(2, 0, 1)).unwrap();
+
+		// when
+		let removed = pool.enforce_limits(&Limit { count: 3, total_bytes: 1024 }, &DEFAULT_LIMIT);
+
+		// then
+		assert_eq!(removed.len(), 1);
+		assert_eq!(removed[0].hash, sender_tx(1, 2, 10).hash);
+		assert!(pool.ready.contains(&sender_tx(2, 0, 1).hash));
+		assert!(pool.ready.contains(&sender_tx(1, 0, 10).hash));
+	}
+
+	#[test]
+	fn should_evict_lowest_priority_by_default() {
+		// given
+		let mut pool = pool();
+		for nonce in 0..3 {
+			pool.import(sender_tx(1, nonce, 10)).unwrap();
+		}
+		pool.import(sender_tx(2, 0, 1)).unwrap();
+
+		// when
+		let removed = pool.enforce_limits(&Limit { count: 3, total_bytes: 1024 }, &DEFAULT_LIMIT);
+
+		// then
+		assert_eq!(removed.len(), 1);
+		assert_eq!(removed[0].hash, sender_tx(2, 0, 1).hash);
+	}
 }
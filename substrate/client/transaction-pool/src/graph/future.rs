@@ -229,11 +229,21 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		self.waiting.values().fold(None, f)
 	}
 
+	/// Returns when the transaction was imported to the pool, if it's part of the queue.
+	pub fn imported_at(&self, hash: &Hash) -> Option<Instant> {
+		self.waiting.get(hash).map(|tx| tx.imported_at)
+	}
+
 	/// Returns iterator over all future transactions
 	pub fn all(&self) -> impl Iterator<Item = &Transaction<Hash, Ex>> {
 		self.waiting.values().map(|waiting| &*waiting.transaction)
 	}
 
+	/// Returns iterator over all future transactions, along with what they are waiting for.
+	pub fn waiting(&self) -> impl Iterator<Item = &WaitingTransaction<Hash, Ex>> {
+		self.waiting.values()
+	}
+
 	/// Removes and returns all future transactions.
 	pub fn clear(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		self.wanted_tags.clear();
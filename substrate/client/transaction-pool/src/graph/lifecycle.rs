@@ -0,0 +1,188 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Record of the lifecycle of the transactions.
+//!
+//! The events of the most recently active transactions are kept in memory, and reported in a
+//! `transaction` tracing span, which lasts until the transaction leaves the pool for good.
+
+use std::{
+	collections::VecDeque,
+	fmt::Debug,
+	hash,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::LOG_TARGET;
+use linked_hash_map::LinkedHashMap;
+use sc_transaction_pool_api::{LifecycleEvent, LifecycleRecord, TransactionLifecycle};
+
+/// Maximum number of transactions whose lifecycle is tracked.
+const MAX_TRACKED_TRANSACTIONS: usize = 4096;
+
+/// Maximum number of events kept per transaction.
+const MAX_EVENTS_PER_TRANSACTION: usize = 64;
+
+/// Lifecycle of a tracked transaction.
+struct Tracked<H, BlockHash> {
+	events: VecDeque<LifecycleRecord<H, BlockHash>>,
+	span: Option<tracing::Span>,
+}
+
+/// Bounded record of the lifecycle of the transactions.
+///
+/// The least recently active transactions are forgotten first.
+pub struct Lifecycles<H: hash::Hash + Eq, BlockHash> {
+	transactions: LinkedHashMap<H, Tracked<H, BlockHash>>,
+	max_transactions: usize,
+	max_events: usize,
+}
+
+impl<H: hash::Hash + Eq, BlockHash> Default for Lifecycles<H, BlockHash> {
+	fn default() -> Self {
+		Self {
+			transactions: Default::default(),
+			max_transactions: MAX_TRACKED_TRANSACTIONS,
+			max_events: MAX_EVENTS_PER_TRANSACTION,
+		}
+	}
+}
+
+impl<H: hash::Hash + Eq + Clone + Debug, BlockHash: Clone + Debug> Lifecycles<H, BlockHash> {
+	/// Record an event of the given transaction.
+	pub fn record(&mut self, tx: &H, event: LifecycleEvent<H, BlockHash>) {
+		let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+
+		if self.transactions.get_refresh(tx).is_none() {
+			self.transactions
+				.insert(tx.clone(), Tracked { events: VecDeque::new(), span: None });
+		}
+		let tracked = self.transactions.get_mut(tx).expect("Inserted above; qed");
+
+		let span = tracked.span.get_or_insert_with(
+			|| tracing::debug_span!(target: LOG_TARGET, "transaction", hash = ?tx),
+		);
+		span.in_scope(|| tracing::debug!(target: LOG_TARGET, event = ?event, "Lifecycle event"));
+		if is_final(&event) {
+			tracked.span = None;
+		}
+
+		if tracked.events.len() >= self.max_events {
+			tracked.events.pop_front();
+		}
+		tracked.events.push_back(LifecycleRecord { time, event });
+
+		while self.transactions.len() > self.max_transactions {
+			self.transactions.pop_front();
+		}
+	}
+
+	/// Returns the recorded lifecycle of the given transaction, if it's still tracked.
+	pub fn get(&self, tx: &H) -> Option<TransactionLifecycle<H, BlockHash>> {
+		self.transactions.get(tx).map(|tracked| TransactionLifecycle {
+			hash: tx.clone(),
+			events: tracked.events.iter().cloned().collect(),
+		})
+	}
+}
+
+/// Whether the transaction left the pool for good after the given event.
+fn is_final<H, BlockHash>(event: &LifecycleEvent<H, BlockHash>) -> bool {
+	matches!(
+		event,
+		LifecycleEvent::Rejected(_) |
+			LifecycleEvent::FinalityTimeout(_) |
+			LifecycleEvent::Finalized(_) |
+			LifecycleEvent::Usurped(_) |
+			LifecycleEvent::Dropped |
+			LifecycleEvent::Invalid
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	type Hash = u64;
+
+	fn lifecycles() -> Lifecycles<Hash, Hash> {
+		Lifecycles { max_transactions: 2, max_events: 3, ..Default::default() }
+	}
+
+	fn events(lifecycles: &Lifecycles<Hash, Hash>, tx: Hash) -> Vec<LifecycleEvent<Hash, Hash>> {
+		lifecycles
+			.get(&tx)
+			.map(|lifecycle| lifecycle.events.into_iter().map(|record| record.event).collect())
+			.unwrap_or_default()
+	}
+
+	#[test]
+	fn should_record_events_in_order() {
+		let mut lifecycles = lifecycles();
+
+		lifecycles.record(&1, LifecycleEvent::Future);
+		lifecycles.record(&1, LifecycleEvent::Ready);
+		lifecycles.record(&1, LifecycleEvent::InBlock(10));
+
+		let lifecycle = lifecycles.get(&1).unwrap();
+		assert_eq!(lifecycle.hash, 1);
+		assert_eq!(
+			events(&lifecycles, 1),
+			vec![LifecycleEvent::Future, LifecycleEvent::Ready, LifecycleEvent::InBlock(10)]
+		);
+		assert!(lifecycles.get(&2).is_none());
+	}
+
+	#[test]
+	fn should_keep_the_most_recent_events() {
+		let mut lifecycles = lifecycles();
+
+		lifecycles.record(&1, LifecycleEvent::Ready);
+		lifecycles.record(&1, LifecycleEvent::Propagated(vec!["a".into()]));
+		lifecycles.record(&1, LifecycleEvent::InBlock(10));
+		lifecycles.record(&1, LifecycleEvent::Finalized(10));
+
+		assert_eq!(
+			events(&lifecycles, 1),
+			vec![
+				LifecycleEvent::Propagated(vec!["a".into()]),
+				LifecycleEvent::InBlock(10),
+				LifecycleEvent::Finalized(10),
+			]
+		);
+	}
+
+	#[test]
+	fn should_forget_the_least_recently_active_transactions() {
+		let mut lifecycles = lifecycles();
+
+		lifecycles.record(&1, LifecycleEvent::Ready);
+		lifecycles.record(&2, LifecycleEvent::Ready);
+		// Transaction 1 is now more recently active than transaction 2
+		lifecycles.record(&1, LifecycleEvent::InBlock(10));
+		lifecycles.record(&3, LifecycleEvent::Ready);
+
+		assert_eq!(
+			events(&lifecycles, 1),
+			vec![LifecycleEvent::Ready, LifecycleEvent::InBlock(10)]
+		);
+		assert!(lifecycles.get(&2).is_none());
+		assert_eq!(events(&lifecycles, 3), vec![LifecycleEvent::Ready]);
+	}
+}
@@ -22,15 +22,17 @@ use std::{collections::HashMap, fmt::Debug, hash};
 use crate::LOG_TARGET;
 use linked_hash_map::LinkedHashMap;
 use log::{debug, trace};
+use sc_transaction_pool_api::{LifecycleEvent, TransactionLifecycle};
 use serde::Serialize;
-use sp_runtime::traits;
+use sp_runtime::{traits, transaction_validity::TransactionSource};
 
-use super::{watcher, BlockHash, ChainApi, ExtrinsicHash};
+use super::{lifecycle::Lifecycles, watcher, BlockHash, ChainApi, ExtrinsicHash};
 
 /// Extrinsic pool default listener.
 pub struct Listener<H: hash::Hash + Eq, C: ChainApi> {
 	watchers: HashMap<H, watcher::Sender<H, ExtrinsicHash<C>>>,
 	finality_watchers: LinkedHashMap<ExtrinsicHash<C>, Vec<H>>,
+	lifecycles: Lifecycles<H, BlockHash<C>>,
 }
 
 /// Maximum number of blocks awaiting finality at any time.
@@ -38,7 +40,11 @@ const MAX_FINALITY_WATCHERS: usize = 512;
 
 impl<H: hash::Hash + Eq + Debug, C: ChainApi> Default for Listener<H, C> {
 	fn default() -> Self {
-		Self { watchers: Default::default(), finality_watchers: Default::default() }
+		Self {
+			watchers: Default::default(),
+			finality_watchers: Default::default(),
+			lifecycles: Default::default(),
+		}
 	}
 }
 
@@ -67,17 +73,45 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 		sender.new_watcher(hash)
 	}
 
+	/// Returns the recorded lifecycle of given extrinsic, if it's still tracked.
+	pub fn lifecycle(&self, hash: &H) -> Option<TransactionLifecycle<H, BlockHash<C>>> {
+		self.lifecycles.get(hash)
+	}
+
+	/// Extrinsic was submitted to the pool, and validated at given block.
+	pub fn submitted(&mut self, tx: &H, source: TransactionSource, at: BlockHash<C>) {
+		trace!(target: LOG_TARGET, "[{:?}] Submitted at {:?}", tx, at);
+		self.lifecycles.record(tx, LifecycleEvent::Submitted { source, at });
+	}
+
+	/// Extrinsic was rejected by the validation.
+	pub fn rejected(&mut self, tx: &H, error: String) {
+		trace!(target: LOG_TARGET, "[{:?}] Rejected: {}", tx, error);
+		self.lifecycles.record(tx, LifecycleEvent::Rejected(error));
+	}
+
+	/// Extrinsics were included in the proposal of given block.
+	pub fn proposed(&mut self, block_hash: BlockHash<C>, txs: &[H]) {
+		for tx in txs {
+			trace!(target: LOG_TARGET, "[{:?}] Proposed in {:?}", tx, block_hash);
+			self.lifecycles.record(tx, LifecycleEvent::Proposed(block_hash));
+		}
+	}
+
 	/// Notify the listeners about extrinsic broadcast.
 	pub fn broadcasted(&mut self, hash: &H, peers: Vec<String>) {
 		trace!(target: LOG_TARGET, "[{:?}] Broadcasted", hash);
+		self.lifecycles.record(hash, LifecycleEvent::Propagated(peers.clone()));
 		self.fire(hash, |watcher| watcher.broadcast(peers));
 	}
 
 	/// New transaction was added to the ready pool or promoted from the future pool.
 	pub fn ready(&mut self, tx: &H, old: Option<&H>) {
 		trace!(target: LOG_TARGET, "[{:?}] Ready (replaced with {:?})", tx, old);
+		self.lifecycles.record(tx, LifecycleEvent::Ready);
 		self.fire(tx, |watcher| watcher.ready());
 		if let Some(old) = old {
+			self.lifecycles.record(old, LifecycleEvent::Usurped(tx.clone()));
 			self.fire(old, |watcher| watcher.usurped(tx.clone()));
 		}
 	}
@@ -85,12 +119,20 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 	/// New transaction was added to the future pool.
 	pub fn future(&mut self, tx: &H) {
 		trace!(target: LOG_TARGET, "[{:?}] Future", tx);
+		self.lifecycles.record(tx, LifecycleEvent::Future);
 		self.fire(tx, |watcher| watcher.future());
 	}
 
 	/// Transaction was dropped from the pool because of the limit.
 	pub fn dropped(&mut self, tx: &H, by: Option<&H>) {
 		trace!(target: LOG_TARGET, "[{:?}] Dropped (replaced with {:?})", tx, by);
+		self.lifecycles.record(
+			tx,
+			match by {
+				Some(t) => LifecycleEvent::Usurped(t.clone()),
+				None => LifecycleEvent::Dropped,
+			},
+		);
 		self.fire(tx, |watcher| match by {
 			Some(t) => watcher.usurped(t.clone()),
 			None => watcher.dropped(),
@@ -100,23 +142,43 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 	/// Transaction was removed as invalid.
 	pub fn invalid(&mut self, tx: &H) {
 		debug!(target: LOG_TARGET, "[{:?}] Extrinsic invalid", tx);
+		self.lifecycles.record(tx, LifecycleEvent::Invalid);
 		self.fire(tx, |watcher| watcher.invalid());
 	}
 
 	/// Transaction was pruned from the pool.
 	pub fn pruned(&mut self, block_hash: BlockHash<C>, tx: &H) {
 		debug!(target: LOG_TARGET, "[{:?}] Pruned at {:?}", tx, block_hash);
+		self.in_block(block_hash, tx);
+	}
+
+	/// Watched transaction was included in a block of a fork the pool doesn't follow.
+	pub fn in_fork_block(&mut self, block_hash: BlockHash<C>, tx: &H) {
+		if !self.watchers.contains_key(tx) {
+			return
+		}
+		debug!(target: LOG_TARGET, "[{:?}] Included in fork block {:?}", tx, block_hash);
+		self.in_block(block_hash, tx);
+	}
+
+	fn in_block(&mut self, block_hash: BlockHash<C>, tx: &H) {
 		// Get the transactions included in the given block hash.
 		let txs = self.finality_watchers.entry(block_hash).or_insert(vec![]);
+		// The block was already reported while it was on a fork.
+		if txs.contains(tx) {
+			return
+		}
 		txs.push(tx.clone());
 		// Current transaction is the last one included.
 		let tx_index = txs.len() - 1;
+		self.lifecycles.record(tx, LifecycleEvent::InBlock(block_hash));
 
 		self.fire(tx, |watcher| watcher.in_block(block_hash, tx_index));
 
 		while self.finality_watchers.len() > MAX_FINALITY_WATCHERS {
 			if let Some((hash, txs)) = self.finality_watchers.pop_front() {
 				for tx in txs {
+					self.lifecycles.record(&tx, LifecycleEvent::FinalityTimeout(hash));
 					self.fire(&tx, |watcher| watcher.finality_timeout(hash));
 				}
 			}
@@ -127,6 +189,7 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 	pub fn retracted(&mut self, block_hash: BlockHash<C>) {
 		if let Some(hashes) = self.finality_watchers.remove(&block_hash) {
 			for hash in hashes {
+				self.lifecycles.record(&hash, LifecycleEvent::Retracted(block_hash));
 				self.fire(&hash, |watcher| watcher.retracted(block_hash))
 			}
 		}
@@ -142,6 +205,7 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 					hash,
 					block_hash,
 				);
+				self.lifecycles.record(&hash, LifecycleEvent::Finalized(block_hash));
 				self.fire(&hash, |watcher| watcher.finalized(block_hash, tx_index))
 			}
 		}
@@ -28,6 +28,7 @@
 #![warn(unused_extern_crates)]
 
 mod future;
+mod lifecycle;
 mod listener;
 mod pool;
 mod ready;
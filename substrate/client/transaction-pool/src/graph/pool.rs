@@ -120,6 +120,10 @@ pub struct Options {
 	pub reject_future_transactions: bool,
 	/// How long the extrinsic is banned for.
 	pub ban_time: Duration,
+	/// Keep the transactions in a journal on disk, to resubmit them once the pool is recreated.
+	pub journal: Option<crate::JournalOptions>,
+	/// Limits, eviction and replacement rules between the senders of the transactions.
+	pub fairness: base::Fairness,
 }
 
 impl Default for Options {
@@ -129,6 +133,8 @@ impl Default for Options {
 			future: base::Limit { count: 512, total_bytes: 1 * 1024 * 1024 },
 			reject_future_transactions: false,
 			ban_time: Duration::from_secs(60 * 30),
+			journal: None,
+			fairness: Default::default(),
 		}
 	}
 }
@@ -141,6 +147,17 @@ enum CheckBannedBeforeVerify {
 	No,
 }
 
+/// Should we record the submission of the transaction in its lifecycle once verified?
+///
+/// Only the transactions submitted to the pool are recorded, not the ones verified again, and
+/// the transactions rejected by the validation only when submitted locally, so the garbage
+/// received from peers doesn't evict the lifecycles of the pool's transactions.
+#[derive(Copy, Clone)]
+enum RecordSubmission {
+	Yes,
+	No,
+}
+
 /// Extrinsics pool that performs validation.
 pub struct Pool<B: ChainApi> {
 	validated_pool: Arc<ValidatedPool<B>>,
@@ -160,10 +177,33 @@ impl<B: ChainApi> Pool<B> {
 		xts: impl IntoIterator<Item = ExtrinsicFor<B>>,
 	) -> Result<Vec<Result<ExtrinsicHash<B>, B::Error>>, B::Error> {
 		let xts = xts.into_iter().map(|xt| (source, xt));
-		let validated_transactions = self.verify(at, xts, CheckBannedBeforeVerify::Yes).await?;
+		let validated_transactions = self
+			.verify(at, xts, CheckBannedBeforeVerify::Yes, RecordSubmission::Yes)
+			.await?;
 		Ok(self.validated_pool.submit(validated_transactions.into_values()))
 	}
 
+	/// Imports a bunch of unverified extrinsics which were received privately to the pool.
+	///
+	/// Those are never propagated, even after being revalidated or resubmitted.
+	pub async fn submit_private_at(
+		&self,
+		at: <B::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xts: impl IntoIterator<Item = ExtrinsicFor<B>>,
+	) -> Result<Vec<Result<ExtrinsicHash<B>, B::Error>>, B::Error> {
+		let xts = xts.into_iter().map(|xt| (source, xt));
+		let validated_transactions = self
+			.verify(at, xts, CheckBannedBeforeVerify::Yes, RecordSubmission::Yes)
+			.await?;
+		Ok(self.validated_pool.submit(validated_transactions.into_values().map(|mut tx| {
+			if let ValidatedTransaction::Valid(ref mut tx) = tx {
+				tx.private = true;
+			}
+			tx
+		})))
+	}
+
 	/// Resubmit the given extrinsics to the pool.
 	///
 	/// This does not check if a transaction is banned, before we verify it again.
@@ -174,7 +214,8 @@ impl<B: ChainApi> Pool<B> {
 		xts: impl IntoIterator<Item = ExtrinsicFor<B>>,
 	) -> Result<Vec<Result<ExtrinsicHash<B>, B::Error>>, B::Error> {
 		let xts = xts.into_iter().map(|xt| (source, xt));
-		let validated_transactions = self.verify(at, xts, CheckBannedBeforeVerify::No).await?;
+		let validated_transactions =
+			self.verify(at, xts, CheckBannedBeforeVerify::No, RecordSubmission::No).await?;
 		Ok(self.validated_pool.submit(validated_transactions.into_values()))
 	}
 
@@ -198,11 +239,47 @@ impl<B: ChainApi> Pool<B> {
 	) -> Result<Watcher<ExtrinsicHash<B>, ExtrinsicHash<B>>, B::Error> {
 		let block_number = self.resolve_block_number(&BlockId::Hash(at))?;
 		let (_, tx) = self
-			.verify_one(at, block_number, source, xt, CheckBannedBeforeVerify::Yes)
+			.verify_one(
+				at,
+				block_number,
+				source,
+				xt,
+				CheckBannedBeforeVerify::Yes,
+				RecordSubmission::Yes,
+			)
 			.await;
 		self.validated_pool.submit_and_watch(tx)
 	}
 
+	/// Validates the extrinsics of a bundle at given block, without importing them to the pool.
+	///
+	/// Fails with the error of the first extrinsic which isn't valid.
+	pub async fn verify_bundle(
+		&self,
+		at: <B::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xts: Vec<ExtrinsicFor<B>>,
+	) -> Result<Vec<TransactionFor<B>>, B::Error> {
+		let block_number = self.resolve_block_number(&BlockId::Hash(at))?;
+		futures::future::join_all(xts.into_iter().map(|xt| {
+			self.verify_one(
+				at,
+				block_number,
+				source,
+				xt,
+				CheckBannedBeforeVerify::Yes,
+				RecordSubmission::No,
+			)
+		}))
+		.await
+		.into_iter()
+		.map(|(_, tx)| match tx {
+			ValidatedTransaction::Valid(tx) => Ok(Arc::new(tx)),
+			ValidatedTransaction::Invalid(_, e) | ValidatedTransaction::Unknown(_, e) => Err(e),
+		})
+		.collect()
+	}
+
 	/// Resubmit some transaction that were validated elsewhere.
 	pub fn resubmit(
 		&self,
@@ -245,12 +322,14 @@ impl<B: ChainApi> Pool<B> {
 	/// To perform pruning we need the tags that each extrinsic provides and to avoid calling
 	/// into runtime too often we first lookup all extrinsics that are in the pool and get
 	/// their provided tags from there. Otherwise we query the runtime at the `parent` block.
+	///
+	/// Returns the tags provided by the extrinsics.
 	pub async fn prune(
 		&self,
 		at: <B::Block as BlockT>::Hash,
 		parent: <B::Block as BlockT>::Hash,
 		extrinsics: &[ExtrinsicFor<B>],
-	) -> Result<(), B::Error> {
+	) -> Result<Vec<Tag>, B::Error> {
 		log::debug!(
 			target: LOG_TARGET,
 			"Starting pruning of block {:?} (extrinsics: {})",
@@ -299,7 +378,8 @@ impl<B: ChainApi> Pool<B> {
 			}
 		}
 
-		self.prune_tags(at, future_tags, in_pool_hashes).await
+		self.prune_tags(at, future_tags.clone(), in_pool_hashes).await?;
+		Ok(future_tags)
 	}
 
 	/// Prunes ready transactions that provide given list of tags.
@@ -345,8 +425,9 @@ impl<B: ChainApi> Pool<B> {
 		let pruned_transactions =
 			prune_status.pruned.into_iter().map(|tx| (tx.source, tx.data.clone()));
 
-		let reverified_transactions =
-			self.verify(at, pruned_transactions, CheckBannedBeforeVerify::Yes).await?;
+		let reverified_transactions = self
+			.verify(at, pruned_transactions, CheckBannedBeforeVerify::Yes, RecordSubmission::No)
+			.await?;
 
 		log::trace!(target: LOG_TARGET, "Pruning at {:?}. Resubmitting transactions.", at);
 		// And finally - submit reverified transactions back to the pool
@@ -377,13 +458,14 @@ impl<B: ChainApi> Pool<B> {
 		at: <B::Block as BlockT>::Hash,
 		xts: impl IntoIterator<Item = (TransactionSource, ExtrinsicFor<B>)>,
 		check: CheckBannedBeforeVerify,
+		record: RecordSubmission,
 	) -> Result<HashMap<ExtrinsicHash<B>, ValidatedTransactionFor<B>>, B::Error> {
 		// we need a block number to compute tx validity
 		let block_number = self.resolve_block_number(&BlockId::Hash(at))?;
 
 		let res = futures::future::join_all(
 			xts.into_iter()
-				.map(|(source, xt)| self.verify_one(at, block_number, source, xt, check)),
+				.map(|(source, xt)| self.verify_one(at, block_number, source, xt, check, record)),
 		)
 		.await
 		.into_iter()
@@ -400,6 +482,7 @@ impl<B: ChainApi> Pool<B> {
 		source: TransactionSource,
 		xt: ExtrinsicFor<B>,
 		check: CheckBannedBeforeVerify,
+		record: RecordSubmission,
 	) -> (ExtrinsicHash<B>, ValidatedTransactionFor<B>) {
 		let (hash, bytes) = self.validated_pool.api().hash_and_length(&xt);
 
@@ -414,13 +497,9 @@ impl<B: ChainApi> Pool<B> {
 			.validate_transaction(block_hash, source, xt.clone())
 			.await;
 
-		let status = match validation_result {
-			Ok(status) => status,
-			Err(e) => return (hash, ValidatedTransaction::Invalid(hash, e)),
-		};
-
-		let validity = match status {
-			Ok(validity) =>
+		let validity = match validation_result {
+			Err(e) => ValidatedTransaction::Invalid(hash, e),
+			Ok(Ok(validity)) =>
 				if validity.provides.is_empty() {
 					ValidatedTransaction::Invalid(hash, error::Error::NoTagsProvided.into())
 				} else {
@@ -433,12 +512,23 @@ impl<B: ChainApi> Pool<B> {
 						validity,
 					)
 				},
-			Err(TransactionValidityError::Invalid(e)) =>
+			Ok(Err(TransactionValidityError::Invalid(e))) =>
 				ValidatedTransaction::Invalid(hash, error::Error::InvalidTransaction(e).into()),
-			Err(TransactionValidityError::Unknown(e)) =>
+			Ok(Err(TransactionValidityError::Unknown(e))) =>
 				ValidatedTransaction::Unknown(hash, error::Error::UnknownTransaction(e).into()),
 		};
 
+		if let RecordSubmission::Yes = record {
+			match validity {
+				ValidatedTransaction::Invalid(_, ref e) =>
+					if source == TransactionSource::Local {
+						self.validated_pool.on_submitted(&hash, source, block_hash);
+						self.validated_pool.on_rejected(&hash, e);
+					},
+				_ => self.validated_pool.on_submitted(&hash, source, block_hash),
+			}
+		}
+
 		(hash, validity)
 	}
 
@@ -462,7 +552,7 @@ mod tests {
 	use codec::Encode;
 	use futures::executor::block_on;
 	use parking_lot::Mutex;
-	use sc_transaction_pool_api::TransactionStatus;
+	use sc_transaction_pool_api::{LifecycleEvent, TransactionStatus};
 	use sp_runtime::transaction_validity::TransactionSource;
 	use std::{collections::HashMap, time::Instant};
 	use substrate_test_runtime::{AccountId, ExtrinsicBuilder, Transfer, H256};
@@ -492,6 +582,85 @@ mod tests {
 		assert_eq!(pool.validated_pool().ready().map(|v| v.hash).collect::<Vec<_>>(), vec![hash]);
 	}
 
+	#[test]
+	fn should_keep_private_transactions_private_once_revalidated() {
+		// given
+		let (pool, api) = pool();
+		let at = api.expect_hash_from_number(0);
+		let uxt = uxt(Transfer {
+			from: Alice.into(),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+		let hash = block_on(pool.submit_private_at(at, SOURCE, vec![uxt.clone()])).unwrap()[0]
+			.clone()
+			.unwrap();
+		assert!(pool.validated_pool().ready().all(|tx| tx.private));
+
+		// when
+		let (_, revalidated) = block_on(pool.verify_one(
+			at,
+			0,
+			SOURCE,
+			uxt,
+			CheckBannedBeforeVerify::No,
+			RecordSubmission::No,
+		));
+		pool.resubmit(HashMap::from([(hash, revalidated)]));
+
+		// then
+		assert_eq!(
+			pool.validated_pool()
+				.ready()
+				.map(|tx| (tx.hash, tx.private))
+				.collect::<Vec<_>>(),
+			vec![(hash, true)],
+		);
+	}
+
+	#[test]
+	fn should_record_submissions_but_not_rejected_external_transactions() {
+		// given
+		let (pool, api) = pool();
+		let at = api.expect_hash_from_number(0);
+		let transfer = |amount, nonce| {
+			uxt(Transfer {
+				from: Alice.into(),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount,
+				nonce,
+			})
+		};
+		let events = |hash| {
+			pool.validated_pool()
+				.lifecycle(&hash)
+				.map(|lifecycle| lifecycle.events.into_iter().map(|record| record.event).collect())
+		};
+
+		// when
+		let valid = block_on(pool.submit_one(at, SOURCE, transfer(5, 0))).unwrap();
+		let external = pool.hash_of(&transfer(5, INVALID_NONCE));
+		block_on(pool.submit_one(at, SOURCE, transfer(5, INVALID_NONCE))).unwrap_err();
+		let local = pool.hash_of(&transfer(6, INVALID_NONCE));
+		block_on(pool.submit_one(at, TransactionSource::Local, transfer(6, INVALID_NONCE)))
+			.unwrap_err();
+
+		// then
+		assert_eq!(
+			events(valid),
+			Some(vec![LifecycleEvent::Submitted { source: SOURCE, at }, LifecycleEvent::Ready]),
+		);
+		assert_eq!(events(external), None);
+		assert_matches!(
+			&events(local).unwrap()[..],
+			[
+				LifecycleEvent::Submitted { source: TransactionSource::Local, .. },
+				LifecycleEvent::Rejected(_),
+			]
+		);
+	}
+
 	#[test]
 	fn should_reject_if_temporarily_banned() {
 		// given
@@ -22,6 +22,7 @@ use std::{
 	collections::{BTreeSet, HashMap, HashSet},
 	hash,
 	sync::Arc,
+	time::Instant,
 };
 
 use crate::LOG_TARGET;
@@ -87,6 +88,8 @@ pub struct ReadyTx<Hash, Ex> {
 	/// Some transactions might be already pruned from the queue,
 	/// so when we compute ready set we may consider this transactions ready earlier.
 	pub requires_offset: usize,
+	/// Time of import to the pool.
+	pub imported_at: Instant,
 }
 
 impl<Hash: Clone, Ex> Clone for ReadyTx<Hash, Ex> {
@@ -95,6 +98,7 @@ impl<Hash: Clone, Ex> Clone for ReadyTx<Hash, Ex> {
 			transaction: self.transaction.clone(),
 			unlocks: self.unlocks.clone(),
 			requires_offset: self.requires_offset,
+			imported_at: self.imported_at,
 		}
 	}
 }
@@ -118,6 +122,8 @@ pub struct ReadyTransactions<Hash: hash::Hash + Eq, Ex> {
 	/// Best transactions that are ready to be included to the block without any other previous
 	/// transaction.
 	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	/// Minimum increase of priority, in percent, for a transaction to replace others.
+	replacement_bump_percent: u64,
 }
 
 impl<Hash, Ex> tracked_map::Size for ReadyTx<Hash, Ex> {
@@ -133,11 +139,18 @@ impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 			provided_tags: Default::default(),
 			ready: Default::default(),
 			best: Default::default(),
+			replacement_bump_percent: 0,
 		}
 	}
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
+	/// Sets the minimum increase of priority, in percent, for a transaction to replace the
+	/// transactions providing the same tags.
+	pub fn set_replacement_bump(&mut self, percent: u64) {
+		self.replacement_bump_percent = percent;
+	}
+
 	/// Borrows a map of tags that are provided by transactions in this queue.
 	pub fn provided_tags(&self) -> &HashMap<Tag, Hash> {
 		&self.provided_tags
@@ -193,6 +206,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 		let insertion_id = self.insertion_id;
 		let hash = tx.transaction.hash.clone();
 		let transaction = tx.transaction;
+		let imported_at = tx.imported_at;
 
 		let (replaced, unlocks) = self.replace_previous(&transaction)?;
 
@@ -227,7 +241,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 		}
 
 		// insert to Ready
-		ready.insert(hash, ReadyTx { transaction, unlocks, requires_offset });
+		ready.insert(hash, ReadyTx { transaction, unlocks, requires_offset, imported_at });
 
 		Ok(replaced)
 	}
@@ -245,6 +259,16 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 		self.ready.read().contains_key(hash)
 	}
 
+	/// Returns when the transaction was imported to the pool, if it's part of the queue.
+	pub fn imported_at(&self, hash: &Hash) -> Option<Instant> {
+		self.ready.read().get(hash).map(|tx| tx.imported_at)
+	}
+
+	/// Returns the insertion id of the transaction, if it's part of the queue.
+	pub fn insertion_id(&self, hash: &Hash) -> Option<u64> {
+		self.ready.read().get(hash).map(|tx| tx.transaction.insertion_id)
+	}
+
 	/// Retrieve transaction by hash
 	pub fn by_hash(&self, hash: &Hash) -> Option<Arc<Transaction<Hash, Ex>>> {
 		self.by_hashes(&[hash.clone()]).into_iter().next().unwrap_or(None)
@@ -414,7 +438,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 	///
 	/// In case that's true it determines if the priority of transactions that
 	/// we are about to replace is lower than the priority of the replacement transaction.
-	/// We remove/replace old transactions in case they have lower priority.
+	/// We remove/replace old transactions in case they have lower priority, by at least the
+	/// configured bump.
 	///
 	/// In case replacement is successful returns a list of removed transactions
 	/// and a list of hashes that are still in pool and gets unlocked by the new transaction.
@@ -447,7 +472,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 			};
 
 			// bail - the transaction has too low priority to replace the old ones
-			if old_priority >= tx.priority {
+			let required_priority = old_priority
+				.saturating_add(old_priority.saturating_mul(self.replacement_bump_percent) / 100);
+			if old_priority >= tx.priority || required_priority > tx.priority {
 				return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
 			}
 
@@ -601,6 +628,7 @@ mod tests {
 			requires: vec![vec![1], vec![2]],
 			provides: vec![vec![3], vec![4]],
 			propagate: true,
+			private: false,
 			source: Source::External,
 		}
 	}
@@ -678,6 +706,29 @@ mod tests {
 		assert_eq!(ready.get().count(), 3);
 	}
 
+	#[test]
+	fn should_require_priority_bump_to_replace() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		ready.set_replacement_bump(10);
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.priority = 100;
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+		tx2.priority = 105;
+		import(&mut ready, tx1).unwrap();
+
+		// when
+		let err = import(&mut ready, tx2.clone()).unwrap_err();
+		tx2.priority = 110;
+		import(&mut ready, tx2).unwrap();
+
+		// then
+		assert!(matches!(err, error::Error::TooLowPriority { old: 100, new: 105 }));
+		assert_eq!(ready.get().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+	}
+
 	/// Populate the pool, with a graph that looks like so:
 	///
 	/// tx1 -> tx2 \
@@ -711,6 +762,7 @@ mod tests {
 			requires: vec![tx1.provides[0].clone()],
 			provides: vec![],
 			propagate: true,
+			private: false,
 			source: Source::External,
 		};
 
@@ -96,6 +96,13 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 		true
 	}
 
+	/// Returns the extrinsics which are still banned at `now`.
+	pub fn banned(&self, now: &Instant) -> Vec<Hash> {
+		let banned = self.banned_until.read();
+
+		banned.iter().filter(|(_, v)| *v >= now).map(|(hash, _)| hash.clone()).collect()
+	}
+
 	/// Removes timed bans.
 	pub fn clear_timeouts(&self, now: &Instant) {
 		let mut banned = self.banned_until.write();
@@ -127,6 +134,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![],
 			propagate: true,
+			private: false,
 			source: TransactionSource::External,
 		};
 
@@ -193,6 +201,7 @@ mod tests {
 				requires: vec![],
 				provides: vec![],
 				propagate: true,
+				private: false,
 				source: TransactionSource::External,
 			}
 		}
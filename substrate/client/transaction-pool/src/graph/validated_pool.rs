@@ -26,7 +26,9 @@ use std::{
 use crate::LOG_TARGET;
 use futures::channel::mpsc::{channel, Sender};
 use parking_lot::{Mutex, RwLock};
-use sc_transaction_pool_api::{error, PoolStatus, ReadyTransactions};
+use sc_transaction_pool_api::{
+	error, PoolInspection, PoolStatus, ReadyTransactions, TransactionLifecycle,
+};
 use serde::Serialize;
 use sp_runtime::{
 	generic::BlockId,
@@ -77,6 +79,7 @@ impl<Hash, Ex, Error> ValidatedTransaction<Hash, Ex, Error> {
 			requires: validity.requires,
 			provides: validity.provides,
 			propagate: validity.propagate,
+			private: false,
 			valid_till: at.saturated_into::<u64>().saturating_add(validity.longevity),
 		})
 	}
@@ -115,7 +118,8 @@ pub struct ValidatedPool<B: ChainApi> {
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, is_validator: IsValidator, api: Arc<B>) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+		let mut base_pool = base::BasePool::new(options.reject_future_transactions);
+		base_pool.set_fairness(options.fairness.clone());
 		let ban_time = options.ban_time;
 		Self {
 			is_validator,
@@ -334,7 +338,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 				for removed_tx in removed {
 					let removed_hash = removed_tx.hash;
 					let updated_transaction = updated_transactions.remove(&removed_hash);
-					let tx_to_resubmit = if let Some(updated_tx) = updated_transaction {
+					let tx_to_resubmit = if let Some(mut updated_tx) = updated_transaction {
+						// revalidation doesn't know how the transaction has been received
+						if let ValidatedTransaction::Valid(ref mut tx) = updated_tx {
+							tx.private = removed_tx.private;
+						}
 						updated_tx
 					} else {
 						// in most cases we'll end up in successful `try_unwrap`, but if not
@@ -576,6 +584,34 @@ impl<B: ChainApi> ValidatedPool<B> {
 		stream
 	}
 
+	/// Invoked when an extrinsic submitted to the pool has been validated at the given block.
+	pub fn on_submitted(
+		&self,
+		hash: &ExtrinsicHash<B>,
+		source: TransactionSource,
+		at: BlockHash<B>,
+	) {
+		self.listener.write().submitted(hash, source, at);
+	}
+
+	/// Invoked when the validation of an extrinsic failed.
+	pub fn on_rejected(&self, hash: &ExtrinsicHash<B>, error: &B::Error) {
+		self.listener.write().rejected(hash, error.to_string());
+	}
+
+	/// Invoked when extrinsics are included in the proposal of a block.
+	pub fn on_proposed(&self, block_hash: BlockHash<B>, hashes: &[ExtrinsicHash<B>]) {
+		self.listener.write().proposed(block_hash, hashes);
+	}
+
+	/// Returns the recorded lifecycle of the given extrinsic, if it's still tracked.
+	pub fn lifecycle(
+		&self,
+		hash: &ExtrinsicHash<B>,
+	) -> Option<TransactionLifecycle<ExtrinsicHash<B>, BlockHash<B>>> {
+		self.listener.read().lifecycle(hash)
+	}
+
 	/// Invoked when extrinsics are broadcasted.
 	pub fn on_broadcasted(&self, propagated: HashMap<ExtrinsicHash<B>, Vec<String>>) {
 		let mut listener = self.listener.write();
@@ -628,6 +664,14 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.pool.read().status()
 	}
 
+	/// Returns the structure of the pool.
+	pub fn inspect(&self) -> PoolInspection<ExtrinsicHash<B>> {
+		let now = Instant::now();
+		let pool = self.pool.read();
+		let (ready, future) = pool.inspect(now);
+		PoolInspection { status: pool.status(), ready, future, banned: self.rotator.banned(&now) }
+	}
+
 	/// Notify all watchers that transactions in the block with hash have been finalized
 	pub async fn on_block_finalized(&self, block_hash: BlockHash<B>) -> Result<(), B::Error> {
 		log::trace!(
@@ -639,6 +683,14 @@ impl<B: ChainApi> ValidatedPool<B> {
 		Ok(())
 	}
 
+	/// Notify the listener of the transactions included in a block of another fork.
+	pub fn on_fork_block_imported(&self, block_hash: BlockHash<B>, hashes: &[ExtrinsicHash<B>]) {
+		let mut listener = self.listener.write();
+		for hash in hashes {
+			listener.in_fork_block(block_hash, hash);
+		}
+	}
+
 	/// Notify the listener of retracted blocks
 	pub fn on_block_retracted(&self, block_hash: BlockHash<B>) {
 		self.listener.write().retracted(block_hash)
@@ -0,0 +1,400 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk journal of the transaction pool.
+//!
+//! The transactions are appended to the journal as they're submitted, and the journal is
+//! periodically rewritten from the content of the pool, dropping the transactions which left it.
+//! When the pool is created, the journaled transactions are submitted again, and so revalidated,
+//! at the best block. Whether a transaction was received privately is journaled along with it, so
+//! it's still never gossiped once restored.
+
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::Duration,
+};
+
+use codec::{Decode, Encode};
+use parking_lot::Mutex;
+use sp_runtime::transaction_validity::TransactionSource;
+
+use crate::{
+	graph::{self, base_pool::Limit, BlockHash, ChainApi, ExtrinsicFor},
+	metrics::MetricsLink as PrometheusMetrics,
+	LOG_TARGET,
+};
+
+/// Which transactions are kept in the journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalScope {
+	/// Only the transactions submitted locally.
+	Local,
+	/// All the transactions of the pool.
+	All,
+}
+
+impl JournalScope {
+	fn includes(&self, source: TransactionSource) -> bool {
+		match self {
+			JournalScope::Local => source == TransactionSource::Local,
+			JournalScope::All => true,
+		}
+	}
+}
+
+/// Transaction pool journal options.
+#[derive(Debug, Clone)]
+pub struct JournalOptions {
+	/// Path of the journal file.
+	pub path: PathBuf,
+	/// Which transactions are kept in the journal.
+	pub scope: JournalScope,
+	/// Limits of the transactions kept in the journal.
+	pub limit: Limit,
+	/// How often the journal is rewritten from the content of the pool.
+	pub rewrite_period: Duration,
+}
+
+impl JournalOptions {
+	/// Journal the transactions of the given scope at `path`, with the default limits.
+	pub fn new(path: PathBuf, scope: JournalScope) -> Self {
+		Self {
+			path,
+			scope,
+			limit: Limit { count: 8192, total_bytes: 20 * 1024 * 1024 },
+			rewrite_period: Duration::from_secs(60),
+		}
+	}
+}
+
+struct JournalFile {
+	file: Option<File>,
+	count: usize,
+	bytes: usize,
+}
+
+/// The journal of a transaction pool.
+pub(crate) struct Journal<Ex> {
+	options: JournalOptions,
+	file: Mutex<JournalFile>,
+	metrics: PrometheusMetrics,
+	_phantom: std::marker::PhantomData<fn(Ex)>,
+}
+
+impl<Ex: Encode + Decode> Journal<Ex> {
+	pub fn new(options: JournalOptions, metrics: PrometheusMetrics) -> Self {
+		Self {
+			options,
+			file: Mutex::new(JournalFile { file: None, count: 0, bytes: 0 }),
+			metrics,
+			_phantom: Default::default(),
+		}
+	}
+
+	/// Whether transactions from `source` are kept in the journal.
+	pub fn includes(&self, source: TransactionSource) -> bool {
+		self.options.scope.includes(source)
+	}
+
+	fn report(&self, file: &JournalFile) {
+		self.metrics.report(|metrics| {
+			metrics.journal_transactions.set(file.count as u64);
+			metrics.journal_bytes.set(file.bytes as u64);
+		});
+	}
+
+	/// Read the journaled transactions.
+	///
+	/// A record which can't be decoded, such as one partially written when the node stopped, ends
+	/// the journal.
+	fn read(&self) -> io::Result<Vec<(TransactionSource, bool, Ex)>> {
+		let data = match fs::read(&self.options.path) {
+			Ok(data) => data,
+			Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(err) => return Err(err),
+		};
+
+		let mut input = &data[..];
+		let mut transactions = Vec::new();
+		while !input.is_empty() {
+			let Ok(record) = Vec::<u8>::decode(&mut input) else {
+				log::warn!(target: LOG_TARGET, "Ignoring the truncated end of the journal");
+				break
+			};
+			match <(TransactionSource, bool, Ex)>::decode(&mut &record[..]) {
+				Ok(transaction) => transactions.push(transaction),
+				Err(err) => log::debug!(target: LOG_TARGET, "Invalid journal record: {}", err),
+			}
+		}
+
+		let mut file = self.file.lock();
+		file.count = transactions.len();
+		file.bytes = data.len();
+		self.report(&file);
+		Ok(transactions)
+	}
+
+	/// Append transactions submitted to the pool to the journal.
+	///
+	/// `private` is whether the transactions were received privately.
+	pub fn append<'a>(
+		&self,
+		source: TransactionSource,
+		private: bool,
+		xts: impl IntoIterator<Item = &'a Ex>,
+	) where
+		Ex: 'a,
+	{
+		if !self.includes(source) {
+			return
+		}
+
+		let mut file = self.file.lock();
+		for xt in xts {
+			let record = (source, private, xt).encode().encode();
+			if file.count >= self.options.limit.count ||
+				file.bytes + record.len() > self.options.limit.total_bytes
+			{
+				self.metrics.report(|metrics| metrics.journal_dropped.inc());
+				continue
+			}
+
+			if file.file.is_none() {
+				file.file = match open_append(&self.options.path) {
+					Ok(handle) => Some(handle),
+					Err(err) => {
+						log::warn!(target: LOG_TARGET, "Failed to open the journal: {}", err);
+						return
+					},
+				};
+			}
+			let handle = file.file.as_mut().expect("Opened above; qed");
+			if let Err(err) = handle.write_all(&record) {
+				log::warn!(target: LOG_TARGET, "Failed to write to the journal: {}", err);
+				file.file = None;
+				return
+			}
+			file.count += 1;
+			file.bytes += record.len();
+		}
+		self.report(&file);
+	}
+
+	/// Replace the journal with the given transactions, up to its limits.
+	fn rewrite<'a>(&self, transactions: impl IntoIterator<Item = (TransactionSource, bool, &'a Ex)>)
+	where
+		Ex: 'a,
+	{
+		let mut file = self.file.lock();
+		let mut content = Vec::new();
+		let mut count = 0;
+		for (source, private, xt) in
+			transactions.into_iter().filter(|(source, _, _)| self.includes(*source))
+		{
+			let record = (source, private, xt).encode().encode();
+			if count >= self.options.limit.count ||
+				content.len() + record.len() > self.options.limit.total_bytes
+			{
+				break
+			}
+			content.extend(record);
+			count += 1;
+		}
+
+		let temporary = self.options.path.with_extension("tmp");
+		let written = (|| {
+			if let Some(parent) = self.options.path.parent() {
+				fs::create_dir_all(parent)?;
+			}
+			let mut handle = File::create(&temporary)?;
+			handle.write_all(&content)?;
+			handle.sync_all()?;
+			fs::rename(&temporary, &self.options.path)?;
+			open_append(&self.options.path)
+		})();
+		match written {
+			Ok(handle) => {
+				file.file = Some(handle);
+				file.count = count;
+				file.bytes = content.len();
+			},
+			Err(err) => {
+				log::warn!(target: LOG_TARGET, "Failed to rewrite the journal: {}", err);
+				file.file = None;
+			},
+		}
+		self.report(&file);
+	}
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+	OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Submit the journaled transactions to the pool at the block `at`, then keep rewriting the
+/// journal from the content of the pool.
+pub(crate) async fn journal_task<PoolApi: ChainApi>(
+	journal: Arc<Journal<ExtrinsicFor<PoolApi>>>,
+	pool: Arc<graph::Pool<PoolApi>>,
+	at: BlockHash<PoolApi>,
+) {
+	let transactions = journal.read().unwrap_or_else(|err| {
+		log::warn!(target: LOG_TARGET, "Failed to read the journal: {}", err);
+		Vec::new()
+	});
+	let total = transactions.len();
+
+	let mut restored = 0;
+	for (source, private) in
+		[TransactionSource::Local, TransactionSource::External, TransactionSource::InBlock]
+			.into_iter()
+			.flat_map(|source| [(source, false), (source, true)])
+	{
+		let xts = transactions
+			.iter()
+			.filter(|(tx_source, tx_private, _)| *tx_source == source && *tx_private == private)
+			.map(|(_, _, xt)| xt.clone())
+			.collect::<Vec<_>>();
+		if xts.is_empty() {
+			continue
+		}
+
+		let results = if private {
+			pool.submit_private_at(at, source, xts).await
+		} else {
+			pool.submit_at(at, source, xts).await
+		};
+		match results {
+			Ok(results) => restored += results.iter().filter(|result| result.is_ok()).count(),
+			Err(err) =>
+				log::warn!(target: LOG_TARGET, "Failed to resubmit journaled transactions: {}", err),
+		}
+	}
+	if total > 0 {
+		log::info!(
+			target: LOG_TARGET,
+			"Resubmitted {} transactions from the journal, {} were rejected",
+			restored,
+			total - restored,
+		);
+	}
+	journal.metrics.report(|metrics| {
+		metrics.journal_restored.inc_by(restored as u64);
+		metrics.journal_rejected.inc_by((total - restored) as u64);
+	});
+
+	loop {
+		rewrite_from_pool(&journal, &pool);
+		futures_timer::Delay::new(journal.options.rewrite_period).await;
+	}
+}
+
+/// Rewrite the journal with the ready transactions, in the order they'd be included, then the
+/// future transactions.
+fn rewrite_from_pool<PoolApi: ChainApi>(
+	journal: &Journal<ExtrinsicFor<PoolApi>>,
+	pool: &graph::Pool<PoolApi>,
+) {
+	let validated_pool = pool.validated_pool();
+	let ready = validated_pool.ready().collect::<Vec<_>>();
+	let futures = validated_pool.pool.read().futures().cloned().collect::<Vec<_>>();
+	journal.rewrite(
+		ready
+			.iter()
+			.map(|tx| (tx.source, tx.private, &tx.data))
+			.chain(futures.iter().map(|tx| (tx.source, tx.private, &tx.data))),
+	);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{uxt, TestApi};
+	use futures::FutureExt;
+	use substrate_test_runtime::{AccountId, Extrinsic, Transfer, H256};
+	use substrate_test_runtime_client::AccountKeyring::Alice;
+
+	fn xt(nonce: u64) -> Extrinsic {
+		uxt(Transfer {
+			from: Alice.into(),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		})
+	}
+
+	#[test]
+	fn should_journal_and_restore_transactions() {
+		let dir = tempfile::tempdir().unwrap();
+		let options = JournalOptions {
+			limit: Limit { count: 2, total_bytes: 1024 },
+			..JournalOptions::new(dir.path().join("txpool").join("journal"), JournalScope::Local)
+		};
+		let journal = Arc::new(Journal::new(options.clone(), Default::default()));
+
+		// Only the local transactions are journaled, up to the limit.
+		journal.append(TransactionSource::Local, false, [&xt(0)]);
+		journal.append(TransactionSource::External, false, [&xt(1)]);
+		journal.append(TransactionSource::Local, true, [&xt(1), &xt(2)]);
+		assert_eq!(
+			journal.read().unwrap(),
+			vec![(TransactionSource::Local, false, xt(0)), (TransactionSource::Local, true, xt(1))],
+		);
+
+		// A partially written record is ignored.
+		let mut file = OpenOptions::new().append(true).open(&options.path).unwrap();
+		file.write_all(&xt(2).encode().encode()[..8]).unwrap();
+		assert_eq!(journal.read().unwrap().len(), 2);
+
+		journal.rewrite([
+			(TransactionSource::Local, false, &xt(2)),
+			(TransactionSource::External, false, &xt(3)),
+		]);
+		assert_eq!(journal.read().unwrap(), vec![(TransactionSource::Local, false, xt(2))]);
+
+		// The journaled transactions are resubmitted once the pool is recreated, keeping whether
+		// they were received privately, then the journal is rewritten from the pool.
+		journal.rewrite([
+			(TransactionSource::Local, false, &xt(0)),
+			(TransactionSource::Local, true, &xt(1)),
+		]);
+		let api = Arc::new(TestApi::default());
+		let pool = Arc::new(graph::Pool::new(Default::default(), true.into(), api.clone()));
+		let journal = Arc::new(Journal::new(options, Default::default()));
+		assert!(journal_task(journal.clone(), pool.clone(), api.expect_hash_from_number(0))
+			.boxed()
+			.now_or_never()
+			.is_none());
+		assert_eq!(pool.validated_pool().status().ready, 2);
+		assert_eq!(
+			pool.validated_pool().ready().map(|tx| tx.private).collect::<Vec<_>>(),
+			vec![false, true],
+		);
+		assert_eq!(
+			journal.read().unwrap(),
+			vec![(TransactionSource::Local, false, xt(0)), (TransactionSource::Local, true, xt(1))],
+		);
+	}
+}
@@ -24,13 +24,16 @@
 #![warn(unused_extern_crates)]
 
 mod api;
+mod bundles;
 mod enactment_state;
 pub mod error;
 mod graph;
+mod journal;
 mod metrics;
 mod revalidation;
 #[cfg(test)]
 mod tests;
+mod view;
 
 pub use crate::api::FullChainApi;
 use async_trait::async_trait;
@@ -41,8 +44,12 @@ use futures::{
 	prelude::*,
 };
 pub use graph::{
-	base_pool::Limit as PoolLimit, ChainApi, Options, Pool, Transaction, ValidatedTransaction,
+	base_pool::{
+		EvictionPolicy as PoolEvictionPolicy, Fairness as PoolFairness, Limit as PoolLimit,
+	},
+	ChainApi, Options, Pool, Transaction, ValidatedTransaction,
 };
+pub use journal::{JournalOptions, JournalScope};
 use parking_lot::Mutex;
 use std::{
 	collections::{HashMap, HashSet},
@@ -52,14 +59,19 @@ use std::{
 
 use graph::{ExtrinsicHash, IsValidator};
 use sc_transaction_pool_api::{
-	error::Error as TxPoolError, ChainEvent, ImportNotificationStream, MaintainedTransactionPool,
-	PoolFuture, PoolStatus, ReadyTransactions, TransactionFor, TransactionPool, TransactionSource,
+	error::Error as TxPoolError, BlockHash, ChainEvent, ImportNotificationStream,
+	MaintainedTransactionPool, PoolFuture, PoolInspection, PoolStatus, ReadyTransactions,
+	TransactionFor, TransactionLifecycle, TransactionPool, TransactionSource,
 	TransactionStatusStreamFor, TxHash,
 };
 use sp_core::traits::SpawnEssentialNamed;
 use sp_runtime::{
 	generic::BlockId,
-	traits::{AtLeast32Bit, Block as BlockT, Extrinsic, Header as HeaderT, NumberFor, Zero},
+	traits::{
+		AtLeast32Bit, Block as BlockT, Extrinsic, Hash as HashT, Header as HeaderT, NumberFor,
+		SaturatedConversion, Zero,
+	},
+	transaction_validity::{TransactionTag as Tag, TransactionValidityError},
 };
 use std::time::Instant;
 
@@ -76,6 +88,8 @@ type BoxedReadyIterator<Hash, Data> =
 type ReadyIteratorFor<PoolApi> =
 	BoxedReadyIterator<graph::ExtrinsicHash<PoolApi>, graph::ExtrinsicFor<PoolApi>>;
 
+type JournalFor<PoolApi> = Arc<journal::Journal<graph::ExtrinsicFor<PoolApi>>>;
+
 type PolledIterator<PoolApi> = Pin<Box<dyn Future<Output = ReadyIteratorFor<PoolApi>> + Send>>;
 
 /// A transaction pool for a full node.
@@ -94,6 +108,9 @@ where
 	ready_poll: Arc<Mutex<ReadyPoll<ReadyIteratorFor<PoolApi>, Block>>>,
 	metrics: PrometheusMetrics,
 	enactment_state: Arc<Mutex<EnactmentState<Block>>>,
+	journal: Option<JournalFor<PoolApi>>,
+	views: Arc<Mutex<view::ViewStore<PoolApi>>>,
+	bundles: Arc<Mutex<bundles::Bundles<PoolApi>>>,
 }
 
 struct ReadyPoll<T, Block: BlockT> {
@@ -171,6 +188,7 @@ where
 			pool_api.clone(),
 			pool.clone(),
 			finalized_hash,
+			Default::default(),
 		);
 		(
 			Self {
@@ -184,6 +202,9 @@ where
 					best_block_hash,
 					finalized_hash,
 				))),
+				journal: None,
+				views: Arc::new(Mutex::new(view::ViewStore::new(best_block_hash))),
+				bundles: Default::default(),
 			},
 			background_task,
 		)
@@ -202,6 +223,11 @@ where
 		best_block_hash: Block::Hash,
 		finalized_hash: Block::Hash,
 	) -> Self {
+		let metrics = PrometheusMetrics::new(prometheus);
+		let journal = options
+			.journal
+			.clone()
+			.map(|options| Arc::new(journal::Journal::new(options, metrics.clone())));
 		let pool = Arc::new(graph::Pool::new(options, is_validator, pool_api.clone()));
 		let (revalidation_queue, background_task) = match revalidation_type {
 			RevalidationType::Light =>
@@ -211,6 +237,7 @@ where
 					pool_api.clone(),
 					pool.clone(),
 					finalized_hash,
+					metrics.clone(),
 				);
 				(queue, Some(background))
 			},
@@ -220,6 +247,14 @@ where
 			spawner.spawn_essential("txpool-background", Some("transaction-pool"), background_task);
 		}
 
+		if let Some(journal) = &journal {
+			spawner.spawn_essential(
+				"txpool-journal",
+				Some("transaction-pool"),
+				journal::journal_task(journal.clone(), pool.clone(), best_block_hash).boxed(),
+			);
+		}
+
 		Self {
 			api: pool_api,
 			pool,
@@ -230,11 +265,14 @@ where
 				RevalidationType::Full => RevalidationStrategy::Always,
 			})),
 			ready_poll: Arc::new(Mutex::new(ReadyPoll::new(best_block_number))),
-			metrics: PrometheusMetrics::new(prometheus),
+			metrics,
 			enactment_state: Arc::new(Mutex::new(EnactmentState::new(
 				best_block_hash,
 				finalized_hash,
 			))),
+			journal,
+			views: Arc::new(Mutex::new(view::ViewStore::new(best_block_hash))),
+			bundles: Default::default(),
 		}
 	}
 
@@ -247,6 +285,67 @@ where
 	pub fn api(&self) -> &PoolApi {
 		&self.api
 	}
+
+	/// The journal and a copy of the transaction, if the transaction is to be journaled once
+	/// submitted.
+	fn journaled(
+		&self,
+		source: TransactionSource,
+		xt: &graph::ExtrinsicFor<PoolApi>,
+	) -> Option<(JournalFor<PoolApi>, graph::ExtrinsicFor<PoolApi>)> {
+		self.journal
+			.as_ref()
+			.filter(|journal| journal.includes(source))
+			.map(|journal| (journal.clone(), xt.clone()))
+	}
+
+	/// Returns the hash of the transaction if it's part of a bundle, as the transactions of the
+	/// bundles are only included along with their bundle.
+	fn bundled(&self, xt: &graph::ExtrinsicFor<PoolApi>) -> Option<ExtrinsicHash<PoolApi>> {
+		let hash = self.pool.hash_of(xt);
+		self.bundles.lock().contains(&hash).then_some(hash)
+	}
+
+	/// Returns a future that validates a bundle of transactions at given block and imports it,
+	/// along with a watcher of its progress if requested.
+	fn import_bundle(
+		&self,
+		at: Block::Hash,
+		source: TransactionSource,
+		xts: Vec<graph::ExtrinsicFor<PoolApi>>,
+		watch: bool,
+	) -> PoolFuture<
+		(
+			ExtrinsicHash<PoolApi>,
+			Option<graph::watcher::Watcher<ExtrinsicHash<PoolApi>, Block::Hash>>,
+		),
+		PoolApi::Error,
+	> {
+		let pool = self.pool.clone();
+		let bundles = self.bundles.clone();
+
+		self.metrics
+			.report(|metrics| metrics.submitted_transactions.inc_by(xts.len() as u64));
+
+		async move {
+			if xts.is_empty() || xts.len() > bundles::MAX_BUNDLE_LENGTH {
+				return Err(TxPoolError::InvalidBundleLength(xts.len()).into())
+			}
+
+			let transactions = pool.verify_bundle(at, source, xts).await?;
+			let hash = <<Block::Header as HeaderT>::Hashing as HashT>::hash_of(
+				&transactions.iter().map(|tx| tx.hash).collect::<Vec<_>>(),
+			);
+			let mut bundles = bundles.lock();
+			// The transactions already in the pool could be included on their own.
+			for tx in &transactions {
+				pool.validated_pool().check_is_known(&tx.hash, true)?;
+			}
+			let watcher = bundles.import(hash, transactions, watch)?;
+			Ok((hash, watcher))
+		}
+		.boxed()
+	}
 }
 
 impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
@@ -266,11 +365,40 @@ where
 		xts: Vec<TransactionFor<Self>>,
 	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
 		let pool = self.pool.clone();
+		let views = self.views.clone();
+		let journal = self.journal.clone().filter(|journal| journal.includes(source));
+		let journaled = journal.as_ref().map(|_| xts.clone()).unwrap_or_default();
+		let bundled = xts.iter().map(|xt| self.bundled(xt)).collect::<Vec<_>>();
+		let xts = xts
+			.into_iter()
+			.zip(&bundled)
+			.filter(|(_, bundled)| bundled.is_none())
+			.map(|(xt, _)| xt)
+			.collect::<Vec<_>>();
 
 		self.metrics
-			.report(|metrics| metrics.submitted_transactions.inc_by(xts.len() as u64));
+			.report(|metrics| metrics.submitted_transactions.inc_by(bundled.len() as u64));
 
-		async move { pool.submit_at(at, source, xts).await }.boxed()
+		async move {
+			let mut submitted = pool.submit_at(at, source, xts).await?.into_iter();
+			let results = bundled
+				.into_iter()
+				.map(|bundled| match bundled {
+					Some(hash) => Err(TxPoolError::AlreadyImported(Box::new(hash)).into()),
+					None => submitted.next().expect("One result per submitted transaction; qed"),
+				})
+				.collect::<Vec<_>>();
+			views.lock().invalidate();
+			if let Some(journal) = journal {
+				journal.append(
+					source,
+					false,
+					journaled.iter().zip(&results).filter(|(_, r)| r.is_ok()).map(|(xt, _)| xt),
+				);
+			}
+			Ok(results)
+		}
+		.boxed()
 	}
 
 	fn submit_one(
@@ -280,10 +408,55 @@ where
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<TxHash<Self>, Self::Error> {
 		let pool = self.pool.clone();
+		let views = self.views.clone();
+		let journaled = self.journaled(source, &xt);
+		let bundled = self.bundled(&xt);
 
 		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
 
-		async move { pool.submit_one(at, source, xt).await }.boxed()
+		async move {
+			if let Some(hash) = bundled {
+				return Err(TxPoolError::AlreadyImported(Box::new(hash)).into())
+			}
+			let hash = pool.submit_one(at, source, xt).await?;
+			views.lock().invalidate();
+			if let Some((journal, xt)) = journaled {
+				journal.append(source, false, [&xt]);
+			}
+			Ok(hash)
+		}
+		.boxed()
+	}
+
+	fn submit_private(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		let pool = self.pool.clone();
+		let views = self.views.clone();
+		let journaled = self.journaled(source, &xt);
+		let bundled = self.bundled(&xt);
+
+		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
+
+		async move {
+			if let Some(hash) = bundled {
+				return Err(TxPoolError::AlreadyImported(Box::new(hash)).into())
+			}
+			let hash = pool
+				.submit_private_at(at, source, std::iter::once(xt))
+				.await?
+				.pop()
+				.expect("One extrinsic passed; one result returned")?;
+			views.lock().invalidate();
+			if let Some((journal, xt)) = journaled {
+				journal.append(source, true, [&xt]);
+			}
+			Ok(hash)
+		}
+		.boxed()
 	}
 
 	fn submit_and_watch(
@@ -293,28 +466,77 @@ where
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
 		let pool = self.pool.clone();
+		let views = self.views.clone();
+		let journaled = self.journaled(source, &xt);
+		let bundled = self.bundled(&xt);
 
 		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
 
 		async move {
+			if let Some(hash) = bundled {
+				return Err(TxPoolError::AlreadyImported(Box::new(hash)).into())
+			}
 			let watcher = pool.submit_and_watch(at, source, xt).await?;
+			views.lock().invalidate();
+			if let Some((journal, xt)) = journaled {
+				journal.append(source, false, [&xt]);
+			}
 
 			Ok(watcher.into_stream().boxed())
 		}
 		.boxed()
 	}
 
+	fn submit_bundle(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		self.import_bundle(at, source, xts, false).map_ok(|(hash, _)| hash).boxed()
+	}
+
+	fn submit_and_watch_bundle(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		self.import_bundle(at, source, xts, true)
+			.map_ok(|(_, watcher)| {
+				watcher.expect("A watcher is requested; qed").into_stream().boxed()
+			})
+			.boxed()
+	}
+
 	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
-		let removed = self.pool.validated_pool().remove_invalid(hashes);
+		let mut removed = self.pool.validated_pool().remove_invalid(hashes);
+		removed.extend(self.bundles.lock().remove_invalid(hashes));
+		self.views.lock().invalidate();
 		self.metrics
 			.report(|metrics| metrics.validations_invalid.inc_by(removed.len() as u64));
 		removed
 	}
 
+	fn on_proposed(&self, block: BlockHash<Self>, hashes: &[TxHash<Self>]) {
+		self.pool.validated_pool().on_proposed(block, hashes)
+	}
+
 	fn status(&self) -> PoolStatus {
 		self.pool.validated_pool().status()
 	}
 
+	fn inspect(&self) -> PoolInspection<TxHash<Self>> {
+		self.pool.validated_pool().inspect()
+	}
+
+	fn lifecycle(
+		&self,
+		hash: &TxHash<Self>,
+	) -> Option<TransactionLifecycle<TxHash<Self>, BlockHash<Self>>> {
+		self.pool.validated_pool().lifecycle(hash)
+	}
+
 	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
 		self.pool.validated_pool().import_notification_stream()
 	}
@@ -359,10 +581,65 @@ where
 			.boxed()
 	}
 
+	fn ready_at_block(&self, at: Block::Hash, number: NumberFor<Block>) -> PolledIterator<PoolApi> {
+		let best = self.enactment_state.lock().recent_best_block();
+		// The pool itself follows the best chain, and there is no view of unknown blocks.
+		if at == best || !self.views.lock().is_leaf(&at) {
+			return self.ready_at(number)
+		}
+
+		let generation = match self.views.lock().view(&at) {
+			Ok(view) => {
+				let iterator: ReadyIteratorFor<PoolApi> = Box::new(view.ready());
+				return async move { iterator }.boxed()
+			},
+			Err(generation) => generation,
+		};
+
+		let api = self.api.clone();
+		let pool = self.pool.clone();
+		let views = self.views.clone();
+		async move {
+			let iterator: ReadyIteratorFor<PoolApi> =
+				match view::build_view(&*api, &pool, best, at).await {
+					Ok(view) => {
+						let view = Arc::new(view);
+						views.lock().insert_view(at, view.clone(), generation);
+						Box::new(view.ready())
+					},
+					Err(e) => {
+						log::debug!(target: LOG_TARGET, "Failed to build the view at {:?}: {}", at, e);
+						Box::new(pool.validated_pool().ready())
+					},
+				};
+			iterator
+		}
+		.boxed()
+	}
+
 	fn ready(&self) -> ReadyIteratorFor<PoolApi> {
 		Box::new(self.pool.validated_pool().ready())
 	}
 
+	fn ready_bundles(&self, at: Block::Hash) -> Vec<Vec<Arc<Self::InPoolTransaction>>> {
+		let best = self.enactment_state.lock().recent_best_block();
+		if at == best {
+			return self.bundles.lock().ready()
+		}
+
+		match self.api.tree_route(best, at) {
+			Ok(tree_route) => {
+				let retracted = tree_route.retracted().iter().map(|block| block.hash).collect();
+				let enacted = tree_route.enacted().iter().map(|block| block.hash).collect();
+				self.bundles.lock().ready_on_fork(&retracted, &enacted)
+			},
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Error computing the route to {:?}: {}", at, e);
+				self.bundles.lock().ready()
+			},
+		}
+	}
+
 	fn futures(&self) -> Vec<Self::InPoolTransaction> {
 		let pool = self.pool.validated_pool().pool.read();
 
@@ -451,6 +728,7 @@ where
 			.block_id_to_number(&BlockId::hash(at))?
 			.ok_or_else(|| error::Error::BlockIdConversion(format!("{:?}", at)))?;
 
+		let journaled = self.journaled(TransactionSource::Local, &xt);
 		let validated = ValidatedTransaction::valid_at(
 			block_number.saturated_into::<u64>(),
 			hash,
@@ -460,7 +738,12 @@ where
 			validity,
 		);
 
-		self.pool.validated_pool().submit(vec![validated]).remove(0)
+		let result = self.pool.validated_pool().submit(vec![validated]).remove(0);
+		self.views.lock().invalidate();
+		if let (Some((journal, xt)), Ok(_)) = (journaled, &result) {
+			journal.append(TransactionSource::Local, false, [&xt]);
+		}
+		result
 	}
 }
 
@@ -546,12 +829,12 @@ impl<N: Clone + Copy + AtLeast32Bit> RevalidationStatus<N> {
 	}
 }
 
-/// Prune the known txs for the given block.
+/// Prunes the transactions of the given block, returning their hashes and the tags they provide.
 async fn prune_known_txs_for_block<Block: BlockT, Api: graph::ChainApi<Block = Block>>(
 	block_hash: Block::Hash,
 	api: &Api,
 	pool: &graph::Pool<Api>,
-) -> Vec<ExtrinsicHash<Api>> {
+) -> (Vec<ExtrinsicHash<Api>>, Vec<Tag>) {
 	let extrinsics = api
 		.block_body(block_hash)
 		.await
@@ -569,19 +852,21 @@ async fn prune_known_txs_for_block<Block: BlockT, Api: graph::ChainApi<Block = B
 		Ok(Some(h)) => h,
 		Ok(None) => {
 			log::debug!(target: LOG_TARGET, "Could not find header for {:?}.", block_hash);
-			return hashes
+			return (hashes, Vec::new())
 		},
 		Err(e) => {
 			log::debug!(target: LOG_TARGET, "Error retrieving header for {:?}: {}", block_hash, e);
-			return hashes
+			return (hashes, Vec::new())
 		},
 	};
 
-	if let Err(e) = pool.prune(block_hash, *header.parent_hash(), &extrinsics).await {
-		log::error!("Cannot prune known in the pool: {}", e);
+	match pool.prune(block_hash, *header.parent_hash(), &extrinsics).await {
+		Ok(tags) => (hashes, tags),
+		Err(e) => {
+			log::error!("Cannot prune known in the pool: {}", e);
+			(hashes, Vec::new())
+		},
 	}
-
-	hashes
 }
 
 impl<PoolApi, Block> BasicPool<PoolApi, Block>
@@ -589,6 +874,54 @@ where
 	Block: BlockT,
 	PoolApi: 'static + graph::ChainApi<Block = Block>,
 {
+	/// Records a new block as a leaf of the chain.
+	fn insert_leaf(&self, hash: Block::Hash) {
+		match self.api.block_header(hash) {
+			Ok(Some(header)) => self.views.lock().insert_leaf(hash, *header.parent_hash()),
+			Ok(None) => {
+				log::debug!(target: LOG_TARGET, "Could not find header for {:?}.", hash);
+			},
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Error retrieving header for {:?}: {}", hash, e);
+			},
+		}
+	}
+
+	/// Notifies the watchers and the bundles of the transactions included in a block which isn't
+	/// the best.
+	async fn notify_fork_block(&self, hash: Block::Hash) {
+		let extrinsics = match self.api.block_body(hash).await {
+			Ok(Some(extrinsics)) => extrinsics,
+			Ok(None) => return,
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Error retrieving body of {:?}: {}", hash, e);
+				return
+			},
+		};
+		let hashes = extrinsics.iter().map(|xt| self.api.hash_and_length(xt).0).collect::<Vec<_>>();
+		self.bundles.lock().on_fork_block(hash, &hashes);
+		self.pool.validated_pool().on_fork_block_imported(hash, &hashes);
+	}
+
+	/// Forgets the leaves which aren't descendants of the finalized block, retracting the blocks
+	/// of their forks.
+	fn prune_leaves(&self, finalized: Block::Hash) {
+		let leaves = self.views.lock().leaves();
+		let mut stale = Vec::new();
+		for leaf in leaves {
+			match self.api.tree_route(finalized, leaf) {
+				Ok(tree_route) if tree_route.retracted().is_empty() => continue,
+				Ok(tree_route) =>
+					for block in tree_route.enacted() {
+						self.pool.validated_pool().on_block_retracted(block.hash);
+					},
+				Err(_) => {},
+			}
+			stale.push(leaf);
+		}
+		self.views.lock().remove_leaves(&stale);
+	}
+
 	/// Handles enactment and retraction of blocks, prunes stale transactions
 	/// (that have already been enacted) and resubmits transactions that were
 	/// retracted.
@@ -618,6 +951,8 @@ where
 		// We keep track of everything we prune so that later we won't add
 		// transactions with those hashes from the retracted blocks.
 		let mut pruned_log = HashSet::<ExtrinsicHash<PoolApi>>::new();
+		// The tags provided by the enacted transactions, whose dependents are revalidated first.
+		let mut enacted_tags = HashSet::<Tag>::new();
 
 		// If there is a tree route, we use this to prune known tx based on the enacted
 		// blocks. Before pruning enacted transactions, we inform the listeners about
@@ -627,6 +962,7 @@ where
 		for retracted in tree_route.retracted() {
 			// notify txs awaiting finality that it has been retracted
 			pool.validated_pool().on_block_retracted(retracted.hash);
+			self.bundles.lock().on_block_retracted(retracted.hash);
 		}
 
 		future::join_all(
@@ -637,8 +973,11 @@ where
 		)
 		.await
 		.into_iter()
-		.for_each(|enacted_log| {
+		.zip(tree_route.enacted())
+		.for_each(|((enacted_log, tags), enacted)| {
+			self.bundles.lock().on_block_enacted(enacted.hash, &enacted_log);
 			pruned_log.extend(enacted_log);
+			enacted_tags.extend(tags);
 		});
 
 		self.metrics
@@ -665,7 +1004,9 @@ where
 
 				resubmit_transactions.extend(block_transactions.into_iter().filter(|tx| {
 					let tx_hash = pool.hash_of(tx);
-					let contains = pruned_log.contains(&tx_hash);
+					// The transactions of the bundles are only included along with their bundle.
+					let contains =
+						pruned_log.contains(&tx_hash) || self.bundles.lock().contains(&tx_hash);
 
 					// need to count all transactions, not just filtered, here
 					resubmitted_to_report += 1;
@@ -713,11 +1054,41 @@ where
 			.trigger(*block_number, move || Box::new(extra_pool.validated_pool().ready()));
 
 		if next_action.revalidate {
-			let hashes = pool.validated_pool().ready().map(|tx| tx.hash).collect();
-			self.revalidation_queue.revalidate_later(*hash, hashes).await;
+			let (hashes, prioritized) = pool.validated_pool().ready().fold(
+				(Vec::new(), Vec::new()),
+				|(mut hashes, mut prioritized), tx| {
+					if tx.requires.iter().any(|tag| enacted_tags.contains(tag)) {
+						prioritized.push(tx.hash);
+					}
+					hashes.push(tx.hash);
+					(hashes, prioritized)
+				},
+			);
+			self.revalidation_queue.revalidate_later(*hash, hashes, prioritized).await;
 
 			self.revalidation_strategy.lock().clear();
 		}
+
+		self.revalidate_bundles(*hash, *block_number).await;
+	}
+
+	/// Invalidates the bundles awaiting inclusion which expired, or whose first transaction
+	/// became invalid, at given block.
+	async fn revalidate_bundles(&self, at: Block::Hash, number: NumberFor<Block>) {
+		let number = number.saturated_into::<u64>();
+		let pending = self.bundles.lock().pending();
+
+		let invalid = future::join_all(pending.into_iter().map(|(bundle, tx)| async move {
+			let valid = tx.valid_till >= number &&
+				!matches!(
+					self.api.validate_transaction(at, tx.source, tx.data.clone()).await,
+					Ok(Err(TransactionValidityError::Invalid(_)))
+				);
+			(!valid).then_some(bundle)
+		}))
+		.await;
+
+		self.bundles.lock().invalidate(invalid.into_iter().flatten());
 	}
 }
 
@@ -728,6 +1099,17 @@ where
 	PoolApi: 'static + graph::ChainApi<Block = Block>,
 {
 	async fn maintain(&self, event: ChainEvent<Self::Block>) {
+		match event {
+			ChainEvent::NewBlock { hash } => {
+				// The pool follows the best chain, other blocks are only new leaves for the views.
+				self.insert_leaf(hash);
+				self.notify_fork_block(hash).await;
+				return
+			},
+			ChainEvent::NewBestBlock { hash, .. } => self.insert_leaf(hash),
+			ChainEvent::Finalized { .. } => {},
+		}
+
 		let prev_finalized_block = self.enactment_state.lock().recent_finalized_block();
 		let compute_tree_route = |from, to| -> Result<TreeRoute<Block>, String> {
 			match self.api.tree_route(from, to) {
@@ -751,12 +1133,17 @@ where
 				log::debug!(target: LOG_TARGET, "{msg}");
 				self.enactment_state.lock().force_update(&event);
 			},
-			Ok(EnactmentAction::Skip) => return,
+			Ok(EnactmentAction::Skip) => {
+				self.views.lock().invalidate();
+				return
+			},
 			Ok(EnactmentAction::HandleFinalization) => {},
 			Ok(EnactmentAction::HandleEnactment(tree_route)) => {
 				self.handle_enactment(tree_route).await;
 			},
 		};
+		// The views are built against the transactions of the pool and the best block.
+		self.views.lock().invalidate();
 
 		if let ChainEvent::Finalized { hash, tree_route } = event {
 			log::trace!(
@@ -766,6 +1153,7 @@ where
 			);
 
 			for hash in tree_route.iter().chain(std::iter::once(&hash)) {
+				self.bundles.lock().on_block_finalized(*hash);
 				if let Err(e) = self.pool.validated_pool().on_block_finalized(*hash).await {
 					log::warn!(
 						target: LOG_TARGET,
@@ -774,6 +1162,8 @@ where
 					)
 				}
 			}
+
+			self.prune_leaves(hash);
 		}
 	}
 }
@@ -21,7 +21,10 @@
 
 use std::sync::Arc;
 
-use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+use prometheus_endpoint::{
+	exponential_buckets, register, Counter, Gauge, Histogram, HistogramOpts, PrometheusError,
+	Registry, U64,
+};
 
 #[derive(Clone, Default)]
 pub struct MetricsLink(Arc<Option<Metrics>>);
@@ -50,6 +53,14 @@ pub struct Metrics {
 	pub validations_invalid: Counter<U64>,
 	pub block_transactions_pruned: Counter<U64>,
 	pub block_transactions_resubmitted: Counter<U64>,
+	pub journal_transactions: Gauge<U64>,
+	pub journal_bytes: Gauge<U64>,
+	pub journal_dropped: Counter<U64>,
+	pub journal_restored: Counter<U64>,
+	pub journal_rejected: Counter<U64>,
+	pub revalidation_queue: Gauge<U64>,
+	pub revalidation_batch_time: Histogram,
+	pub revalidation_latency: Histogram,
 }
 
 impl Metrics {
@@ -83,6 +94,66 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			journal_transactions: register(
+				Gauge::new(
+					"substrate_sub_txpool_journal_transactions",
+					"Number of transactions in the journal",
+				)?,
+				registry,
+			)?,
+			journal_bytes: register(
+				Gauge::new("substrate_sub_txpool_journal_bytes", "Size of the journal in bytes")?,
+				registry,
+			)?,
+			journal_dropped: register(
+				Counter::new(
+					"substrate_sub_txpool_journal_dropped",
+					"Total number of transactions not journaled because the journal was full",
+				)?,
+				registry,
+			)?,
+			journal_restored: register(
+				Counter::new(
+					"substrate_sub_txpool_journal_restored",
+					"Total number of journaled transactions resubmitted to the pool",
+				)?,
+				registry,
+			)?,
+			journal_rejected: register(
+				Counter::new(
+					"substrate_sub_txpool_journal_rejected",
+					"Total number of journaled transactions rejected when resubmitted to the pool",
+				)?,
+				registry,
+			)?,
+			revalidation_queue: register(
+				Gauge::new(
+					"substrate_sub_txpool_revalidation_queue",
+					"Number of transactions awaiting background revalidation",
+				)?,
+				registry,
+			)?,
+			revalidation_batch_time: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"substrate_sub_txpool_revalidation_batch_time",
+					"Histogram of time taken to revalidate a batch of transactions",
+				))?,
+				registry,
+			)?,
+			revalidation_latency: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"substrate_sub_txpool_revalidation_latency",
+						"Histogram of time between queueing a transaction for revalidation and \
+						 revalidating it",
+					)
+					.buckets(
+						exponential_buckets(0.01, 2.0, 14)
+							.expect("parameters are always valid values"),
+					),
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -27,6 +27,7 @@ use std::{
 
 use crate::{
 	graph::{BlockHash, ChainApi, ExtrinsicHash, Pool, ValidatedTransaction},
+	metrics::MetricsLink as PrometheusMetrics,
 	LOG_TARGET,
 };
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
@@ -35,7 +36,7 @@ use sp_runtime::{
 };
 
 use futures::prelude::*;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 const BACKGROUND_REVALIDATION_INTERVAL: Duration = Duration::from_millis(200);
 
@@ -45,17 +46,29 @@ const MIN_BACKGROUND_REVALIDATION_BATCH_SIZE: usize = 20;
 struct WorkerPayload<Api: ChainApi> {
 	at: BlockHash<Api>,
 	transactions: Vec<ExtrinsicHash<Api>>,
+	prioritized: Vec<ExtrinsicHash<Api>>,
 }
 
 /// Async revalidation worker.
 ///
 /// Implements future and can be spawned in place or in background.
+///
+/// The number of transactions revalidated at each interval is such that the queue is revalidated
+/// within the estimated block time, with the prioritized transactions revalidated first.
 struct RevalidationWorker<Api: ChainApi> {
 	api: Arc<Api>,
 	pool: Arc<Pool<Api>>,
 	best_block: BlockHash<Api>,
 	block_ordered: BTreeMap<BlockHash<Api>, HashSet<ExtrinsicHash<Api>>>,
-	members: HashMap<ExtrinsicHash<Api>, BlockHash<Api>>,
+	/// The queued transactions to revalidate before the others.
+	prioritized: HashSet<ExtrinsicHash<Api>>,
+	/// The queued transactions, with the block they were queued at and when.
+	members: HashMap<ExtrinsicHash<Api>, (BlockHash<Api>, Instant)>,
+	/// The estimated time between two best blocks.
+	block_time: Option<Duration>,
+	/// When the last best block was received.
+	last_block: Option<Instant>,
+	metrics: PrometheusMetrics,
 }
 
 impl<Api: ChainApi> Unpin for RevalidationWorker<Api> {}
@@ -149,20 +162,44 @@ async fn batch_revalidate<Api: ChainApi>(
 }
 
 impl<Api: ChainApi> RevalidationWorker<Api> {
-	fn new(api: Arc<Api>, pool: Arc<Pool<Api>>, best_block: BlockHash<Api>) -> Self {
+	fn new(
+		api: Arc<Api>,
+		pool: Arc<Pool<Api>>,
+		best_block: BlockHash<Api>,
+		metrics: PrometheusMetrics,
+	) -> Self {
 		Self {
 			api,
 			pool,
 			best_block,
 			block_ordered: Default::default(),
+			prioritized: Default::default(),
 			members: Default::default(),
+			block_time: None,
+			last_block: None,
+			metrics,
 		}
 	}
 
-	fn prepare_batch(&mut self) -> Vec<ExtrinsicHash<Api>> {
-		let mut queued_exts = Vec::new();
-		let mut left =
-			std::cmp::max(MIN_BACKGROUND_REVALIDATION_BATCH_SIZE, self.members.len() / 4);
+	/// The number of transactions to revalidate at each `interval`.
+	///
+	/// It's enough to revalidate the whole queue within the estimated block time, and all the
+	/// prioritized transactions.
+	fn batch_size(&self, interval: Duration) -> usize {
+		let len = self.len();
+		let spread = match self.block_time {
+			Some(block_time) => (len as u128 * interval.as_micros() /
+				block_time.as_micros().max(1))
+			.saturated_into::<usize>(),
+			None => len / 4,
+		};
+		spread.max(MIN_BACKGROUND_REVALIDATION_BATCH_SIZE).max(self.prioritized.len())
+	}
+
+	/// Takes the next transactions to revalidate, with when they were queued.
+	fn prepare_batch(&mut self, interval: Duration) -> Vec<(ExtrinsicHash<Api>, Instant)> {
+		let mut left = self.batch_size(interval).saturating_sub(self.prioritized.len());
+		let mut queued_exts = self.prioritized.drain().collect::<Vec<_>>();
 
 		// Take maximum of count transaction by order
 		// which they got into the pool
@@ -190,18 +227,29 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 			}
 		}
 
-		for hash in queued_exts.iter() {
-			self.members.remove(hash);
-		}
-
 		queued_exts
+			.into_iter()
+			.filter_map(|hash| self.members.remove(&hash).map(|(_, queued_at)| (hash, queued_at)))
+			.collect()
 	}
 
 	fn len(&self) -> usize {
-		self.block_ordered.iter().map(|b| b.1.len()).sum()
+		self.block_ordered.iter().map(|b| b.1.len()).sum::<usize>() + self.prioritized.len()
+	}
+
+	/// Records that a new best block was received at `now`, updating the estimated block time.
+	fn note_block(&mut self, now: Instant) {
+		if let Some(last_block) = self.last_block {
+			let elapsed = now.saturating_duration_since(last_block);
+			self.block_time = Some(match self.block_time {
+				Some(block_time) => (block_time * 3 + elapsed) / 4,
+				None => elapsed,
+			});
+		}
+		self.last_block = Some(now);
 	}
 
-	fn push(&mut self, worker_payload: WorkerPayload<Api>) {
+	fn push(&mut self, worker_payload: WorkerPayload<Api>, now: Instant) {
 		// we don't add something that already scheduled for revalidation
 		let transactions = worker_payload.transactions;
 		let block_number = worker_payload.at;
@@ -228,7 +276,17 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 					bt.insert(ext_hash);
 					bt
 				});
-			self.members.insert(ext_hash, block_number);
+			self.members.insert(ext_hash, (block_number, now));
+		}
+
+		for ext_hash in worker_payload.prioritized {
+			let Some((block, _)) = self.members.get(&ext_hash) else { continue };
+			if let Some(extrinsics) = self.block_ordered.get_mut(block) {
+				if extrinsics.remove(&ext_hash) && extrinsics.is_empty() {
+					self.block_ordered.remove(block);
+				}
+				self.prioritized.insert(ext_hash);
+			}
 		}
 	}
 
@@ -251,10 +309,31 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 			futures::select! {
 				// Using `fuse()` in here is okay, because we reset the interval when it has fired.
 				_ = (&mut interval_fut).fuse() => {
-					let next_batch = this.prepare_batch();
+					let next_batch = this.prepare_batch(interval);
 					let batch_len = next_batch.len();
-
-					batch_revalidate(this.pool.clone(), this.api.clone(), this.best_block, next_batch).await;
+					let batch_start = Instant::now();
+
+					batch_revalidate(
+						this.pool.clone(),
+						this.api.clone(),
+						this.best_block,
+						next_batch.iter().map(|(hash, _)| *hash),
+					).await;
+
+					let now = Instant::now();
+					this.metrics.report(|metrics| {
+						metrics.revalidation_queue.set(this.len() as u64);
+						if batch_len > 0 {
+							metrics.revalidation_batch_time.observe(
+								now.saturating_duration_since(batch_start).as_secs_f64(),
+							);
+						}
+						for (_, queued_at) in &next_batch {
+							metrics.revalidation_latency.observe(
+								now.saturating_duration_since(*queued_at).as_secs_f64(),
+							);
+						}
+					});
 
 					if batch_len > 0 || this.len() > 0 {
 						log::debug!(
@@ -270,8 +349,15 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 				workload = from_queue.next() => {
 					match workload {
 						Some(worker_payload) => {
+							let now = Instant::now();
+							if this.best_block != worker_payload.at {
+								this.note_block(now);
+							}
 							this.best_block = worker_payload.at;
-							this.push(worker_payload);
+							this.push(worker_payload, now);
+							this.metrics.report(|metrics| {
+								metrics.revalidation_queue.set(this.len() as u64)
+							});
 
 							if this.members.len() > 0 {
 								log::debug!(
@@ -318,10 +404,11 @@ where
 		pool: Arc<Pool<Api>>,
 		interval: Duration,
 		best_block: BlockHash<Api>,
+		metrics: PrometheusMetrics,
 	) -> (Self, Pin<Box<dyn Future<Output = ()> + Send>>) {
 		let (to_worker, from_queue) = tracing_unbounded("mpsc_revalidation_queue", 100_000);
 
-		let worker = RevalidationWorker::new(api.clone(), pool.clone(), best_block);
+		let worker = RevalidationWorker::new(api.clone(), pool.clone(), best_block, metrics);
 
 		let queue = Self { api, pool, background: Some(to_worker) };
 
@@ -333,12 +420,15 @@ where
 		api: Arc<Api>,
 		pool: Arc<Pool<Api>>,
 		best_block: BlockHash<Api>,
+		metrics: PrometheusMetrics,
 	) -> (Self, Pin<Box<dyn Future<Output = ()> + Send>>) {
-		Self::new_with_interval(api, pool, BACKGROUND_REVALIDATION_INTERVAL, best_block)
+		Self::new_with_interval(api, pool, BACKGROUND_REVALIDATION_INTERVAL, best_block, metrics)
 	}
 
 	/// Queue some transaction for later revalidation.
 	///
+	/// The `prioritized` transactions, among `transactions`, are revalidated before the others.
+	///
 	/// If queue configured with background worker, this will return immediately.
 	/// If queue configured without background worker, this will resolve after
 	/// revalidation is actually done.
@@ -346,6 +436,7 @@ where
 		&self,
 		at: BlockHash<Api>,
 		transactions: Vec<ExtrinsicHash<Api>>,
+		prioritized: Vec<ExtrinsicHash<Api>>,
 	) {
 		if transactions.len() > 0 {
 			log::debug!(
@@ -356,7 +447,9 @@ where
 		}
 
 		if let Some(ref to_worker) = self.background {
-			if let Err(e) = to_worker.unbounded_send(WorkerPayload { at, transactions }) {
+			if let Err(e) =
+				to_worker.unbounded_send(WorkerPayload { at, transactions, prioritized })
+			{
 				log::warn!(target: LOG_TARGET, "Failed to update background worker: {:?}", e);
 			}
 		} else {
@@ -398,7 +491,7 @@ mod tests {
 			block_on(pool.submit_one(hash_of_block0, TransactionSource::External, uxt.clone()))
 				.expect("Should be valid");
 
-		block_on(queue.revalidate_later(hash_of_block0, vec![uxt_hash]));
+		block_on(queue.revalidate_later(hash_of_block0, vec![uxt_hash], vec![]));
 
 		// revalidated in sync offload 2nd time
 		assert_eq!(api.validation_requests().len(), 2);
@@ -439,15 +532,69 @@ mod tests {
 		assert_eq!(pool.validated_pool().status().ready, 2);
 
 		// revalidation works fine for block 0:
-		block_on(queue.revalidate_later(hash_of_block0, uxt_hashes.clone()));
+		block_on(queue.revalidate_later(hash_of_block0, uxt_hashes.clone(), vec![]));
 		assert_eq!(api.validation_requests().len(), 4);
 		assert_eq!(pool.validated_pool().status().ready, 2);
 
 		// revalidation shall be skipped for unknown block:
-		block_on(queue.revalidate_later(unknown_block, uxt_hashes));
+		block_on(queue.revalidate_later(unknown_block, uxt_hashes, vec![]));
 		// no revalidation shall be done
 		assert_eq!(api.validation_requests().len(), 4);
 		// number of ready shall not change
 		assert_eq!(pool.validated_pool().status().ready, 2);
 	}
+
+	fn test_worker() -> RevalidationWorker<TestApi> {
+		let api = Arc::new(TestApi::default());
+		let pool = Arc::new(Pool::new(Default::default(), true.into(), api.clone()));
+		let best_block = api.expect_hash_from_number(0);
+		RevalidationWorker::new(api, pool, best_block, Default::default())
+	}
+
+	#[test]
+	fn revalidation_worker_revalidates_prioritized_transactions_first() {
+		let mut worker = test_worker();
+		let hashes = (0..30).map(H256::from_low_u64_be).collect::<Vec<_>>();
+
+		worker.push(
+			WorkerPayload {
+				at: worker.best_block,
+				transactions: hashes.clone(),
+				prioritized: vec![hashes[29]],
+			},
+			Instant::now(),
+		);
+		assert_eq!(worker.len(), 30);
+
+		let batch = worker.prepare_batch(BACKGROUND_REVALIDATION_INTERVAL);
+		assert_eq!(batch.len(), MIN_BACKGROUND_REVALIDATION_BATCH_SIZE);
+		assert_eq!(batch[0].0, hashes[29]);
+		assert_eq!(batch.iter().filter(|(hash, _)| *hash == hashes[29]).count(), 1);
+		assert_eq!(worker.len(), 10);
+	}
+
+	#[test]
+	fn revalidation_worker_spreads_the_queue_over_the_block_time() {
+		let mut worker = test_worker();
+		let interval = BACKGROUND_REVALIDATION_INTERVAL;
+		let hashes = (0..3000).map(H256::from_low_u64_be).collect::<Vec<_>>();
+		worker.push(
+			WorkerPayload { at: worker.best_block, transactions: hashes, prioritized: vec![] },
+			Instant::now(),
+		);
+
+		// without any block time estimate, a quarter of the queue is revalidated at once.
+		assert_eq!(worker.batch_size(interval), 750);
+
+		// with 6s blocks, the queue is revalidated within 30 intervals.
+		let start = Instant::now();
+		worker.note_block(start);
+		worker.note_block(start + Duration::from_secs(6));
+		assert_eq!(worker.batch_size(interval), 100);
+
+		// faster blocks shorten the estimate.
+		worker.note_block(start + Duration::from_secs(8));
+		assert_eq!(worker.block_time, Some(Duration::from_secs(5)));
+		assert_eq!(worker.batch_size(interval), 120);
+	}
 }
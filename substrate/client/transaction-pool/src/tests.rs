@@ -113,6 +113,7 @@ impl ChainApi for TestApi {
 						},
 						longevity: 3,
 						propagate: true,
+						private: false,
 					};
 
 					if self.clear_requirements.lock().contains(&hash) {
@@ -135,6 +136,7 @@ impl ChainApi for TestApi {
 				provides: vec![vec![42]],
 				longevity: 9001,
 				propagate: false,
+				private: false,
 			}),
 			Extrinsic {
 				function: RuntimeCall::SubstrateTest(PalletCall::indexed_call { .. }),
@@ -145,6 +147,7 @@ impl ChainApi for TestApi {
 				provides: vec![vec![43]],
 				longevity: 9001,
 				propagate: false,
+				private: false,
 			}),
 			_ => unimplemented!(),
 		};
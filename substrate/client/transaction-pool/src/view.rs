@@ -0,0 +1,255 @@
+// This file is part of a fork of Substrate which has had various changes.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// Copyright (C) 2022-2023 Luke Parker
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Views of the ready transactions on top of the non-finalized leaves of the chain.
+//!
+//! The pool follows the best chain. The view of another leaf is made of the transactions of the
+//! pool which aren't included in the blocks of the leaf's fork, along with the transactions of
+//! the retracted blocks of the best chain, once revalidated on top of the leaf. The transactions
+//! still waiting for a tag are revalidated on top of the leaf too, as the tag may have been
+//! provided below the fork point, long after the pool pruned it.
+
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
+
+use crate::{
+	graph::{
+		base_pool::{BasePool, Transaction},
+		BlockHash, ChainApi, ExtrinsicFor, ExtrinsicHash, NumberFor, Pool, TransactionFor,
+		ValidatedTransaction,
+	},
+	LOG_TARGET,
+};
+use futures::future;
+use sc_transaction_pool_api::{error, ReadyTransactions};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Extrinsic, Header as HeaderT, SaturatedConversion},
+	transaction_validity::TransactionSource,
+};
+
+/// The ready transactions on top of a block.
+pub(crate) struct View<Api: ChainApi> {
+	pool: BasePool<ExtrinsicHash<Api>, ExtrinsicFor<Api>>,
+}
+
+impl<Api: ChainApi> View<Api> {
+	/// Get an iterator for ready transactions ordered by priority.
+	pub(crate) fn ready(&self) -> impl ReadyTransactions<Item = TransactionFor<Api>> + Send {
+		self.pool.ready()
+	}
+}
+
+/// The non-finalized leaves of the chain, along with the views built on top of them.
+pub(crate) struct ViewStore<Api: ChainApi> {
+	leaves: HashMap<BlockHash<Api>, Option<Arc<View<Api>>>>,
+	/// Incremented every time the views are invalidated, so views built meanwhile are discarded.
+	generation: u64,
+}
+
+impl<Api: ChainApi> ViewStore<Api> {
+	/// Create a new store, with the best block as the only leaf.
+	pub(crate) fn new(best_block_hash: BlockHash<Api>) -> Self {
+		Self { leaves: HashMap::from([(best_block_hash, None)]), generation: 0 }
+	}
+
+	/// Records a new block, which replaces its parent as a leaf.
+	pub(crate) fn insert_leaf(&mut self, hash: BlockHash<Api>, parent_hash: BlockHash<Api>) {
+		self.leaves.remove(&parent_hash);
+		self.leaves.entry(hash).or_default();
+	}
+
+	/// Returns true if the block is a known leaf.
+	pub(crate) fn is_leaf(&self, hash: &BlockHash<Api>) -> bool {
+		self.leaves.contains_key(hash)
+	}
+
+	/// Returns the known leaves.
+	pub(crate) fn leaves(&self) -> Vec<BlockHash<Api>> {
+		self.leaves.keys().cloned().collect()
+	}
+
+	/// Forgets the given leaves, once they are no longer descendants of the finalized block.
+	pub(crate) fn remove_leaves(&mut self, hashes: &[BlockHash<Api>]) {
+		for hash in hashes {
+			self.leaves.remove(hash);
+		}
+	}
+
+	/// Drops the views built so far, once the transactions of the pool changed.
+	pub(crate) fn invalidate(&mut self) {
+		self.generation += 1;
+		self.leaves.values_mut().for_each(|view| *view = None);
+	}
+
+	/// Returns the view on top of the leaf, and the current generation if it wasn't built yet.
+	pub(crate) fn view(&self, hash: &BlockHash<Api>) -> Result<Arc<View<Api>>, u64> {
+		self.leaves.get(hash).cloned().flatten().ok_or(self.generation)
+	}
+
+	/// Keeps the view built on top of the leaf, unless the views were invalidated meanwhile.
+	pub(crate) fn insert_view(
+		&mut self,
+		hash: BlockHash<Api>,
+		view: Arc<View<Api>>,
+		generation: u64,
+	) {
+		if generation != self.generation {
+			return
+		}
+		if let Some(entry) = self.leaves.get_mut(&hash) {
+			*entry = Some(view);
+		}
+	}
+}
+
+/// Builds the view on top of `leaf`, the pool being up to date with the block `best`.
+pub(crate) async fn build_view<Api: ChainApi>(
+	api: &Api,
+	pool: &Pool<Api>,
+	best: BlockHash<Api>,
+	leaf: BlockHash<Api>,
+) -> Result<View<Api>, Api::Error> {
+	let tree_route = api.tree_route(best, leaf)?;
+	let leaf_number = api
+		.block_id_to_number(&BlockId::Hash(leaf))?
+		.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", leaf)))?;
+
+	let mut transactions = {
+		let pool = pool.validated_pool().pool.read();
+		pool.ready()
+			.map(|tx| tx.duplicate())
+			.chain(pool.futures().map(|tx| tx.duplicate()))
+			.collect::<Vec<_>>()
+	};
+
+	// The transactions of the retracted blocks may be valid on top of the leaf again.
+	let mut retracted = Vec::new();
+	for block in tree_route.retracted() {
+		let body = api.block_body(block.hash).await?.unwrap_or_default();
+		retracted.extend(
+			body.into_iter()
+				.filter(|xt| xt.is_signed().unwrap_or(true))
+				.map(|xt| (TransactionSource::External, xt)),
+		);
+	}
+	transactions.extend(validate_at(api, leaf, leaf_number, retracted).await);
+
+	// The blocks of the leaf's fork include some of the transactions, and provide tags the
+	// others may depend on.
+	let provides = transactions
+		.iter()
+		.map(|tx| (tx.hash, tx.provides.clone()))
+		.collect::<HashMap<_, _>>();
+	let mut included = HashSet::new();
+	let mut tags = Vec::new();
+	for block in tree_route.enacted() {
+		let body = api.block_body(block.hash).await?.unwrap_or_default();
+		let parent_hash = match api.block_header(block.hash)? {
+			Some(header) => *header.parent_hash(),
+			None => continue,
+		};
+		for xt in body {
+			let (hash, _) = api.hash_and_length(&xt);
+			included.insert(hash);
+			match provides.get(&hash) {
+				Some(provides) => tags.extend(provides.iter().cloned()),
+				// if it's not a transaction of the view, query the runtime at the parent block
+				// to get the tags it provides
+				None if !provides.is_empty() => {
+					let validity =
+						api.validate_transaction(parent_hash, TransactionSource::InBlock, xt).await;
+					if let Ok(Ok(validity)) = validity {
+						tags.extend(validity.provides);
+					}
+				},
+				None => {},
+			}
+		}
+	}
+
+	let mut view = BasePool::default();
+	for tx in transactions.into_iter().filter(|tx| !included.contains(&tx.hash)) {
+		let hash = tx.hash;
+		if let Err(e) = view.import(tx) {
+			log::trace!(target: LOG_TARGET, "[{:?}] Not part of the view at {:?}: {}", hash, leaf, e);
+		}
+	}
+	view.prune_tags(tags);
+
+	// The tags the futures wait for may have been provided at or below the common ancestor, so
+	// they are revalidated to only require the tags which aren't provided on top of the leaf.
+	let futures = view.futures().map(|tx| tx.hash).collect::<Vec<_>>();
+	let futures = view
+		.remove_subtree(&futures)
+		.into_iter()
+		.map(|tx| (tx.source, tx.data.clone()))
+		.collect();
+	for tx in validate_at(api, leaf, leaf_number, futures).await {
+		let hash = tx.hash;
+		if let Err(e) = view.import(tx) {
+			log::trace!(target: LOG_TARGET, "[{:?}] Not part of the view at {:?}: {}", hash, leaf, e);
+		}
+	}
+
+	log::debug!(
+		target: LOG_TARGET,
+		"Built the view at {:?} (best {:?}): {:?}",
+		leaf,
+		best,
+		view.status(),
+	);
+
+	Ok(View { pool: view })
+}
+
+/// Validates the extrinsics on top of `at`, keeping the valid ones.
+async fn validate_at<Api: ChainApi>(
+	api: &Api,
+	at: BlockHash<Api>,
+	number: NumberFor<Api>,
+	extrinsics: Vec<(TransactionSource, ExtrinsicFor<Api>)>,
+) -> Vec<Transaction<ExtrinsicHash<Api>, ExtrinsicFor<Api>>> {
+	let validations = future::join_all(extrinsics.into_iter().map(|(source, xt)| async move {
+		let validity = api.validate_transaction(at, source, xt.clone()).await;
+		(source, xt, validity)
+	}))
+	.await;
+
+	validations
+		.into_iter()
+		.filter_map(|(source, xt, validity)| {
+			let Ok(Ok(validity)) = validity else { return None };
+			let (hash, bytes) = api.hash_and_length(&xt);
+			match ValidatedTransaction::<_, _, Api::Error>::valid_at(
+				number.saturated_into::<u64>(),
+				hash,
+				source,
+				xt,
+				bytes,
+				validity,
+			) {
+				ValidatedTransaction::Valid(tx) => Some(tx),
+				_ => None,
+			}
+		})
+		.collect()
+}
@@ -1656,3 +1656,176 @@ fn best_block_after_finalization_does_not_retract() {
 		assert_eq!(stream.next(), None);
 	}
 }
+
+#[test]
+fn ready_at_block_should_include_transactions_retracted_on_fork() {
+	let xt0 = uxt(Alice, 209);
+	let xt1 = uxt(Alice, 210);
+	let (pool, api, _guard) = maintained_pool();
+	let genesis = api.expect_hash_from_number(0);
+
+	block_on(pool.submit_at(genesis, SOURCE, vec![xt0.clone(), xt1.clone()])).expect("1. Imported");
+	assert_eq!(pool.status().ready, 2);
+
+	let header = api.push_block(1, vec![xt0.clone()], true);
+	block_on(pool.maintain(block_event(header.clone())));
+	assert_eq!(pool.status().ready, 1);
+
+	let fork_header = api.push_block_with_parent(genesis, vec![], false);
+	block_on(pool.maintain(ChainEvent::NewBlock { hash: fork_header.hash() }));
+
+	let best_ready = block_on(pool.ready_at_block(header.hash(), 1))
+		.map(|tx| tx.hash)
+		.collect::<Vec<_>>();
+	assert_eq!(best_ready, vec![pool.hash_of(&xt1)]);
+
+	// The fork doesn't include `xt0`, so it's ready again on top of it.
+	let fork_ready = block_on(pool.ready_at_block(fork_header.hash(), 1))
+		.map(|tx| tx.hash)
+		.collect::<Vec<_>>();
+	assert_eq!(fork_ready, vec![pool.hash_of(&xt0), pool.hash_of(&xt1)]);
+	assert_eq!(pool.status().ready, 1);
+}
+
+#[test]
+fn ready_at_block_should_exclude_transactions_included_on_fork() {
+	let xt0 = uxt(Alice, 209);
+	let xt1 = uxt(Alice, 210);
+	let (pool, api, _guard) = maintained_pool();
+	let genesis = api.expect_hash_from_number(0);
+
+	block_on(pool.submit_at(genesis, SOURCE, vec![xt0.clone(), xt1.clone()])).expect("1. Imported");
+	assert_eq!(pool.status().ready, 2);
+
+	let fork_header = api.push_block(1, vec![xt0.clone()], false);
+	block_on(pool.maintain(ChainEvent::NewBlock { hash: fork_header.hash() }));
+
+	let fork_ready = block_on(pool.ready_at_block(fork_header.hash(), 1))
+		.map(|tx| tx.hash)
+		.collect::<Vec<_>>();
+	assert_eq!(fork_ready, vec![pool.hash_of(&xt1)]);
+	// The pool still follows the best chain.
+	assert_eq!(pool.status().ready, 2);
+}
+
+#[test]
+fn ready_at_block_should_include_transactions_depending_on_tags_provided_below_fork() {
+	let xt0 = uxt(Alice, 209);
+	let xt1 = uxt(Alice, 210);
+	let (pool, api, _guard) = maintained_pool();
+	let genesis = api.expect_hash_from_number(0);
+
+	block_on(pool.submit_at(genesis, SOURCE, vec![xt0.clone(), xt1.clone()])).expect("1. Imported");
+	assert_eq!(pool.status().ready, 2);
+
+	// `xt1` still requires the tag `xt0` provided below the fork point.
+	let header = api.push_block(1, vec![xt0.clone()], true);
+	api.increment_nonce(Alice.into());
+	block_on(pool.maintain(block_event(header.clone())));
+	assert_eq!(pool.status().ready, 1);
+
+	let best_header = api.push_block_with_parent(header.hash(), vec![], true);
+	block_on(pool.maintain(block_event(best_header)));
+	let fork_header = api.push_block_with_parent(header.hash(), vec![], false);
+	block_on(pool.maintain(ChainEvent::NewBlock { hash: fork_header.hash() }));
+
+	let fork_ready = block_on(pool.ready_at_block(fork_header.hash(), 2))
+		.map(|tx| tx.hash)
+		.collect::<Vec<_>>();
+	assert_eq!(fork_ready, vec![pool.hash_of(&xt1)]);
+}
+
+#[test]
+fn should_notify_watchers_of_transactions_included_on_fork() {
+	let xt0 = uxt(Alice, 209);
+	let (pool, api, _guard) = maintained_pool();
+	let genesis = api.expect_hash_from_number(0);
+
+	let watcher =
+		block_on(pool.submit_and_watch(genesis, SOURCE, xt0.clone())).expect("1. Imported");
+
+	let header = api.push_block(1, vec![], true);
+	block_on(pool.maintain(block_event(header.clone())));
+	let fork_header = api.push_block_with_parent(genesis, vec![xt0.clone()], false);
+	block_on(pool.maintain(ChainEvent::NewBlock { hash: fork_header.hash() }));
+
+	// The fork is abandoned once the best block is finalized.
+	let event = ChainEvent::Finalized { hash: header.hash(), tree_route: Arc::from(vec![]) };
+	block_on(pool.maintain(event));
+	assert_eq!(pool.status().ready, 1);
+
+	let mut stream = futures::executor::block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock((fork_header.hash(), 0))));
+	assert_eq!(stream.next(), Some(TransactionStatus::Retracted(fork_header.hash())));
+}
+
+#[test]
+fn should_include_bundles_entirely_and_follow_them_until_finalized() {
+	let xt0 = uxt(Alice, 209);
+	let xt1 = uxt(Alice, 210);
+	let (pool, api, _guard) = maintained_pool();
+	let genesis = api.expect_hash_from_number(0);
+
+	let watcher =
+		block_on(pool.submit_and_watch_bundle(genesis, SOURCE, vec![xt0.clone(), xt1.clone()]))
+			.expect("1. Imported");
+
+	// The transactions of the bundle aren't ready on their own.
+	assert_eq!(pool.status().ready, 0);
+	let bundles = pool
+		.ready_bundles(genesis)
+		.into_iter()
+		.map(|bundle| bundle.iter().map(|tx| tx.hash).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+	assert_eq!(bundles, vec![vec![pool.hash_of(&xt0), pool.hash_of(&xt1)]]);
+
+	let header = api.push_block(1, vec![xt0, xt1], true);
+	block_on(pool.maintain(block_event(header.clone())));
+	assert!(pool.ready_bundles(header.hash()).is_empty());
+
+	let event = ChainEvent::Finalized { hash: header.hash(), tree_route: Arc::from(vec![]) };
+	block_on(pool.maintain(event));
+
+	assert_eq!(
+		block_on_stream(watcher).collect::<Vec<_>>(),
+		vec![
+			TransactionStatus::Ready,
+			TransactionStatus::InBlock((header.hash(), 0)),
+			TransactionStatus::Finalized((header.hash(), 0)),
+		],
+	);
+}
+
+#[test]
+fn should_reject_invalid_bundles() {
+	let (pool, api, _guard) = maintained_pool();
+	let genesis = api.expect_hash_from_number(0);
+
+	assert!(block_on(pool.submit_bundle(genesis, SOURCE, vec![])).is_err());
+	assert!(block_on(pool.submit_bundle(genesis, SOURCE, vec![uxt(Alice, 209), uxt(Alice, 208)]))
+		.is_err());
+	assert!(pool.ready_bundles(genesis).is_empty());
+}
+
+#[test]
+fn should_not_import_transactions_of_bundles_on_their_own() {
+	let xt0 = uxt(Alice, 209);
+	let xt1 = uxt(Alice, 210);
+	let xt2 = uxt(Alice, 211);
+	let (pool, api, _guard) = maintained_pool();
+	let genesis = api.expect_hash_from_number(0);
+
+	block_on(pool.submit_one(genesis, SOURCE, xt0.clone())).expect("1. Imported");
+	// `xt0` could be included without the rest of the bundle.
+	assert!(block_on(pool.submit_bundle(genesis, SOURCE, vec![xt0.clone(), xt1.clone()])).is_err());
+
+	block_on(pool.submit_bundle(genesis, SOURCE, vec![xt1.clone(), xt2.clone()]))
+		.expect("2. Imported");
+	assert!(block_on(pool.submit_one(genesis, SOURCE, xt1.clone())).is_err());
+	let results = block_on(pool.submit_at(genesis, SOURCE, vec![xt2.clone(), uxt(Bob, 0)]))
+		.expect("3. Submitted");
+	assert!(results[0].is_err());
+	assert!(results[1].is_ok());
+	assert_eq!(pool.status().ready, 2);
+}
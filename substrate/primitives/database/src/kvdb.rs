@@ -19,7 +19,7 @@
 /// A wrapper around `kvdb::Database` that implements `sp_database::Database` trait
 use ::kvdb::{DBTransaction, KeyValueDB};
 
-use crate::{error, Change, ColumnId, Database, Transaction};
+use crate::{error, Change, ColumnEntry, ColumnId, Database, Transaction};
 
 struct DbAdapter<D: KeyValueDB + 'static>(D);
 
@@ -116,4 +116,29 @@ impl<D: KeyValueDB, H: Clone + AsRef<[u8]>> Database<H> for DbAdapter<D> {
 	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
 		handle_err(self.0.has_key(col, key))
 	}
+
+	fn iter_column_while(&self, col: ColumnId, f: &mut dyn FnMut(ColumnEntry) -> bool) -> bool {
+		for entry in self.0.iter(col) {
+			let (key, value) = handle_err(entry);
+			if !f(ColumnEntry { key: Some(&key[..]), value: &value, ref_count: None }) {
+				break
+			}
+		}
+		true
+	}
+
+	fn iter_prefix_while(
+		&self,
+		col: ColumnId,
+		prefix: &[u8],
+		f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> bool {
+		for entry in self.0.iter_with_prefix(col, prefix) {
+			let (key, value) = handle_err(entry);
+			if !f(ColumnEntry { key: Some(&key[..]), value: &value, ref_count: None }) {
+				break
+			}
+		}
+		true
+	}
 }
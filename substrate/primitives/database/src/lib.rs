@@ -78,6 +78,16 @@ impl<H> Transaction<H> {
 	}
 }
 
+/// An entry of a column, as visited by `Database::iter_column_while`.
+pub struct ColumnEntry<'a> {
+	/// The key of the entry, if known to the database.
+	pub key: Option<&'a [u8]>,
+	/// The value of the entry.
+	pub value: &'a [u8],
+	/// The number of references on the entry, if the column is reference counted.
+	pub ref_count: Option<u32>,
+}
+
 pub trait Database<H: Clone + AsRef<[u8]>>: Send + Sync {
 	/// Commit the `transaction` to the database atomically. Any further calls to `get` or `lookup`
 	/// will reflect the new state.
@@ -114,6 +124,28 @@ pub trait Database<H: Clone + AsRef<[u8]>>: Send + Sync {
 		false
 	}
 
+	/// Call `f` with every entry of `col`, in no particular order, until it returns `false`.
+	///
+	/// Changes committed while iterating may or may not be visited. `f` must not commit to the
+	/// database. Returns `false` if the database doesn't support iteration.
+	fn iter_column_while(&self, _col: ColumnId, _f: &mut dyn FnMut(ColumnEntry) -> bool) -> bool {
+		false
+	}
+
+	/// Call `f` with the entries of `col` whose key starts with `prefix`, in ascending key order,
+	/// until it returns `false`.
+	///
+	/// `f` must not commit to the database. Returns `false` if the database doesn't support
+	/// ordered iteration over `col`.
+	fn iter_prefix_while(
+		&self,
+		_col: ColumnId,
+		_prefix: &[u8],
+		_f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> bool {
+		false
+	}
+
 	/// Remove a possible path-prefix from the key.
 	///
 	/// Not all database implementations use a prefix for keys, so this function may be a noop.
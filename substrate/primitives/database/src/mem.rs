@@ -18,7 +18,7 @@
 
 //! In-memory implementation of `Database`
 
-use crate::{error, Change, ColumnId, Database, Transaction};
+use crate::{error, Change, ColumnEntry, ColumnId, Database, Transaction};
 use parking_lot::RwLock;
 use std::collections::{hash_map::Entry, HashMap};
 
@@ -74,6 +74,38 @@ where
 		let s = self.0.read();
 		s.get(&col).and_then(|c| c.get(key).map(|(_, v)| v.clone()))
 	}
+
+	fn iter_column_while(&self, col: ColumnId, f: &mut dyn FnMut(ColumnEntry) -> bool) -> bool {
+		let s = self.0.read();
+		for (key, (count, value)) in s.get(&col).into_iter().flatten() {
+			if !f(ColumnEntry { key: Some(key.as_slice()), value, ref_count: Some(*count) }) {
+				break
+			}
+		}
+		true
+	}
+
+	fn iter_prefix_while(
+		&self,
+		col: ColumnId,
+		prefix: &[u8],
+		f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> bool {
+		let s = self.0.read();
+		let mut entries = s
+			.get(&col)
+			.into_iter()
+			.flatten()
+			.filter(|(key, _)| key.starts_with(prefix))
+			.collect::<Vec<_>>();
+		entries.sort_by(|a, b| a.0.cmp(b.0));
+		for (key, (count, value)) in entries {
+			if !f(ColumnEntry { key: Some(key.as_slice()), value, ref_count: Some(*count) }) {
+				break
+			}
+		}
+		true
+	}
 }
 
 impl MemDb {